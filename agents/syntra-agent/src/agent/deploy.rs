@@ -1,36 +1,257 @@
 //! Deploy Handler
 //!
-//! Handles container deployment commands from the control plane.
+//! Handles container deployment commands from the control plane: pulling the
+//! image, replacing the service's containers using the Recreate or BlueGreen
+//! strategy, and reporting progress and the outcome.
 
-use anyhow::{Context, Result};
-use std::collections::HashMap;
+use anyhow::{anyhow, bail, Context, Result};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
 use tracing::{debug, error, info, warn};
 
+use crate::agent::secrets::{self, SecretStore, SECRETS_LABEL};
+use crate::agent::state::AgentStateManager;
+use crate::cli::config::{ContainerLogConfig, ResourceLimits, RuntimeConfig};
+use crate::connection::channel;
 use crate::connection::protocol::{
-    AgentMessage, ContainerStatusPayload, DeployContainerPayload, ErrorPayload,
-    PortMapping, StopContainerPayload, TaskResultPayload,
+    AgentMessage, ContainerStatusPayload, DeployContainerPayload, DeployEventPayload,
+    DeployPhase, DeployStrategy, ErrorPayload, HealthCheck, PortMapping, ResourceSpec,
+    SecretFile, StopContainerPayload, TaskResultPayload,
 };
 use crate::runtime::adapter::{
-    ContainerStatus, CreateContainerOptions, PortBinding, RestartPolicy, RuntimeAdapter,
-    VolumeBinding,
+    ContainerHealth, ContainerInfo, ContainerStatus, CreateContainerOptions, CreateNetworkOptions,
+    MountSpec, PortBinding, RestartPolicy, RuntimeAdapter,
 };
+use crate::runtime::error::RuntimeError;
+
+/// Suffix of the temporary name a blue-green deploy starts the new container under
+const STAGING_SUFFIX: &str = "-syntra-next";
+
+/// Suffix the old container is renamed to while a blue-green deploy swaps in the new one
+const RETIRED_SUFFIX: &str = "-syntra-old";
+
+/// Default number of deployments allowed to run at once
+pub const DEFAULT_MAX_CONCURRENT_DEPLOYS: usize = 4;
+
+/// Seconds a signalled container gets to exit before SIGKILL, matching Docker
+const DEFAULT_STOP_TIMEOUT_SECS: u64 = 10;
+
+/// How often to check whether a signalled container has exited
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often to check a starting container's health while its image's own
+/// health check settles
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Upper bound on cleaning up after a failed or timed-out deployment
+const CLEANUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Attempts at pulling an image when the runtime fails transiently
+const PULL_ATTEMPTS: u32 = 3;
+
+/// Delay before retrying a pull, multiplied by the attempt number
+const PULL_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Completed requests remembered for answering resent ones
+const COMPLETED_REQUESTS: usize = 256;
+
+/// How long a completed request's result is remembered
+const COMPLETED_REQUEST_TTL: Duration = Duration::from_secs(600);
+
+/// Task results of recently completed requests, least recently used first,
+/// so a request the control plane resends isn't run a second time
+#[derive(Default)]
+struct CompletedRequests {
+    entries: VecDeque<(Instant, TaskResultPayload)>,
+}
+
+impl CompletedRequests {
+    /// The result `request_id` completed with, if it's still remembered
+    fn get(&mut self, request_id: &str) -> Option<TaskResultPayload> {
+        self.entries.retain(|(completed_at, _)| completed_at.elapsed() < COMPLETED_REQUEST_TTL);
+        let index = self
+            .entries
+            .iter()
+            .position(|(_, result)| result.task_id == request_id)?;
+        let entry = self.entries.remove(index)?;
+        let result = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(result)
+    }
+
+    /// Remember a request's result, evicting the least recently used when full
+    fn insert(&mut self, result: TaskResultPayload) {
+        self.entries.retain(|(_, r)| r.task_id != result.task_id);
+        if self.entries.len() >= COMPLETED_REQUESTS {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((Instant::now(), result));
+    }
+}
+
+/// How far a deployment has got, so a failure can report the stage it was
+/// stuck in and clean up the container it created
+#[derive(Default)]
+struct DeployProgress {
+    /// Container the deployment is for, as named in its timeline
+    name: String,
+    stage: Mutex<&'static str>,
+    /// Container created by this deployment
+    created: Mutex<Option<String>>,
+}
+
+impl DeployProgress {
+    fn enter(&self, stage: &'static str) {
+        *self.stage.lock() = stage;
+    }
+}
 
 /// Deploy handler for processing container deployments
 pub struct DeployHandler<R: RuntimeAdapter> {
     runtime: Arc<R>,
     message_tx: mpsc::Sender<AgentMessage>,
+    startup_grace: Duration,
+    deploy_permits: Arc<Semaphore>,
+    limits: ResourceLimits,
+    deploy_timeout: Duration,
+    /// Network containers join when the payload names none
+    default_network: String,
+    allow_privileged: bool,
+    allow_gpu: bool,
+    container_logs: ContainerLogConfig,
+    secrets: SecretStore,
+    /// Last successful deploy of each service, keyed by name
+    desired: Mutex<HashMap<String, DeployContainerPayload>>,
+    /// Services with a deployment in progress
+    deploying: Mutex<HashSet<String>>,
+    completed: Mutex<CompletedRequests>,
+    /// Agent state, consulted to refuse new deployments while draining
+    state_manager: Option<AgentStateManager>,
 }
 
 impl<R: RuntimeAdapter> DeployHandler<R> {
     /// Create a new deploy handler
-    pub fn new(runtime: Arc<R>, message_tx: mpsc::Sender<AgentMessage>) -> Self {
-        Self { runtime, message_tx }
+    pub fn new(
+        runtime: Arc<R>,
+        config: &RuntimeConfig,
+        message_tx: mpsc::Sender<AgentMessage>,
+    ) -> Self {
+        Self {
+            runtime,
+            message_tx,
+            startup_grace: Duration::from_secs(2),
+            deploy_permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_DEPLOYS)),
+            limits: config.resource_limits.clone(),
+            deploy_timeout: Duration::from_secs(config.deploy_timeout_secs),
+            default_network: config.default_network.clone(),
+            allow_privileged: config.allow_privileged,
+            allow_gpu: config.allow_gpu,
+            container_logs: config.container_logs.clone(),
+            secrets: SecretStore::new(&config.secrets_dir),
+            desired: Mutex::new(HashMap::new()),
+            deploying: Mutex::new(HashSet::new()),
+            completed: Mutex::new(CompletedRequests::default()),
+            state_manager: None,
+        }
+    }
+
+    /// Refuse new deployments while the agent is draining
+    pub fn with_state_manager(mut self, state_manager: AgentStateManager) -> Self {
+        self.state_manager = Some(state_manager);
+        self
+    }
+
+    /// Whether new deployments are currently refused
+    pub fn is_draining(&self) -> bool {
+        self.state_manager.as_ref().is_some_and(AgentStateManager::is_draining)
+    }
+
+    /// Share a deploy concurrency limit; deployments beyond it are queued
+    pub fn with_deploy_limit(mut self, permits: Arc<Semaphore>) -> Self {
+        self.deploy_permits = permits;
+        self
+    }
+
+    /// Set how long to wait after start before checking a container is running
+    pub fn with_startup_grace(mut self, grace: Duration) -> Self {
+        self.startup_grace = grace;
+        self
+    }
+
+    /// Set the overall deadline for pulling, starting, and health-checking
+    pub fn with_deploy_timeout(mut self, timeout: Duration) -> Self {
+        self.deploy_timeout = timeout;
+        self
     }
 
     /// Deploy a container based on the payload from control plane
+    ///
+    /// A service that deploys successfully becomes desired state, which
+    /// reconciliation keeps running until it's stopped or scaled to zero.
+    ///
+    /// A request that already completed, resent because its ack was lost,
+    /// gets its earlier task result again instead of being redeployed. New
+    /// requests are rejected with `AGENT_DRAINING` while the agent drains.
     pub async fn deploy(&self, payload: DeployContainerPayload) -> Result<String> {
+        let completed = self.completed.lock().get(&payload.request_id);
+        if let Some(result) = completed {
+            info!(
+                request_id = %payload.request_id,
+                "Deployment already completed, resending its result"
+            );
+            let output = result.output.clone().unwrap_or_default();
+            let message = AgentMessage::TaskResult(result);
+            if let Err(e) = channel::send(&self.message_tx, message).await {
+                warn!(error = %e, "Failed to send task result");
+            }
+            return Ok(output);
+        }
+
+        if self.is_draining() {
+            let message = "Agent is draining and not accepting new deployments";
+            warn!(request_id = %payload.request_id, "{}", message);
+            self.send_error(&payload.request_id, "AGENT_DRAINING", message).await;
+            bail!(message);
+        }
+
+        let name = payload.name.clone();
+        let desired = (!payload.wait_for_exit).then(|| payload.clone());
+
+        self.deploying.lock().insert(name.clone());
+        let result = self.deploy_service(payload).await;
+        self.deploying.lock().remove(&name);
+
+        if let (Ok(_), Some(desired)) = (&result, desired) {
+            let mut services = self.desired.lock();
+            if desired.replicas == 0 {
+                services.remove(&name);
+            } else {
+                services.insert(name, desired);
+            }
+        }
+        result
+    }
+
+    /// Services to keep running, leaving out any being deployed right now
+    pub fn desired_services(&self) -> Vec<DeployContainerPayload> {
+        let deploying = self.deploying.lock();
+        self.desired
+            .lock()
+            .values()
+            .filter(|service| !deploying.contains(&service.name))
+            .cloned()
+            .collect()
+    }
+
+    /// Validate a deployment and run its replicas
+    ///
+    /// Several replicas run as `<name>-1` to `<name>-N`; replicas beyond the
+    /// requested count are removed, so repeated deploys converge on it.
+    async fn deploy_service(&self, payload: DeployContainerPayload) -> Result<String> {
+        let started_at = Instant::now();
         let request_id = payload.request_id.clone();
         let container_name = payload.name.clone();
         let image = payload.image.clone();
@@ -39,142 +260,181 @@ impl<R: RuntimeAdapter> DeployHandler<R> {
             request_id = %request_id,
             image = %image,
             name = %container_name,
+            strategy = ?payload.strategy,
+            replicas = payload.replicas,
             "Starting container deployment"
         );
 
-        // Send deployment started status
-        self.send_status(&container_name, "deploying", None).await;
+        if payload.max_restart_retries.is_some()
+            && payload.restart_policy != Some(RestartPolicy::OnFailure)
+        {
+            let message = "max_restart_retries requires the OnFailure restart policy";
+            warn!(request_id = %request_id, "{}", message);
+            self.send_error(&request_id, "INVALID_RESTART_POLICY", message).await;
+            bail!(message);
+        }
 
-        // Step 1: Pull the image
-        info!(request_id = %request_id, image = %image, "Pulling image");
-        if let Err(e) = self.runtime.pull_image(&image).await {
-            error!(request_id = %request_id, error = %e, "Failed to pull image");
-            self.send_error(&request_id, "PULL_FAILED", &format!("Failed to pull image: {}", e))
-                .await;
+        if let Some(ulimit) = payload.ulimits.iter().find(|u| u.soft > u.hard) {
+            let message = format!(
+                "ulimit {} has a soft limit ({}) above its hard limit ({})",
+                ulimit.name, ulimit.soft, ulimit.hard
+            );
+            warn!(request_id = %request_id, "{}", message);
+            self.send_error(&request_id, "INVALID_ULIMIT", &message).await;
+            bail!(message);
+        }
+
+        if let Err(e) = secrets::validate(&payload.secret_files) {
+            warn!(request_id = %request_id, error = %e, "Invalid secret files");
+            self.send_error(&request_id, "INVALID_SECRET_FILE", &e.to_string()).await;
             return Err(e);
         }
-        debug!(request_id = %request_id, "Image pulled successfully");
 
-        // Step 2: Check if container with same name exists and remove it
-        if let Some(existing) = self
-            .runtime
-            .get_container(&container_name)
-            .await
-            .context("Failed to check existing container")?
-        {
-            info!(
-                request_id = %request_id,
-                container_id = %existing.id,
-                "Removing existing container"
-            );
+        if payload.strategy == DeployStrategy::BlueGreen && !payload.wait_for_exit {
+            // The swap renames both containers
+            if !self.runtime.capabilities().iter().any(|c| c == "rename") {
+                let message = format!(
+                    "The {} runtime can't rename containers, which blue-green deploys need; \
+                     use the Recreate strategy",
+                    self.runtime.runtime_type()
+                );
+                warn!(request_id = %request_id, "{}", message);
+                self.send_error(&request_id, "INVALID_STRATEGY", &message).await;
+                bail!(message);
+            }
 
-            // Stop if running
-            if existing.status == ContainerStatus::Running {
-                if let Err(e) = self.runtime.stop_container(&existing.id, Some(30)).await {
-                    warn!(
-                        request_id = %request_id,
-                        error = %e,
-                        "Failed to stop existing container, forcing removal"
-                    );
-                }
+            // The new container starts while the old one still holds its ports
+            if let Some(port) = payload.ports.iter().flatten().find(|p| p.host_port != 0) {
+                let message = format!(
+                    "Blue-green deploys can't bind host port {}, which the running container \
+                     holds; use the Recreate strategy",
+                    port.host_port
+                );
+                warn!(request_id = %request_id, "{}", message);
+                self.send_error(&request_id, "INVALID_STRATEGY", &message).await;
+                bail!(message);
             }
+        }
 
-            // Remove container
-            if let Err(e) = self.runtime.remove_container(&existing.id, true).await {
-                error!(request_id = %request_id, error = %e, "Failed to remove existing container");
-                self.send_error(
-                    &request_id,
-                    "REMOVE_FAILED",
-                    &format!("Failed to remove existing container: {}", e),
-                )
-                .await;
-                return Err(e);
+        if payload.privileged && !self.allow_privileged {
+            let message = "Privileged containers are not allowed by this agent";
+            warn!(request_id = %request_id, "{}", message);
+            self.send_error(&request_id, "PRIVILEGED_DENIED", message).await;
+            bail!(message);
+        }
+
+        if payload.gpus.is_some() {
+            self.check_gpu_support(&request_id).await?;
+        }
+
+        if payload.wait_for_exit {
+            if payload.replicas != 1 {
+                let message = "A job (wait_for_exit) runs exactly one replica";
+                warn!(request_id = %request_id, "{}", message);
+                self.send_error(&request_id, "INVALID_REPLICAS", message).await;
+                bail!(message);
             }
+            return self.deploy_container(payload, started_at).await;
         }
 
-        // Step 3: Prepare container options
-        let env_vars: Vec<(String, String)> = payload
-            .env
-            .unwrap_or_default()
-            .into_iter()
-            .map(|e| (e.name, e.value))
-            .collect();
+        let container_ids = if payload.replicas == 1 {
+            vec![self.deploy_container(payload.clone(), started_at).await?]
+        } else {
+            let mut ids = Vec::new();
+            for index in 1..=payload.replicas {
+                let replica = DeployContainerPayload {
+                    name: replica_name(&container_name, index),
+                    ..payload.clone()
+                };
+                ids.push(self.deploy_container(replica, started_at).await?);
+            }
+            ids
+        };
+        self.remove_surplus_replicas(&payload).await?;
 
-        let ports: Vec<PortBinding> = payload
-            .ports
-            .unwrap_or_default()
-            .into_iter()
-            .map(|p| PortBinding {
-                container_port: p.container_port,
-                host_port: Some(p.host_port),
-                host_ip: Some("0.0.0.0".to_string()),
-                protocol: p.protocol,
-            })
-            .collect();
+        let output = if payload.replicas == 1 {
+            container_ids[0].clone()
+        } else {
+            serde_json::json!({ "container_ids": container_ids }).to_string()
+        };
+        self.send_task_result(&request_id, true, Some(output), None, started_at.elapsed())
+            .await;
 
-        let volumes: Vec<VolumeBinding> = payload
-            .volumes
-            .unwrap_or_default()
-            .into_iter()
-            .map(|v| VolumeBinding {
-                source: v.host_path,
-                target: v.container_path,
-                read_only: v.read_only,
-            })
-            .collect();
+        Ok(container_ids.join(","))
+    }
 
-        let mut labels = HashMap::new();
-        labels.insert("syntra.managed".to_string(), "true".to_string());
-        labels.insert("syntra.request_id".to_string(), request_id.clone());
+    /// Deploy one container under `payload.name` and report its status
+    ///
+    /// A job's task result is sent once it exits; a service's is left to the
+    /// caller, which may be deploying several replicas.
+    async fn deploy_container(
+        &self,
+        mut payload: DeployContainerPayload,
+        started_at: Instant,
+    ) -> Result<String> {
+        let request_id = payload.request_id.clone();
+        let container_name = payload.name.clone();
 
-        let options = CreateContainerOptions {
-            name: container_name.clone(),
-            image: image.clone(),
-            command: None,
-            env: env_vars,
-            ports,
-            volumes,
-            labels,
-            network: None,
-            memory_limit: payload.resources.as_ref().and_then(|r| r.memory_mb),
-            cpu_limit: payload.resources.as_ref().and_then(|r| r.cpu_cores),
-            restart_policy: Some(RestartPolicy::UnlessStopped),
+        // Wait for a deploy slot, letting the control plane know if we have to queue
+        let _permit = match self.deploy_permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                info!(request_id = %request_id, "Deployment queued");
+                self.send_status(&container_name, "queued", None).await;
+                self.deploy_permits
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .context("Deploy limiter closed")?
+            }
         };
 
-        // Step 4: Create the container
-        info!(request_id = %request_id, "Creating container");
-        let container_id = match self.runtime.create_container(options).await {
-            Ok(id) => id,
+        // Hold the deployment to the host's resource policy
+        let limited = match self.resolve_resources(payload.resources.as_ref()) {
+            Ok(resources) => self.check_container_limit(&container_name).await.map(|_| resources),
+            Err(e) => Err(e),
+        };
+        match limited {
+            Ok(resources) => payload.resources = Some(resources),
             Err(e) => {
-                error!(request_id = %request_id, error = %e, "Failed to create container");
-                self.send_error(
-                    &request_id,
-                    "CREATE_FAILED",
-                    &format!("Failed to create container: {}", e),
-                )
-                .await;
+                warn!(request_id = %request_id, error = %e, "Deployment exceeds resource limits");
+                self.send_error(&request_id, "LIMIT_EXCEEDED", &e.to_string()).await;
                 return Err(e);
             }
-        };
-        debug!(request_id = %request_id, container_id = %container_id, "Container created");
+        }
 
-        // Step 5: Start the container
-        info!(request_id = %request_id, container_id = %container_id, "Starting container");
-        if let Err(e) = self.runtime.start_container(&container_id).await {
-            error!(request_id = %request_id, error = %e, "Failed to start container");
-            // Clean up the created container
-            let _ = self.runtime.remove_container(&container_id, true).await;
-            self.send_error(
-                &request_id,
-                "START_FAILED",
-                &format!("Failed to start container: {}", e),
-            )
-            .await;
-            return Err(e);
+        // Pin the image so the container runs exactly the requested content
+        if let Some(digest) = &payload.image_digest {
+            payload.image = pinned_reference(&payload.image, digest);
         }
 
-        // Step 6: Verify container is running
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        // Send deployment started status
+        self.send_status(&container_name, "deploying", None).await;
+
+        let progress = DeployProgress {
+            name: container_name.clone(),
+            ..DeployProgress::default()
+        };
+        let outcome =
+            tokio::time::timeout(self.deploy_timeout, self.run_steps(&payload, &progress)).await;
+        let (container_id, image_digest) = match outcome {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => {
+                self.clean_up_failed(&payload, progress.created.into_inner()).await;
+                return Err(e);
+            }
+            Err(_) => return Err(self.handle_timeout(&payload, progress).await),
+        };
+
+        if payload.wait_for_exit {
+            let result = self
+                .finish_job(&request_id, &container_name, &container_id, image_digest, started_at)
+                .await;
+            if result.is_err() {
+                self.clean_up_failed(&payload, Some(container_id)).await;
+            }
+            return result;
+        }
 
         let container = self
             .runtime
@@ -183,24 +443,6 @@ impl<R: RuntimeAdapter> DeployHandler<R> {
             .context("Failed to get container status")?
             .ok_or_else(|| anyhow::anyhow!("Container not found after start"))?;
 
-        if container.status != ContainerStatus::Running {
-            error!(
-                request_id = %request_id,
-                status = %container.status,
-                "Container is not running after start"
-            );
-            self.send_error(
-                &request_id,
-                "NOT_RUNNING",
-                &format!("Container status is {} after start", container.status),
-            )
-            .await;
-            return Err(anyhow::anyhow!(
-                "Container is not running: {}",
-                container.status
-            ));
-        }
-
         // Send success status
         let port_mappings: Vec<PortMapping> = container
             .ports
@@ -214,12 +456,15 @@ impl<R: RuntimeAdapter> DeployHandler<R> {
             })
             .collect();
 
-        self.send_container_status(&container_id, &container_name, "running", port_mappings)
-            .await;
-
-        // Send task result
-        self.send_task_result(&request_id, true, Some(container_id.clone()), None)
-            .await;
+        self.send_container_status(
+            &container_id,
+            &container_name,
+            "running",
+            Some(&container),
+            port_mappings,
+            image_digest,
+        )
+        .await;
 
         info!(
             request_id = %request_id,
@@ -230,166 +475,2291 @@ impl<R: RuntimeAdapter> DeployHandler<R> {
         Ok(container_id)
     }
 
-    /// Stop a container based on the payload from control plane
-    pub async fn stop(&self, payload: StopContainerPayload) -> Result<()> {
-        let request_id = payload.request_id.clone();
-        let container_id = payload.container_id.clone();
-
-        info!(
-            request_id = %request_id,
-            container_id = %container_id,
-            force = payload.force,
-            "Stopping container"
-        );
-
-        // Get container info first
-        let container = self
-            .runtime
-            .get_container(&container_id)
-            .await
-            .context("Failed to get container")?;
+    /// Pull the image, then replace the existing container according to the
+    /// strategy, returning the container ID and the image's registry digest
+    async fn run_steps(
+        &self,
+        payload: &DeployContainerPayload,
+        progress: &DeployProgress,
+    ) -> Result<(String, Option<String>)> {
+        let request_id = &payload.request_id;
 
-        if container.is_none() {
-            warn!(request_id = %request_id, "Container not found");
-            self.send_error(&request_id, "NOT_FOUND", "Container not found")
-                .await;
-            return Err(anyhow::anyhow!("Container not found"));
+        // Step 1: Pull the image, unless it's present and the payload doesn't force a pull
+        progress.enter("pulling the image");
+        if self.should_pull(payload).await {
+            info!(request_id = %request_id, image = %payload.image, "Pulling image");
+            self.send_deploy_event(request_id, progress, DeployPhase::PullStarted).await;
+            if let Err(e) = self.pull_image(payload).await {
+                error!(request_id = %request_id, error = %e, "Failed to pull image");
+                let message = format!("Failed to pull image: {}", e);
+                self.send_error(request_id, pull_error_code(&e), &message).await;
+                return Err(e.into());
+            }
+            debug!(request_id = %request_id, "Image pulled successfully");
+            self.send_deploy_event(request_id, progress, DeployPhase::PullDone).await;
+        } else {
+            info!(request_id = %request_id, image = %payload.image, "Image present, skipping pull");
         }
 
-        let container = container.unwrap();
+        progress.enter("checking the image digest");
+        let image_digest = self.image_digest(payload).await?;
 
-        // Stop the container
-        if container.status == ContainerStatus::Running {
-            if let Err(e) = self
+        // Step 2: Replace the existing container according to the strategy
+        let container_id = match payload.strategy {
+            // A job has nothing to health check before a swap, so it's always recreated
+            DeployStrategy::BlueGreen if !payload.wait_for_exit => {
+                self.deploy_blue_green(payload, progress).await?
+            }
+            _ => self.deploy_recreate(payload, progress).await?,
+        };
+        Ok((container_id, image_digest))
+    }
+
+    /// Pull the payload's image, retrying while the runtime fails transiently
+    async fn pull_image(&self, payload: &DeployContainerPayload) -> Result<(), RuntimeError> {
+        let mut attempt = 1;
+        loop {
+            let result = self
                 .runtime
-                .stop_container(&container_id, payload.timeout_secs)
-                .await
-            {
-                if payload.force {
+                .pull_image(&payload.image, payload.registry_auth.clone())
+                .await;
+            match result {
+                Err(e) if e.is_transient() && attempt < PULL_ATTEMPTS => {
                     warn!(
-                        request_id = %request_id,
+                        request_id = %payload.request_id,
+                        attempt,
                         error = %e,
-                        "Failed to stop gracefully, forcing"
+                        "Pull failed, retrying"
                     );
-                } else {
-                    error!(request_id = %request_id, error = %e, "Failed to stop container");
-                    self.send_error(
-                        &request_id,
-                        "STOP_FAILED",
-                        &format!("Failed to stop container: {}", e),
-                    )
-                    .await;
-                    return Err(e);
+                    tokio::time::sleep(PULL_RETRY_DELAY * attempt).await;
+                    attempt += 1;
                 }
+                result => return result,
             }
         }
+    }
 
-        // Remove container if force is true
-        if payload.force {
-            if let Err(e) = self.runtime.remove_container(&container_id, true).await {
-                error!(request_id = %request_id, error = %e, "Failed to remove container");
-                self.send_error(
-                    &request_id,
-                    "REMOVE_FAILED",
-                    &format!("Failed to remove container: {}", e),
-                )
-                .await;
-                return Err(e);
+    /// Whether to pull the payload's image: always when forced (the default for
+    /// tags), otherwise only when it isn't present locally
+    async fn should_pull(&self, payload: &DeployContainerPayload) -> bool {
+        let force_pull = payload.force_pull.unwrap_or(!payload.image.contains('@'));
+        if force_pull {
+            return true;
+        }
+        match self.runtime.image_exists(&payload.image).await {
+            Ok(exists) => !exists,
+            Err(e) => {
+                warn!(request_id = %payload.request_id, error = %e, "Failed to check for image");
+                true
             }
         }
+    }
 
-        // Send status update
-        self.send_status(&container.name, "stopped", None).await;
-        self.send_task_result(&request_id, true, None, None).await;
+    /// Verify a pulled image against the payload's digest, or look up the
+    /// digest the tag resolved to when none was requested
+    async fn image_digest(&self, payload: &DeployContainerPayload) -> Result<Option<String>> {
+        let request_id = &payload.request_id;
+        let digests = self.runtime.image_digests(&payload.image).await;
 
-        info!(
-            request_id = %request_id,
-            container_id = %container_id,
-            "Container stopped successfully"
-        );
+        let Some(expected) = &payload.image_digest else {
+            return Ok(match digests {
+                Ok(digests) => digests.into_iter().next(),
+                Err(e) => {
+                    warn!(request_id = %request_id, error = %e, "Failed to read image digest");
+                    None
+                }
+            });
+        };
 
-        Ok(())
+        let message = match digests {
+            Ok(digests) if digests.contains(expected) => return Ok(Some(expected.clone())),
+            Ok(digests) if digests.is_empty() => {
+                format!("Pulled image {} has no registry digest", payload.image)
+            }
+            Ok(digests) => format!(
+                "Pulled image {} has digest {}, expected {}",
+                payload.image,
+                digests.join(", "),
+                expected
+            ),
+            Err(e) => format!("Failed to verify digest of {}: {}", payload.image, e),
+        };
+        error!(request_id = %request_id, "{}", message);
+        self.send_error(request_id, "DIGEST_MISMATCH", &message).await;
+        Err(anyhow!(message))
     }
 
-    /// Send a status update message
-    async fn send_status(&self, name: &str, status: &str, health: Option<String>) {
-        let msg = AgentMessage::ContainerStatus(ContainerStatusPayload {
-            container_id: String::new(),
-            name: name.to_string(),
-            status: status.to_string(),
-            health,
-            ports: vec![],
-            timestamp: chrono::Utc::now(),
-        });
+    /// Wait for a job's container to exit and report its exit code
+    ///
+    /// A job that exits non-zero fails its task, with the exit code in the
+    /// output either way.
+    async fn finish_job(
+        &self,
+        request_id: &str,
+        name: &str,
+        container_id: &str,
+        image_digest: Option<String>,
+        started_at: Instant,
+    ) -> Result<String> {
+        self.send_container_status(
+            container_id,
+            name,
+            "running",
+            None,
+            Vec::new(),
+            image_digest.clone(),
+        )
+        .await;
 
-        if let Err(e) = self.message_tx.send(msg).await {
-            warn!(error = %e, "Failed to send status update");
+        info!(request_id = %request_id, container_id = %container_id, "Waiting for job to exit");
+        let exit_code = match self.runtime.wait_container(container_id).await {
+            Ok(code) => code,
+            Err(e) => {
+                error!(request_id = %request_id, error = %e, "Failed to wait for job");
+                let message = format!("Failed to wait for job: {}", e);
+                self.send_error(request_id, "WAIT_FAILED", &message).await;
+                return Err(e.into());
+            }
+        };
+        info!(request_id = %request_id, exit_code, "Job exited");
+
+        self.send_container_status(container_id, name, "exited", None, Vec::new(), image_digest)
+            .await;
+        let output = serde_json::json!({ "container_id": container_id, "exit_code": exit_code });
+        let error = (exit_code != 0).then(|| format!("Job exited with code {}", exit_code));
+        self.send_task_result(
+            request_id,
+            exit_code == 0,
+            Some(output.to_string()),
+            error,
+            started_at.elapsed(),
+        )
+        .await;
+
+        Ok(container_id.to_string())
+    }
+
+    /// Clean up after a timed-out deployment and report the stage it was
+    /// stuck in
+    async fn handle_timeout(
+        &self,
+        payload: &DeployContainerPayload,
+        progress: DeployProgress,
+    ) -> anyhow::Error {
+        let request_id = &payload.request_id;
+        let stage = progress.stage.into_inner();
+        let message = format!(
+            "Deployment timed out after {}s while {}",
+            self.deploy_timeout.as_secs(),
+            stage
+        );
+        error!(request_id = %request_id, stage, "Deployment timed out");
+
+        self.clean_up_failed(payload, progress.created.into_inner()).await;
+
+        self.send_error(request_id, "DEPLOY_TIMEOUT", &message).await;
+        anyhow!(message)
+    }
+
+    /// Stop and remove the container a failed deployment created, unless the
+    /// payload asks to keep it for inspection
+    async fn clean_up_failed(&self, payload: &DeployContainerPayload, created: Option<String>) {
+        let request_id = &payload.request_id;
+        let Some(container_id) = created else {
+            return;
+        };
+        if !payload.on_failure_cleanup {
+            info!(
+                request_id = %request_id,
+                container_id = %container_id,
+                "Keeping container left by failed deployment for inspection"
+            );
+            return;
+        }
+
+        info!(
+            request_id = %request_id,
+            container_id = %container_id,
+            "Removing container left by failed deployment"
+        );
+        let cleanup = async {
+            let _ = self.runtime.stop_container(&container_id, Some(10)).await;
+            self.remove_container(&container_id).await
+        };
+        match tokio::time::timeout(CLEANUP_TIMEOUT, cleanup).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!(error = %e, "Failed to remove container left by failed deployment"),
+            Err(_) => warn!("Timed out removing container left by failed deployment"),
+        }
+    }
+
+    /// Apply the configured maxima to the requested resources
+    ///
+    /// Unset requests get the maximum; larger ones are clamped to it, or
+    /// rejected when limits are strict.
+    fn resolve_resources(&self, requested: Option<&ResourceSpec>) -> Result<ResourceSpec> {
+        let strict = self.limits.strict;
+        Ok(ResourceSpec {
+            memory_mb: apply_max(
+                "memory_mb",
+                requested.and_then(|r| r.memory_mb),
+                self.limits.max_memory_mb,
+                strict,
+            )?,
+            cpu_cores: apply_max(
+                "cpu_cores",
+                requested.and_then(|r| r.cpu_cores),
+                self.limits.max_cpu_cores,
+                strict,
+            )?,
+        })
+    }
+
+    /// Refuse GPU deployments unless allowed and the runtime can provide GPUs
+    async fn check_gpu_support(&self, request_id: &str) -> Result<()> {
+        if !self.allow_gpu {
+            let message = "GPU access is not allowed by this agent";
+            warn!(request_id = %request_id, "{}", message);
+            self.send_error(request_id, "GPU_DENIED", message).await;
+            bail!(message);
+        }
+
+        let message = match self.runtime.gpu_available().await {
+            Ok(true) => return Ok(()),
+            Ok(false) => "No NVIDIA container runtime is available on this host".to_string(),
+            Err(e) => format!("Could not check for GPU support: {}", e),
+        };
+        warn!(request_id = %request_id, "{}", message);
+        self.send_error(request_id, "GPU_UNSUPPORTED", &message).await;
+        bail!(message)
+    }
+
+    /// Refuse a new container once `max_containers` managed containers exist
+    ///
+    /// The container being replaced (and leftover blue-green staging and
+    /// retired containers) doesn't count, so redeploys are always allowed.
+    async fn check_container_limit(&self, name: &str) -> Result<()> {
+        let Some(max) = self.limits.max_containers else {
+            return Ok(());
+        };
+
+        let staging_name = format!("{}{}", name, STAGING_SUFFIX);
+        let retired_name = format!("{}{}", name, RETIRED_SUFFIX);
+        let managed = self
+            .runtime
+            .list_containers(true)
+            .await
+            .context("Failed to list containers")?
+            .into_iter()
+            .filter(|c| c.labels.get("syntra.managed").map(String::as_str) == Some("true"))
+            .filter(|c| c.name != name && c.name != staging_name && c.name != retired_name)
+            .count();
+
+        if managed >= max as usize {
+            bail!(
+                "Host already runs {} managed containers (max_containers is {})",
+                managed,
+                max
+            );
+        }
+        Ok(())
+    }
+
+    /// Stop and remove the old container, then start the new one
+    async fn deploy_recreate(
+        &self,
+        payload: &DeployContainerPayload,
+        progress: &DeployProgress,
+    ) -> Result<String> {
+        let request_id = &payload.request_id;
+
+        progress.enter("removing the existing container");
+        if let Some(existing) = self
+            .runtime
+            .get_container(&payload.name)
+            .await
+            .context("Failed to check existing container")?
+        {
+            self.remove_existing(request_id, &existing).await?;
+        }
+
+        let container_id = self
+            .create_and_start(
+                request_id,
+                self.container_options(payload, &payload.name),
+                &payload.secret_files,
+                progress,
+            )
+            .await?;
+
+        // Verify a service is running; a job may already have finished
+        if !payload.wait_for_exit {
+            progress.enter("waiting for the container to run");
+            if let Err(e) = self.wait_until_healthy(&container_id, None).await {
+                error!(request_id = %request_id, error = %e, "Container not running after start");
+                self.send_error(request_id, "NOT_RUNNING", &e.to_string()).await;
+                return Err(e);
+            }
+            self.send_deploy_event(request_id, progress, DeployPhase::Healthy).await;
+        }
+
+        Ok(container_id)
+    }
+
+    /// Start the new container under a temporary name and swap it in once
+    /// healthy, leaving the old container serving on failure
+    ///
+    /// The old container is renamed aside and only removed once the new one
+    /// holds the service's name. Both containers run at once, so `deploy`
+    /// rejects fixed host ports.
+    async fn deploy_blue_green(
+        &self,
+        payload: &DeployContainerPayload,
+        progress: &DeployProgress,
+    ) -> Result<String> {
+        let request_id = &payload.request_id;
+        let staging_name = format!("{}{}", payload.name, STAGING_SUFFIX);
+        let retired_name = format!("{}{}", payload.name, RETIRED_SUFFIX);
+
+        // Clear out containers left behind by an interrupted deploy
+        progress.enter("removing stale staging containers");
+        for name in [&staging_name, &retired_name] {
+            if let Some(stale) = self
+                .runtime
+                .get_container(name)
+                .await
+                .context("Failed to check staging container")?
+            {
+                self.remove_existing(request_id, &stale).await?;
+            }
+        }
+
+        let container_id = self
+            .create_and_start(
+                request_id,
+                self.container_options(payload, &staging_name),
+                &payload.secret_files,
+                progress,
+            )
+            .await?;
+
+        progress.enter("waiting for the health check");
+        info!(
+            request_id = %request_id,
+            container_id = %container_id,
+            "Waiting for new container to become healthy"
+        );
+        if let Err(e) = self
+            .wait_until_healthy(&container_id, payload.health_check.as_ref())
+            .await
+        {
+            warn!(request_id = %request_id, error = %e, "New container unhealthy, rolling back");
+            self.send_error(
+                request_id,
+                "DEPLOY_ROLLED_BACK",
+                &format!("New container failed health check, kept previous version: {}", e),
+            )
+            .await;
+            return Err(e);
+        }
+        self.send_deploy_event(request_id, progress, DeployPhase::Healthy).await;
+
+        // Swap: move the old container aside, promote the new one, then retire the old
+        progress.enter("swapping in the new container");
+        let existing = self
+            .runtime
+            .get_container(&payload.name)
+            .await
+            .context("Failed to check existing container")?;
+
+        if let Some(existing) = &existing {
+            if let Err(e) = self.runtime.rename_container(&existing.id, &retired_name).await {
+                error!(request_id = %request_id, error = %e, "Failed to rename old container");
+                self.send_error(
+                    request_id,
+                    "RENAME_FAILED",
+                    &format!("Failed to rename {} to {}: {}", payload.name, retired_name, e),
+                )
+                .await;
+                return Err(e.into());
+            }
+        }
+
+        if let Err(e) = self
+            .runtime
+            .rename_container(&container_id, &payload.name)
+            .await
+        {
+            error!(request_id = %request_id, error = %e, "Failed to rename new container");
+            match &existing {
+                Some(existing) => {
+                    if let Err(e) = self.runtime.rename_container(&existing.id, &payload.name).await
+                    {
+                        warn!(
+                            request_id = %request_id,
+                            error = %e,
+                            "Failed to restore the old container's name"
+                        );
+                    }
+                }
+                // Nothing else is serving, so leave the new container running under its
+                // staging name rather than cleaning it up
+                None => {
+                    progress.created.lock().take();
+                }
+            }
+            self.send_error(
+                request_id,
+                "RENAME_FAILED",
+                &format!("Failed to rename {} to {}: {}", staging_name, payload.name, e),
+            )
+            .await;
+            return Err(e.into());
+        }
+
+        // The new container is serving, so failing to retire the old one doesn't fail the
+        // deploy; a stale retired container is cleared by the next one
+        if let Some(existing) = existing {
+            if let Err(e) = self.remove_existing(request_id, &existing).await {
+                warn!(request_id = %request_id, error = %e, "Failed to retire old container");
+            }
+        }
+
+        Ok(container_id)
+    }
+
+    /// Remove replicas of `payload.name` beyond the requested count, so
+    /// scaling down converges however many deploys ran before
+    async fn remove_surplus_replicas(&self, payload: &DeployContainerPayload) -> Result<()> {
+        let surplus: Vec<ContainerInfo> = self
+            .runtime
+            .list_containers(true)
+            .await
+            .context("Failed to list containers")?
+            .into_iter()
+            .filter(|c| c.labels.get("syntra.managed").map(String::as_str) == Some("true"))
+            .filter(|c| is_surplus_replica(&payload.name, &c.name, payload.replicas))
+            .collect();
+
+        for container in surplus {
+            info!(
+                request_id = %payload.request_id,
+                name = %container.name,
+                "Removing surplus replica"
+            );
+            self.remove_existing(&payload.request_id, &container).await?;
+            self.send_container_status(
+                &container.id,
+                &container.name,
+                "removed",
+                None,
+                Vec::new(),
+                None,
+            )
+            .await;
+        }
+        Ok(())
+    }
+
+    /// Stop (if running) and remove an existing container
+    async fn remove_existing(&self, request_id: &str, existing: &ContainerInfo) -> Result<()> {
+        info!(
+            request_id = %request_id,
+            container_id = %existing.id,
+            "Removing existing container"
+        );
+
+        // Stop if running
+        if existing.status == ContainerStatus::Running {
+            if let Err(e) = self.runtime.stop_container(&existing.id, Some(30)).await {
+                warn!(
+                    request_id = %request_id,
+                    error = %e,
+                    "Failed to stop existing container, forcing removal"
+                );
+            }
+        }
+
+        // Remove container
+        if let Err(e) = self.remove_container(&existing.id).await {
+            error!(request_id = %request_id, error = %e, "Failed to remove existing container");
+            self.send_error(
+                request_id,
+                "REMOVE_FAILED",
+                &format!("Failed to remove existing container: {}", e),
+            )
+            .await;
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    /// Build runtime options for the payload's container under `name`
+    fn container_options(
+        &self,
+        payload: &DeployContainerPayload,
+        name: &str,
+    ) -> CreateContainerOptions {
+        let env_vars: Vec<(String, String)> = payload
+            .env
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| (e.name, e.value))
+            .collect();
+
+        let ports: Vec<PortBinding> = payload
+            .ports
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| PortBinding {
+                container_port: p.container_port,
+                host_port: Some(p.host_port),
+                host_ip: Some("0.0.0.0".to_string()),
+                protocol: p.protocol,
+            })
+            .collect();
+
+        let mounts: Vec<MountSpec> = payload
+            .volumes
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(MountSpec::from)
+            .chain(payload.mounts.clone().unwrap_or_default())
+            .collect();
+
+        let mut labels = HashMap::new();
+        labels.insert("syntra.managed".to_string(), "true".to_string());
+        labels.insert("syntra.request_id".to_string(), payload.request_id.clone());
+
+        // A deploy's own driver replaces the default; bare options tweak the default driver
+        let (log_driver, log_options) = match &payload.log_driver {
+            Some(driver) => (driver.clone(), payload.log_options.clone()),
+            None => {
+                let mut options = self.container_logs.options.clone();
+                options.extend(payload.log_options.clone());
+                (self.container_logs.driver.clone(), options)
+            }
+        };
+
+        // Services come back after a crash or reboot; jobs run once
+        let default_restart = if payload.wait_for_exit {
+            RestartPolicy::No
+        } else {
+            RestartPolicy::UnlessStopped
+        };
+
+        CreateContainerOptions {
+            name: name.to_string(),
+            image: payload.image.clone(),
+            entrypoint: payload.entrypoint.clone(),
+            command: payload.command.clone(),
+            working_dir: payload.working_dir.clone(),
+            user: payload.user.clone(),
+            env: env_vars,
+            ports,
+            mounts,
+            labels,
+            networks: payload
+                .networks
+                .clone()
+                .filter(|networks| !networks.is_empty())
+                .unwrap_or_else(|| vec![self.default_network.clone()]),
+            extra_hosts: payload.extra_hosts.clone(),
+            dns: payload.dns.clone(),
+            hostname: payload.hostname.clone(),
+            cap_add: payload.cap_add.clone(),
+            cap_drop: payload.cap_drop.clone(),
+            privileged: payload.privileged,
+            gpus: payload.gpus.clone(),
+            ulimits: payload.ulimits.clone(),
+            sysctls: payload.sysctls.clone(),
+            log_driver: Some(log_driver),
+            log_options,
+            memory_limit: payload.resources.as_ref().and_then(|r| r.memory_mb),
+            cpu_limit: payload.resources.as_ref().and_then(|r| r.cpu_cores),
+            shm_size_mb: payload.shm_size_mb,
+            restart_policy: Some(payload.restart_policy.unwrap_or(default_restart)),
+            max_restart_retries: payload.max_restart_retries,
+            registry_auth: payload.registry_auth.clone(),
+        }
+    }
+
+    /// Create volumes and write secret files, then create and start the container
+    async fn create_and_start(
+        &self,
+        request_id: &str,
+        mut options: CreateContainerOptions,
+        secret_files: &[SecretFile],
+        progress: &DeployProgress,
+    ) -> Result<String> {
+        // Ensure named volumes exist before the container references them
+        progress.enter("creating volumes");
+        if let Err(e) = self.ensure_volumes(&options.mounts).await {
+            error!(request_id = %request_id, error = %e, "Failed to create volumes");
+            self.send_error(
+                request_id,
+                "VOLUME_FAILED",
+                &format!("Failed to create volumes: {}", e),
+            )
+            .await;
+            return Err(e);
+        }
+
+        if let Err(e) = self.ensure_default_network(&options.networks).await {
+            error!(request_id = %request_id, error = %e, "Failed to create default network");
+            self.send_error(
+                request_id,
+                "NETWORK_FAILED",
+                &format!("Failed to create network {}: {}", self.default_network, e),
+            )
+            .await;
+            return Err(e);
+        }
+
+        // Write secret files for the container to mount; its label finds them for removal
+        let mut secrets_id = None;
+        if !secret_files.is_empty() {
+            progress.enter("writing secret files");
+            match self.secrets.write(secret_files) {
+                Ok((id, mounts)) => {
+                    options.labels.insert(SECRETS_LABEL.to_string(), id.clone());
+                    options.mounts.extend(mounts);
+                    secrets_id = Some(id);
+                }
+                Err(e) => {
+                    error!(request_id = %request_id, error = %e, "Failed to write secret files");
+                    let message = format!("Failed to write secret files: {}", e);
+                    self.send_error(request_id, "SECRETS_FAILED", &message).await;
+                    return Err(e);
+                }
+            }
+        }
+
+        // Create the container
+        progress.enter("creating the container");
+        info!(request_id = %request_id, name = %options.name, "Creating container");
+        let container_id = match self.runtime.create_container(options).await {
+            Ok(id) => id,
+            Err(e) => {
+                if let Some(id) = &secrets_id {
+                    self.secrets.remove(id);
+                }
+                error!(request_id = %request_id, error = %e, "Failed to create container");
+                self.send_error(
+                    request_id,
+                    "CREATE_FAILED",
+                    &format!("Failed to create container: {}", e),
+                )
+                .await;
+                return Err(e.into());
+            }
+        };
+        debug!(request_id = %request_id, container_id = %container_id, "Container created");
+        *progress.created.lock() = Some(container_id.clone());
+        self.send_deploy_event(request_id, progress, DeployPhase::Created).await;
+
+        // Start the container
+        progress.enter("starting the container");
+        info!(request_id = %request_id, container_id = %container_id, "Starting container");
+        if let Err(e) = self.runtime.start_container(&container_id).await {
+            error!(request_id = %request_id, error = %e, "Failed to start container");
+            self.send_error(
+                request_id,
+                "START_FAILED",
+                &format!("Failed to start container: {}", e),
+            )
+            .await;
+            return Err(e.into());
+        }
+        self.send_deploy_event(request_id, progress, DeployPhase::Started).await;
+
+        Ok(container_id)
+    }
+
+    /// Wait for a started container to be running and, if a health check is
+    /// given, to pass it within its retry budget
+    ///
+    /// When the image defines its own health check, the runtime's verdict
+    /// outranks the raw status: a starting container is waited on and an
+    /// unhealthy one fails, however long it stays running.
+    async fn wait_until_healthy(
+        &self,
+        container_id: &str,
+        health_check: Option<&HealthCheck>,
+    ) -> Result<()> {
+        tokio::time::sleep(self.startup_grace).await;
+
+        let container = self
+            .runtime
+            .get_container(container_id)
+            .await
+            .context("Failed to get container status")?
+            .ok_or_else(|| anyhow::anyhow!("Container not found after start"))?;
+
+        if container.status != ContainerStatus::Running {
+            bail!("Container status is {} after start", container.status);
+        }
+
+        // Bounded by the deploy timeout, like the rest of the deployment
+        let mut health = container.health;
+        while health == Some(ContainerHealth::Starting) {
+            tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+            let container = self
+                .runtime
+                .get_container(container_id)
+                .await
+                .context("Failed to get container status")?
+                .ok_or_else(|| anyhow::anyhow!("Container not found after start"))?;
+            if container.status != ContainerStatus::Running {
+                bail!("Container status is {} after start", container.status);
+            }
+            health = container.health;
+        }
+        if health == Some(ContainerHealth::Unhealthy) {
+            bail!("Container is unhealthy after start");
+        }
+
+        let Some(check) = health_check else {
+            return Ok(());
+        };
+
+        let retries = check.retries.max(1);
+        let check_timeout = Duration::from_secs(check.timeout_secs.max(1));
+        let mut last_failure = String::new();
+
+        for attempt in 1..=retries {
+            match tokio::time::timeout(
+                check_timeout,
+                self.runtime.exec_output(container_id, check.cmd.clone()),
+            )
+            .await
+            {
+                Ok(Ok((0, _))) => return Ok(()),
+                Ok(Ok((code, output))) => {
+                    last_failure = format!("exited with code {}: {}", code, output.trim());
+                }
+                Ok(Err(e)) => last_failure = e.to_string(),
+                Err(_) => last_failure = format!("timed out after {:?}", check_timeout),
+            }
+
+            debug!(
+                container_id = %container_id,
+                attempt,
+                retries,
+                failure = %last_failure,
+                "Health check failed"
+            );
+            if attempt < retries {
+                tokio::time::sleep(Duration::from_secs(check.interval_secs)).await;
+            }
+        }
+
+        bail!("Health check failed after {} attempts: {}", retries, last_failure)
+    }
+
+    /// Stop a container based on the payload from control plane
+    pub async fn stop(&self, payload: StopContainerPayload) -> Result<()> {
+        let started_at = Instant::now();
+        let request_id = payload.request_id.clone();
+        let container_id = payload.container_id.clone();
+
+        info!(
+            request_id = %request_id,
+            container_id = %container_id,
+            force = payload.force,
+            "Stopping container"
+        );
+
+        // Get container info first
+        let container = self
+            .runtime
+            .get_container(&container_id)
+            .await
+            .context("Failed to get container")?;
+
+        if container.is_none() {
+            warn!(request_id = %request_id, "Container not found");
+            self.send_error(&request_id, "NOT_FOUND", "Container not found")
+                .await;
+            return Err(anyhow::anyhow!("Container not found"));
+        }
+
+        let container = container.unwrap();
+
+        // A stopped container shouldn't be brought back by reconciliation
+        self.desired.lock().retain(|_, service| {
+            !replica_names(&service.name, service.replicas).contains(&container.name)
+        });
+
+        // Stop the container
+        if container.status == ContainerStatus::Running {
+            let stopped = match &payload.stop_signal {
+                Some(signal) => {
+                    self.stop_with_signal(&container_id, signal, payload.timeout_secs)
+                        .await
+                }
+                None => {
+                    self.runtime
+                        .stop_container(&container_id, payload.timeout_secs)
+                        .await
+                        .map_err(anyhow::Error::from)
+                }
+            };
+            if let Err(e) = stopped {
+                if payload.force {
+                    warn!(
+                        request_id = %request_id,
+                        error = %e,
+                        "Failed to stop gracefully, forcing"
+                    );
+                } else {
+                    error!(request_id = %request_id, error = %e, "Failed to stop container");
+                    self.send_error(
+                        &request_id,
+                        "STOP_FAILED",
+                        &format!("Failed to stop container: {}", e),
+                    )
+                    .await;
+                    return Err(e);
+                }
+            }
+        }
+
+        // Remove container if force is true
+        if payload.force {
+            if let Err(e) = self.remove_container(&container_id).await {
+                error!(request_id = %request_id, error = %e, "Failed to remove container");
+                self.send_error(
+                    &request_id,
+                    "REMOVE_FAILED",
+                    &format!("Failed to remove container: {}", e),
+                )
+                .await;
+                return Err(e.into());
+            }
+        }
+
+        // Send status update
+        self.send_status(&container.name, "stopped", None).await;
+        self.send_task_result(&request_id, true, None, None, started_at.elapsed())
+            .await;
+
+        info!(
+            request_id = %request_id,
+            container_id = %container_id,
+            "Container stopped successfully"
+        );
+
+        Ok(())
+    }
+
+    /// Remove a container, then the secret files its label names
+    async fn remove_container(&self, id: &str) -> Result<(), RuntimeError> {
+        let secrets_id = match self.runtime.get_container(id).await {
+            Ok(Some(container)) => container.labels.get(SECRETS_LABEL).cloned(),
+            _ => None,
+        };
+        self.runtime.remove_container(id, true).await?;
+        if let Some(secrets_id) = secrets_id {
+            self.secrets.remove(&secrets_id);
+        }
+        Ok(())
+    }
+
+    /// Create any named volumes referenced by the mounts that don't exist yet
+    ///
+    /// Bind sources that aren't paths are named volumes too, as Docker treats them.
+    async fn ensure_volumes(&self, mounts: &[MountSpec]) -> Result<()> {
+        let named: Vec<&str> = mounts
+            .iter()
+            .filter_map(|mount| match mount {
+                MountSpec::Volume { name, .. } => Some(name.as_str()),
+                MountSpec::Bind { source, .. } if is_named_volume(source) => Some(source.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if named.is_empty() {
+            return Ok(());
+        }
+
+        let existing: Vec<String> = self
+            .runtime
+            .list_volumes()
+            .await
+            .context("Failed to list volumes")?
+            .into_iter()
+            .map(|v| v.name)
+            .collect();
+
+        for name in named {
+            if existing.iter().any(|v| v == name) {
+                continue;
+            }
+
+            let mut labels = HashMap::new();
+            labels.insert("syntra.managed".to_string(), "true".to_string());
+            self.runtime.create_volume(name, labels).await?;
+            debug!(volume = %name, "Named volume created");
+        }
+
+        Ok(())
+    }
+
+    /// Create the default network if the container joins it and it's missing
+    async fn ensure_default_network(&self, networks: &[String]) -> Result<()> {
+        if !networks.contains(&self.default_network) {
+            return Ok(());
+        }
+
+        let existing = match self.runtime.inspect_network(&self.default_network).await {
+            Ok(existing) => existing,
+            Err(RuntimeError::Unsupported { .. }) => return Ok(()),
+            Err(e) => return Err(e).context("Failed to inspect the default network"),
+        };
+
+        if existing.is_none() {
+            self.runtime
+                .create_network(CreateNetworkOptions::bridge(&self.default_network))
+                .await?;
+            info!(network = %self.default_network, "Default network created");
+        }
+        Ok(())
+    }
+
+    /// Send `signal`, then SIGKILL if the container is still running after the timeout
+    async fn stop_with_signal(
+        &self,
+        container_id: &str,
+        signal: &str,
+        timeout_secs: Option<u64>,
+    ) -> Result<()> {
+        self.runtime.kill_container(container_id, signal).await?;
+
+        let grace = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_STOP_TIMEOUT_SECS));
+        let exited = tokio::time::timeout(grace, async {
+            loop {
+                match self.runtime.get_container(container_id).await {
+                    Ok(Some(c)) if c.status == ContainerStatus::Running => {}
+                    _ => return,
+                }
+                tokio::time::sleep(STOP_POLL_INTERVAL).await;
+            }
+        })
+        .await;
+
+        if exited.is_err() {
+            warn!(
+                container_id = %container_id,
+                signal = %signal,
+                "Container ignored stop signal, sending SIGKILL"
+            );
+            self.runtime.kill_container(container_id, "SIGKILL").await?;
+        }
+        Ok(())
+    }
+
+    /// Send a status update message
+    async fn send_status(&self, name: &str, status: &str, health: Option<String>) {
+        let msg = AgentMessage::ContainerStatus(ContainerStatusPayload {
+            container_id: String::new(),
+            name: name.to_string(),
+            status: status.to_string(),
+            health,
+            started_at: None,
+            ports: vec![],
+            image_digest: None,
+            timestamp: chrono::Utc::now(),
+            message_id: None,
+        });
+
+        if let Err(e) = channel::send(&self.message_tx, msg).await {
+            warn!(error = %e, "Failed to send status update");
+        }
+    }
+
+    /// Send a container status update with full details
+    async fn send_container_status(
+        &self,
+        container_id: &str,
+        name: &str,
+        status: &str,
+        container: Option<&ContainerInfo>,
+        ports: Vec<PortMapping>,
+        image_digest: Option<String>,
+    ) {
+        let msg = AgentMessage::ContainerStatus(ContainerStatusPayload {
+            container_id: container_id.to_string(),
+            name: name.to_string(),
+            status: status.to_string(),
+            health: container.and_then(|c| c.health).map(|h| h.to_string()),
+            started_at: container.and_then(|c| c.started_at.clone()),
+            ports,
+            image_digest,
+            timestamp: chrono::Utc::now(),
+            message_id: None,
+        });
+
+        if let Err(e) = channel::send(&self.message_tx, msg).await {
+            warn!(error = %e, "Failed to send container status");
+        }
+    }
+
+    /// Report a step of a deployment's timeline, finer grained than the
+    /// container status updates
+    async fn send_deploy_event(
+        &self,
+        request_id: &str,
+        progress: &DeployProgress,
+        phase: DeployPhase,
+    ) {
+        let msg = AgentMessage::DeployEvent(DeployEventPayload {
+            request_id: request_id.to_string(),
+            name: progress.name.clone(),
+            phase,
+            timestamp: chrono::Utc::now(),
+        });
+
+        if let Err(e) = channel::send(&self.message_tx, msg).await {
+            warn!(error = %e, "Failed to send deploy event");
+        }
+    }
+
+    /// Send an error message
+    async fn send_error(&self, request_id: &str, code: &str, message: &str) {
+        let msg = AgentMessage::Error(ErrorPayload {
+            code: code.to_string(),
+            message: message.to_string(),
+            details: Some(serde_json::json!({ "request_id": request_id })),
+            timestamp: chrono::Utc::now(),
+        });
+
+        if let Err(e) = channel::send(&self.message_tx, msg).await {
+            warn!(error = %e, "Failed to send error message");
+        }
+    }
+
+    /// Send a task result message
+    async fn send_task_result(
+        &self,
+        task_id: &str,
+        success: bool,
+        output: Option<String>,
+        error: Option<String>,
+        duration: Duration,
+    ) {
+        let result = TaskResultPayload {
+            task_id: task_id.to_string(),
+            agent_id: String::new(), // Will be filled by WebSocket client
+            success,
+            output,
+            error,
+            duration_ms: duration.as_millis() as u64,
+            timestamp: chrono::Utc::now(),
+            message_id: None,
+        };
+        self.completed.lock().insert(result.clone());
+
+        if let Err(e) = channel::send(&self.message_tx, AgentMessage::TaskResult(result)).await {
+            warn!(error = %e, "Failed to send task result");
+        }
+    }
+}
+
+/// Hold a requested limit to a maximum, clamping it unless `strict`
+fn apply_max<T: PartialOrd + Copy + std::fmt::Display>(
+    name: &str,
+    requested: Option<T>,
+    max: Option<T>,
+    strict: bool,
+) -> Result<Option<T>> {
+    match (requested, max) {
+        (Some(requested), Some(max)) if requested > max => {
+            if strict {
+                bail!("Requested {} {} exceeds the host limit of {}", name, requested, max);
+            }
+            warn!(limit = name, requested = %requested, max = %max, "Clamping resource request");
+            Ok(Some(max))
+        }
+        (None, Some(max)) => Ok(Some(max)),
+        (requested, _) => Ok(requested),
+    }
+}
+
+/// Error code reported for a failed pull, so the control plane can tell bad
+/// credentials and an unreachable runtime apart from other failures
+fn pull_error_code(error: &RuntimeError) -> &'static str {
+    match error {
+        RuntimeError::Unauthorized { .. } => "PULL_UNAUTHORIZED",
+        RuntimeError::DaemonUnavailable { .. } => "RUNTIME_UNAVAILABLE",
+        _ => "PULL_FAILED",
+    }
+}
+
+/// Reference `image` by `digest` in place of any tag, e.g. `nginx@sha256:...`
+fn pinned_reference(image: &str, digest: &str) -> String {
+    let name = image.split_once('@').map_or(image, |(name, _)| name);
+    // A colon after the last slash is a tag, not a registry port
+    let repository = match name.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => name,
+    };
+    format!("{}@{}", repository, digest)
+}
+
+/// Name of the `index`th replica of a service
+fn replica_name(service: &str, index: u32) -> String {
+    format!("{}-{}", service, index)
+}
+
+/// Names of the containers a service with `replicas` replicas runs
+pub fn replica_names(service: &str, replicas: u32) -> Vec<String> {
+    match replicas {
+        1 => vec![service.to_string()],
+        _ => (1..=replicas).map(|index| replica_name(service, index)).collect(),
+    }
+}
+
+/// Whether `name` is a replica of `service` that shouldn't exist with
+/// `replicas` requested: the plain name unless there's exactly one, and
+/// numbered replicas outside `1..=replicas` (all of them for a single one)
+fn is_surplus_replica(service: &str, name: &str, replicas: u32) -> bool {
+    if name == service {
+        return replicas != 1;
+    }
+    match name
+        .strip_prefix(service)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .and_then(|index| index.parse::<u32>().ok())
+    {
+        Some(index) => replicas == 1 || index == 0 || index > replicas,
+        None => false,
+    }
+}
+
+/// Whether a volume source refers to a named volume rather than a host path
+fn is_named_volume(source: &str) -> bool {
+    !source.is_empty()
+        && !source.starts_with('/')
+        && !source.starts_with('.')
+        && !source.starts_with('~')
+}
+
+/// Deploy payloads shared by the agent tests
+#[cfg(test)]
+pub(crate) mod test_payloads {
+    use super::*;
+
+    /// A single-replica service named `app` with a passing health check
+    pub fn payload(image: &str, strategy: DeployStrategy) -> DeployContainerPayload {
+        // Every payload is a new request; resending one means reusing it
+        DeployContainerPayload {
+            request_id: format!("req-{}", uuid::Uuid::new_v4()),
+            image: image.to_string(),
+            image_digest: None,
+            force_pull: None,
+            name: "app".to_string(),
+            entrypoint: None,
+            command: None,
+            working_dir: None,
+            user: None,
+            env: None,
+            ports: None,
+            volumes: None,
+            mounts: None,
+            secret_files: Vec::new(),
+            networks: None,
+            extra_hosts: Vec::new(),
+            dns: Vec::new(),
+            hostname: None,
+            cap_add: Vec::new(),
+            cap_drop: Vec::new(),
+            privileged: false,
+            gpus: None,
+            ulimits: Vec::new(),
+            sysctls: HashMap::new(),
+            shm_size_mb: None,
+            log_driver: None,
+            log_options: HashMap::new(),
+            resources: None,
+            health_check: Some(HealthCheck {
+                cmd: vec!["true".to_string()],
+                interval_secs: 0,
+                timeout_secs: 1,
+                retries: 2,
+            }),
+            registry_auth: None,
+            strategy,
+            restart_policy: None,
+            max_restart_retries: None,
+            wait_for_exit: false,
+            on_failure_cleanup: true,
+            replicas: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_payloads::payload;
+    use super::*;
+
+    #[test]
+    fn test_is_named_volume() {
+        assert!(is_named_volume("pgdata"));
+        assert!(!is_named_volume("/var/lib/data"));
+        assert!(!is_named_volume("./data"));
+        assert!(!is_named_volume(""));
+    }
+
+    #[test]
+    fn test_is_surplus_replica() {
+        assert!(!is_surplus_replica("app", "app", 1));
+        assert!(is_surplus_replica("app", "app-1", 1));
+        assert!(is_surplus_replica("app", "app", 2));
+        assert!(!is_surplus_replica("app", "app-2", 2));
+        assert!(is_surplus_replica("app", "app-3", 2));
+        assert!(is_surplus_replica("app", "app-0", 2));
+        assert!(is_surplus_replica("app", "app", 0));
+        assert!(!is_surplus_replica("app", "app-syntra-next", 2));
+        assert!(!is_surplus_replica("app", "application-1", 2));
+        assert!(!is_surplus_replica("app", "web-1", 2));
+    }
+
+    #[test]
+    fn test_pinned_reference_replaces_tag() {
+        assert_eq!(pinned_reference("nginx:1.25", "sha256:abc"), "nginx@sha256:abc");
+        assert_eq!(
+            pinned_reference("registry:5000/team/app", "sha256:abc"),
+            "registry:5000/team/app@sha256:abc"
+        );
+        assert_eq!(
+            pinned_reference("registry:5000/app:v2@sha256:old", "sha256:abc"),
+            "registry:5000/app@sha256:abc"
+        );
+    }
+
+    #[test]
+    fn test_pull_error_code() {
+        let unauthorized = RuntimeError::unauthorized("nginx", "pull access denied");
+        assert_eq!(pull_error_code(&unauthorized), "PULL_UNAUTHORIZED");
+        let unavailable = RuntimeError::daemon_unavailable("connection refused");
+        assert_eq!(pull_error_code(&unavailable), "RUNTIME_UNAVAILABLE");
+        let failed = RuntimeError::image_pull_failed("nginx:nope", "manifest unknown");
+        assert_eq!(pull_error_code(&failed), "PULL_FAILED");
+    }
+
+    use crate::runtime::adapter::{GpuSpec, Ulimit};
+    use crate::runtime::mock::MockRuntimeAdapter;
+
+    fn handler(
+        runtime: &Arc<MockRuntimeAdapter>,
+    ) -> (DeployHandler<MockRuntimeAdapter>, mpsc::Receiver<AgentMessage>) {
+        handler_with_limits(runtime, ResourceLimits::default())
+    }
+
+    fn handler_with_limits(
+        runtime: &Arc<MockRuntimeAdapter>,
+        limits: ResourceLimits,
+    ) -> (DeployHandler<MockRuntimeAdapter>, mpsc::Receiver<AgentMessage>) {
+        let (tx, rx) = mpsc::channel(64);
+        let config = RuntimeConfig {
+            resource_limits: limits,
+            ..RuntimeConfig::default()
+        };
+        let handler =
+            DeployHandler::new(runtime.clone(), &config, tx).with_startup_grace(Duration::ZERO);
+        (handler, rx)
+    }
+
+    fn statuses(rx: &mut mpsc::Receiver<AgentMessage>) -> Vec<String> {
+        let mut statuses = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            if let AgentMessage::ContainerStatus(s) = msg {
+                statuses.push(s.status);
+            }
+        }
+        statuses
+    }
+
+    fn error_codes(rx: &mut mpsc::Receiver<AgentMessage>) -> Vec<String> {
+        let mut codes = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            if let AgentMessage::Error(e) = msg {
+                codes.push(e.code);
+            }
+        }
+        codes
+    }
+
+    #[tokio::test]
+    async fn test_recreate_replaces_container() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let old_id = runtime.add_running("app", "nginx:1.24");
+        let (handler, _rx) = handler(&runtime);
+
+        let new_id = handler
+            .deploy(payload("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .unwrap();
+
+        assert_ne!(new_id, old_id);
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].image, "nginx:1.25");
+    }
+
+    #[tokio::test]
+    async fn test_deploy_pulls_creates_and_starts() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+
+        let id = handler
+            .deploy(payload("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .unwrap();
+
+        let calls: Vec<&str> = runtime
+            .calls()
+            .into_iter()
+            .filter(|call| ["pull_image", "create_container", "start_container"].contains(call))
+            .collect();
+        assert_eq!(calls, vec!["pull_image", "create_container", "start_container"]);
+
+        let mut statuses = Vec::new();
+        let mut result = None;
+        while let Ok(msg) = rx.try_recv() {
+            match msg {
+                AgentMessage::ContainerStatus(s) => statuses.push(s.status),
+                AgentMessage::TaskResult(r) => result = Some(r),
+                _ => {}
+            }
         }
+        assert_eq!(statuses, vec!["deploying", "running"]);
+        let result = result.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, Some(id));
     }
 
-    /// Send a container status update with full details
-    async fn send_container_status(
-        &self,
-        container_id: &str,
-        name: &str,
-        status: &str,
-        ports: Vec<PortMapping>,
-    ) {
-        let msg = AgentMessage::ContainerStatus(ContainerStatusPayload {
-            container_id: container_id.to_string(),
-            name: name.to_string(),
-            status: status.to_string(),
-            health: None,
-            ports,
-            timestamp: chrono::Utc::now(),
-        });
+    #[tokio::test]
+    async fn test_resent_deploy_resends_result_without_redeploying() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
 
-        if let Err(e) = self.message_tx.send(msg).await {
-            warn!(error = %e, "Failed to send container status");
+        let request = payload("nginx:1.25", DeployStrategy::Recreate);
+        let first = handler.deploy(request.clone()).await.unwrap();
+        let second = handler.deploy(request).await.unwrap();
+
+        assert_eq!(first, second);
+        let creates = runtime.calls().iter().filter(|call| **call == "create_container").count();
+        assert_eq!(creates, 1);
+
+        let mut results = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            if let AgentMessage::TaskResult(r) = msg {
+                results.push(r);
+            }
         }
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.success && r.output.as_deref() == Some(&*first)));
+
+        // A new request for the same service is deployed as usual
+        let next = payload("nginx:1.26", DeployStrategy::Recreate);
+        assert_ne!(handler.deploy(next).await.unwrap(), first);
     }
 
-    /// Send an error message
-    async fn send_error(&self, request_id: &str, code: &str, message: &str) {
-        let msg = AgentMessage::Error(ErrorPayload {
-            code: code.to_string(),
-            message: message.to_string(),
-            details: Some(serde_json::json!({ "request_id": request_id })),
-            timestamp: chrono::Utc::now(),
+    #[tokio::test]
+    async fn test_deploy_reports_pull_failure() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let old_id = runtime.add_running("app", "nginx:1.24");
+        runtime.set_fails("pull_image", true);
+        let (handler, mut rx) = handler(&runtime);
+
+        assert!(handler.deploy(payload("nginx:1.25", DeployStrategy::Recreate)).await.is_err());
+
+        assert_eq!(error_codes(&mut rx), vec!["PULL_FAILED"]);
+        assert!(!runtime.calls().contains(&"create_container"));
+        // The running container is untouched
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, old_id);
+        assert_eq!(containers[0].status, ContainerStatus::Running);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_reports_container_not_running_after_start() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.queue_statuses("app", [ContainerStatus::Exited]);
+        let (handler, mut rx) = handler(&runtime);
+
+        let err = handler
+            .deploy(payload("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("exited after start"));
+        assert_eq!(error_codes(&mut rx), vec!["NOT_RUNNING"]);
+        assert!(runtime.containers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deploy_waits_for_runtime_health() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.queue_health("app", [ContainerHealth::Starting, ContainerHealth::Healthy]);
+        let (handler, mut rx) = handler(&runtime);
+
+        handler
+            .deploy(payload("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .unwrap();
+
+        let mut running = None;
+        while let Ok(msg) = rx.try_recv() {
+            if let AgentMessage::ContainerStatus(s) = msg {
+                if s.status == "running" {
+                    running = Some(s);
+                }
+            }
+        }
+        let running = running.unwrap();
+        assert_eq!(running.health.as_deref(), Some("healthy"));
+        assert!(running.started_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_deploy_fails_when_runtime_reports_unhealthy() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.queue_health("app", [ContainerHealth::Starting, ContainerHealth::Unhealthy]);
+        let (handler, mut rx) = handler(&runtime);
+
+        let err = handler
+            .deploy(payload("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("unhealthy"));
+        assert_eq!(error_codes(&mut rx), vec!["NOT_RUNNING"]);
+        assert!(runtime.containers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_blue_green_swaps_after_healthy() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.add_running("app", "nginx:1.24");
+        let (handler, _rx) = handler(&runtime);
+
+        let new_id = handler
+            .deploy(payload("nginx:1.25", DeployStrategy::BlueGreen))
+            .await
+            .unwrap();
+
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, new_id);
+        assert_eq!(containers[0].name, "app");
+        assert_eq!(containers[0].image, "nginx:1.25");
+    }
+
+    #[tokio::test]
+    async fn test_blue_green_rolls_back_when_unhealthy() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let old_id = runtime.add_running("app", "nginx:1.24");
+        runtime.set_exec_exit_code(1);
+        let (handler, mut rx) = handler(&runtime);
+
+        assert!(handler.deploy(payload("nginx:1.25", DeployStrategy::BlueGreen)).await.is_err());
+
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, old_id);
+        assert_eq!(containers[0].status, ContainerStatus::Running);
+        assert_eq!(error_codes(&mut rx), vec!["DEPLOY_ROLLED_BACK"]);
+    }
+
+    #[tokio::test]
+    async fn test_blue_green_rejects_host_ports() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+        let with_port = |strategy| DeployContainerPayload {
+            ports: Some(vec![PortMapping {
+                container_port: 80,
+                host_port: 8080,
+                protocol: "tcp".to_string(),
+            }]),
+            ..payload("nginx:1.25", strategy)
+        };
+        let old_id = handler.deploy(with_port(DeployStrategy::Recreate)).await.unwrap();
+        while rx.try_recv().is_ok() {}
+
+        let err = handler
+            .deploy(with_port(DeployStrategy::BlueGreen))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("host port 8080"));
+        assert_eq!(error_codes(&mut rx), vec!["INVALID_STRATEGY"]);
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, old_id);
+        assert_eq!(containers[0].status, ContainerStatus::Running);
+
+        // Recreate frees the port before the new container binds it
+        handler.deploy(with_port(DeployStrategy::Recreate)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_deploy_queues_beyond_limit() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let permits = Arc::new(Semaphore::new(1));
+        let (handler, mut rx) = handler(&runtime);
+        let handler = Arc::new(handler.with_deploy_limit(permits.clone()));
+
+        // Occupy the only slot so the next deploy has to wait
+        let held = permits.clone().acquire_owned().await.unwrap();
+        let task = tokio::spawn({
+            let handler = handler.clone();
+            async move { handler.deploy(payload("nginx:1.25", DeployStrategy::Recreate)).await }
         });
 
-        if let Err(e) = self.message_tx.send(msg).await {
-            warn!(error = %e, "Failed to send error message");
+        let queued = rx.recv().await.unwrap();
+        assert!(matches!(queued, AgentMessage::ContainerStatus(ref s) if s.status == "queued"));
+        assert!(runtime.containers().is_empty());
+
+        drop(held);
+        task.await.unwrap().unwrap();
+        assert_eq!(statuses(&mut rx), vec!["deploying", "running"]);
+    }
+
+    #[test]
+    fn test_resources_clamped_to_limits() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let limits = ResourceLimits {
+            max_memory_mb: Some(512),
+            max_cpu_cores: Some(1.0),
+            ..ResourceLimits::default()
+        };
+        let (handler, _rx) = handler_with_limits(&runtime, limits.clone());
+
+        let resources = handler
+            .resolve_resources(Some(&ResourceSpec {
+                memory_mb: Some(2048),
+                cpu_cores: Some(0.5),
+            }))
+            .unwrap();
+        assert_eq!(resources.memory_mb, Some(512));
+        assert_eq!(resources.cpu_cores, Some(0.5));
+
+        // Unset requests are held to the maxima too
+        let resources = handler.resolve_resources(None).unwrap();
+        assert_eq!(resources.memory_mb, Some(512));
+        assert_eq!(resources.cpu_cores, Some(1.0));
+
+        let (strict, _rx) = handler_with_limits(
+            &runtime,
+            ResourceLimits {
+                strict: true,
+                ..limits
+            },
+        );
+        let err = strict
+            .resolve_resources(Some(&ResourceSpec {
+                memory_mb: Some(2048),
+                cpu_cores: None,
+            }))
+            .unwrap_err();
+        assert!(err.to_string().contains("memory_mb 2048"));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_rejected_at_max_containers() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let limits = ResourceLimits {
+            max_containers: Some(1),
+            ..ResourceLimits::default()
+        };
+        let (handler, mut rx) = handler_with_limits(&runtime, limits);
+
+        let mut web = payload("nginx:1.25", DeployStrategy::Recreate);
+        web.name = "web".to_string();
+        handler.deploy(web).await.unwrap();
+
+        // Redeploying the existing container is still allowed
+        let mut web = payload("nginx:1.26", DeployStrategy::Recreate);
+        web.name = "web".to_string();
+        handler.deploy(web).await.unwrap();
+        error_codes(&mut rx);
+
+        assert!(handler.deploy(payload("nginx:1.25", DeployStrategy::Recreate)).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["LIMIT_EXCEEDED"]);
+        assert_eq!(runtime.containers().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_times_out_on_stalled_pull() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_pull_hangs(true);
+        let (handler, mut rx) = handler(&runtime);
+        let handler = handler.with_deploy_timeout(Duration::from_millis(50));
+
+        let err = handler
+            .deploy(payload("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("while pulling the image"));
+
+        let mut errors = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            if let AgentMessage::Error(e) = msg {
+                errors.push((e.code, e.message));
+            }
         }
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "DEPLOY_TIMEOUT");
+        assert!(errors[0].1.contains("pulling the image"));
+        assert!(runtime.containers().is_empty());
     }
 
-    /// Send a task result message
-    async fn send_task_result(
-        &self,
-        task_id: &str,
-        success: bool,
-        output: Option<String>,
-        error: Option<String>,
-    ) {
-        let msg = AgentMessage::TaskResult(TaskResultPayload {
-            task_id: task_id.to_string(),
-            agent_id: String::new(), // Will be filled by WebSocket client
-            success,
-            output,
-            error,
-            duration_ms: 0,
-            timestamp: chrono::Utc::now(),
+    /// (name, status) of each ContainerStatus sent
+    fn named_statuses(rx: &mut mpsc::Receiver<AgentMessage>) -> Vec<(String, String)> {
+        let mut statuses = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            if let AgentMessage::ContainerStatus(s) = msg {
+                statuses.push((s.name, s.status));
+            }
+        }
+        statuses
+    }
+
+    fn container_names(runtime: &MockRuntimeAdapter) -> Vec<String> {
+        let mut names: Vec<String> = runtime.containers().into_iter().map(|c| c.name).collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_replicas_scale_up_and_down() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+
+        let scaled = |replicas| DeployContainerPayload {
+            replicas,
+            ..payload("nginx:1.25", DeployStrategy::Recreate)
+        };
+        handler.deploy(scaled(1)).await.unwrap();
+        assert_eq!(container_names(&runtime), vec!["app"]);
+        named_statuses(&mut rx);
+
+        let ids = handler.deploy(scaled(3)).await.unwrap();
+        assert_eq!(ids.split(',').count(), 3);
+        assert_eq!(container_names(&runtime), vec!["app-1", "app-2", "app-3"]);
+        let statuses = named_statuses(&mut rx);
+        for name in ["app-1", "app-2", "app-3"] {
+            assert!(statuses.contains(&(name.to_string(), "running".to_string())));
+        }
+        assert!(statuses.contains(&("app".to_string(), "removed".to_string())));
+
+        // Repeating the deploy converges on the same replicas
+        handler.deploy(scaled(3)).await.unwrap();
+        assert_eq!(container_names(&runtime), vec!["app-1", "app-2", "app-3"]);
+
+        handler.deploy(scaled(2)).await.unwrap();
+        assert_eq!(container_names(&runtime), vec!["app-1", "app-2"]);
+
+        handler.deploy(scaled(1)).await.unwrap();
+        assert_eq!(container_names(&runtime), vec!["app"]);
+
+        handler.deploy(scaled(0)).await.unwrap();
+        assert!(runtime.containers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_job_rejects_replicas() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+
+        let job = DeployContainerPayload {
+            wait_for_exit: true,
+            replicas: 2,
+            ..payload("migrate:v1", DeployStrategy::Recreate)
+        };
+        assert!(handler.deploy(job).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["INVALID_REPLICAS"]);
+        assert!(runtime.containers().is_empty());
+    }
+
+    fn kept_on_failure(image: &str, strategy: DeployStrategy) -> DeployContainerPayload {
+        DeployContainerPayload {
+            on_failure_cleanup: false,
+            ..payload(image, strategy)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_start_removes_container_unless_kept() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_fails("start_container", true);
+        let (handler, mut rx) = handler(&runtime);
+
+        assert!(handler.deploy(payload("nginx:1.25", DeployStrategy::Recreate)).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["START_FAILED"]);
+        assert!(runtime.containers().is_empty());
+
+        assert!(handler
+            .deploy(kept_on_failure("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .is_err());
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].status, ContainerStatus::Created);
+    }
+
+    #[tokio::test]
+    async fn test_exited_container_removed_unless_kept() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_exits_on_start(true);
+        let (handler, mut rx) = handler(&runtime);
+
+        assert!(handler.deploy(payload("nginx:1.25", DeployStrategy::Recreate)).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["NOT_RUNNING"]);
+        assert!(runtime.containers().is_empty());
+
+        assert!(handler
+            .deploy(kept_on_failure("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .is_err());
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].status, ContainerStatus::Exited);
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_staging_container_kept_on_request() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let old_id = runtime.add_running("app", "nginx:1.24");
+        runtime.set_exec_exit_code(1);
+        let (handler, mut rx) = handler(&runtime);
+
+        assert!(handler
+            .deploy(kept_on_failure("nginx:1.25", DeployStrategy::BlueGreen))
+            .await
+            .is_err());
+        assert_eq!(error_codes(&mut rx), vec!["DEPLOY_ROLLED_BACK"]);
+
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[0].id, old_id);
+        assert_eq!(containers[1].name, format!("app{}", STAGING_SUFFIX));
+    }
+
+    #[tokio::test]
+    async fn test_failed_rename_keeps_staging_container_running() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_fails("rename_container", true);
+        let (handler, mut rx) = handler(&runtime);
+
+        assert!(handler.deploy(payload("nginx:1.25", DeployStrategy::BlueGreen)).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["RENAME_FAILED"]);
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].name, format!("app{}", STAGING_SUFFIX));
+        assert_eq!(containers[0].status, ContainerStatus::Running);
+    }
+
+    #[tokio::test]
+    async fn test_failed_rename_keeps_old_container_serving() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let old_id = runtime.add_running("app", "nginx:1.24");
+        runtime.refuse_rename(&format!("app{}", STAGING_SUFFIX));
+        let (handler, mut rx) = handler(&runtime);
+
+        // The old container is moved aside, then given its name back
+        assert!(handler.deploy(payload("nginx:1.25", DeployStrategy::BlueGreen)).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["RENAME_FAILED"]);
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, old_id);
+        assert_eq!(containers[0].name, "app");
+        assert_eq!(containers[0].status, ContainerStatus::Running);
+    }
+
+    #[tokio::test]
+    async fn test_blue_green_rejected_without_rename() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let old_id = runtime.add_running("app", "nginx:1.24");
+        runtime.set_unsupported("rename");
+        let (handler, mut rx) = handler(&runtime);
+
+        assert!(handler.deploy(payload("nginx:1.25", DeployStrategy::BlueGreen)).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["INVALID_STRATEGY"]);
+        let containers = runtime.containers();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, old_id);
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_wait_removes_container() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_fails("wait_container", true);
+        let (handler, mut rx) = handler(&runtime);
+
+        let mut job = payload("migrate:v1", DeployStrategy::Recreate);
+        job.wait_for_exit = true;
+        assert!(handler.deploy(job).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["WAIT_FAILED"]);
+        assert!(runtime.containers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_deploy_keeps_container_on_request() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_exec_exit_code(1);
+        let (handler, mut rx) = handler(&runtime);
+        let handler = handler.with_deploy_timeout(Duration::from_millis(50));
+
+        let mut slow = kept_on_failure("nginx:1.25", DeployStrategy::BlueGreen);
+        slow.health_check = Some(HealthCheck {
+            cmd: vec!["false".to_string()],
+            interval_secs: 60,
+            timeout_secs: 1,
+            retries: 2,
         });
+        assert!(handler.deploy(slow).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["DEPLOY_TIMEOUT"]);
+        assert_eq!(runtime.containers().len(), 1);
+    }
 
-        if let Err(e) = self.message_tx.send(msg).await {
-            warn!(error = %e, "Failed to send task result");
+    fn running_digest(rx: &mut mpsc::Receiver<AgentMessage>) -> Option<String> {
+        let mut digest = None;
+        while let Ok(msg) = rx.try_recv() {
+            if let AgentMessage::ContainerStatus(s) = msg {
+                if s.status == "running" {
+                    digest = s.image_digest;
+                }
+            }
         }
+        digest
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_deploy_reports_resolved_digest() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_image_digest("nginx:1.25", "sha256:resolved");
+        let (handler, mut rx) = handler(&runtime);
+
+        handler
+            .deploy(payload("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .unwrap();
+        assert_eq!(running_digest(&mut rx).as_deref(), Some("sha256:resolved"));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_pins_and_verifies_digest() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+
+        let mut pinned = payload("nginx:1.25", DeployStrategy::Recreate);
+        pinned.image_digest = Some("sha256:abc".to_string());
+        handler.deploy(pinned).await.unwrap();
+        assert_eq!(runtime.containers()[0].image, "nginx@sha256:abc");
+        assert_eq!(running_digest(&mut rx).as_deref(), Some("sha256:abc"));
+
+        runtime.set_image_digest("nginx@sha256:def", "sha256:tampered");
+        let mut pinned = payload("nginx:1.25", DeployStrategy::Recreate);
+        pinned.image_digest = Some("sha256:def".to_string());
+        assert!(handler.deploy(pinned).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["DIGEST_MISMATCH"]);
+        assert_eq!(runtime.containers()[0].image, "nginx@sha256:abc");
+    }
+
+    #[tokio::test]
+    async fn test_job_reports_exit_code() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_exit_code(3);
+        let (handler, mut rx) = handler(&runtime);
+
+        let mut job = payload("migrate:v1", DeployStrategy::BlueGreen);
+        job.wait_for_exit = true;
+        let options = handler.container_options(&job, "app");
+        assert_eq!(options.restart_policy, Some(RestartPolicy::No));
+
+        let id = handler.deploy(job).await.unwrap();
+        let mut statuses = Vec::new();
+        let mut result = None;
+        while let Ok(msg) = rx.try_recv() {
+            match msg {
+                AgentMessage::ContainerStatus(s) => statuses.push(s.status),
+                AgentMessage::TaskResult(r) => result = Some(r),
+                _ => {}
+            }
+        }
+        assert_eq!(statuses, vec!["deploying", "running", "exited"]);
+
+        let result = result.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error.as_deref(), Some("Job exited with code 3"));
+        let output: serde_json::Value = serde_json::from_str(&result.output.unwrap()).unwrap();
+        assert_eq!(output, serde_json::json!({ "container_id": id, "exit_code": 3 }));
+        // Jobs skip blue-green staging and run under their own name
+        assert_eq!(runtime.get_container("app").await.unwrap().unwrap().id, id);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_skips_pull_of_present_pinned_image() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.add_image("nginx@sha256:abc");
+        runtime.add_image("nginx:latest");
+        let (handler, _rx) = handler(&runtime);
+
+        let mut pinned = payload("nginx:1.25", DeployStrategy::Recreate);
+        pinned.image_digest = Some("sha256:abc".to_string());
+        handler.deploy(pinned.clone()).await.unwrap();
+        assert!(runtime.pulls().is_empty());
+
+        // Tags are pulled again by default, since they may have moved
+        handler
+            .deploy(payload("nginx:latest", DeployStrategy::Recreate))
+            .await
+            .unwrap();
+        assert_eq!(runtime.pulls(), vec!["nginx:latest"]);
+
+        let mut not_forced = payload("nginx:latest", DeployStrategy::Recreate);
+        not_forced.force_pull = Some(false);
+        handler.deploy(not_forced).await.unwrap();
+        assert_eq!(runtime.pulls(), vec!["nginx:latest"]);
+
+        // A new request, since a resent one is answered without deploying
+        pinned.request_id = "req-forced".to_string();
+        pinned.force_pull = Some(true);
+        handler.deploy(pinned).await.unwrap();
+        assert_eq!(runtime.pulls(), vec!["nginx:latest", "nginx@sha256:abc"]);
+    }
+
+    fn stop_payload(container_id: &str, signal: &str) -> StopContainerPayload {
+        StopContainerPayload {
+            request_id: "req-stop".to_string(),
+            container_id: container_id.to_string(),
+            force: false,
+            timeout_secs: Some(0),
+            stop_signal: Some(signal.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stop_signal_escalates_to_sigkill() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, _rx) = handler(&runtime);
+
+        let graceful = runtime.add_running("worker", "app:v1");
+        handler.stop(stop_payload(&graceful, "SIGQUIT")).await.unwrap();
+        assert_eq!(runtime.signals(), vec![(graceful.clone(), "SIGQUIT".to_string())]);
+
+        runtime.set_ignores_signals(true);
+        let hung = runtime.add_running("hung", "app:v1");
+        handler.stop(stop_payload(&hung, "SIGQUIT")).await.unwrap();
+        assert_eq!(
+            runtime.signals()[1..],
+            [(hung.clone(), "SIGQUIT".to_string()), (hung.clone(), "SIGKILL".to_string())]
+        );
+        let container = runtime.get_container(&hung).await.unwrap().unwrap();
+        assert_eq!(container.status, ContainerStatus::Exited);
+    }
+
+    #[test]
+    fn test_container_options_keep_image_defaults_when_unset() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, _rx) = handler(&runtime);
+
+        let mut custom = payload("app:v1", DeployStrategy::Recreate);
+        custom.entrypoint = Some(vec!["/bin/sh".to_string(), "-c".to_string()]);
+        custom.working_dir = Some("/srv".to_string());
+        custom.user = Some("1000:1000".to_string());
+        custom.shm_size_mb = Some(256);
+        let options = handler.container_options(&custom, "app");
+        assert_eq!(options.entrypoint, custom.entrypoint);
+        assert_eq!(options.command, None);
+        assert_eq!(options.working_dir.as_deref(), Some("/srv"));
+        assert_eq!(options.user.as_deref(), Some("1000:1000"));
+        assert_eq!(options.shm_size_mb, Some(256));
+
+        let defaults = payload("app:v1", DeployStrategy::Recreate);
+        let options = handler.container_options(&defaults, "app");
+        assert!(options.entrypoint.is_none() && options.command.is_none());
+        assert!(options.working_dir.is_none());
+        assert!(options.user.is_none() && options.shm_size_mb.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_max_restart_retries_requires_on_failure() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+
+        let mut invalid = payload("nginx:1.25", DeployStrategy::Recreate);
+        invalid.max_restart_retries = Some(3);
+        assert!(handler.deploy(invalid).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["INVALID_RESTART_POLICY"]);
+        assert!(runtime.containers().is_empty());
+
+        let mut on_failure = payload("nginx:1.25", DeployStrategy::Recreate);
+        on_failure.restart_policy = Some(RestartPolicy::OnFailure);
+        on_failure.max_restart_retries = Some(3);
+        let options = handler.container_options(&on_failure, "app");
+        assert_eq!(options.restart_policy, Some(RestartPolicy::OnFailure));
+        assert_eq!(options.max_restart_retries, Some(3));
+        handler.deploy(on_failure).await.unwrap();
+    }
+
+    #[test]
+    fn test_container_options_merge_legacy_volumes_and_mounts() {
+        use crate::connection::protocol::VolumeMount;
+
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, _rx) = handler(&runtime);
+
+        let mut with_mounts = payload("app:v1", DeployStrategy::Recreate);
+        with_mounts.volumes = Some(vec![VolumeMount {
+            host_path: "/srv/data".to_string(),
+            container_path: "/data".to_string(),
+            read_only: true,
+        }]);
+        with_mounts.mounts = Some(
+            serde_json::from_value(serde_json::json!([
+                { "type": "volume", "name": "pgdata", "target": "/var/lib/postgresql" },
+                { "type": "tmpfs", "target": "/run/secrets", "size_mb": 16 },
+            ]))
+            .unwrap(),
+        );
+
+        let options = handler.container_options(&with_mounts, "app");
+        assert_eq!(
+            options.mounts,
+            vec![
+                MountSpec::Bind {
+                    source: "/srv/data".to_string(),
+                    target: "/data".to_string(),
+                    read_only: true,
+                },
+                MountSpec::Volume {
+                    name: "pgdata".to_string(),
+                    target: "/var/lib/postgresql".to_string(),
+                    read_only: false,
+                },
+                MountSpec::Tmpfs {
+                    target: "/run/secrets".to_string(),
+                    size_mb: Some(16),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deploy_joins_default_and_extra_networks() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, _rx) = handler(&runtime);
+
+        let id = handler
+            .deploy(payload("nginx:1.25", DeployStrategy::Recreate))
+            .await
+            .unwrap();
+        assert_eq!(runtime.network_members("syntra-network"), Some(vec![id]));
+
+        runtime
+            .create_network(CreateNetworkOptions::bridge("backend"))
+            .await
+            .unwrap();
+        let mut multi = payload("nginx:1.25", DeployStrategy::Recreate);
+        multi.name = "api".to_string();
+        multi.networks = Some(vec!["syntra-network".to_string(), "backend".to_string()]);
+        let options = handler.container_options(&multi, "api");
+        assert_eq!(options.networks, vec!["syntra-network", "backend"]);
+    }
+
+    #[tokio::test]
+    async fn test_privileged_deploy_requires_allowlist() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+
+        let mut privileged = payload("nginx:1.25", DeployStrategy::Recreate);
+        privileged.privileged = true;
+        assert!(handler.deploy(privileged.clone()).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["PRIVILEGED_DENIED"]);
+        assert!(runtime.containers().is_empty());
+
+        let (tx, _rx) = mpsc::channel(64);
+        let config = RuntimeConfig {
+            allow_privileged: true,
+            ..RuntimeConfig::default()
+        };
+        let allowed =
+            DeployHandler::new(runtime.clone(), &config, tx).with_startup_grace(Duration::ZERO);
+        allowed.deploy(privileged).await.unwrap();
+    }
+
+    #[test]
+    fn test_container_options_apply_default_log_config() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, _rx) = handler(&runtime);
+
+        let mut tuned = payload("app:v1", DeployStrategy::Recreate);
+        tuned.log_options = HashMap::from([("max-size".to_string(), "50m".to_string())]);
+        let options = handler.container_options(&tuned, "app");
+        assert_eq!(options.log_driver.as_deref(), Some("json-file"));
+        assert_eq!(options.log_options["max-size"], "50m");
+        assert_eq!(options.log_options["max-file"], "3");
+
+        let mut journald = payload("app:v1", DeployStrategy::Recreate);
+        journald.log_driver = Some("journald".to_string());
+        let options = handler.container_options(&journald, "app");
+        assert_eq!(options.log_driver.as_deref(), Some("journald"));
+        assert!(options.log_options.is_empty());
+    }
 
-    // Tests would go here with a mock RuntimeAdapter
+    #[tokio::test]
+    async fn test_gpu_deploy_requires_allowlist_and_runtime() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+
+        let mut gpu = payload("trainer:v1", DeployStrategy::Recreate);
+        gpu.gpus = Some(GpuSpec::Count(1));
+        assert!(handler.deploy(gpu.clone()).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["GPU_DENIED"]);
+
+        let (tx, mut rx) = mpsc::channel(64);
+        let config = RuntimeConfig {
+            allow_gpu: true,
+            ..RuntimeConfig::default()
+        };
+        let allowed =
+            DeployHandler::new(runtime.clone(), &config, tx).with_startup_grace(Duration::ZERO);
+        assert!(allowed.deploy(gpu.clone()).await.is_err());
+        assert_eq!(error_codes(&mut rx), vec!["GPU_UNSUPPORTED"]);
+        assert!(runtime.containers().is_empty());
+
+        runtime.set_gpu_available(true);
+        allowed.deploy(gpu).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_deploy_rejects_soft_ulimit_above_hard() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+
+        let mut db = payload("postgres:16", DeployStrategy::Recreate);
+        db.ulimits = vec![Ulimit {
+            name: "nofile".to_string(),
+            soft: 65536,
+            hard: 1024,
+        }];
+        let err = handler.deploy(db).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ulimit nofile has a soft limit (65536) above its hard limit (1024)"
+        );
+        assert_eq!(error_codes(&mut rx), vec!["INVALID_ULIMIT"]);
+        assert!(runtime.containers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_draining_rejects_deploys_but_not_stops() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+        let state_manager = AgentStateManager::new();
+        let handler = handler.with_state_manager(state_manager.clone());
+
+        let id = handler.deploy(payload("app:v1", DeployStrategy::Recreate)).await.unwrap();
+        error_codes(&mut rx);
+
+        state_manager.set_draining(true);
+        let err = handler.deploy(payload("app:v2", DeployStrategy::Recreate)).await.unwrap_err();
+        assert!(err.to_string().contains("draining"));
+        assert_eq!(error_codes(&mut rx), vec!["AGENT_DRAINING"]);
+        assert_eq!(runtime.containers().len(), 1);
+
+        // Existing containers can still be stopped
+        handler.stop(stop_payload(&id, "SIGTERM")).await.unwrap();
+        let container = runtime.get_container(&id).await.unwrap().unwrap();
+        assert_eq!(container.status, ContainerStatus::Exited);
+
+        state_manager.set_draining(false);
+        handler.deploy(payload("app:v2", DeployStrategy::Recreate)).await.unwrap();
+    }
+
+    fn deploy_phases(rx: &mut mpsc::Receiver<AgentMessage>) -> Vec<DeployPhase> {
+        let mut phases = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            if let AgentMessage::DeployEvent(event) = msg {
+                phases.push(event.phase);
+            }
+        }
+        phases
+    }
+
+    #[tokio::test]
+    async fn test_deploy_reports_phase_timeline() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (handler, mut rx) = handler(&runtime);
+
+        handler.deploy(payload("app:v1", DeployStrategy::Recreate)).await.unwrap();
+        assert_eq!(
+            deploy_phases(&mut rx),
+            vec![
+                DeployPhase::PullStarted,
+                DeployPhase::PullDone,
+                DeployPhase::Created,
+                DeployPhase::Started,
+                DeployPhase::Healthy,
+            ]
+        );
+
+        // A failed start ends the timeline early
+        runtime.set_fails("start_container", true);
+        let blue_green = payload("app:v2", DeployStrategy::BlueGreen);
+        assert!(handler.deploy(blue_green).await.is_err());
+        assert_eq!(
+            deploy_phases(&mut rx),
+            vec![DeployPhase::PullStarted, DeployPhase::PullDone, DeployPhase::Created]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_secret_files_written_and_removed_with_container() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let dir = std::env::temp_dir().join(format!("syntra-secrets-{}", uuid::Uuid::new_v4()));
+        let (tx, mut rx) = mpsc::channel(64);
+        let config = RuntimeConfig {
+            secrets_dir: dir.to_string_lossy().into_owned(),
+            ..RuntimeConfig::default()
+        };
+        let handler =
+            DeployHandler::new(runtime.clone(), &config, tx).with_startup_grace(Duration::ZERO);
+
+        let mut with_secret = payload("app:v1", DeployStrategy::Recreate);
+        with_secret.secret_files = vec![SecretFile {
+            path: "/run/secrets/db".to_string(),
+            content: b"hunter2".to_vec(),
+            mode: 0o400,
+        }];
+        handler.deploy(with_secret.clone()).await.unwrap();
+        let first = runtime.containers()[0].labels[SECRETS_LABEL].clone();
+        assert_eq!(std::fs::read(dir.join(&first).join("0")).unwrap(), b"hunter2");
+
+        // Replacing the container replaces its secrets
+        with_secret.request_id = "req-redeploy".to_string();
+        handler.deploy(with_secret.clone()).await.unwrap();
+        let second = runtime.containers()[0].labels[SECRETS_LABEL].clone();
+        assert_ne!(first, second);
+        assert!(!dir.join(&first).exists());
+        assert!(dir.join(&second).exists());
+
+        with_secret.request_id = "req-invalid".to_string();
+        with_secret.secret_files[0].path = "secrets/db".to_string();
+        assert!(handler.deploy(with_secret).await.is_err());
+        assert!(error_codes(&mut rx).contains(&"INVALID_SECRET_FILE".to_string()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
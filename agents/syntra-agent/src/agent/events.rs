@@ -0,0 +1,208 @@
+//! Container Event Watcher
+//!
+//! Follows the runtime's event stream and reports managed containers that
+//! die unexpectedly (crashes and OOM kills) as soon as it happens, rather
+//! than waiting for the next heartbeat.
+
+use futures_util::StreamExt;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::connection::channel;
+use crate::connection::protocol::{AgentMessage, ContainerStatusPayload};
+use crate::runtime::adapter::{RuntimeAdapter, RuntimeEvent, RuntimeEventAction};
+use crate::runtime::error::RuntimeError;
+
+/// Delay before resubscribing after the event stream ends or fails
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Watches runtime events for unexpected container exits
+pub struct EventWatcher<R: RuntimeAdapter> {
+    runtime: Arc<R>,
+    message_tx: mpsc::Sender<AgentMessage>,
+    /// Containers that were sent a signal, so their exit is expected; dropped
+    /// once the container dies or is removed
+    signalled: HashSet<String>,
+    /// Containers the kernel OOM-killed, until they die or are removed
+    oom_killed: HashSet<String>,
+}
+
+impl<R: RuntimeAdapter> EventWatcher<R> {
+    /// Create a new event watcher
+    pub fn new(runtime: Arc<R>, message_tx: mpsc::Sender<AgentMessage>) -> Self {
+        Self {
+            runtime,
+            message_tx,
+            signalled: HashSet::new(),
+            oom_killed: HashSet::new(),
+        }
+    }
+
+    /// Follow runtime events until the message channel closes
+    pub async fn run(mut self) {
+        loop {
+            match self.runtime.events().await {
+                Ok(mut events) => {
+                    while let Some(event) = events.next().await {
+                        let event = match event {
+                            Ok(event) => event,
+                            Err(e) => {
+                                warn!(error = %e, "Runtime event stream failed");
+                                break;
+                            }
+                        };
+
+                        if let Some(message) = self.handle_event(event) {
+                            if channel::send(&self.message_tx, message).await.is_err() {
+                                debug!("Message channel closed, stopping event watcher");
+                                return;
+                            }
+                        }
+                    }
+                }
+                Err(RuntimeError::Unsupported { .. }) => {
+                    info!("Runtime does not report events, event watcher disabled");
+                    return;
+                }
+                Err(e) => warn!(error = %e, "Failed to subscribe to runtime events"),
+            }
+
+            if self.message_tx.is_closed() {
+                return;
+            }
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    }
+
+    /// Update tracking for an event, returning a status update if a
+    /// container died unexpectedly
+    fn handle_event(&mut self, event: RuntimeEvent) -> Option<AgentMessage> {
+        let id = event.container_id;
+        match event.action {
+            RuntimeEventAction::Kill => {
+                self.signalled.insert(id);
+                None
+            }
+            RuntimeEventAction::Oom => {
+                self.oom_killed.insert(id);
+                None
+            }
+            RuntimeEventAction::Stop => {
+                self.signalled.remove(&id);
+                None
+            }
+            // A signal that didn't end the container leaves it tracked until it's removed
+            RuntimeEventAction::Destroy => {
+                self.signalled.remove(&id);
+                self.oom_killed.remove(&id);
+                None
+            }
+            RuntimeEventAction::Die => {
+                let oom = self.oom_killed.remove(&id);
+                if self.signalled.remove(&id) && !oom {
+                    debug!(container_id = %id, "Container stopped on request");
+                    return None;
+                }
+
+                let status = if oom { "oom_killed" } else { "exited" };
+                info!(
+                    container_id = %id,
+                    exit_code = ?event.exit_code,
+                    status,
+                    "Container died unexpectedly"
+                );
+                Some(AgentMessage::ContainerStatus(ContainerStatusPayload {
+                    container_id: id,
+                    name: event.name.unwrap_or_default(),
+                    status: status.to_string(),
+                    health: None,
+                    started_at: None,
+                    ports: Vec::new(),
+                    image_digest: None,
+                    timestamp: event.timestamp,
+                    message_id: None,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::mock::MockRuntimeAdapter;
+
+    fn event(action: RuntimeEventAction) -> RuntimeEvent {
+        RuntimeEvent {
+            container_id: "c1".to_string(),
+            name: Some("app".to_string()),
+            action,
+            exit_code: None,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    fn status(message: Option<AgentMessage>) -> Option<String> {
+        match message? {
+            AgentMessage::ContainerStatus(payload) => Some(payload.status),
+            other => panic!("Expected ContainerStatus, got {:?}", other),
+        }
+    }
+
+    fn watcher() -> EventWatcher<MockRuntimeAdapter> {
+        let (tx, _rx) = mpsc::channel(1);
+        EventWatcher::new(Arc::new(MockRuntimeAdapter::new()), tx)
+    }
+
+    #[test]
+    fn test_requested_stop_is_not_reported() {
+        let mut watcher = watcher();
+        assert!(watcher.handle_event(event(RuntimeEventAction::Kill)).is_none());
+        assert!(watcher.handle_event(event(RuntimeEventAction::Die)).is_none());
+        assert!(watcher.handle_event(event(RuntimeEventAction::Stop)).is_none());
+    }
+
+    #[test]
+    fn test_crash_and_oom_are_reported() {
+        let mut watcher = watcher();
+        assert_eq!(
+            status(watcher.handle_event(event(RuntimeEventAction::Die))).as_deref(),
+            Some("exited")
+        );
+
+        assert!(watcher.handle_event(event(RuntimeEventAction::Oom)).is_none());
+        assert_eq!(
+            status(watcher.handle_event(event(RuntimeEventAction::Die))).as_deref(),
+            Some("oom_killed")
+        );
+    }
+
+    #[test]
+    fn test_tracking_dropped_once_container_dies_or_is_removed() {
+        let mut watcher = watcher();
+        watcher.handle_event(event(RuntimeEventAction::Kill));
+        watcher.handle_event(event(RuntimeEventAction::Die));
+        assert!(watcher.signalled.is_empty());
+
+        // A signal the container survived, e.g. SIGHUP to reload
+        watcher.handle_event(event(RuntimeEventAction::Kill));
+        watcher.handle_event(event(RuntimeEventAction::Oom));
+        watcher.handle_event(event(RuntimeEventAction::Destroy));
+        assert!(watcher.signalled.is_empty());
+        assert!(watcher.oom_killed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_sends_status_for_crash() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.push_event(event(RuntimeEventAction::Die));
+        let (tx, mut rx) = mpsc::channel(1);
+
+        let handle = tokio::spawn(EventWatcher::new(runtime, tx).run());
+        assert!(matches!(rx.recv().await, Some(AgentMessage::ContainerStatus(_))));
+        handle.abort();
+    }
+}
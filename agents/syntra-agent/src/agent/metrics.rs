@@ -0,0 +1,78 @@
+//! Host Metrics
+//!
+//! Samples host-level CPU and memory utilization for heartbeat reporting.
+
+use anyhow::{bail, Result};
+use sysinfo::System;
+use tracing::warn;
+
+/// Cached host metrics sampler
+///
+/// Keeps a single `System` instance alive between samples so that CPU usage
+/// is computed as a delta since the previous refresh, and the system does not
+/// need to be re-initialized on every heartbeat tick.
+pub struct HostMetrics {
+    system: System,
+}
+
+impl HostMetrics {
+    /// Create a new sampler and take the baseline CPU reading
+    pub fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu();
+        system.refresh_memory();
+        Self { system }
+    }
+
+    /// Sample host CPU and memory usage as percentages
+    ///
+    /// Returns `(cpu_percent, memory_percent)`. Falls back to `(0.0, 0.0)`
+    /// and logs a warning if the sample cannot be taken.
+    pub fn sample(&mut self) -> (f64, f64) {
+        match self.try_sample() {
+            Ok(sample) => sample,
+            Err(e) => {
+                warn!(error = %e, "Failed to sample host metrics");
+                (0.0, 0.0)
+            }
+        }
+    }
+
+    /// Refresh the cached system and compute usage percentages
+    fn try_sample(&mut self) -> Result<(f64, f64)> {
+        self.system.refresh_cpu();
+        self.system.refresh_memory();
+
+        let cpu_percent = self.system.global_cpu_info().cpu_usage() as f64;
+        if !cpu_percent.is_finite() {
+            bail!("CPU usage is not a finite number");
+        }
+
+        let total_memory = self.system.total_memory();
+        if total_memory == 0 {
+            bail!("Total memory reported as zero");
+        }
+        let memory_percent = self.system.used_memory() as f64 / total_memory as f64 * 100.0;
+
+        Ok((cpu_percent, memory_percent))
+    }
+}
+
+impl Default for HostMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_within_bounds() {
+        let mut metrics = HostMetrics::new();
+        let (cpu, memory) = metrics.sample();
+        assert!(cpu >= 0.0);
+        assert!((0.0..=100.0).contains(&memory));
+    }
+}
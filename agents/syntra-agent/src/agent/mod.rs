@@ -1,7 +1,14 @@
 //! Agent module
 //!
-//! This module contains the core agent functionality including state management
-//! and deployment handling.
+//! This module contains the core agent functionality including state management,
+//! deployment and task handling, container event watching, host metrics sampling,
+//! desired state reconciliation, secret files, and container telemetry.
 
 pub mod deploy;
+pub mod events;
+pub mod metrics;
+pub mod reconcile;
+pub mod secrets;
 pub mod state;
+pub mod task;
+pub mod telemetry;
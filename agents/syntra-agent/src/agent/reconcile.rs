@@ -0,0 +1,245 @@
+//! Desired State Reconciliation
+//!
+//! Periodically compares the services the control plane deployed against the
+//! containers actually present, restarting ones that exited and redeploying
+//! ones that went missing, so a container its restart policy gave up on
+//! still comes back.
+
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::agent::deploy::{replica_names, DeployHandler};
+use crate::connection::channel;
+use crate::connection::protocol::{AgentMessage, ContainerStatusPayload, DeployContainerPayload};
+use crate::runtime::adapter::{ContainerStatus, RuntimeAdapter};
+
+/// Keeps deployed services running
+pub struct Reconciler<R: RuntimeAdapter> {
+    runtime: Arc<R>,
+    handler: Arc<DeployHandler<R>>,
+    interval: Duration,
+    message_tx: mpsc::Sender<AgentMessage>,
+}
+
+impl<R: RuntimeAdapter> Reconciler<R> {
+    /// Create a reconciler enforcing `handler`'s desired state every `interval`
+    pub fn new(
+        runtime: Arc<R>,
+        handler: Arc<DeployHandler<R>>,
+        interval: Duration,
+        message_tx: mpsc::Sender<AgentMessage>,
+    ) -> Self {
+        Self {
+            runtime,
+            handler,
+            interval,
+            message_tx,
+        }
+    }
+
+    /// Reconcile every interval until the message channel closes
+    pub async fn run(self) {
+        let mut ticker = interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately; nothing has been deployed yet
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            if self.message_tx.is_closed() {
+                debug!("Message channel closed, stopping reconciler");
+                break;
+            }
+            self.reconcile().await;
+        }
+    }
+
+    /// Bring every desired service back to its deployed state once
+    pub async fn reconcile(&self) {
+        for service in self.handler.desired_services() {
+            if let Err(e) = self.reconcile_service(&service).await {
+                warn!(name = %service.name, error = %e, "Failed to reconcile service");
+            }
+        }
+    }
+
+    /// Restart a service's exited containers, or redeploy it if any are gone
+    async fn reconcile_service(&self, service: &DeployContainerPayload) -> Result<()> {
+        for name in replica_names(&service.name, service.replicas) {
+            let container = self.runtime.get_container(&name).await?;
+            match container {
+                Some(container) if container.status == ContainerStatus::Exited => {
+                    info!(
+                        container_id = %container.id,
+                        name = %name,
+                        "Desired container exited, restarting"
+                    );
+                    self.runtime.start_container(&container.id).await?;
+                    let started_at = self
+                        .runtime
+                        .get_container(&container.id)
+                        .await
+                        .ok()
+                        .flatten()
+                        .and_then(|c| c.started_at);
+                    self.send_status(&container.id, &name, "restarted", started_at).await;
+                }
+                Some(container) if container.status != ContainerStatus::Dead => {}
+                _ if self.handler.is_draining() => {
+                    // A draining agent doesn't create containers, even replacements
+                    debug!(name = %name, "Desired container missing, left alone while draining");
+                    return Ok(());
+                }
+                _ => {
+                    // Redeploying converges every replica, so one is enough
+                    info!(name = %name, "Desired container missing, redeploying");
+                    self.send_status("", &name, "redeploying", None).await;
+                    let payload = DeployContainerPayload {
+                        request_id: format!("reconcile-{}", Uuid::new_v4()),
+                        ..service.clone()
+                    };
+                    self.handler.deploy(payload).await?;
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Report a corrective action taken on a container
+    async fn send_status(
+        &self,
+        container_id: &str,
+        name: &str,
+        status: &str,
+        started_at: Option<String>,
+    ) {
+        let msg = AgentMessage::ContainerStatus(ContainerStatusPayload {
+            container_id: container_id.to_string(),
+            name: name.to_string(),
+            status: status.to_string(),
+            health: None,
+            started_at,
+            ports: Vec::new(),
+            image_digest: None,
+            timestamp: chrono::Utc::now(),
+            message_id: None,
+        });
+
+        if let Err(e) = channel::send(&self.message_tx, msg).await {
+            warn!(error = %e, "Failed to send reconciliation status");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::deploy::test_payloads::payload;
+    use crate::cli::config::RuntimeConfig;
+    use crate::connection::protocol::{DeployStrategy, StopContainerPayload};
+    use crate::runtime::mock::MockRuntimeAdapter;
+
+    fn reconciler(
+        runtime: &Arc<MockRuntimeAdapter>,
+    ) -> (Reconciler<MockRuntimeAdapter>, mpsc::Receiver<AgentMessage>) {
+        let (tx, rx) = mpsc::channel(64);
+        let handler = DeployHandler::new(runtime.clone(), &RuntimeConfig::default(), tx.clone())
+            .with_startup_grace(Duration::ZERO);
+        let reconciler =
+            Reconciler::new(runtime.clone(), Arc::new(handler), Duration::from_secs(1), tx);
+        (reconciler, rx)
+    }
+
+    fn service(replicas: u32) -> DeployContainerPayload {
+        DeployContainerPayload {
+            health_check: None,
+            replicas,
+            ..payload("nginx:1.25", DeployStrategy::Recreate)
+        }
+    }
+
+    fn statuses(rx: &mut mpsc::Receiver<AgentMessage>) -> Vec<(String, String)> {
+        let mut statuses = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            if let AgentMessage::ContainerStatus(s) = msg {
+                statuses.push((s.name, s.status));
+            }
+        }
+        statuses
+    }
+
+    #[tokio::test]
+    async fn test_restarts_exited_container() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (reconciler, mut rx) = reconciler(&runtime);
+        let id = reconciler.handler.deploy(service(1)).await.unwrap();
+        runtime.stop_container(&id, None).await.unwrap();
+        let stopped = runtime.get_container("app").await.unwrap().unwrap();
+        assert!(stopped.finished_at.is_some());
+        statuses(&mut rx);
+
+        reconciler.reconcile().await;
+
+        let container = runtime.get_container("app").await.unwrap().unwrap();
+        assert_eq!(container.id, id);
+        assert_eq!(container.status, ContainerStatus::Running);
+        assert_ne!(container.started_at, stopped.started_at);
+        assert_eq!(statuses(&mut rx), vec![("app".to_string(), "restarted".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_redeploys_missing_replica() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (reconciler, mut rx) = reconciler(&runtime);
+        reconciler.handler.deploy(service(2)).await.unwrap();
+        runtime.remove_container("app-2", true).await.unwrap();
+        statuses(&mut rx);
+
+        reconciler.reconcile().await;
+
+        let mut names: Vec<String> = runtime.containers().into_iter().map(|c| c.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["app-1", "app-2"]);
+        let statuses = statuses(&mut rx);
+        assert_eq!(statuses[0], ("app-2".to_string(), "redeploying".to_string()));
+        assert!(statuses.contains(&("app-2".to_string(), "running".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_leaves_stopped_services_and_jobs_alone() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (reconciler, mut rx) = reconciler(&runtime);
+        let id = reconciler.handler.deploy(service(1)).await.unwrap();
+        let stop = StopContainerPayload {
+            request_id: "req-2".to_string(),
+            container_id: id,
+            timeout_secs: None,
+            force: false,
+            stop_signal: None,
+        };
+        reconciler.handler.stop(stop).await.unwrap();
+
+        let job = DeployContainerPayload {
+            name: "migrate".to_string(),
+            wait_for_exit: true,
+            ..service(1)
+        };
+        reconciler.handler.deploy(job).await.unwrap();
+        assert!(reconciler.handler.desired_services().is_empty());
+        statuses(&mut rx);
+
+        reconciler.reconcile().await;
+
+        assert!(statuses(&mut rx).is_empty());
+        assert!(runtime
+            .containers()
+            .iter()
+            .all(|c| c.status == ContainerStatus::Exited));
+    }
+}
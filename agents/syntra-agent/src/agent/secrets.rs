@@ -0,0 +1,215 @@
+//! Secret Files
+//!
+//! Secret files from a deploy payload are written under the agent's
+//! `runtime.secrets_dir`, in a directory of their own per container, and
+//! bind-mounted read-only into it. The default directory is under `/run`,
+//! which is tmpfs on most hosts, so secrets never reach disk; a container's
+//! secrets are capped at `MAX_SECRET_BYTES` in total to bound the memory
+//! they take. The container's `syntra.secrets` label names its directory,
+//! which is removed along with the container.
+//!
+//! Contents are never logged; errors name a file by its path only.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::connection::protocol::SecretFile;
+use crate::runtime::adapter::MountSpec;
+
+/// Label naming the directory that holds a container's secret files
+pub const SECRETS_LABEL: &str = "syntra.secrets";
+
+/// Largest total size of one container's secret files, as Kubernetes allows
+/// for a secret
+pub const MAX_SECRET_BYTES: usize = 1024 * 1024;
+
+/// Check that secret files can be mounted: absolute, distinct paths and a
+/// total size within `MAX_SECRET_BYTES`
+pub fn validate(files: &[SecretFile]) -> Result<()> {
+    let mut paths = HashSet::new();
+    for file in files {
+        let path = Path::new(&file.path);
+        let normal = path
+            .components()
+            .all(|c| matches!(c, Component::RootDir | Component::Normal(_)));
+        if !path.is_absolute() || !normal || path.parent().is_none() {
+            bail!("Secret file path {} must be an absolute file path", file.path);
+        }
+        if !paths.insert(path) {
+            bail!("Secret file path {} is given more than once", file.path);
+        }
+        if file.mode > 0o777 {
+            bail!("Secret file {} has invalid mode {:o}", file.path, file.mode);
+        }
+    }
+
+    let total: usize = files.iter().map(|f| f.content.len()).sum();
+    if total > MAX_SECRET_BYTES {
+        bail!(
+            "Secret files total {} bytes, more than the {} byte limit",
+            total,
+            MAX_SECRET_BYTES
+        );
+    }
+    Ok(())
+}
+
+/// Directory of the agent's secret files, one subdirectory per container
+#[derive(Debug, Clone)]
+pub struct SecretStore {
+    dir: PathBuf,
+}
+
+impl SecretStore {
+    /// Keep secret files under `dir`, which should be on tmpfs
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Write a container's secret files into a new directory, returning its
+    /// ID for the `syntra.secrets` label and the mounts for the files
+    pub fn write(&self, files: &[SecretFile]) -> Result<(String, Vec<MountSpec>)> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let dir = self.dir.join(&id);
+        let result = create_private_dir(&dir).and_then(|_| {
+            files
+                .iter()
+                .enumerate()
+                .map(|(index, file)| {
+                    let source = dir.join(index.to_string());
+                    write_file(&source, file)?;
+                    Ok(MountSpec::Bind {
+                        source: source.to_string_lossy().into_owned(),
+                        target: file.path.clone(),
+                        read_only: true,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        });
+
+        match result {
+            Ok(mounts) => {
+                debug!(secrets = %id, files = files.len(), "Secret files written");
+                Ok((id, mounts))
+            }
+            Err(e) => {
+                self.remove(&id);
+                Err(e)
+            }
+        }
+    }
+
+    /// Remove the secret files directory a container's label names
+    ///
+    /// Anything but a directory ID this store made is ignored, so a label
+    /// can't point the agent at another path.
+    pub fn remove(&self, id: &str) {
+        if uuid::Uuid::parse_str(id).is_err() {
+            warn!(secrets = %id, "Ignoring invalid secret files label");
+            return;
+        }
+        match fs::remove_dir_all(self.dir.join(id)) {
+            Ok(()) => debug!(secrets = %id, "Secret files removed"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!(secrets = %id, error = %e, "Failed to remove secret files"),
+        }
+    }
+}
+
+/// Create a directory only the agent's user can enter
+fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(dir)
+        .with_context(|| format!("Failed to create secrets directory {}", dir.display()))
+}
+
+/// Write one secret file with its requested mode
+fn write_file(source: &Path, file: &SecretFile) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let context = || format!("Failed to write secret file {}", file.path);
+    let mut handle = options.open(source).with_context(context)?;
+    handle.write_all(&file.content).with_context(context)?;
+
+    // Set after writing, since the mode may not allow the agent to write
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(source, fs::Permissions::from_mode(file.mode)).with_context(context)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret(path: &str, content: &[u8]) -> SecretFile {
+        SecretFile {
+            path: path.to_string(),
+            content: content.to_vec(),
+            mode: 0o400,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let db = secret("/run/secrets/db", b"hunter2");
+        assert!(validate(&[db.clone(), secret("/etc/app/key.pem", b"key")]).is_ok());
+
+        assert!(validate(&[secret("run/secrets/db", b"x")]).is_err());
+        assert!(validate(&[secret("/run/../etc/shadow", b"x")]).is_err());
+        assert!(validate(&[secret("/", b"x")]).is_err());
+        assert!(validate(&[db.clone(), db.clone()]).is_err());
+        assert!(validate(&[SecretFile { mode: 0o4755, ..db }]).is_err());
+
+        let large = vec![0; MAX_SECRET_BYTES / 2 + 1];
+        assert!(validate(&[secret("/a", &large), secret("/b", &large)]).is_err());
+    }
+
+    #[test]
+    fn test_write_and_remove() {
+        let dir = std::env::temp_dir().join(format!("syntra-secrets-{}", uuid::Uuid::new_v4()));
+        let store = SecretStore::new(&dir);
+
+        let (id, mounts) = store.write(&[secret("/run/secrets/db", b"hunter2")]).unwrap();
+        let [MountSpec::Bind { source, target, read_only }] = mounts.as_slice() else {
+            panic!("Expected one bind mount, got {:?}", mounts);
+        };
+        assert_eq!(target, "/run/secrets/db");
+        assert!(*read_only);
+        assert!(source.starts_with(dir.to_str().unwrap()));
+        assert_eq!(fs::read(source).unwrap(), b"hunter2");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(source).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o400);
+        }
+
+        // Only IDs the store made are removed
+        store.remove("..");
+        assert!(dir.exists());
+        store.remove(&id);
+        assert!(!dir.join(&id).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
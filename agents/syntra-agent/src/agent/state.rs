@@ -6,9 +6,17 @@
 use parking_lot::RwLock;
 use std::sync::Arc;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Capacity of the state-change broadcast channel
+const TRANSITION_CHANNEL_CAPACITY: usize = 64;
+
+/// Most recent transitions included in status reports
+pub const STATUS_TRANSITIONS: usize = 20;
 
 /// Represents the possible states of the agent
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AgentState {
     /// Agent is disconnected from the control plane
     Disconnected,
@@ -35,7 +43,7 @@ impl std::fmt::Display for AgentState {
 }
 
 /// State transition information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateTransition {
     pub from: AgentState,
     pub to: AgentState,
@@ -49,12 +57,17 @@ struct AgentStateInner {
     last_connected: Option<DateTime<Utc>>,
     connection_attempts: u32,
     transitions: Vec<StateTransition>,
+    /// Version of the last configuration applied from the control plane
+    config_version: Option<String>,
+    /// Whether new deployments are being refused ahead of maintenance
+    draining: bool,
 }
 
 /// Thread-safe agent state manager
 #[derive(Clone)]
 pub struct AgentStateManager {
     inner: Arc<RwLock<AgentStateInner>>,
+    transitions_tx: broadcast::Sender<StateTransition>,
 }
 
 impl AgentStateManager {
@@ -66,10 +79,22 @@ impl AgentStateManager {
                 last_connected: None,
                 connection_attempts: 0,
                 transitions: Vec::new(),
+                config_version: None,
+                draining: false,
             })),
+            transitions_tx: broadcast::channel(TRANSITION_CHANNEL_CAPACITY).0,
         }
     }
 
+    /// Subscribe to state transitions
+    ///
+    /// Every successful transition is published after it has been recorded.
+    /// Slow receivers that fall more than the channel capacity behind get
+    /// `RecvError::Lagged` and skip ahead.
+    pub fn subscribe(&self) -> broadcast::Receiver<StateTransition> {
+        self.transitions_tx.subscribe()
+    }
+
     /// Get the current state
     pub fn current_state(&self) -> AgentState {
         self.inner.read().current
@@ -85,6 +110,28 @@ impl AgentStateManager {
         self.inner.read().connection_attempts
     }
 
+    /// Get the version of the last applied configuration, if any
+    pub fn config_version(&self) -> Option<String> {
+        self.inner.read().config_version.clone()
+    }
+
+    /// Record the version of a configuration that was applied
+    pub fn set_config_version(&self, version: &str) {
+        self.inner.write().config_version = Some(version.to_string());
+    }
+
+    /// Whether the agent is draining and refuses new deployments
+    pub fn is_draining(&self) -> bool {
+        self.inner.read().draining
+    }
+
+    /// Start or stop draining, returning whether the drain state changed
+    ///
+    /// Draining is independent of the connection state and survives reconnects.
+    pub fn set_draining(&self, draining: bool) -> bool {
+        std::mem::replace(&mut self.inner.write().draining, draining) != draining
+    }
+
     /// Transition to a new state
     pub fn transition_to(&self, new_state: AgentState, reason: Option<String>) -> bool {
         let mut inner = self.inner.write();
@@ -118,7 +165,7 @@ impl AgentStateManager {
         }
 
         // Record transition
-        inner.transitions.push(transition);
+        inner.transitions.push(transition.clone());
 
         // Keep only last 100 transitions
         if inner.transitions.len() > 100 {
@@ -131,6 +178,10 @@ impl AgentStateManager {
             attempts = inner.connection_attempts,
             "Agent state transition"
         );
+        drop(inner);
+
+        // Notify subscribers; having none is not an error
+        let _ = self.transitions_tx.send(transition);
 
         true
     }
@@ -188,7 +239,7 @@ impl AgentStateManager {
         self.transition_to(AgentState::ShuttingDown, Some("Shutdown requested".to_string()));
     }
 
-    /// Get recent state transitions
+    /// Get recent state transitions, newest first
     pub fn recent_transitions(&self, count: usize) -> Vec<StateTransition> {
         let inner = self.inner.read();
         inner.transitions.iter().rev().take(count).cloned().collect()
@@ -218,6 +269,25 @@ impl Default for AgentStateManager {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_transition_serialization() {
+        let manager = AgentStateManager::new();
+        manager.set_connecting();
+        manager.set_connected();
+        manager.transition_to(AgentState::Reconnecting, Some("WebSocket error: reset".to_string()));
+
+        let transitions = manager.recent_transitions(2);
+        let json = serde_json::to_value(&transitions).unwrap();
+        assert_eq!(json[0]["from"], "Connected");
+        assert_eq!(json[0]["to"], "Reconnecting");
+        assert_eq!(json[0]["reason"], "WebSocket error: reset");
+        assert_eq!(json[1]["to"], "Connected");
+
+        let parsed: Vec<StateTransition> = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed[0].to, AgentState::Reconnecting);
+        assert_eq!(parsed[0].timestamp, transitions[0].timestamp);
+    }
+
     #[test]
     fn test_initial_state() {
         let manager = AgentStateManager::new();
@@ -255,4 +325,54 @@ mod tests {
         manager.set_connected();
         assert_eq!(manager.connection_attempts(), 0);
     }
+
+    #[test]
+    fn test_subscribe_receives_transitions() {
+        let manager = AgentStateManager::new();
+        let mut rx = manager.subscribe();
+
+        manager.set_connecting();
+        manager.set_connected();
+        // Invalid transitions are not published
+        assert!(!manager.transition_to(AgentState::Connecting, None));
+
+        let first = rx.try_recv().unwrap();
+        assert_eq!((first.from, first.to), (AgentState::Disconnected, AgentState::Connecting));
+
+        let second = rx.try_recv().unwrap();
+        assert_eq!((second.from, second.to), (AgentState::Connecting, AgentState::Connected));
+        assert_eq!(second.reason.as_deref(), Some("Connection established"));
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_config_version_survives_reconnects() {
+        let manager = AgentStateManager::new();
+        assert_eq!(manager.config_version(), None);
+
+        manager.set_connecting();
+        manager.set_connected();
+        manager.set_config_version("v2");
+        manager.set_reconnecting();
+        manager.set_connected();
+        assert_eq!(manager.config_version().as_deref(), Some("v2"));
+    }
+
+    #[test]
+    fn test_draining_survives_reconnects() {
+        let manager = AgentStateManager::new();
+        assert!(!manager.is_draining());
+
+        manager.set_connecting();
+        manager.set_connected();
+        assert!(manager.set_draining(true));
+        assert!(!manager.set_draining(true));
+        manager.set_reconnecting();
+        manager.set_connected();
+        assert!(manager.is_draining());
+
+        assert!(manager.set_draining(false));
+        assert!(!manager.is_draining());
+    }
 }
@@ -0,0 +1,483 @@
+//! Task Handler
+//!
+//! Runs generic maintenance tasks requested by the control plane and reports
+//! each outcome as a task result.
+//!
+//! Supported task types:
+//!
+//! - `prune` - reclaim disk space. `params.scope` is one of `containers`,
+//!   `images`, `volumes`, or `all`; `params.dangling_only` (default `true`)
+//!   limits image pruning to untagged images.
+//! - `top` - list the processes running in the container `params.container_id`.
+//! - `diff` - list the paths the container `params.container_id` has added,
+//!   modified, or deleted relative to its image.
+//! - `commit` - snapshot the container `params.container_id` as the image
+//!   `params.repo:params.tag` (tag defaults to `latest`), with an optional
+//!   `params.message`. Outputs the new image ID.
+//! - `push` - push the image `params.repo:params.tag` (tag defaults to
+//!   `latest`) to its registry, first tagging `params.source` as it when set.
+//!   `params.registry_auth` overrides the agent's default credentials.
+//! - `logs` - send the logs of the container `params.container_id` as log
+//!   messages, starting from the last `params.tail` lines (default all) or
+//!   `params.since` (Unix seconds). With `params.follow`, new lines keep
+//!   coming until the container stops or the task is cancelled. Outputs the
+//!   number of lines sent.
+//!
+//! A `CancelTask` from the control plane ends a running task, which then
+//! reports a "Task cancelled" failure.
+
+use anyhow::{anyhow, bail, Context, Result};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify};
+use tracing::{info, warn};
+
+use crate::connection::channel;
+use crate::connection::protocol::{
+    AgentMessage, LogPayload, TaskRequestPayload, TaskResultPayload,
+};
+use crate::runtime::adapter::{LogsOptions, RegistryAuth, RuntimeAdapter};
+
+/// Task handler for processing control plane task requests
+pub struct TaskHandler<R: RuntimeAdapter> {
+    runtime: Arc<R>,
+    message_tx: mpsc::Sender<AgentMessage>,
+    /// Notified to stop the task early
+    cancel: Arc<Notify>,
+}
+
+impl<R: RuntimeAdapter> TaskHandler<R> {
+    /// Create a new task handler
+    pub fn new(runtime: Arc<R>, message_tx: mpsc::Sender<AgentMessage>) -> Self {
+        Self {
+            runtime,
+            message_tx,
+            cancel: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Stop the task once `cancel` is notified, even if that happened first
+    pub fn with_cancel(mut self, cancel: Arc<Notify>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Run a task and send its result to the control plane
+    pub async fn handle(&self, payload: TaskRequestPayload) {
+        let started_at = Instant::now();
+        info!(task_id = %payload.task_id, task_type = %payload.task_type, "Running task");
+
+        let run = async {
+            match payload.timeout_secs {
+                Some(secs) => tokio::time::timeout(Duration::from_secs(secs), self.run(&payload))
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("Task timed out after {}s", secs))),
+                None => self.run(&payload).await,
+            }
+        };
+        let outcome = tokio::select! {
+            outcome = run => outcome,
+            _ = self.cancel.notified() => Err(anyhow!("Task cancelled")),
+        };
+
+        let (success, output, error) = match outcome {
+            Ok(output) => (true, Some(output.to_string()), None),
+            Err(e) => {
+                warn!(task_id = %payload.task_id, error = %e, "Task failed");
+                (false, None, Some(e.to_string()))
+            }
+        };
+
+        let msg = AgentMessage::TaskResult(TaskResultPayload {
+            task_id: payload.task_id,
+            agent_id: String::new(), // Will be filled by WebSocket client
+            success,
+            output,
+            error,
+            duration_ms: started_at.elapsed().as_millis() as u64,
+            timestamp: chrono::Utc::now(),
+            message_id: None,
+        });
+
+        if let Err(e) = channel::send(&self.message_tx, msg).await {
+            warn!(error = %e, "Failed to send task result");
+        }
+    }
+
+    /// Dispatch a task by type, returning its JSON output
+    async fn run(&self, payload: &TaskRequestPayload) -> Result<Value> {
+        match payload.task_type.as_str() {
+            "prune" => self.prune(&payload.params).await,
+            "top" => self.top(&payload.params).await,
+            "diff" => self.diff(&payload.params).await,
+            "commit" => self.commit(&payload.params).await,
+            "push" => self.push(&payload.params).await,
+            "logs" => self.logs(&payload.task_id, &payload.params).await,
+            other => bail!("Unsupported task type: {}", other),
+        }
+    }
+
+    /// Prune stopped containers, unused images, and/or unused volumes
+    async fn prune(&self, params: &Value) -> Result<Value> {
+        let scope = params
+            .get("scope")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("prune requires a scope parameter"))?;
+        let dangling_only = params
+            .get("dangling_only")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+
+        match scope {
+            "containers" => Ok(json!({ "containers": self.runtime.prune_containers().await? })),
+            "images" => Ok(json!({ "images": self.runtime.prune_images(dangling_only).await? })),
+            "volumes" => Ok(json!({ "volumes": self.runtime.prune_volumes().await? })),
+            "all" => {
+                // Containers first so the images and volumes they held become unused
+                let containers = self.runtime.prune_containers().await?;
+                let images = self.runtime.prune_images(dangling_only).await?;
+                let volumes = self.runtime.prune_volumes().await?;
+                Ok(json!({ "containers": containers, "images": images, "volumes": volumes }))
+            }
+            other => bail!("Unknown prune scope: {}", other),
+        }
+    }
+
+    /// List the processes running in a container
+    async fn top(&self, params: &Value) -> Result<Value> {
+        let container_id = container_id("top", params)?;
+        Ok(json!({ "processes": self.runtime.top(container_id).await? }))
+    }
+
+    /// List the filesystem changes a container has made to its image
+    async fn diff(&self, params: &Value) -> Result<Value> {
+        let container_id = container_id("diff", params)?;
+        Ok(json!({ "changes": self.runtime.container_diff(container_id).await? }))
+    }
+
+    /// Save a container's current state as a new image
+    async fn commit(&self, params: &Value) -> Result<Value> {
+        let container_id = container_id("commit", params)?;
+        let repo = params
+            .get("repo")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("commit requires a repo parameter"))?;
+        let tag = params.get("tag").and_then(Value::as_str).unwrap_or("latest");
+        let message = params
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        let image_id = self
+            .runtime
+            .commit_container(container_id, repo, tag, message)
+            .await?;
+        Ok(json!({ "image_id": image_id, "image": format!("{}:{}", repo, tag) }))
+    }
+
+    /// Push an image to its registry, tagging a source image as it first if asked
+    async fn push(&self, params: &Value) -> Result<Value> {
+        let repo = params
+            .get("repo")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("push requires a repo parameter"))?;
+        let tag = params.get("tag").and_then(Value::as_str).unwrap_or("latest");
+        let auth = params
+            .get("registry_auth")
+            .cloned()
+            .map(serde_json::from_value::<RegistryAuth>)
+            .transpose()
+            .context("Invalid registry_auth parameter")?;
+
+        if let Some(source) = params.get("source").and_then(Value::as_str) {
+            self.runtime.tag_image(source, repo, tag).await?;
+        }
+
+        let image = format!("{}:{}", repo, tag);
+        self.runtime.push_image(&image, auth).await?;
+        Ok(json!({ "image": image }))
+    }
+
+    /// Send a container's logs to the control plane, one log message per line
+    async fn logs(&self, task_id: &str, params: &Value) -> Result<Value> {
+        let container_id = container_id("logs", params)?;
+        let options = LogsOptions {
+            stdout: true,
+            stderr: true,
+            follow: params.get("follow").and_then(Value::as_bool).unwrap_or(false),
+            tail: params.get("tail").and_then(Value::as_u64).map(|tail| tail as usize),
+            since: params.get("since").and_then(Value::as_u64).map(|since| since.to_string()),
+            until: None,
+        };
+
+        // A followed stream ends when the runtime sees the container stop
+        let mut stream = self.runtime.logs_stream(container_id, options).await?;
+        let mut lines = 0;
+        while let Some(line) = stream.next().await {
+            let line = line?;
+            let message = line.trim_end().to_string();
+            let msg = AgentMessage::Log(LogPayload {
+                level: log_level(&message).to_string(),
+                message,
+                context: Some(json!({ "task_id": task_id, "container_id": container_id })),
+                timestamp: chrono::Utc::now(),
+            });
+            if channel::send(&self.message_tx, msg).await.is_err() {
+                bail!("Message channel closed");
+            }
+            lines += 1;
+        }
+        Ok(json!({ "lines": lines }))
+    }
+}
+
+/// Guess the level of a log line from a level word near its start, such as
+/// `ERROR`, `[warn]`, or `level=debug`, defaulting to `info`
+fn log_level(line: &str) -> &'static str {
+    for word in line.split_whitespace().take(4) {
+        let word = word.strip_prefix("level=").unwrap_or(word);
+        let word = word.trim_matches(|c: char| !c.is_ascii_alphabetic());
+        match word.to_ascii_lowercase().as_str() {
+            "error" | "err" | "fatal" | "panic" | "critical" => return "error",
+            "warn" | "warning" => return "warn",
+            "info" => return "info",
+            "debug" => return "debug",
+            "trace" => return "trace",
+            _ => {}
+        }
+    }
+    "info"
+}
+
+/// Read the `container_id` parameter that `task_type` requires
+fn container_id<'a>(task_type: &str, params: &'a Value) -> Result<&'a str> {
+    params
+        .get("container_id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("{} requires a container_id parameter", task_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::mock::MockRuntimeAdapter;
+
+    fn request(task_type: &str, params: Value) -> TaskRequestPayload {
+        TaskRequestPayload {
+            task_id: "task-1".to_string(),
+            task_type: task_type.to_string(),
+            params,
+            timeout_secs: None,
+            priority: None,
+        }
+    }
+
+    async fn run_task(
+        runtime: Arc<MockRuntimeAdapter>,
+        payload: TaskRequestPayload,
+    ) -> TaskResultPayload {
+        let (tx, mut rx) = mpsc::channel(1);
+        TaskHandler::new(runtime, tx).handle(payload).await;
+        match rx.recv().await {
+            Some(AgentMessage::TaskResult(result)) => result,
+            other => panic!("Expected TaskResult, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prune_containers_removes_stopped_only() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.add_running("app", "nginx:latest");
+        let stopped = runtime.add_running("old", "nginx:1.24");
+        runtime.stop_container(&stopped, None).await.unwrap();
+
+        let result =
+            run_task(runtime.clone(), request("prune", json!({ "scope": "containers" }))).await;
+        assert!(result.success);
+
+        let output: Value = serde_json::from_str(&result.output.unwrap()).unwrap();
+        assert_eq!(output["containers"]["items_removed"], json!([stopped]));
+        assert_eq!(runtime.containers().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_prune_rejects_unknown_scope() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let result = run_task(runtime, request("prune", json!({ "scope": "everything" }))).await;
+        assert!(!result.success);
+        assert_eq!(result.error.unwrap(), "Unknown prune scope: everything");
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_task_type_fails() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let result = run_task(runtime, request("reboot", Value::Null)).await;
+        assert!(!result.success);
+    }
+
+    #[tokio::test]
+    async fn test_top_lists_container_processes() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let id = runtime.add_running("app", "nginx:latest");
+
+        let result = run_task(runtime, request("top", json!({ "container_id": id }))).await;
+        assert!(result.success);
+
+        let output: Value = serde_json::from_str(&result.output.unwrap()).unwrap();
+        assert_eq!(output["processes"][0]["pid"], json!(1));
+        assert_eq!(output["processes"][0]["command"], json!("nginx:latest"));
+    }
+
+    #[tokio::test]
+    async fn test_top_requires_container_id() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let result = run_task(runtime, request("top", json!({}))).await;
+        assert!(!result.success);
+        assert_eq!(result.error.unwrap(), "top requires a container_id parameter");
+    }
+
+    #[tokio::test]
+    async fn test_diff_reports_container_changes() {
+        use crate::runtime::adapter::{FsChange, FsChangeKind};
+
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let id = runtime.add_running("app", "nginx:latest");
+        runtime.set_fs_changes(
+            &id,
+            vec![FsChange {
+                path: "/etc/nginx/nginx.conf".to_string(),
+                kind: FsChangeKind::Modified,
+            }],
+        );
+
+        let result = run_task(runtime, request("diff", json!({ "container_id": "app" }))).await;
+        assert!(result.success);
+
+        let output: Value = serde_json::from_str(&result.output.unwrap()).unwrap();
+        assert_eq!(
+            output["changes"],
+            json!([{ "path": "/etc/nginx/nginx.conf", "kind": "Modified" }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_commit_outputs_image_id() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.add_running("app", "nginx:latest");
+
+        let params = json!({ "container_id": "app", "repo": "debug/app" });
+        let result = run_task(runtime, request("commit", params)).await;
+        assert!(result.success);
+
+        let output: Value = serde_json::from_str(&result.output.unwrap()).unwrap();
+        assert_eq!(output["image_id"], json!("sha256:debug/app-latest"));
+        assert_eq!(output["image"], json!("debug/app:latest"));
+    }
+
+    #[tokio::test]
+    async fn test_commit_requires_repo() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.add_running("app", "nginx:latest");
+
+        let result = run_task(runtime, request("commit", json!({ "container_id": "app" }))).await;
+        assert!(!result.success);
+        assert_eq!(result.error.unwrap(), "commit requires a repo parameter");
+    }
+
+    #[tokio::test]
+    async fn test_push_tags_source_then_pushes() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+
+        let params = json!({ "source": "sha256:built", "repo": "ghcr.io/team/app", "tag": "v2" });
+        let result = run_task(runtime.clone(), request("push", params)).await;
+        assert!(result.success);
+
+        let output: Value = serde_json::from_str(&result.output.unwrap()).unwrap();
+        assert_eq!(output["image"], json!("ghcr.io/team/app:v2"));
+        assert_eq!(runtime.pushes(), vec!["ghcr.io/team/app:v2"]);
+        assert!(runtime.image_exists("ghcr.io/team/app:v2").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_push_reports_denied_credentials() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_push_denied(true);
+
+        let params = json!({ "repo": "ghcr.io/team/app" });
+        let result = run_task(runtime, request("push", params)).await;
+        assert!(!result.success);
+        assert_eq!(
+            result.error.unwrap(),
+            "registry denied access to ghcr.io/team/app:latest: authentication required"
+        );
+    }
+
+    #[test]
+    fn test_log_level_parsing() {
+        assert_eq!(log_level("2024-01-01T00:00:00Z ERROR connection refused"), "error");
+        assert_eq!(log_level("[warn] disk almost full"), "warn");
+        assert_eq!(log_level("ts=1 level=debug msg=tick"), "debug");
+        assert_eq!(log_level("listening on :8080"), "info");
+        // Only words near the start count
+        assert_eq!(log_level("served /api/errors in 3ms with no error"), "info");
+    }
+
+    #[tokio::test]
+    async fn test_logs_sends_tail_as_log_messages() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let id = runtime.add_running("app", "nginx:latest");
+        runtime.set_logs(&id, &["starting\n", "WARN slow request\n", "ERROR crashed\n"]);
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let params = json!({ "container_id": "app", "tail": 2 });
+        TaskHandler::new(runtime, tx).handle(request("logs", params)).await;
+
+        let mut logs = Vec::new();
+        for _ in 0..2 {
+            match rx.try_recv() {
+                Ok(AgentMessage::Log(log)) => logs.push((log.level, log.message)),
+                other => panic!("Expected Log, got {:?}", other),
+            }
+        }
+        assert_eq!(
+            logs,
+            vec![
+                ("warn".to_string(), "WARN slow request".to_string()),
+                ("error".to_string(), "ERROR crashed".to_string()),
+            ]
+        );
+        match rx.try_recv() {
+            Ok(AgentMessage::TaskResult(result)) => {
+                assert!(result.success);
+                assert_eq!(result.output.as_deref(), Some(r#"{"lines":2}"#));
+            }
+            other => panic!("Expected TaskResult, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_followed_logs_end_on_cancel() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let id = runtime.add_running("app", "nginx:latest");
+        runtime.set_logs(&id, &["ready\n"]);
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let cancel = Arc::new(Notify::new());
+        let handler = TaskHandler::new(runtime, tx).with_cancel(cancel.clone());
+        let params = json!({ "container_id": "app", "follow": true });
+        let task = tokio::spawn(async move { handler.handle(request("logs", params)).await });
+
+        assert!(matches!(rx.recv().await, Some(AgentMessage::Log(_))));
+        cancel.notify_one();
+        task.await.unwrap();
+        match rx.recv().await {
+            Some(AgentMessage::TaskResult(result)) => {
+                assert!(!result.success);
+                assert_eq!(result.error.as_deref(), Some("Task cancelled"));
+            }
+            other => panic!("Expected TaskResult, got {:?}", other),
+        }
+    }
+}
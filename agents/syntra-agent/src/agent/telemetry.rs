@@ -0,0 +1,259 @@
+//! Container Telemetry
+//!
+//! Periodically samples stats for running containers and reports them to the
+//! control plane as `AgentMessage::Metrics`.
+
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{interval, interval_at, Instant, MissedTickBehavior};
+use tracing::{debug, info, warn};
+
+use crate::cli::reload::next_update;
+use crate::connection::channel;
+use crate::connection::protocol::{AgentMessage, MetricsPayload};
+use crate::runtime::adapter::{ContainerInfo, ContainerStats, RuntimeAdapter};
+
+/// Periodic container metrics reporter
+pub struct MetricsReporter<R: RuntimeAdapter> {
+    runtime: Arc<R>,
+    agent_id: String,
+    interval: Duration,
+    detailed: bool,
+    /// Include runtime disk usage in each report
+    disk_usage: bool,
+    /// New intervals in seconds, applied without restarting the reporter
+    interval_updates: Option<watch::Receiver<u64>>,
+    message_tx: mpsc::Sender<AgentMessage>,
+}
+
+impl<R: RuntimeAdapter> MetricsReporter<R> {
+    /// Create a reporter sending a sample every `interval`
+    ///
+    /// With `detailed` set, each report breaks stats down per container;
+    /// otherwise only totals are sent.
+    pub fn new(
+        runtime: Arc<R>,
+        agent_id: &str,
+        interval: Duration,
+        detailed: bool,
+        message_tx: mpsc::Sender<AgentMessage>,
+    ) -> Self {
+        Self {
+            runtime,
+            agent_id: agent_id.to_string(),
+            interval,
+            detailed,
+            disk_usage: false,
+            interval_updates: None,
+            message_tx,
+        }
+    }
+
+    /// Add runtime disk usage to each report, so the control plane can warn
+    /// before the disk fills
+    pub fn with_disk_usage(mut self, enabled: bool) -> Self {
+        self.disk_usage = enabled;
+        self
+    }
+
+    /// Switch to each interval (in seconds) published on `updates`
+    pub fn with_interval_updates(mut self, updates: watch::Receiver<u64>) -> Self {
+        self.interval_updates = Some(updates);
+        self
+    }
+
+    /// Report metrics every interval until the message channel closes
+    pub async fn run(mut self) {
+        let mut ticker = interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut updates = self.interval_updates.take();
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                secs = next_update(&mut updates) => {
+                    info!(interval_secs = secs, "Metrics interval changed");
+                    self.interval = Duration::from_secs(secs);
+                    ticker = interval_at(Instant::now() + self.interval, self.interval);
+                    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                    continue;
+                }
+            }
+
+            let message = self.collect().await;
+            if channel::send(&self.message_tx, message).await.is_err() {
+                debug!("Message channel closed, stopping metrics reporter");
+                break;
+            }
+        }
+    }
+
+    /// Sample every running container into a metrics message
+    pub async fn collect(&self) -> AgentMessage {
+        let containers = match self.runtime.list_containers(false).await {
+            Ok(containers) => containers,
+            Err(e) => {
+                warn!(error = %e, "Failed to list containers for metrics");
+                Vec::new()
+            }
+        };
+
+        let mut samples = Vec::with_capacity(containers.len());
+        for container in containers {
+            match self.runtime.stats(&container.id).await {
+                Ok(stats) => samples.push((container, stats)),
+                Err(e) => {
+                    debug!(
+                        container_id = %container.id,
+                        error = %e,
+                        "Failed to get container stats"
+                    );
+                }
+            }
+        }
+
+        let mut metrics = build_metrics(&samples, self.detailed);
+        if self.disk_usage {
+            match self.runtime.disk_usage().await {
+                Ok(usage) => metrics["disk_usage"] = json!(usage),
+                Err(e) => debug!(error = %e, "Failed to get disk usage"),
+            }
+        }
+
+        AgentMessage::Metrics(MetricsPayload {
+            agent_id: self.agent_id.clone(),
+            timestamp: chrono::Utc::now(),
+            metrics,
+        })
+    }
+}
+
+/// Build the metrics JSON from container samples
+fn build_metrics(samples: &[(ContainerInfo, ContainerStats)], detailed: bool) -> Value {
+    let (cpu, memory, rx, tx) = samples.iter().fold(
+        (0.0, 0u64, 0u64, 0u64),
+        |(cpu, memory, rx, tx), (_, stats)| {
+            (
+                cpu + stats.cpu_usage_percent,
+                memory + stats.memory_usage_bytes,
+                rx + stats.network_rx_bytes,
+                tx + stats.network_tx_bytes,
+            )
+        },
+    );
+
+    let mut metrics = json!({
+        "container_count": samples.len(),
+        "totals": {
+            "cpu_usage_percent": cpu,
+            "memory_usage_bytes": memory,
+            "network_rx_bytes": rx,
+            "network_tx_bytes": tx,
+        },
+    });
+
+    if detailed {
+        let containers: serde_json::Map<String, Value> = samples
+            .iter()
+            .map(|(container, stats)| {
+                (
+                    container.id.clone(),
+                    json!({
+                        "name": container.name,
+                        "cpu_usage_percent": stats.cpu_usage_percent,
+                        "memory_usage_bytes": stats.memory_usage_bytes,
+                        "memory_limit_bytes": stats.memory_limit_bytes,
+                        "network_rx_bytes": stats.network_rx_bytes,
+                        "network_tx_bytes": stats.network_tx_bytes,
+                    }),
+                )
+            })
+            .collect();
+        metrics["containers"] = Value::Object(containers);
+    }
+
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::adapter::DiskUsage;
+    use crate::runtime::mock::MockRuntimeAdapter;
+
+    fn reporter(
+        runtime: Arc<MockRuntimeAdapter>,
+        detailed: bool,
+    ) -> MetricsReporter<MockRuntimeAdapter> {
+        let (tx, _rx) = mpsc::channel(1);
+        MetricsReporter::new(runtime, "agent-1", Duration::from_secs(15), detailed, tx)
+    }
+
+    #[tokio::test]
+    async fn test_detailed_metrics_per_container() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let id = runtime.add_running("app", "nginx:latest");
+
+        let AgentMessage::Metrics(payload) = reporter(runtime, true).collect().await else {
+            panic!("Expected Metrics message");
+        };
+        assert_eq!(payload.metrics["container_count"], 1);
+        assert_eq!(payload.metrics["containers"][&id]["name"], "app");
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_metrics_omit_containers() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.add_running("app", "nginx:latest");
+        runtime.add_running("worker", "busybox:latest");
+
+        let AgentMessage::Metrics(payload) = reporter(runtime, false).collect().await else {
+            panic!("Expected Metrics message");
+        };
+        assert_eq!(payload.metrics["container_count"], 2);
+        assert!(payload.metrics["totals"].is_object());
+        assert!(payload.metrics.get("containers").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_disk_usage_reported_when_enabled() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_disk_usage(DiskUsage {
+            images_bytes: 1024,
+            reclaimable_bytes: 512,
+            ..Default::default()
+        });
+
+        let AgentMessage::Metrics(payload) = reporter(runtime.clone(), false).collect().await else {
+            panic!("Expected Metrics message");
+        };
+        assert!(payload.metrics.get("disk_usage").is_none());
+
+        let with_disk_usage = reporter(runtime, false).with_disk_usage(true);
+        let AgentMessage::Metrics(payload) = with_disk_usage.collect().await else {
+            panic!("Expected Metrics message");
+        };
+        assert_eq!(payload.metrics["disk_usage"]["images_bytes"], 1024);
+        assert_eq!(payload.metrics["disk_usage"]["reclaimable_bytes"], 512);
+    }
+
+    #[tokio::test]
+    async fn test_interval_update_applies_without_restart() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let (tx, mut rx) = mpsc::channel(4);
+        let (interval_tx, interval_rx) = watch::channel(3600);
+        let reporter =
+            MetricsReporter::new(runtime, "agent-1", Duration::from_secs(3600), false, tx)
+                .with_interval_updates(interval_rx);
+        let task = tokio::spawn(reporter.run());
+
+        // The first report goes out immediately
+        rx.recv().await.unwrap();
+        interval_tx.send(1).unwrap();
+        let next = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await;
+        task.abort();
+        assert!(matches!(next, Ok(Some(AgentMessage::Metrics(_)))));
+    }
+}
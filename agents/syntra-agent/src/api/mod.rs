@@ -0,0 +1,10 @@
+//! Local API Module
+//!
+//! Embedded HTTP servers that let local tooling and Prometheus query a
+//! running agent.
+
+pub mod prometheus;
+pub mod server;
+
+pub use self::prometheus::{AgentMetrics, PrometheusExporter};
+pub use server::{LocalApiServer, LocalStatus};
@@ -0,0 +1,235 @@
+//! Prometheus Exporter
+//!
+//! Serves agent metrics in the Prometheus text format on `GET /metrics`.
+//!
+//! Exposed metrics:
+//!
+//! - `agent_up` - always 1 while the agent is running
+//! - `agent_connection_attempts_total` - control plane connection attempts
+//! - `agent_state{state="..."}` - 1 for the current agent state, 0 otherwise
+//! - `container_count` - running containers as of the last heartbeat
+//! - `reconnects_total` - times the agent lost its connection and reconnected
+//! - `message_channel_full_total` - times a handler found the outbound message
+//!   channel full, as of the last heartbeat
+
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use prometheus::{IntCounter, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::agent::state::{AgentState, AgentStateManager, StateTransition};
+
+/// Every state reported by the `agent_state` gauge
+const STATES: [AgentState; 5] = [
+    AgentState::Disconnected,
+    AgentState::Connecting,
+    AgentState::Connected,
+    AgentState::Reconnecting,
+    AgentState::ShuttingDown,
+];
+
+/// Agent metrics registered with a dedicated Prometheus registry
+#[derive(Clone)]
+pub struct AgentMetrics {
+    registry: Registry,
+    connection_attempts: IntCounter,
+    state: IntGaugeVec,
+    container_count: IntGauge,
+    reconnects: IntCounter,
+    message_channel_full: IntCounter,
+}
+
+impl AgentMetrics {
+    /// Create and register all agent metrics
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new();
+
+        let up = IntGauge::new("agent_up", "Whether the agent is running")?;
+        let connection_attempts = IntCounter::new(
+            "agent_connection_attempts_total",
+            "Control plane connection attempts",
+        )?;
+        let state = IntGaugeVec::new(
+            Opts::new("agent_state", "Current agent state (1 for the active state)"),
+            &["state"],
+        )?;
+        let container_count = IntGauge::new("container_count", "Running containers")?;
+        let reconnects = IntCounter::new("reconnects_total", "Reconnects to the control plane")?;
+        let message_channel_full = IntCounter::new(
+            "message_channel_full_total",
+            "Times the outbound message channel was full",
+        )?;
+
+        registry.register(Box::new(up.clone()))?;
+        registry.register(Box::new(connection_attempts.clone()))?;
+        registry.register(Box::new(state.clone()))?;
+        registry.register(Box::new(container_count.clone()))?;
+        registry.register(Box::new(reconnects.clone()))?;
+        registry.register(Box::new(message_channel_full.clone()))?;
+
+        up.set(1);
+        let metrics = Self {
+            registry,
+            connection_attempts,
+            state,
+            container_count,
+            reconnects,
+            message_channel_full,
+        };
+        metrics.set_state(AgentState::Disconnected);
+        Ok(metrics)
+    }
+
+    /// Update the metrics for a state transition
+    pub fn record_transition(&self, transition: &StateTransition) {
+        self.set_state(transition.to);
+        match transition.to {
+            AgentState::Connecting => self.connection_attempts.inc(),
+            AgentState::Reconnecting => {
+                self.connection_attempts.inc();
+                self.reconnects.inc();
+            }
+            _ => {}
+        }
+    }
+
+    /// Record the running container count reported in a heartbeat
+    pub fn set_container_count(&self, count: u32) {
+        self.container_count.set(count as i64);
+    }
+
+    /// Record how often the outbound message channel has been found full
+    pub fn set_message_channel_full(&self, total: u64) {
+        let counted = self.message_channel_full.get();
+        if total > counted {
+            self.message_channel_full.inc_by(total - counted);
+        }
+    }
+
+    /// Follow state transitions until the state manager is dropped
+    pub fn watch(&self, state_manager: &AgentStateManager) -> JoinHandle<()> {
+        let metrics = self.clone();
+        let state_manager = state_manager.clone();
+        let mut transitions = state_manager.subscribe();
+        metrics.set_state(state_manager.current_state());
+
+        tokio::spawn(async move {
+            loop {
+                match transitions.recv().await {
+                    Ok(transition) => metrics.record_transition(&transition),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "Metrics missed state transitions");
+                        metrics.set_state(state_manager.current_state());
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Render all metrics in the Prometheus text format
+    pub fn encode(&self) -> Result<String> {
+        Ok(TextEncoder::new().encode_to_string(&self.registry.gather())?)
+    }
+
+    fn set_state(&self, current: AgentState) {
+        for state in STATES {
+            self.state
+                .with_label_values(&[&state.to_string()])
+                .set((state == current) as i64);
+        }
+    }
+}
+
+/// Prometheus scrape endpoint
+pub struct PrometheusExporter {
+    addr: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl PrometheusExporter {
+    /// Bind to `addr` and serve `metrics` in a background task
+    pub async fn start(addr: SocketAddr, metrics: AgentMetrics) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind Prometheus exporter to {}", addr))?;
+        let addr = listener.local_addr()?;
+
+        let router = Router::new()
+            .route("/metrics", get(scrape))
+            .with_state(metrics);
+
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                error!(error = %e, "Prometheus exporter failed");
+            }
+        });
+
+        info!(addr = %addr, "Prometheus exporter listening");
+        Ok(Self { addr, handle })
+    }
+
+    /// Address the exporter is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop serving
+    pub fn shutdown(self) {
+        self.handle.abort();
+    }
+}
+
+async fn scrape(State(metrics): State<AgentMetrics>) -> impl IntoResponse {
+    match metrics.encode() {
+        Ok(body) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+            body,
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "text/plain")],
+            e.to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions_update_metrics() {
+        let metrics = AgentMetrics::new().unwrap();
+        let manager = AgentStateManager::new();
+        let mut transitions = manager.subscribe();
+
+        manager.set_connecting();
+        manager.set_connected();
+        manager.set_reconnecting();
+        while let Ok(transition) = transitions.try_recv() {
+            metrics.record_transition(&transition);
+        }
+        metrics.set_container_count(3);
+        metrics.set_message_channel_full(2);
+        metrics.set_message_channel_full(2);
+
+        let body = metrics.encode().unwrap();
+        assert!(body.contains("agent_up 1"));
+        assert!(body.contains("agent_connection_attempts_total 2"));
+        assert!(body.contains("reconnects_total 1"));
+        assert!(body.contains("agent_state{state=\"Reconnecting\"} 1"));
+        assert!(body.contains("agent_state{state=\"Connected\"} 0"));
+        assert!(body.contains("container_count 3"));
+        assert!(body.contains("message_channel_full_total 2"));
+    }
+}
@@ -0,0 +1,160 @@
+//! Local HTTP Server
+//!
+//! Serves `GET /status` with the agent's connection state and `GET /healthz`
+//! reflecting the container runtime's health.
+
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::agent::state::{AgentStateManager, StateTransition, STATUS_TRANSITIONS};
+use crate::runtime::adapter::RuntimeAdapter;
+
+/// Response body of `GET /status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalStatus {
+    pub state: String,
+    pub uptime_secs: u64,
+    pub connection_attempts: u32,
+    pub last_connected: Option<DateTime<Utc>>,
+    pub runtime: String,
+    /// Running container count, `None` if the runtime could not be queried
+    pub container_count: Option<usize>,
+    /// Whether new deployments are being rejected ahead of maintenance
+    #[serde(default)]
+    pub draining: bool,
+    /// Recent connection state transitions, newest first
+    #[serde(default)]
+    pub transitions: Vec<StateTransition>,
+}
+
+impl LocalStatus {
+    /// Snapshot the agent state
+    fn collect(
+        state_manager: &AgentStateManager,
+        started_at: Instant,
+        runtime: &str,
+        container_count: Option<usize>,
+    ) -> Self {
+        Self {
+            state: state_manager.current_state().to_string(),
+            uptime_secs: started_at.elapsed().as_secs(),
+            connection_attempts: state_manager.connection_attempts(),
+            last_connected: state_manager.last_connected(),
+            runtime: runtime.to_string(),
+            container_count,
+            draining: state_manager.is_draining(),
+            transitions: state_manager.recent_transitions(STATUS_TRANSITIONS),
+        }
+    }
+}
+
+/// Shared handler state
+struct ApiState<R: RuntimeAdapter> {
+    runtime: Arc<R>,
+    state_manager: AgentStateManager,
+    started_at: Instant,
+}
+
+/// Embedded local HTTP API server
+pub struct LocalApiServer {
+    addr: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl LocalApiServer {
+    /// Bind to `addr` and serve the local API in a background task
+    pub async fn start<R: RuntimeAdapter + 'static>(
+        addr: SocketAddr,
+        runtime: Arc<R>,
+        state_manager: AgentStateManager,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind local API to {}", addr))?;
+        let addr = listener.local_addr()?;
+
+        let state = Arc::new(ApiState {
+            runtime,
+            state_manager,
+            started_at: Instant::now(),
+        });
+        let router = Router::new()
+            .route("/status", get(status::<R>))
+            .route("/healthz", get(healthz::<R>))
+            .with_state(state);
+
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                error!(error = %e, "Local API server failed");
+            }
+        });
+
+        info!(addr = %addr, "Local API listening");
+        Ok(Self { addr, handle })
+    }
+
+    /// Address the server is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop serving
+    pub fn shutdown(self) {
+        self.handle.abort();
+    }
+}
+
+async fn status<R: RuntimeAdapter + 'static>(
+    State(state): State<Arc<ApiState<R>>>,
+) -> Json<LocalStatus> {
+    let container_count = state.runtime.list_containers(false).await.ok().map(|c| c.len());
+
+    Json(LocalStatus::collect(
+        &state.state_manager,
+        state.started_at,
+        state.runtime.runtime_type(),
+        container_count,
+    ))
+}
+
+async fn healthz<R: RuntimeAdapter + 'static>(State(state): State<Arc<ApiState<R>>>) -> StatusCode {
+    match state.runtime.health_check().await {
+        Ok(true) => StatusCode::OK,
+        _ => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_status() {
+        let manager = AgentStateManager::new();
+        manager.set_connecting();
+        manager.set_connected();
+
+        let status = LocalStatus::collect(&manager, Instant::now(), "docker", Some(3));
+        assert_eq!(status.state, "Connected");
+        assert_eq!(status.connection_attempts, 0);
+        assert!(status.last_connected.is_some());
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["container_count"], 3);
+        assert_eq!(json["runtime"], "docker");
+        assert_eq!(json["draining"], false);
+        assert_eq!(json["transitions"][0]["to"], "Connected");
+        assert_eq!(json["transitions"][1]["to"], "Connecting");
+    }
+}
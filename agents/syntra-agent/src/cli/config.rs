@@ -4,9 +4,56 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::Path;
+use thiserror::Error;
 use uuid::Uuid;
 
+use crate::connection::protocol::Encoding;
+use crate::runtime::adapter::RegistryAuth;
+
+/// Container runtimes the agent can drive
+pub const SUPPORTED_RUNTIMES: &[&str] = &["docker", "containerd", "podman"];
+
+/// A single problem found while validating a configuration
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ConfigError {
+    /// The control plane URL does not use a WebSocket scheme
+    #[error("control_plane.url must start with ws:// or wss:// (got \"{0}\")")]
+    InvalidUrlScheme(String),
+
+    /// A value that must be positive is zero
+    #[error("{0} must be greater than 0")]
+    ZeroValue(&'static str),
+
+    /// The runtime type is not one the agent supports
+    #[error(
+        "runtime.runtime_type \"{0}\" is not supported (expected one of: {list})",
+        list = SUPPORTED_RUNTIMES.join(", ")
+    )]
+    UnknownRuntime(String),
+
+    /// The local API bind address cannot be parsed
+    #[error("local_api.addr \"{0}\" is not a valid socket address")]
+    InvalidLocalApiAddr(String),
+
+    /// The Prometheus exporter bind address cannot be parsed
+    #[error("telemetry.prometheus_addr \"{0}\" is not a valid socket address")]
+    InvalidPrometheusAddr(String),
+
+    /// Secret files must be bind-mounted from an absolute host path
+    #[error("runtime.secrets_dir \"{0}\" must be an absolute path")]
+    RelativeSecretsDir(String),
+
+    /// A resource limit has an unusable value
+    #[error("runtime.resource_limits.{field} {reason}")]
+    InvalidResourceLimit {
+        field: &'static str,
+        reason: &'static str,
+    },
+}
+
 /// Main configuration structure for the Syntra Agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -33,6 +80,14 @@ pub struct Config {
     /// Logging configuration
     #[serde(default)]
     pub logging: LoggingConfig,
+
+    /// Local HTTP API settings
+    #[serde(default)]
+    pub local_api: LocalApiConfig,
+
+    /// Background (daemon) mode settings
+    #[serde(default)]
+    pub daemon: DaemonConfig,
 }
 
 /// Control plane connection configuration
@@ -57,6 +112,43 @@ pub struct ControlPlaneConfig {
     /// Heartbeat interval in seconds
     #[serde(default = "default_heartbeat_interval")]
     pub heartbeat_interval_secs: u64,
+
+    /// Unacknowledged heartbeats in a row before the agent reconnects
+    #[serde(default = "default_max_missed_heartbeat_acks")]
+    pub max_missed_heartbeat_acks: u32,
+
+    /// Seconds to wait for an Ack before resending a task result or status update
+    #[serde(default = "default_ack_timeout")]
+    pub ack_timeout_secs: u64,
+
+    /// Resends of an unacknowledged message before giving up (0 = never resend)
+    #[serde(default = "default_ack_max_retries")]
+    pub ack_max_retries: u32,
+
+    /// Offer zstd compression of large messages; used only if the control plane accepts
+    #[serde(default = "default_true")]
+    pub compression: bool,
+
+    /// Message encoding offered on registration; JSON unless the control plane accepts it
+    #[serde(default)]
+    pub encoding: Encoding,
+
+    /// Queued messages sent per second at most (0 = unlimited)
+    #[serde(default = "default_max_messages_per_sec")]
+    pub max_messages_per_sec: u32,
+
+    /// Messages that may be sent in a burst above `max_messages_per_sec`
+    #[serde(default = "default_message_burst")]
+    pub message_burst: u32,
+
+    /// Messages handlers may queue for the connection before metrics and logs
+    /// are dropped and task results wait for room
+    #[serde(default = "default_message_channel_capacity")]
+    pub message_channel_capacity: usize,
+
+    /// Largest message accepted from the control plane; a larger one ends the session
+    #[serde(default = "default_max_message_bytes")]
+    pub max_message_bytes: usize,
 }
 
 /// Runtime configuration
@@ -70,6 +162,18 @@ pub struct RuntimeConfig {
     #[serde(default = "default_docker_socket")]
     pub docker_socket: String,
 
+    /// containerd socket path
+    #[serde(default = "default_containerd_socket")]
+    pub containerd_socket: String,
+
+    /// containerd namespace for agent-managed containers
+    #[serde(default = "default_containerd_namespace")]
+    pub containerd_namespace: String,
+
+    /// Podman API socket path (auto-detected when unset)
+    #[serde(default)]
+    pub podman_socket: Option<String>,
+
     /// Default network for containers
     #[serde(default = "default_network")]
     pub default_network: String,
@@ -77,6 +181,46 @@ pub struct RuntimeConfig {
     /// Resource limits
     #[serde(default)]
     pub resource_limits: ResourceLimits,
+
+    /// Logging for deployed containers that don't specify their own
+    #[serde(default)]
+    pub container_logs: ContainerLogConfig,
+
+    /// Default registry credentials for image pulls
+    #[serde(default)]
+    pub registry_auth: Option<RegistryAuth>,
+
+    /// Maximum deployments run at once; further requests are queued
+    #[serde(default = "default_max_concurrent_deploys")]
+    pub max_concurrent_deploys: usize,
+
+    /// Overall deadline in seconds for pulling, starting, and health-checking a deployment
+    #[serde(default = "default_deploy_timeout")]
+    pub deploy_timeout_secs: u64,
+
+    /// Seconds to let in-flight deployments finish on shutdown before aborting them
+    #[serde(default = "default_shutdown_grace")]
+    pub shutdown_grace_secs: u64,
+
+    /// Allow the control plane to deploy privileged containers
+    #[serde(default)]
+    pub allow_privileged: bool,
+
+    /// Allow the control plane to give containers GPUs
+    #[serde(default)]
+    pub allow_gpu: bool,
+
+    /// Restart or redeploy deployed containers that have died or gone missing
+    #[serde(default = "default_true")]
+    pub reconcile: bool,
+
+    /// Seconds between checks of deployed containers against their desired state
+    #[serde(default = "default_reconcile_interval")]
+    pub reconcile_interval_secs: u64,
+
+    /// Directory deployed containers' secret files are written to; keep it on tmpfs
+    #[serde(default = "default_secrets_dir")]
+    pub secrets_dir: String,
 }
 
 /// Resource limits configuration
@@ -90,6 +234,22 @@ pub struct ResourceLimits {
 
     /// Maximum containers
     pub max_containers: Option<u32>,
+
+    /// Reject deployments that request more than a limit instead of clamping them
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// Default log driver for deployed containers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerLogConfig {
+    /// Log driver (e.g. json-file, local, journald)
+    #[serde(default = "default_container_log_driver")]
+    pub driver: String,
+
+    /// Driver options
+    #[serde(default = "default_container_log_options")]
+    pub options: HashMap<String, String>,
 }
 
 /// Telemetry configuration
@@ -106,6 +266,14 @@ pub struct TelemetryConfig {
     /// Enable detailed container metrics
     #[serde(default)]
     pub detailed_metrics: bool,
+
+    /// Include runtime disk usage in each metrics report
+    #[serde(default)]
+    pub report_disk_usage: bool,
+
+    /// Address to serve Prometheus metrics on (disabled when unset)
+    #[serde(default)]
+    pub prometheus_addr: Option<String>,
 }
 
 /// Logging configuration
@@ -131,6 +299,26 @@ pub struct LoggingConfig {
     pub max_size_mb: u64,
 }
 
+/// Local HTTP API configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalApiConfig {
+    /// Serve the local status API
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Address to bind the local API to
+    #[serde(default = "default_local_api_addr")]
+    pub addr: String,
+}
+
+/// Daemon mode configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    /// PID file written by a daemonized agent, and checked before starting
+    #[serde(default = "default_pid_file")]
+    pub pid_file: String,
+}
+
 // Default value functions
 fn default_agent_id() -> String {
     Uuid::new_v4().to_string()
@@ -154,6 +342,34 @@ fn default_heartbeat_interval() -> u64 {
     30
 }
 
+fn default_max_missed_heartbeat_acks() -> u32 {
+    3
+}
+
+fn default_ack_timeout() -> u64 {
+    10
+}
+
+fn default_ack_max_retries() -> u32 {
+    5
+}
+
+fn default_max_messages_per_sec() -> u32 {
+    50
+}
+
+fn default_message_burst() -> u32 {
+    100
+}
+
+fn default_message_channel_capacity() -> usize {
+    100
+}
+
+fn default_max_message_bytes() -> usize {
+    16 * 1024 * 1024
+}
+
 fn default_runtime_type() -> String {
     "docker".to_string()
 }
@@ -162,10 +378,50 @@ fn default_docker_socket() -> String {
     "/var/run/docker.sock".to_string()
 }
 
+fn default_containerd_socket() -> String {
+    "/run/containerd/containerd.sock".to_string()
+}
+
+fn default_containerd_namespace() -> String {
+    "syntra".to_string()
+}
+
 fn default_network() -> String {
     "syntra-network".to_string()
 }
 
+fn default_max_concurrent_deploys() -> usize {
+    4
+}
+
+fn default_deploy_timeout() -> u64 {
+    600
+}
+
+fn default_shutdown_grace() -> u64 {
+    60
+}
+
+fn default_reconcile_interval() -> u64 {
+    30
+}
+
+fn default_secrets_dir() -> String {
+    "/run/syntra/secrets".to_string()
+}
+
+fn default_container_log_driver() -> String {
+    "json-file".to_string()
+}
+
+fn default_container_log_options() -> HashMap<String, String> {
+    // Cap each container at 30MB of logs
+    HashMap::from([
+        ("max-size".to_string(), "10m".to_string()),
+        ("max-file".to_string(), "3".to_string()),
+    ])
+}
+
 fn default_true() -> bool {
     true
 }
@@ -186,6 +442,14 @@ fn default_max_log_size() -> u64 {
     100
 }
 
+fn default_local_api_addr() -> String {
+    "127.0.0.1:9090".to_string()
+}
+
+fn default_pid_file() -> String {
+    "/var/run/syntra-agent.pid".to_string()
+}
+
 impl Default for ControlPlaneConfig {
     fn default() -> Self {
         Self {
@@ -194,6 +458,15 @@ impl Default for ControlPlaneConfig {
             reconnect_interval_ms: default_reconnect_interval(),
             max_reconnect_attempts: 0,
             heartbeat_interval_secs: default_heartbeat_interval(),
+            max_missed_heartbeat_acks: default_max_missed_heartbeat_acks(),
+            ack_timeout_secs: default_ack_timeout(),
+            ack_max_retries: default_ack_max_retries(),
+            compression: default_true(),
+            encoding: Encoding::default(),
+            max_messages_per_sec: default_max_messages_per_sec(),
+            message_burst: default_message_burst(),
+            message_channel_capacity: default_message_channel_capacity(),
+            max_message_bytes: default_max_message_bytes(),
         }
     }
 }
@@ -203,8 +476,21 @@ impl Default for RuntimeConfig {
         Self {
             runtime_type: default_runtime_type(),
             docker_socket: default_docker_socket(),
+            containerd_socket: default_containerd_socket(),
+            containerd_namespace: default_containerd_namespace(),
+            podman_socket: None,
             default_network: default_network(),
             resource_limits: ResourceLimits::default(),
+            container_logs: ContainerLogConfig::default(),
+            registry_auth: None,
+            max_concurrent_deploys: default_max_concurrent_deploys(),
+            deploy_timeout_secs: default_deploy_timeout(),
+            shutdown_grace_secs: default_shutdown_grace(),
+            allow_privileged: false,
+            allow_gpu: false,
+            reconcile: default_true(),
+            reconcile_interval_secs: default_reconcile_interval(),
+            secrets_dir: default_secrets_dir(),
         }
     }
 }
@@ -215,6 +501,8 @@ impl Default for TelemetryConfig {
             enabled: default_true(),
             metrics_interval_secs: default_metrics_interval(),
             detailed_metrics: false,
+            report_disk_usage: false,
+            prometheus_addr: None,
         }
     }
 }
@@ -231,6 +519,104 @@ impl Default for LoggingConfig {
     }
 }
 
+/// Comment written above a section header by `to_commented_toml`
+fn section_comment(section: &str) -> Option<&'static str> {
+    match section {
+        "control_plane" => Some("Control plane connection settings"),
+        "runtime" => Some("Runtime configuration"),
+        "runtime.resource_limits" => Some("Per-container resource limits (unset = unlimited)"),
+        "runtime.registry_auth" => Some("Default credentials for private registry pulls"),
+        "runtime.container_logs" => Some("Logging for containers that don't set their own"),
+        "runtime.container_logs.options" => Some("Log driver options"),
+        "telemetry" => Some("Telemetry settings"),
+        "logging" => Some("Logging configuration"),
+        "local_api" => Some("Local HTTP API used by `syntra-agent status`"),
+        "daemon" => Some("Background mode (`start` without --foreground)"),
+        _ => None,
+    }
+}
+
+/// Comment written above a key by `to_commented_toml`
+fn key_comment(section: &str, key: &str) -> Option<&'static str> {
+    match (section, key) {
+        ("", "agent_id") => Some("Unique identifier for this agent"),
+        ("", "server_id") => Some("Server/host identifier reported to the control plane"),
+        ("control_plane", "url") => {
+            Some("WebSocket URL of the control plane (ws:// or wss://) - change this")
+        }
+        ("control_plane", "reconnect_interval_ms") => Some("Reconnect interval in milliseconds"),
+        ("control_plane", "max_reconnect_attempts") => Some("Max reconnects (0 = infinite)"),
+        ("control_plane", "heartbeat_interval_secs") => Some("Heartbeat interval in seconds"),
+        ("control_plane", "max_missed_heartbeat_acks") => {
+            Some("Unacknowledged heartbeats before reconnecting")
+        }
+        ("control_plane", "ack_timeout_secs") => Some("Seconds to wait for an Ack"),
+        ("control_plane", "ack_max_retries") => Some("Resends before giving up (0 = never resend)"),
+        ("control_plane", "compression") => Some("Offer zstd compression of large messages"),
+        ("control_plane", "encoding") => Some("Message encoding to offer: json or msgpack"),
+        ("control_plane", "max_messages_per_sec") => Some("Outbound message rate (0 = unlimited)"),
+        ("control_plane", "message_burst") => Some("Messages allowed in a burst above the rate"),
+        ("control_plane", "message_channel_capacity") => {
+            Some("Messages queued by handlers before metrics are dropped")
+        }
+        ("control_plane", "max_message_bytes") => Some("Largest incoming message in bytes"),
+        ("runtime", "runtime_type") => Some("Container runtime: docker, containerd, or podman"),
+        ("runtime", "docker_socket") => Some("Docker socket path"),
+        ("runtime", "containerd_socket") => Some("containerd socket path"),
+        ("runtime", "containerd_namespace") => Some("containerd namespace for agent containers"),
+        ("runtime", "default_network") => Some("Default network for containers"),
+        ("runtime", "max_concurrent_deploys") => Some("Deployments run at once; extras queue"),
+        ("runtime", "deploy_timeout_secs") => Some("Seconds a deployment may take overall"),
+        ("runtime", "shutdown_grace_secs") => Some("Seconds to finish in-flight work on shutdown"),
+        ("runtime", "allow_privileged") => Some("Allow privileged containers (root on the host)"),
+        ("runtime", "allow_gpu") => Some("Allow containers to request NVIDIA GPUs"),
+        ("runtime", "reconcile") => Some("Restart or redeploy containers that died or vanished"),
+        ("runtime", "reconcile_interval_secs") => Some("Seconds between reconciliation passes"),
+        ("runtime", "secrets_dir") => Some("Where secret files are written; keep it on tmpfs"),
+        ("runtime.container_logs", "driver") => Some("Log driver: json-file, local, journald, ..."),
+        ("runtime.resource_limits", "strict") => Some("Reject over-limit deploys, don't clamp"),
+        ("telemetry", "enabled") => Some("Enable telemetry"),
+        ("telemetry", "metrics_interval_secs") => Some("Metrics collection interval in seconds"),
+        ("telemetry", "detailed_metrics") => Some("Enable detailed container metrics"),
+        ("telemetry", "report_disk_usage") => Some("Report image, container, and volume disk use"),
+        ("telemetry", "prometheus_addr") => Some("Serve Prometheus metrics on this address"),
+        ("logging", "level") => Some("Log level: trace, debug, info, warn, error"),
+        ("logging", "format") => Some("Log format: pretty, json, compact"),
+        ("logging", "rotate") => Some("Enable log rotation"),
+        ("logging", "max_size_mb") => Some("Maximum log file size in MB"),
+        ("local_api", "enabled") => Some("Serve GET /status and GET /healthz"),
+        ("local_api", "addr") => Some("Address to bind the local API to"),
+        ("daemon", "pid_file") => Some("PID file; start refuses to run while it names a live PID"),
+        _ => None,
+    }
+}
+
+impl Default for ContainerLogConfig {
+    fn default() -> Self {
+        Self {
+            driver: default_container_log_driver(),
+            options: default_container_log_options(),
+        }
+    }
+}
+
+impl Default for LocalApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            addr: default_local_api_addr(),
+        }
+    }
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            pid_file: default_pid_file(),
+        }
+    }
+}
+
 impl Config {
     /// Load configuration from a TOML file
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
@@ -253,6 +639,109 @@ impl Config {
             runtime: RuntimeConfig::default(),
             telemetry: TelemetryConfig::default(),
             logging: LoggingConfig::default(),
+            local_api: LocalApiConfig::default(),
+            daemon: DaemonConfig::default(),
+        }
+    }
+
+    /// Check the configuration for values that would fail at runtime,
+    /// collecting every problem instead of stopping at the first
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
+        let url = &self.control_plane.url;
+        if !(url.starts_with("ws://") || url.starts_with("wss://")) {
+            errors.push(ConfigError::InvalidUrlScheme(url.clone()));
+        }
+
+        if self.control_plane.reconnect_interval_ms == 0 {
+            errors.push(ConfigError::ZeroValue("control_plane.reconnect_interval_ms"));
+        }
+
+        if self.control_plane.heartbeat_interval_secs == 0 {
+            errors.push(ConfigError::ZeroValue("control_plane.heartbeat_interval_secs"));
+        }
+
+        if self.control_plane.max_missed_heartbeat_acks == 0 {
+            errors.push(ConfigError::ZeroValue("control_plane.max_missed_heartbeat_acks"));
+        }
+
+        if self.control_plane.ack_timeout_secs == 0 {
+            errors.push(ConfigError::ZeroValue("control_plane.ack_timeout_secs"));
+        }
+
+        if self.control_plane.max_messages_per_sec > 0 && self.control_plane.message_burst == 0 {
+            errors.push(ConfigError::ZeroValue("control_plane.message_burst"));
+        }
+
+        if self.control_plane.message_channel_capacity == 0 {
+            errors.push(ConfigError::ZeroValue("control_plane.message_channel_capacity"));
+        }
+
+        if self.control_plane.max_message_bytes == 0 {
+            errors.push(ConfigError::ZeroValue("control_plane.max_message_bytes"));
+        }
+
+        if self.runtime.max_concurrent_deploys == 0 {
+            errors.push(ConfigError::ZeroValue("runtime.max_concurrent_deploys"));
+        }
+
+        if self.runtime.deploy_timeout_secs == 0 {
+            errors.push(ConfigError::ZeroValue("runtime.deploy_timeout_secs"));
+        }
+
+        if self.runtime.reconcile && self.runtime.reconcile_interval_secs == 0 {
+            errors.push(ConfigError::ZeroValue("runtime.reconcile_interval_secs"));
+        }
+
+        if !std::path::Path::new(&self.runtime.secrets_dir).is_absolute() {
+            errors.push(ConfigError::RelativeSecretsDir(self.runtime.secrets_dir.clone()));
+        }
+
+        if self.telemetry.enabled && self.telemetry.metrics_interval_secs == 0 {
+            errors.push(ConfigError::ZeroValue("telemetry.metrics_interval_secs"));
+        }
+
+        if !SUPPORTED_RUNTIMES.contains(&self.runtime.runtime_type.as_str()) {
+            errors.push(ConfigError::UnknownRuntime(self.runtime.runtime_type.clone()));
+        }
+
+        let limits = &self.runtime.resource_limits;
+        if limits.max_memory_mb == Some(0) {
+            errors.push(ConfigError::InvalidResourceLimit {
+                field: "max_memory_mb",
+                reason: "must be greater than 0",
+            });
+        }
+        if let Some(cores) = limits.max_cpu_cores {
+            if !cores.is_finite() || cores <= 0.0 {
+                errors.push(ConfigError::InvalidResourceLimit {
+                    field: "max_cpu_cores",
+                    reason: "must be a positive number",
+                });
+            }
+        }
+        if limits.max_containers == Some(0) {
+            errors.push(ConfigError::InvalidResourceLimit {
+                field: "max_containers",
+                reason: "must be greater than 0",
+            });
+        }
+
+        if self.local_api.enabled && self.local_api.addr.parse::<SocketAddr>().is_err() {
+            errors.push(ConfigError::InvalidLocalApiAddr(self.local_api.addr.clone()));
+        }
+
+        if let Some(addr) = &self.telemetry.prometheus_addr {
+            if self.telemetry.enabled && addr.parse::<SocketAddr>().is_err() {
+                errors.push(ConfigError::InvalidPrometheusAddr(addr.clone()));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
     }
 
@@ -266,6 +755,46 @@ impl Config {
 
         Ok(())
     }
+
+    /// Serialize to TOML with explanatory comments for each section and key
+    pub fn to_commented_toml(&self) -> Result<String> {
+        let content = toml::to_string_pretty(self)
+            .context("Failed to serialize configuration")?;
+
+        let mut output = String::from("# Syntra Agent Configuration\n\n");
+        let mut section = String::new();
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                section = trimmed.trim_matches(|c| c == '[' || c == ']').to_string();
+                if !output.ends_with("\n\n") {
+                    output.push('\n');
+                }
+                if let Some(comment) = section_comment(&section) {
+                    output.push_str(&format!("# {}\n", comment));
+                }
+            } else if let Some((key, _)) = trimmed.split_once(" = ") {
+                if let Some(comment) = key_comment(&section, key) {
+                    output.push_str(&format!("# {}\n", comment));
+                }
+            }
+            output.push_str(line);
+            output.push('\n');
+        }
+
+        Ok(output)
+    }
+
+    /// Save configuration to a TOML file with explanatory comments
+    pub fn save_commented<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let content = self.to_commented_toml()?;
+
+        std::fs::write(path.as_ref(), content)
+            .with_context(|| format!("Failed to write config file: {}", path.as_ref().display()))?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -290,4 +819,158 @@ mod tests {
         assert_eq!(config.agent_id, "test-agent-123");
         assert_eq!(config.control_plane.url, "ws://localhost:8080");
     }
+
+    #[test]
+    fn test_commented_toml_round_trip() {
+        let config = Config::default_config();
+        let content = config.to_commented_toml().unwrap();
+        assert!(content.contains("# Control plane connection settings\n[control_plane]"));
+        assert!(content.contains("# Heartbeat interval in seconds\nheartbeat_interval_secs = 30"));
+
+        let parsed: Config = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.agent_id, config.agent_id);
+        assert_eq!(parsed.control_plane.url, config.control_plane.url);
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert_eq!(Config::default_config().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_rejects_http_url() {
+        let mut config = Config::default_config();
+        config.control_plane.url = "http://localhost:8080".to_string();
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::InvalidUrlScheme("http://localhost:8080".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_values() {
+        let mut config = Config::default_config();
+        config.control_plane.reconnect_interval_ms = 0;
+        config.control_plane.heartbeat_interval_secs = 0;
+        config.control_plane.message_channel_capacity = 0;
+        assert_eq!(
+            config.validate(),
+            Err(vec![
+                ConfigError::ZeroValue("control_plane.reconnect_interval_ms"),
+                ConfigError::ZeroValue("control_plane.heartbeat_interval_secs"),
+                ConfigError::ZeroValue("control_plane.message_channel_capacity"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_reconcile_interval_only_when_enabled() {
+        let mut config = Config::default_config();
+        config.runtime.reconcile_interval_secs = 0;
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::ZeroValue("runtime.reconcile_interval_secs")])
+        );
+
+        config.runtime.reconcile = false;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_rejects_relative_secrets_dir() {
+        let mut config = Config::default_config();
+        config.runtime.secrets_dir = "secrets".to_string();
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::RelativeSecretsDir("secrets".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_validate_message_burst_only_when_rate_limited() {
+        let mut config = Config::default_config();
+        config.control_plane.message_burst = 0;
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::ZeroValue("control_plane.message_burst")])
+        );
+
+        config.control_plane.max_messages_per_sec = 0;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_metrics_interval_only_when_telemetry_enabled() {
+        let mut config = Config::default_config();
+        config.telemetry.metrics_interval_secs = 0;
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::ZeroValue("telemetry.metrics_interval_secs")])
+        );
+
+        config.telemetry.enabled = false;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_runtime() {
+        let mut config = Config::default_config();
+        config.runtime.runtime_type = "lxc".to_string();
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::UnknownRuntime("lxc".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_resource_limits() {
+        let mut config = Config::default_config();
+        config.runtime.resource_limits = ResourceLimits {
+            max_memory_mb: Some(0),
+            max_cpu_cores: Some(-1.0),
+            max_containers: Some(0),
+            strict: false,
+        };
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().all(|e| matches!(e, ConfigError::InvalidResourceLimit { .. })));
+
+        config.runtime.resource_limits.max_cpu_cores = Some(f64::NAN);
+        assert_eq!(config.validate().unwrap_err().len(), 3);
+    }
+
+    #[test]
+    fn test_validate_local_api_addr_only_when_enabled() {
+        let mut config = Config::default_config();
+        config.local_api.addr = "not-an-addr".to_string();
+        assert_eq!(config.validate(), Ok(()));
+
+        config.local_api.enabled = true;
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::InvalidLocalApiAddr("not-an-addr".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_validate_prometheus_addr() {
+        let mut config = Config::default_config();
+        config.telemetry.prometheus_addr = Some("0.0.0.0:9100".to_string());
+        assert_eq!(config.validate(), Ok(()));
+
+        config.telemetry.prometheus_addr = Some("9100".to_string());
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::InvalidPrometheusAddr("9100".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_validate_collects_all_errors() {
+        let mut config = Config::default_config();
+        config.control_plane.url = "localhost".to_string();
+        config.control_plane.heartbeat_interval_secs = 0;
+        config.runtime.runtime_type = "unknown".to_string();
+        assert_eq!(config.validate().unwrap_err().len(), 3);
+    }
 }
@@ -0,0 +1,164 @@
+//! Daemon Mode
+//!
+//! Detaches the agent from its terminal on Unix: fork, start a new session,
+//! point stdio at the log file, and record the PID in a pidfile that keeps a
+//! second agent from starting alongside it.
+//!
+//! Daemonizing has to happen before the async runtime starts any threads,
+//! since only the forking thread survives a fork.
+
+use anyhow::{bail, Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// A pidfile naming this process, removed again when dropped
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Write this process's PID to `path`, refusing if it names a live process
+    pub fn create(path: &Path) -> Result<Self> {
+        ensure_not_running(path)?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        std::fs::write(path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("Failed to write pidfile: {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Leave the file alone if another agent has taken it over since
+        if read_pid(&self.path).ok().flatten() == Some(std::process::id() as i32) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Fail if the pidfile at `path` names a running process other than this one
+///
+/// A missing, unparsable, or stale pidfile is fine to start over.
+pub fn ensure_not_running(path: &Path) -> Result<()> {
+    if let Some(pid) = read_pid(path)? {
+        if pid != std::process::id() as i32 && process_alive(pid) {
+            bail!(
+                "Agent already running with PID {} (from {})",
+                pid,
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Detach from the terminal and write the pidfile
+///
+/// The original process exits once the daemon is forked. Output goes to
+/// `log_file`, or is discarded without one. The working directory is kept so
+/// relative paths in the configuration still resolve.
+pub fn daemonize(pid_file: &Path, log_file: Option<&Path>) -> Result<PidFile> {
+    ensure_not_running(pid_file)?;
+
+    // Open everything first, so problems are reported on the terminal
+    let null = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")
+        .context("Failed to open /dev/null")?;
+    let output: File = match log_file {
+        Some(path) => OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log file: {}", path.display()))?,
+        None => null.try_clone().context("Failed to open /dev/null")?,
+    };
+
+    // Fork twice around setsid: the new session has no controlling terminal,
+    // and the grandchild, not being its leader, can never acquire one
+    fork_and_exit_parent()?;
+    if unsafe { libc::setsid() } < 0 {
+        return Err(io::Error::last_os_error()).context("Failed to start a new session");
+    }
+    fork_and_exit_parent()?;
+
+    for (file, fd) in [(&null, 0), (&output, 1), (&output, 2)] {
+        if unsafe { libc::dup2(file.as_raw_fd(), fd) } < 0 {
+            return Err(io::Error::last_os_error()).context("Failed to redirect stdio");
+        }
+    }
+
+    PidFile::create(pid_file)
+}
+
+/// Fork, leaving only the child running
+fn fork_and_exit_parent() -> Result<()> {
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()).context("Failed to fork"),
+        0 => Ok(()),
+        _ => std::process::exit(0),
+    }
+}
+
+/// The PID recorded at `path`, if the file exists and holds one
+fn read_pid(path: &Path) -> Result<Option<i32>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(contents.trim().parse().ok()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read pidfile: {}", path.display())),
+    }
+}
+
+/// Whether a process with `pid` exists
+fn process_alive(pid: i32) -> bool {
+    if pid <= 0 {
+        return false;
+    }
+    // Signal 0 checks the process exists without delivering anything; EPERM
+    // means it exists but belongs to another user
+    let signalled = unsafe { libc::kill(pid, 0) == 0 };
+    signalled || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pid_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("syntra-agent-{}-{}.pid", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_refuses_live_pidfile_and_ignores_stale_one() {
+        let path = pid_path("live");
+        // PID 1 always exists
+        std::fs::write(&path, "1\n").unwrap();
+        assert!(ensure_not_running(&path).is_err());
+
+        std::fs::write(&path, "not-a-pid\n").unwrap();
+        assert!(ensure_not_running(&path).is_ok());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(ensure_not_running(&path).is_ok());
+    }
+
+    #[test]
+    fn test_pidfile_written_and_removed() {
+        let path = pid_path("own");
+        let pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(read_pid(&path).unwrap(), Some(std::process::id() as i32));
+        // Our own PID doesn't count as another agent
+        assert!(ensure_not_running(&path).is_ok());
+
+        drop(pid_file);
+        assert!(!path.exists());
+    }
+}
@@ -1,5 +1,9 @@
 //! CLI module
 //!
-//! This module contains CLI-related functionality including configuration.
+//! This module contains CLI-related functionality including configuration,
+//! configuration hot reload, and daemon mode.
 
 pub mod config;
+#[cfg(unix)]
+pub mod daemon;
+pub mod reload;
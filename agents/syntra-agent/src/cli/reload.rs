@@ -0,0 +1,300 @@
+//! Configuration Hot Reload
+//!
+//! Watches the configuration file and, when it changes, loads and validates
+//! the new version. Settings that can change at runtime (log level, heartbeat
+//! and metrics intervals) are published on watch channels for the components
+//! that use them; anything else is logged as needing a restart. An edit that
+//! fails to parse or validate is rejected and the running config kept.
+
+use anyhow::{bail, Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::cli::config::Config;
+
+/// Fields applied to the running agent without a restart
+pub const HOT_RELOADABLE: &[&str] = &[
+    "logging.level",
+    "control_plane.heartbeat_interval_secs",
+    "telemetry.metrics_interval_secs",
+];
+
+/// How long to let a burst of file events settle before reloading
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// What a reload changed, split by whether it took effect
+#[derive(Debug, Default, PartialEq)]
+pub struct ConfigChanges {
+    /// Fields now in effect
+    pub applied: Vec<String>,
+    /// Fields that only take effect after a restart
+    pub needs_restart: Vec<String>,
+}
+
+/// Reloads the configuration file and publishes hot-reloadable settings
+pub struct ConfigWatcher {
+    path: PathBuf,
+    current: Config,
+    log_level: watch::Sender<String>,
+    heartbeat_interval_secs: watch::Sender<u64>,
+    metrics_interval_secs: watch::Sender<u64>,
+}
+
+impl ConfigWatcher {
+    /// Create a watcher for `path`, starting from the already loaded `config`
+    pub fn new(path: &Path, config: Config) -> Self {
+        let (log_level, _) = watch::channel(config.logging.level.clone());
+        let (heartbeat_interval_secs, _) =
+            watch::channel(config.control_plane.heartbeat_interval_secs);
+        let (metrics_interval_secs, _) = watch::channel(config.telemetry.metrics_interval_secs);
+        Self {
+            path: path.to_path_buf(),
+            current: config,
+            log_level,
+            heartbeat_interval_secs,
+            metrics_interval_secs,
+        }
+    }
+
+    /// Receive the log level whenever a reload changes it
+    pub fn log_level(&self) -> watch::Receiver<String> {
+        self.log_level.subscribe()
+    }
+
+    /// Receive the heartbeat interval whenever a reload changes it
+    pub fn heartbeat_interval_secs(&self) -> watch::Receiver<u64> {
+        self.heartbeat_interval_secs.subscribe()
+    }
+
+    /// Receive the metrics interval whenever a reload changes it
+    pub fn metrics_interval_secs(&self) -> watch::Receiver<u64> {
+        self.metrics_interval_secs.subscribe()
+    }
+
+    /// Watch the file in the background, reloading after each change
+    ///
+    /// The containing directory is watched rather than the file itself, since
+    /// many editors save by replacing the file.
+    pub fn spawn(mut self) -> Result<JoinHandle<()>> {
+        let file_name = self
+            .path
+            .file_name()
+            .map(|name| name.to_os_string())
+            .with_context(|| format!("Not a config file path: {}", self.path.display()))?;
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut watcher = notify::recommended_watcher(
+            move |event: notify::Result<notify::Event>| match event {
+                Ok(event) => {
+                    let ours = event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name() == Some(file_name.as_os_str()));
+                    if ours && !matches!(event.kind, EventKind::Access(_)) {
+                        // A full channel already has a reload pending
+                        let _ = tx.try_send(());
+                    }
+                }
+                Err(e) => warn!(error = %e, "Config file watch error"),
+            },
+        )
+        .context("Failed to create config file watcher")?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+        info!(path = %self.path.display(), "Watching configuration for changes");
+
+        Ok(tokio::spawn(async move {
+            // Dropping the watcher would stop events
+            let _watcher: RecommendedWatcher = watcher;
+            while rx.recv().await.is_some() {
+                tokio::time::sleep(DEBOUNCE).await;
+                while rx.try_recv().is_ok() {}
+
+                match self.reload() {
+                    Ok(changes) => log_changes(&changes),
+                    Err(e) => warn!(error = %e, "Keeping the current configuration"),
+                }
+            }
+        }))
+    }
+
+    /// Load and validate the file, then apply whatever can change at runtime
+    ///
+    /// The current configuration is left untouched on error.
+    pub fn reload(&mut self) -> Result<ConfigChanges> {
+        let config = Config::load(&self.path)?;
+        if let Err(errors) = config.validate() {
+            let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            bail!("Invalid configuration: {}", errors.join("; "));
+        }
+
+        let mut changes = ConfigChanges::default();
+        for field in changed_fields(&self.current, &config)? {
+            if HOT_RELOADABLE.contains(&field.as_str()) {
+                changes.applied.push(field);
+            } else {
+                changes.needs_restart.push(field);
+            }
+        }
+
+        let heartbeat_secs = config.control_plane.heartbeat_interval_secs;
+        let metrics_secs = config.telemetry.metrics_interval_secs;
+        self.log_level.send_if_modified(|level| replace(level, &config.logging.level));
+        self.heartbeat_interval_secs.send_if_modified(|secs| replace(secs, &heartbeat_secs));
+        self.metrics_interval_secs.send_if_modified(|secs| replace(secs, &metrics_secs));
+        self.current = config;
+        Ok(changes)
+    }
+}
+
+/// Wait for the next value on an optional update channel
+///
+/// Never resolves without a channel, or once its sender is gone, so it can sit
+/// in a `select!` alongside the work it adjusts.
+pub async fn next_update<T: Clone>(updates: &mut Option<watch::Receiver<T>>) -> T {
+    if let Some(rx) = updates {
+        if rx.changed().await.is_ok() {
+            return rx.borrow_and_update().clone();
+        }
+    }
+    std::future::pending().await
+}
+
+/// Dotted names of every setting that differs between `old` and `new`
+pub fn changed_fields(old: &Config, new: &Config) -> Result<Vec<String>> {
+    let mut old_fields = BTreeMap::new();
+    let mut new_fields = BTreeMap::new();
+    flatten("", &toml::Value::try_from(old)?, &mut old_fields);
+    flatten("", &toml::Value::try_from(new)?, &mut new_fields);
+
+    let mut changed: Vec<String> = old_fields
+        .iter()
+        .filter(|(key, value)| new_fields.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect();
+    changed.extend(new_fields.keys().filter(|key| !old_fields.contains_key(*key)).cloned());
+    changed.sort();
+    Ok(changed)
+}
+
+/// Collect the leaf values of a TOML table under their dotted keys
+fn flatten(prefix: &str, value: &toml::Value, out: &mut BTreeMap<String, toml::Value>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Overwrite `slot` with `value`, reporting whether it changed
+fn replace<T: Clone + PartialEq>(slot: &mut T, value: &T) -> bool {
+    if slot == value {
+        return false;
+    }
+    *slot = value.clone();
+    true
+}
+
+fn log_changes(changes: &ConfigChanges) {
+    if changes.applied.is_empty() && changes.needs_restart.is_empty() {
+        debug!("Configuration file changed, but no settings did");
+        return;
+    }
+    if !changes.applied.is_empty() {
+        info!(fields = ?changes.applied, "Applied configuration changes");
+    }
+    if !changes.needs_restart.is_empty() {
+        warn!(fields = ?changes.needs_restart, "Configuration changes require a restart");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("syntra-agent-{}-{}.toml", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let old = Config::default_config();
+        let mut new = old.clone();
+        assert!(changed_fields(&old, &new).unwrap().is_empty());
+
+        new.logging.level = "debug".to_string();
+        new.runtime.resource_limits.max_memory_mb = Some(512);
+        assert_eq!(
+            changed_fields(&old, &new).unwrap(),
+            vec!["logging.level", "runtime.resource_limits.max_memory_mb"]
+        );
+    }
+
+    #[test]
+    fn test_reload_applies_hot_fields_and_flags_the_rest() {
+        let path = config_path("reload");
+        let config = Config::default_config();
+        config.save(&path).unwrap();
+        let mut watcher = ConfigWatcher::new(&path, config.clone());
+        let mut heartbeat = watcher.heartbeat_interval_secs();
+        let level = watcher.log_level();
+
+        let mut edited = config.clone();
+        edited.control_plane.heartbeat_interval_secs = 5;
+        edited.runtime.max_concurrent_deploys = 8;
+        edited.save(&path).unwrap();
+        let changes = watcher.reload().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            changes,
+            ConfigChanges {
+                applied: vec!["control_plane.heartbeat_interval_secs".to_string()],
+                needs_restart: vec!["runtime.max_concurrent_deploys".to_string()],
+            }
+        );
+        assert!(heartbeat.has_changed().unwrap());
+        assert_eq!(*heartbeat.borrow_and_update(), 5);
+        assert!(!level.has_changed().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_edit_keeps_current_config() {
+        let path = config_path("invalid");
+        let config = Config::default_config();
+        let mut watcher = ConfigWatcher::new(&path, config.clone());
+        let heartbeat = watcher.heartbeat_interval_secs();
+
+        let mut edited = config.clone();
+        edited.control_plane.heartbeat_interval_secs = 0;
+        edited.save(&path).unwrap();
+        assert!(watcher.reload().is_err());
+
+        std::fs::write(&path, "not = [valid").unwrap();
+        assert!(watcher.reload().is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!heartbeat.has_changed().unwrap());
+        assert!(changed_fields(&watcher.current, &config).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+//! Message Acknowledgement
+//!
+//! Tracks critical agent messages (task results and container status) until
+//! the control plane acknowledges them, handing back any that time out so
+//! they can be resent.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::connection::protocol::AgentMessage;
+
+/// A sent message awaiting acknowledgement
+struct PendingMessage {
+    message: AgentMessage,
+    sent_at: Instant,
+    resends: u32,
+}
+
+/// Tracker for unacknowledged critical messages
+pub struct AckTracker {
+    pending: Mutex<HashMap<String, PendingMessage>>,
+    timeout: Duration,
+    max_retries: u32,
+}
+
+impl AckTracker {
+    /// Create a tracker that resends after `timeout`, at most `max_retries` times
+    pub fn new(timeout: Duration, max_retries: u32) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            timeout,
+            max_retries,
+        }
+    }
+
+    /// Record that a message was sent
+    ///
+    /// Messages without a message ID are not tracked. A resent message keeps
+    /// its retry count and has its timer restarted.
+    pub fn track(&self, message: &AgentMessage) {
+        let Some(message_id) = message.message_id() else {
+            return;
+        };
+
+        self.pending
+            .lock()
+            .entry(message_id.to_string())
+            .and_modify(|pending| pending.sent_at = Instant::now())
+            .or_insert_with(|| PendingMessage {
+                message: message.clone(),
+                sent_at: Instant::now(),
+                resends: 0,
+            });
+    }
+
+    /// Clear a pending message, returning whether it was being tracked
+    pub fn ack(&self, message_id: &str) -> bool {
+        self.pending.lock().remove(message_id).is_some()
+    }
+
+    /// Number of messages awaiting acknowledgement
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().len()
+    }
+
+    /// Collect timed-out messages that should be resent
+    ///
+    /// Messages that have used up their retries are dropped with a warning.
+    pub fn take_expired(&self) -> Vec<AgentMessage> {
+        let mut pending = self.pending.lock();
+        let mut resend = Vec::new();
+
+        pending.retain(|message_id, entry| {
+            if entry.sent_at.elapsed() < self.timeout {
+                return true;
+            }
+            if entry.resends >= self.max_retries {
+                warn!(
+                    message_id = %message_id,
+                    retries = entry.resends,
+                    "Message was never acknowledged, giving up"
+                );
+                return false;
+            }
+
+            entry.resends += 1;
+            entry.sent_at = Instant::now();
+            resend.push(entry.message.clone());
+            true
+        });
+
+        resend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::protocol::test_messages;
+
+    fn task_result() -> AgentMessage {
+        let mut message = test_messages::task_result("task-1");
+        message.ensure_message_id();
+        message
+    }
+
+    #[test]
+    fn test_ack_clears_pending() {
+        let tracker = AckTracker::new(Duration::from_secs(10), 3);
+        let message = task_result();
+        tracker.track(&message);
+        assert_eq!(tracker.pending_count(), 1);
+
+        assert!(tracker.ack(message.message_id().unwrap()));
+        assert!(!tracker.ack(message.message_id().unwrap()));
+        assert_eq!(tracker.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_unacked_messages_are_resent_then_dropped() {
+        let tracker = AckTracker::new(Duration::ZERO, 2);
+        let message = task_result();
+        tracker.track(&message);
+
+        assert_eq!(tracker.take_expired().len(), 1);
+        assert_eq!(tracker.take_expired().len(), 1);
+        // Retries exhausted
+        assert!(tracker.take_expired().is_empty());
+        assert_eq!(tracker.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_untagged_messages_are_not_tracked() {
+        let tracker = AckTracker::new(Duration::from_secs(10), 3);
+        tracker.track(&AgentMessage::heartbeat("agent-1", 0, 0, 0.0, 0.0, false));
+        assert_eq!(tracker.pending_count(), 0);
+    }
+}
@@ -0,0 +1,84 @@
+//! Outbound Message Channel
+//!
+//! Handlers hand their messages to the WebSocket client over a bounded mpsc
+//! channel, which a background task drains into the outbox. The channel only
+//! fills if that task falls behind; when it does, metrics and logs are
+//! dropped so a flood of them can't stall a deployment, while task results
+//! and status updates wait for room so they're never lost.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::mpsc::error::{SendError, TrySendError};
+use tokio::sync::mpsc::Sender;
+use tracing::warn;
+
+use crate::connection::protocol::AgentMessage;
+
+/// Default number of messages the channel holds before senders are held back
+pub const DEFAULT_MESSAGE_CHANNEL_CAPACITY: usize = 100;
+
+/// Times a message found the channel full since the agent started
+static CHANNEL_FULL: AtomicU64 = AtomicU64::new(0);
+
+/// Send a message to the client, shedding low-priority messages if the
+/// channel is full
+///
+/// Fails only once the client has stopped receiving, handing the message
+/// back boxed.
+pub async fn send(
+    tx: &Sender<AgentMessage>,
+    message: AgentMessage,
+) -> Result<(), Box<SendError<AgentMessage>>> {
+    let message = match tx.try_send(message) {
+        Ok(()) => return Ok(()),
+        Err(TrySendError::Closed(message)) => return Err(Box::new(SendError(message))),
+        Err(TrySendError::Full(message)) => message,
+    };
+
+    CHANNEL_FULL.fetch_add(1, Ordering::Relaxed);
+    if message.is_low_priority() {
+        warn!(capacity = tx.max_capacity(), "Message channel full, dropping low-priority message");
+        return Ok(());
+    }
+    warn!(capacity = tx.max_capacity(), "Message channel full, waiting for room");
+    tx.send(message).await.map_err(Box::new)
+}
+
+/// Times a message found the channel full since the agent started
+pub fn full_count() -> u64 {
+    CHANNEL_FULL.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::protocol::test_messages::{metrics, task_result};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_full_channel_drops_metrics_and_waits_for_task_results() {
+        let (tx, mut rx) = mpsc::channel(1);
+        send(&tx, metrics(serde_json::json!({}))).await.unwrap();
+        let full_before = full_count();
+
+        // Dropped without waiting
+        send(&tx, metrics(serde_json::json!({}))).await.unwrap();
+        assert!(full_count() > full_before);
+
+        // Waits until the receiver makes room
+        let blocked = tokio::spawn(async move { send(&tx, task_result("t1")).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!blocked.is_finished());
+
+        assert!(matches!(rx.recv().await, Some(AgentMessage::Metrics(_))));
+        blocked.await.unwrap().unwrap();
+        assert!(matches!(rx.recv().await, Some(AgentMessage::TaskResult(_))));
+    }
+
+    #[tokio::test]
+    async fn test_send_fails_once_closed() {
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+        assert!(send(&tx, task_result("t1")).await.is_err());
+    }
+}
@@ -0,0 +1,120 @@
+//! Message Compression
+//!
+//! tungstenite doesn't implement permessage-deflate, so large messages are
+//! compressed with zstd at the message level instead. The agent offers it with
+//! the `x-syntra-compression: zstd` header on the upgrade request and only
+//! compresses once the control plane echoes the header back; control planes
+//! that ignore the header keep receiving uncompressed frames. Compressed
+//! messages travel as binary frames, recognised by the zstd magic number.
+
+use anyhow::{bail, Context, Result};
+use tokio_tungstenite::tungstenite::http::{HeaderMap, HeaderValue};
+use tracing::debug;
+
+/// Header offering (on the request) and accepting (on the response) compression
+pub const COMPRESSION_HEADER: &str = "x-syntra-compression";
+
+/// The only compression scheme offered
+pub const ZSTD: &str = "zstd";
+
+/// Messages shorter than this are sent uncompressed
+pub const MIN_COMPRESSED_BYTES: usize = 1024;
+
+/// zstd compression level, favouring speed over ratio
+const LEVEL: i32 = 3;
+
+/// Magic number every zstd frame starts with
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Offer compression on an upgrade request
+pub fn offer(headers: &mut HeaderMap) {
+    headers.insert(COMPRESSION_HEADER, HeaderValue::from_static(ZSTD));
+}
+
+/// Whether the control plane accepted compression in its upgrade response
+pub fn accepted(headers: &HeaderMap) -> bool {
+    headers
+        .get(COMPRESSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case(ZSTD))
+}
+
+/// Compress an encoded message, or `None` if it's too small to be worth it
+pub fn compress(data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < MIN_COMPRESSED_BYTES {
+        return None;
+    }
+
+    match zstd::encode_all(data, LEVEL) {
+        Ok(compressed) if compressed.len() < data.len() => {
+            debug!(
+                original_bytes = data.len(),
+                compressed_bytes = compressed.len(),
+                ratio = %format_args!("{:.2}", data.len() as f64 / compressed.len() as f64),
+                "Compressed message"
+            );
+            Some(compressed)
+        }
+        Ok(_) => None,
+        Err(e) => {
+            debug!(error = %e, "Failed to compress message, sending uncompressed");
+            None
+        }
+    }
+}
+
+/// Whether a binary frame holds a compressed message
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.starts_with(&ZSTD_MAGIC)
+}
+
+/// Decompress a binary frame back into the encoded message it carries
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    if !is_compressed(data) {
+        bail!("Binary message is not zstd-compressed");
+    }
+    zstd::decode_all(data).context("Failed to decompress message")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn large_message() -> String {
+        let containers: Vec<_> = (0..200)
+            .map(|i| serde_json::json!({ "id": format!("container-{}", i), "status": "running" }))
+            .collect();
+        serde_json::json!({ "type": "StatusResponse", "payload": { "containers": containers } })
+            .to_string()
+    }
+
+    #[test]
+    fn test_large_message_round_trips() {
+        let json = large_message();
+        let data = compress(json.as_bytes()).expect("Expected the message to be compressed");
+        assert!(data.len() < json.len());
+        assert!(is_compressed(&data));
+        assert_eq!(decompress(&data).unwrap(), json.as_bytes());
+    }
+
+    #[test]
+    fn test_small_messages_stay_uncompressed() {
+        assert!(compress(br#"{"type":"Heartbeat"}"#).is_none());
+    }
+
+    #[test]
+    fn test_decompress_rejects_other_binary() {
+        assert!(!is_compressed(b"not compressed"));
+        assert!(decompress(b"not compressed").is_err());
+    }
+
+    #[test]
+    fn test_negotiation() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepted(&headers));
+        offer(&mut headers);
+        assert!(accepted(&headers));
+        headers.insert(COMPRESSION_HEADER, HeaderValue::from_static("gzip"));
+        assert!(!accepted(&headers));
+    }
+}
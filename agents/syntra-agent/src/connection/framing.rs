@@ -0,0 +1,102 @@
+//! Message Framing
+//!
+//! Turns agent messages into WebSocket frames and control plane frames back
+//! into messages, combining the negotiated encoding with compression. JSON
+//! travels in text frames, MessagePack and compressed messages in binary
+//! frames. Binary frames are told apart by content: the zstd magic number
+//! marks a compressed message, and a payload starting with `{` is JSON.
+
+use anyhow::{Context, Result};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::connection::compression;
+use crate::connection::protocol::{AgentMessage, ControlPlaneMessage, Encoding};
+
+/// How messages are written on the current connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Framing {
+    pub encoding: Encoding,
+    /// Whether the control plane accepted compression of large messages
+    pub compress: bool,
+}
+
+impl Framing {
+    /// Encode a message into the frame it's sent as
+    pub fn frame(&self, message: &AgentMessage) -> Result<Message> {
+        let codec = self.encoding.codec();
+        let data = codec.encode(message)?;
+
+        if self.compress {
+            if let Some(compressed) = compression::compress(&data) {
+                return Ok(Message::Binary(compressed));
+            }
+        }
+        if codec.is_binary() {
+            return Ok(Message::Binary(data));
+        }
+        String::from_utf8(data)
+            .map(Message::Text)
+            .context("Encoded message is not UTF-8")
+    }
+}
+
+/// Decode the payload of a text or binary frame from the control plane
+pub fn decode(data: &[u8]) -> Result<ControlPlaneMessage> {
+    let decompressed;
+    let data = if compression::is_compressed(data) {
+        decompressed = compression::decompress(data)?;
+        decompressed.as_slice()
+    } else {
+        data
+    };
+
+    // A MessagePack map never starts with `{` or whitespace
+    let is_json = data.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{');
+    let encoding = if is_json { Encoding::Json } else { Encoding::Msgpack };
+    encoding.codec().decode(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::protocol::test_messages::metrics;
+
+    fn large_metrics() -> AgentMessage {
+        let containers: Vec<_> = (0..200)
+            .map(|i| serde_json::json!({ "id": format!("container-{}", i), "cpu_percent": 1.5 }))
+            .collect();
+        metrics(serde_json::json!({ "containers": containers }))
+    }
+
+    #[test]
+    fn test_frame_types() {
+        let heartbeat = AgentMessage::heartbeat("agent-123", 42, 3, 12.5, 40.0, false);
+        let json = Framing::default();
+        assert!(matches!(json.frame(&heartbeat).unwrap(), Message::Text(_)));
+
+        let msgpack = Framing { encoding: Encoding::Msgpack, compress: true };
+        let Message::Binary(data) = msgpack.frame(&heartbeat).unwrap() else {
+            panic!("Expected a binary frame");
+        };
+        assert!(!compression::is_compressed(&data));
+
+        let compressed = Framing { encoding: Encoding::Json, compress: true };
+        let Message::Binary(data) = compressed.frame(&large_metrics()).unwrap() else {
+            panic!("Expected a compressed binary frame");
+        };
+        assert!(compression::is_compressed(&data));
+    }
+
+    #[test]
+    fn test_decode_detects_encoding() {
+        let json = r#"{"type":"Ack","payload":
+            {"message_id":"msg-1","timestamp":"2024-01-01T00:00:00Z"}}"#;
+        let ack: ControlPlaneMessage = serde_json::from_str(json).unwrap();
+        let msgpack = rmp_serde::to_vec_named(&ack).unwrap();
+        let compressed = zstd::encode_all(msgpack.as_slice(), 3).unwrap();
+
+        for data in [json.as_bytes(), msgpack.as_slice(), compressed.as_slice()] {
+            assert!(matches!(decode(data).unwrap(), ControlPlaneMessage::Ack(_)));
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Heartbeat Watchdog
+//!
+//! Counts heartbeats the control plane hasn't acknowledged. When acks stop
+//! arriving the session is stalled at the application layer, even if the
+//! TCP connection still looks healthy, and should be re-established.
+
+use parking_lot::Mutex;
+use std::time::Instant;
+
+/// Default number of unacknowledged heartbeats before the session is unhealthy
+pub const DEFAULT_MAX_MISSED_HEARTBEAT_ACKS: u32 = 3;
+
+struct WatchdogState {
+    unacked: u32,
+    last_ack: Option<Instant>,
+}
+
+/// Tracker for heartbeats awaiting a HeartbeatAck
+pub struct HeartbeatWatchdog {
+    max_missed: u32,
+    state: Mutex<WatchdogState>,
+}
+
+impl HeartbeatWatchdog {
+    /// Create a watchdog that gives up after `max_missed` unacknowledged heartbeats
+    pub fn new(max_missed: u32) -> Self {
+        Self {
+            max_missed: max_missed.max(1),
+            state: Mutex::new(WatchdogState {
+                unacked: 0,
+                last_ack: None,
+            }),
+        }
+    }
+
+    /// Start counting afresh for a new session
+    pub fn reset(&self) {
+        let mut state = self.state.lock();
+        state.unacked = 0;
+        state.last_ack = None;
+    }
+
+    /// Count a heartbeat about to be sent
+    ///
+    /// Returns `false` instead once `max_missed` earlier heartbeats have gone
+    /// unacknowledged, meaning the session should be torn down.
+    pub fn heartbeat_sent(&self) -> bool {
+        let mut state = self.state.lock();
+        if state.unacked >= self.max_missed {
+            return false;
+        }
+        state.unacked += 1;
+        true
+    }
+
+    /// Record a HeartbeatAck, clearing the missed count
+    pub fn ack_received(&self) {
+        let mut state = self.state.lock();
+        state.unacked = 0;
+        state.last_ack = Some(Instant::now());
+    }
+
+    /// Heartbeats sent since the last ack
+    pub fn missed(&self) -> u32 {
+        self.state.lock().unacked
+    }
+
+    /// When the last HeartbeatAck arrived this session
+    pub fn last_ack(&self) -> Option<Instant> {
+        self.state.lock().last_ack
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unhealthy_after_max_missed_acks() {
+        let watchdog = HeartbeatWatchdog::new(3);
+        assert!(watchdog.heartbeat_sent());
+        assert!(watchdog.heartbeat_sent());
+        assert!(watchdog.heartbeat_sent());
+        assert_eq!(watchdog.missed(), 3);
+        assert!(!watchdog.heartbeat_sent());
+        assert!(watchdog.last_ack().is_none());
+    }
+
+    #[test]
+    fn test_ack_clears_missed_count() {
+        let watchdog = HeartbeatWatchdog::new(2);
+        assert!(watchdog.heartbeat_sent());
+        assert!(watchdog.heartbeat_sent());
+        watchdog.ack_received();
+        assert_eq!(watchdog.missed(), 0);
+        assert!(watchdog.last_ack().is_some());
+        assert!(watchdog.heartbeat_sent());
+
+        watchdog.reset();
+        assert_eq!(watchdog.missed(), 0);
+        assert!(watchdog.last_ack().is_none());
+    }
+}
@@ -3,5 +3,12 @@
 //! This module handles all communication with the control plane,
 //! including WebSocket connections and message protocol handling.
 
+pub mod ack;
+pub mod channel;
+pub mod compression;
+pub mod framing;
+pub mod heartbeat;
+pub mod outbox;
 pub mod protocol;
+pub mod rate_limit;
 pub mod websocket;
@@ -0,0 +1,307 @@
+//! Outbound Message Queue
+//!
+//! Buffers agent messages independently of any single WebSocket connection,
+//! so status updates and task results produced while disconnected are
+//! delivered in order once the agent reconnects. With acknowledgements
+//! enabled, critical messages are also resent until the control plane acks
+//! them. With a rate limit, messages over the limit stay queued, and metrics
+//! and logs are dropped first so task results get through.
+
+use anyhow::Result;
+use futures_util::{Sink, SinkExt};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+use crate::connection::ack::AckTracker;
+use crate::connection::framing::Framing;
+use crate::connection::protocol::AgentMessage;
+use crate::connection::rate_limit::TokenBucket;
+
+/// Default number of messages retained while disconnected
+pub const DEFAULT_OUTBOX_CAPACITY: usize = 1000;
+
+/// Bounded FIFO of messages waiting to be sent to the control plane
+pub struct Outbox {
+    queue: Mutex<VecDeque<AgentMessage>>,
+    capacity: usize,
+    notify: Notify,
+    acks: Option<AckTracker>,
+    limiter: Option<Mutex<TokenBucket>>,
+    /// Whether the last flush stopped at the rate limit
+    throttled: Mutex<bool>,
+}
+
+impl Outbox {
+    /// Create an outbox holding at most `capacity` messages
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+            notify: Notify::new(),
+            acks: None,
+            limiter: None,
+            throttled: Mutex::new(false),
+        }
+    }
+
+    /// Send at most `rate` messages per second, with bursts of up to `burst`
+    pub fn with_rate_limit(mut self, rate: u32, burst: u32) -> Self {
+        self.limiter = Some(Mutex::new(TokenBucket::new(rate, burst)));
+        self
+    }
+
+    /// Track critical messages until they're acknowledged
+    pub fn with_acks(mut self, acks: AckTracker) -> Self {
+        self.acks = Some(acks);
+        self
+    }
+
+    /// Handle an acknowledgement, returning whether the message was pending
+    pub fn ack(&self, message_id: &str) -> bool {
+        self.acks.as_ref().is_some_and(|acks| acks.ack(message_id))
+    }
+
+    /// Queue unacknowledged messages whose ack timeout has passed for resending
+    pub fn requeue_unacked(&self) -> usize {
+        let Some(acks) = &self.acks else {
+            return 0;
+        };
+
+        let expired = acks.take_expired();
+        let count = expired.len();
+        for message in expired {
+            self.push(message);
+        }
+        count
+    }
+
+    /// Queue a message, dropping the oldest one if the outbox is full
+    pub fn push(&self, mut message: AgentMessage) {
+        if self.acks.is_some() {
+            message.ensure_message_id();
+        }
+
+        {
+            let mut queue = self.queue.lock();
+            if queue.len() >= self.capacity {
+                queue.pop_front();
+                warn!(capacity = self.capacity, "Outbox full, dropped oldest message");
+            }
+            queue.push_back(message);
+        }
+        self.notify.notify_one();
+    }
+
+    /// Number of queued messages
+    pub fn len(&self) -> usize {
+        self.queue.lock().len()
+    }
+
+    /// Whether the outbox is empty
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().is_empty()
+    }
+
+    /// Wait until a message is pushed
+    pub async fn notified(&self) {
+        self.notify.notified().await
+    }
+
+    /// Wait until messages held back by the rate limit may be sent
+    ///
+    /// Never resolves while nothing is being held back.
+    pub async fn throttle_released(&self) {
+        let delay = match &self.limiter {
+            Some(limiter) if *self.throttled.lock() => limiter.lock().ready_in(),
+            _ => return std::future::pending().await,
+        };
+        tokio::time::sleep(delay).await
+    }
+
+    /// Move every message received on `rx` into the outbox
+    pub async fn forward(self: Arc<Self>, mut rx: mpsc::Receiver<AgentMessage>) {
+        while let Some(message) = rx.recv().await {
+            self.push(message);
+        }
+    }
+
+    /// Send queued messages in order, returning how many were sent
+    ///
+    /// Messages are framed with the connection's `framing`. A message whose
+    /// send fails is put back at the front of the queue so it's retried first
+    /// on the next connection.
+    pub async fn flush<S>(&self, sink: &mut S, framing: Framing) -> Result<usize>
+    where
+        S: Sink<Message> + Unpin,
+        S::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let mut sent = 0;
+        loop {
+            let next = self.queue.lock().pop_front();
+            let Some(message) = next else {
+                self.set_throttled(false);
+                return Ok(sent);
+            };
+
+            if let Some(limiter) = &self.limiter {
+                if !limiter.lock().try_take() {
+                    self.queue.lock().push_front(message);
+                    self.set_throttled(true);
+                    return Ok(sent);
+                }
+            }
+
+            let frame = match framing.frame(&message) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    warn!(error = %e, "Dropping unserializable message");
+                    continue;
+                }
+            };
+
+            if let Err(e) = sink.send(frame).await {
+                self.queue.lock().push_front(message);
+                return Err(e.into());
+            }
+            if let Some(acks) = &self.acks {
+                acks.track(&message);
+            }
+            sent += 1;
+        }
+    }
+}
+
+impl Outbox {
+    /// Record whether sending is held back by the rate limit, shedding
+    /// low-priority messages when throttling starts
+    fn set_throttled(&self, throttled: bool) {
+        let was_throttled = std::mem::replace(&mut *self.throttled.lock(), throttled);
+        if throttled && !was_throttled {
+            let dropped = {
+                let mut queue = self.queue.lock();
+                let before = queue.len();
+                queue.retain(|message| !message.is_low_priority());
+                before - queue.len()
+            };
+            warn!(
+                queued = self.len(),
+                dropped,
+                "Outbound rate limit reached, throttling messages to the control plane"
+            );
+        } else if was_throttled && !throttled {
+            info!("Outbound messages back under the rate limit");
+        }
+    }
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Self::new(DEFAULT_OUTBOX_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::protocol::test_messages::{metrics, task_result};
+    use futures_util::sink;
+
+    fn sent_task_ids(sent: &[Message]) -> Vec<String> {
+        sent.iter()
+            .map(|m| {
+                let value: serde_json::Value = serde_json::from_str(m.to_text().unwrap()).unwrap();
+                value["payload"]["task_id"].as_str().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_messages_survive_disconnect() {
+        let outbox = Outbox::default();
+        outbox.push(task_result("t1"));
+        outbox.push(task_result("t2"));
+
+        // First connection dies after delivering one message
+        let mut dead = Box::pin(sink::unfold(0, |count, _: Message| async move {
+            if count == 0 {
+                Ok(count + 1)
+            } else {
+                Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+            }
+        }));
+        assert!(outbox.flush(&mut dead, Framing::default()).await.is_err());
+        assert_eq!(outbox.len(), 1);
+
+        // Produced while disconnected
+        outbox.push(task_result("t3"));
+
+        let mut reconnected: Vec<Message> = Vec::new();
+        assert_eq!(outbox.flush(&mut reconnected, Framing::default()).await.unwrap(), 2);
+        assert_eq!(sent_task_ids(&reconnected), vec!["t2", "t3"]);
+        assert!(outbox.is_empty());
+    }
+
+    #[test]
+    fn test_full_outbox_drops_oldest() {
+        let outbox = Outbox::new(2);
+        outbox.push(task_result("t1"));
+        outbox.push(task_result("t2"));
+        outbox.push(task_result("t3"));
+
+        let queued: Vec<String> = outbox
+            .queue
+            .lock()
+            .iter()
+            .map(|m| match m {
+                AgentMessage::TaskResult(p) => p.task_id.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(queued, vec!["t2", "t3"]);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_sheds_metrics_before_task_results() {
+        let outbox = Outbox::default().with_rate_limit(1, 2);
+        outbox.push(task_result("t1"));
+        outbox.push(metrics(serde_json::json!({})));
+        outbox.push(task_result("t2"));
+        outbox.push(metrics(serde_json::json!({})));
+        outbox.push(task_result("t3"));
+
+        // The burst covers two messages; the queued metrics are then dropped
+        let mut sent: Vec<Message> = Vec::new();
+        assert_eq!(outbox.flush(&mut sent, Framing::default()).await.unwrap(), 2);
+        assert_eq!(outbox.len(), 2);
+        assert!(outbox.queue.lock().iter().all(|m| !m.is_low_priority()));
+
+        // Held-back messages go out once tokens refill
+        tokio::time::timeout(std::time::Duration::from_secs(5), outbox.throttle_released())
+            .await
+            .unwrap();
+        assert_eq!(outbox.flush(&mut sent, Framing::default()).await.unwrap(), 1);
+        assert_eq!(sent.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unacked_messages_are_requeued() {
+        let outbox = Outbox::default().with_acks(AckTracker::new(std::time::Duration::ZERO, 1));
+        outbox.push(task_result("t1"));
+
+        let mut sent: Vec<Message> = Vec::new();
+        outbox.flush(&mut sent, Framing::default()).await.unwrap();
+        assert_eq!(outbox.requeue_unacked(), 1);
+
+        outbox.flush(&mut sent, Framing::default()).await.unwrap();
+        assert_eq!(sent_task_ids(&sent), vec!["t1", "t1"]);
+
+        let value: serde_json::Value = serde_json::from_str(sent[0].to_text().unwrap()).unwrap();
+        let message_id = value["payload"]["message_id"].as_str().unwrap();
+        assert!(outbox.ack(message_id));
+        assert_eq!(outbox.requeue_unacked(), 0);
+    }
+}
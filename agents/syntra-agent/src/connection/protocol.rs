@@ -1,9 +1,23 @@
 //! Message Protocol
 //!
-//! Defines the message types exchanged between the agent and control plane.
+//! Defines the message types exchanged between the agent and control plane,
+//! and the codecs they can be encoded with on the wire.
 
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::agent::state::StateTransition;
+use crate::runtime::adapter::{
+    ContainerInfo, ContainerStats, DiskUsage, GpuSpec, MountSpec, RegistryAuth, RestartPolicy,
+    Ulimit,
+};
+
+/// Version of the wire protocol this agent speaks
+///
+/// Bump it whenever a change would break an older control plane or agent.
+pub const PROTOCOL_VERSION: u32 = 1;
 
 /// Messages sent from the agent to the control plane
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +35,9 @@ pub enum AgentMessage {
     /// Container status update
     ContainerStatus(ContainerStatusPayload),
 
+    /// Step reached by a deployment, for a progress timeline
+    DeployEvent(DeployEventPayload),
+
     /// Metrics report
     Metrics(MetricsPayload),
 
@@ -32,6 +49,12 @@ pub enum AgentMessage {
 
     /// Acknowledgement of a control plane message
     Ack(AckPayload),
+
+    /// Response to a control plane status request
+    StatusResponse(StatusResponsePayload),
+
+    /// Request for the latest configuration, sent when the agent's is out of date
+    ConfigRequest(ConfigRequestPayload),
 }
 
 /// Messages sent from the control plane to the agent
@@ -47,8 +70,14 @@ pub enum ControlPlaneMessage {
     /// Task execution request
     TaskRequest(TaskRequestPayload),
 
-    /// Container deployment request
-    DeployContainer(DeployContainerPayload),
+    /// Stop a running task, such as a followed `logs` task
+    CancelTask(CancelTaskPayload),
+
+    /// Acknowledgement of a critical agent message
+    Ack(AckPayload),
+
+    /// Container deployment request, boxed since it dwarfs the other messages
+    DeployContainer(Box<DeployContainerPayload>),
 
     /// Container stop request
     StopContainer(StopContainerPayload),
@@ -64,6 +93,15 @@ pub enum ControlPlaneMessage {
 
     /// Error from control plane
     Error(ErrorPayload),
+
+    /// The control plane doesn't support the agent's protocol version
+    VersionMismatch(VersionMismatchPayload),
+
+    /// Stop accepting new deployments, keeping existing containers running
+    Drain(CordonPayload),
+
+    /// Accept new deployments again after a drain
+    Uncordon(CordonPayload),
 }
 
 // Agent Message Payloads
@@ -73,6 +111,12 @@ pub struct RegisterPayload {
     pub agent_id: String,
     pub server_id: String,
     pub version: String,
+    /// Wire protocol version, 0 for agents from before it was reported
+    #[serde(default)]
+    pub protocol_version: u32,
+    /// Encodings other than JSON the agent offers to switch to, preferred first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub encodings: Vec<Encoding>,
     pub capabilities: Vec<String>,
     pub runtime_type: String,
     pub hostname: String,
@@ -87,6 +131,9 @@ pub struct HeartbeatPayload {
     pub container_count: u32,
     pub cpu_usage: f64,
     pub memory_usage: f64,
+    /// Whether the agent is draining and rejects new deployments
+    #[serde(default)]
+    pub draining: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +145,30 @@ pub struct TaskResultPayload {
     pub error: Option<String>,
     pub duration_ms: u64,
     pub timestamp: DateTime<Utc>,
+    /// Correlation ID the control plane echoes back in an Ack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+}
+
+/// Step of a deployment reported in its timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeployPhase {
+    PullStarted,
+    PullDone,
+    Created,
+    Started,
+    /// Running, and passing its health check if it has one
+    Healthy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeployEventPayload {
+    pub request_id: String,
+    /// Container name, telling the replicas of one request apart
+    pub name: String,
+    pub phase: DeployPhase,
+    pub timestamp: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -106,8 +177,18 @@ pub struct ContainerStatusPayload {
     pub name: String,
     pub status: String,
     pub health: Option<String>,
+    /// When the container last started (RFC 3339), for uptime and restart
+    /// tracking
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
     pub ports: Vec<PortMapping>,
+    /// Registry digest of the image the container runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_digest: Option<String>,
     pub timestamp: DateTime<Utc>,
+    /// Correlation ID the control plane echoes back in an Ack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,12 +221,43 @@ pub struct ErrorPayload {
     pub timestamp: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigRequestPayload {
+    pub agent_id: String,
+    /// Version the agent last applied; `None` before its first config update
+    pub current_version: Option<String>,
+    /// Version the control plane advertised in its welcome
+    pub latest_version: String,
+    pub timestamp: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AckPayload {
     pub message_id: String,
     pub timestamp: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusResponsePayload {
+    pub request_id: String,
+    pub agent_id: String,
+    pub state: String,
+    pub uptime_secs: u64,
+    pub connection_attempts: u32,
+    pub last_connected: Option<DateTime<Utc>>,
+    /// Running containers, present when `include_containers` was requested
+    pub containers: Option<Vec<ContainerInfo>>,
+    /// Per-container stats keyed by container ID, present when `include_metrics` was requested
+    pub metrics: Option<HashMap<String, ContainerStats>>,
+    /// Runtime disk usage, present when `include_disk_usage` was requested
+    #[serde(default)]
+    pub disk_usage: Option<DiskUsage>,
+    /// Recent connection state transitions, newest first
+    #[serde(default)]
+    pub transitions: Vec<StateTransition>,
+    pub timestamp: DateTime<Utc>,
+}
+
 // Control Plane Message Payloads
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,6 +266,10 @@ pub struct WelcomePayload {
     pub session_id: String,
     pub server_time: DateTime<Utc>,
     pub config_version: String,
+    /// Encoding picked from the agent's offer for the rest of the session,
+    /// JSON if absent
+    #[serde(default)]
+    pub encoding: Option<Encoding>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,16 +287,127 @@ pub struct TaskRequestPayload {
     pub priority: Option<i32>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelTaskPayload {
+    pub task_id: String,
+    pub timestamp: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeployContainerPayload {
     pub request_id: String,
     pub image: String,
+    /// Pull the image by this digest (`sha256:...`) and verify it after pulling
+    #[serde(default)]
+    pub image_digest: Option<String>,
+    /// Pull even when the image is present locally; defaults to `true` for tags
+    /// and `false` for digest-pinned images, whose content can't change
+    #[serde(default)]
+    pub force_pull: Option<bool>,
     pub name: String,
+    /// Overrides the image's entrypoint; the image default is kept when unset
+    #[serde(default)]
+    pub entrypoint: Option<Vec<String>>,
+    /// Overrides the image's default command
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+    /// Overrides the image's working directory
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// Overrides the image's user: `uid`, `uid:gid`, or a user name from the image
+    #[serde(default)]
+    pub user: Option<String>,
     pub env: Option<Vec<EnvVar>>,
     pub ports: Option<Vec<PortMapping>>,
+    /// Host bind mounts; kept for older control planes, prefer `mounts`
     pub volumes: Option<Vec<VolumeMount>>,
+    /// Bind, named volume, and tmpfs mounts
+    #[serde(default)]
+    pub mounts: Option<Vec<MountSpec>>,
+    /// Files mounted read-only from memory, for secrets that shouldn't be
+    /// passed as environment variables
+    #[serde(default)]
+    pub secret_files: Vec<SecretFile>,
+    /// Networks to attach, primary first; the agent's default network when unset
+    #[serde(default)]
+    pub networks: Option<Vec<String>>,
+    /// Extra `/etc/hosts` entries, as `host:ip`
+    #[serde(default)]
+    pub extra_hosts: Vec<String>,
+    /// DNS servers for the container
+    #[serde(default)]
+    pub dns: Vec<String>,
+    /// Container hostname
+    #[serde(default)]
+    pub hostname: Option<String>,
+    /// Linux capabilities to grant
+    #[serde(default)]
+    pub cap_add: Vec<String>,
+    /// Linux capabilities to remove
+    #[serde(default)]
+    pub cap_drop: Vec<String>,
+    /// Run privileged; refused unless the agent sets `runtime.allow_privileged`
+    #[serde(default)]
+    pub privileged: bool,
+    /// Log driver; the agent's `runtime.container_logs` default when unset
+    #[serde(default)]
+    pub log_driver: Option<String>,
+    /// Log driver options, merged over the agent's defaults when `log_driver` is unset
+    #[serde(default)]
+    pub log_options: HashMap<String, String>,
+    /// NVIDIA GPUs to expose; refused unless the agent sets `runtime.allow_gpu`
+    #[serde(default)]
+    pub gpus: Option<GpuSpec>,
+    /// Process resource limits; each soft limit must not exceed its hard limit
+    #[serde(default)]
+    pub ulimits: Vec<Ulimit>,
+    /// Namespaced kernel parameters
+    #[serde(default)]
+    pub sysctls: HashMap<String, String>,
+    /// Size of `/dev/shm` in MB; the runtime default (64MB) when unset
+    #[serde(default)]
+    pub shm_size_mb: Option<u64>,
     pub resources: Option<ResourceSpec>,
     pub health_check: Option<HealthCheck>,
+    pub registry_auth: Option<RegistryAuth>,
+    #[serde(default)]
+    pub strategy: DeployStrategy,
+    /// Restart policy for the container (`UnlessStopped` when unset)
+    #[serde(default)]
+    pub restart_policy: Option<RestartPolicy>,
+    /// Restarts attempted before giving up; requires the `OnFailure` policy
+    #[serde(default)]
+    pub max_restart_retries: Option<u32>,
+    /// Run as a one-shot job: wait for the container to exit and report its exit
+    /// code. Jobs are always recreated and don't restart unless a policy is set.
+    #[serde(default)]
+    pub wait_for_exit: bool,
+    /// Remove a container the deployment created when it fails; turn off to
+    /// keep it around for inspection
+    #[serde(default = "default_on_failure_cleanup")]
+    pub on_failure_cleanup: bool,
+    /// Containers to run: one keeps `name`, more are named `<name>-1` to
+    /// `<name>-N`, and zero removes them all
+    #[serde(default = "default_replicas")]
+    pub replicas: u32,
+}
+
+fn default_on_failure_cleanup() -> bool {
+    true
+}
+
+fn default_replicas() -> u32 {
+    1
+}
+
+/// How a deployment replaces an existing container of the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DeployStrategy {
+    /// Stop and remove the old container, then start the new one
+    #[default]
+    Recreate,
+    /// Start the new container alongside the old one and swap once healthy
+    BlueGreen,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -196,6 +423,64 @@ pub struct VolumeMount {
     pub read_only: bool,
 }
 
+/// A file mounted read-only into the container, like a Docker or Kubernetes
+/// secret; unlike an environment variable it doesn't show in `docker inspect`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SecretFile {
+    /// Absolute path of the file in the container
+    pub path: String,
+    /// File contents, base64-encoded on the wire
+    #[serde(with = "base64_bytes")]
+    pub content: Vec<u8>,
+    /// Permission bits; readable by anyone in the container when unset, so a
+    /// non-root user can read it
+    #[serde(default = "default_secret_mode")]
+    pub mode: u32,
+}
+
+fn default_secret_mode() -> u32 {
+    0o444
+}
+
+impl std::fmt::Debug for SecretFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretFile")
+            .field("path", &self.path)
+            .field("content", &format_args!("<{} bytes redacted>", self.content.len()))
+            .field("mode", &format_args!("{:o}", self.mode))
+            .finish()
+    }
+}
+
+/// Serde for bytes carried as a base64 string
+mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        // The decode error would point at the offending byte, so keep it out
+        STANDARD
+            .decode(encoded)
+            .map_err(|_| serde::de::Error::custom("secret file content is not valid base64"))
+    }
+}
+
+impl From<VolumeMount> for MountSpec {
+    fn from(volume: VolumeMount) -> Self {
+        MountSpec::Bind {
+            source: volume.host_path,
+            target: volume.container_path,
+            read_only: volume.read_only,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceSpec {
     pub memory_mb: Option<u64>,
@@ -216,6 +501,10 @@ pub struct StopContainerPayload {
     pub container_id: String,
     pub force: bool,
     pub timeout_secs: Option<u64>,
+    /// Signal to stop with (e.g. `SIGQUIT`), followed by SIGKILL if the container
+    /// is still running after `timeout_secs`; the runtime's usual stop when unset
+    #[serde(default)]
+    pub stop_signal: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -229,6 +518,46 @@ pub struct StatusRequestPayload {
     pub request_id: String,
     pub include_containers: bool,
     pub include_metrics: bool,
+    /// Report disk usage, which is slow to compute on hosts with many images
+    #[serde(default)]
+    pub include_disk_usage: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionMismatchPayload {
+    /// Protocol version the agent registered with
+    pub agent_version: u32,
+    /// Oldest protocol version the control plane supports
+    pub min_version: u32,
+    /// Newest protocol version the control plane supports
+    pub max_version: u32,
+    /// Whether the control plane refuses to serve the agent
+    #[serde(default)]
+    pub rejected: bool,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl VersionMismatchPayload {
+    /// Explain the mismatch and which side needs upgrading
+    pub fn summary(&self) -> String {
+        let upgrade = if self.agent_version < self.min_version {
+            "upgrade this agent"
+        } else {
+            "upgrade the control plane or run an older agent"
+        };
+        format!(
+            "Agent speaks protocol version {}, but the control plane supports {} to {}; {}",
+            self.agent_version, self.min_version, self.max_version, upgrade
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CordonPayload {
+    /// Why the agent is being drained or uncordoned, e.g. a maintenance ticket
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub timestamp: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -237,17 +566,21 @@ pub struct PingPayload {
 }
 
 impl AgentMessage {
-    /// Create a new registration message
-    pub fn register(agent_id: &str, server_id: &str, runtime_type: &str) -> Self {
+    /// Create a new registration message, offering `encoding` for the session
+    pub fn register(
+        agent_id: &str,
+        server_id: &str,
+        runtime_type: &str,
+        capabilities: Vec<String>,
+        encoding: Encoding,
+    ) -> Self {
         AgentMessage::Register(RegisterPayload {
             agent_id: agent_id.to_string(),
             server_id: server_id.to_string(),
             version: env!("CARGO_PKG_VERSION").to_string(),
-            capabilities: vec![
-                "docker".to_string(),
-                "metrics".to_string(),
-                "logs".to_string(),
-            ],
+            protocol_version: PROTOCOL_VERSION,
+            encodings: (encoding != Encoding::Json).then_some(encoding).into_iter().collect(),
+            capabilities,
             runtime_type: runtime_type.to_string(),
             hostname: hostname::get()
                 .map(|h| h.to_string_lossy().to_string())
@@ -256,18 +589,70 @@ impl AgentMessage {
         })
     }
 
+    /// Create a request for the configuration at `latest_version`
+    pub fn config_request(
+        agent_id: &str,
+        current_version: Option<String>,
+        latest_version: &str,
+    ) -> Self {
+        AgentMessage::ConfigRequest(ConfigRequestPayload {
+            agent_id: agent_id.to_string(),
+            current_version,
+            latest_version: latest_version.to_string(),
+            timestamp: Utc::now(),
+        })
+    }
+
     /// Create a heartbeat message
-    pub fn heartbeat(agent_id: &str, uptime_secs: u64, container_count: u32) -> Self {
+    pub fn heartbeat(
+        agent_id: &str,
+        uptime_secs: u64,
+        container_count: u32,
+        cpu_usage: f64,
+        memory_usage: f64,
+        draining: bool,
+    ) -> Self {
         AgentMessage::Heartbeat(HeartbeatPayload {
             agent_id: agent_id.to_string(),
             timestamp: Utc::now(),
             uptime_secs,
             container_count,
-            cpu_usage: 0.0,    // TODO: Implement actual metrics
-            memory_usage: 0.0, // TODO: Implement actual metrics
+            cpu_usage,
+            memory_usage,
+            draining,
         })
     }
 
+    /// Correlation ID of a message that expects an acknowledgement
+    pub fn message_id(&self) -> Option<&str> {
+        match self {
+            AgentMessage::TaskResult(payload) => payload.message_id.as_deref(),
+            AgentMessage::ContainerStatus(payload) => payload.message_id.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Assign a correlation ID to critical messages that don't have one yet
+    ///
+    /// Task results and container status updates are delivered at least
+    /// once; other messages are fire-and-forget and are left untouched.
+    pub fn ensure_message_id(&mut self) {
+        let message_id = match self {
+            AgentMessage::TaskResult(payload) => &mut payload.message_id,
+            AgentMessage::ContainerStatus(payload) => &mut payload.message_id,
+            _ => return,
+        };
+        message_id.get_or_insert_with(|| uuid::Uuid::new_v4().to_string());
+    }
+
+    /// Whether the message may be dropped when sending is throttled
+    ///
+    /// Metrics and logs are superseded by later ones, unlike task results
+    /// and status updates.
+    pub fn is_low_priority(&self) -> bool {
+        matches!(self, AgentMessage::Metrics(_) | AgentMessage::Log(_))
+    }
+
     /// Serialize the message to JSON
     pub fn to_json(&self) -> serde_json::Result<String> {
         serde_json::to_string(self)
@@ -281,16 +666,180 @@ impl ControlPlaneMessage {
     }
 }
 
+/// Wire encoding of protocol messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// JSON in text frames, understood by every control plane
+    #[default]
+    Json,
+    /// MessagePack in binary frames
+    Msgpack,
+}
+
+impl Encoding {
+    /// Codec implementing this encoding
+    pub fn codec(self) -> &'static dyn MessageCodec {
+        match self {
+            Encoding::Json => &JsonCodec,
+            Encoding::Msgpack => &MessagePackCodec,
+        }
+    }
+}
+
+/// Serializes agent messages and deserializes control plane messages
+pub trait MessageCodec: Send + Sync {
+    /// Encode a message for sending
+    fn encode(&self, message: &AgentMessage) -> Result<Vec<u8>>;
+
+    /// Decode a received message
+    fn decode(&self, data: &[u8]) -> Result<ControlPlaneMessage>;
+
+    /// Whether encoded messages are sent as binary rather than text frames
+    fn is_binary(&self) -> bool;
+}
+
+/// JSON codec, the default
+pub struct JsonCodec;
+
+impl MessageCodec for JsonCodec {
+    fn encode(&self, message: &AgentMessage) -> Result<Vec<u8>> {
+        serde_json::to_vec(message).context("Failed to encode message as JSON")
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ControlPlaneMessage> {
+        serde_json::from_slice(data).context("Failed to parse control plane message")
+    }
+
+    fn is_binary(&self) -> bool {
+        false
+    }
+}
+
+/// MessagePack codec, more compact than JSON for high-frequency messages
+///
+/// Structs are encoded as maps with field names, so the payloads keep the
+/// same shape as their JSON form.
+pub struct MessagePackCodec;
+
+impl MessageCodec for MessagePackCodec {
+    fn encode(&self, message: &AgentMessage) -> Result<Vec<u8>> {
+        rmp_serde::to_vec_named(message).context("Failed to encode message as MessagePack")
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<ControlPlaneMessage> {
+        rmp_serde::from_slice(data).context("Failed to parse MessagePack control plane message")
+    }
+
+    fn is_binary(&self) -> bool {
+        true
+    }
+}
+
+/// Message factories shared by the connection tests
+#[cfg(test)]
+pub(crate) mod test_messages {
+    use super::*;
+
+    /// A successful task result for `task_id`, not yet given a message ID
+    pub fn task_result(task_id: &str) -> AgentMessage {
+        AgentMessage::TaskResult(TaskResultPayload {
+            task_id: task_id.to_string(),
+            agent_id: String::new(),
+            success: true,
+            output: None,
+            error: None,
+            duration_ms: 0,
+            timestamp: Utc::now(),
+            message_id: None,
+        })
+    }
+
+    /// A metrics report carrying `metrics`
+    pub fn metrics(metrics: serde_json::Value) -> AgentMessage {
+        AgentMessage::Metrics(MetricsPayload {
+            agent_id: String::new(),
+            timestamp: Utc::now(),
+            metrics,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::test_messages::task_result;
     use super::*;
+    use crate::agent::state::AgentState;
 
     #[test]
     fn test_agent_message_serialization() {
-        let msg = AgentMessage::register("agent-123", "server-456", "docker");
+        let capabilities = vec!["docker".to_string(), "metrics".to_string()];
+        let msg = AgentMessage::register(
+            "agent-123",
+            "server-456",
+            "docker",
+            capabilities,
+            Encoding::Json,
+        );
         let json = msg.to_json().unwrap();
         assert!(json.contains("Register"));
         assert!(json.contains("agent-123"));
+        assert!(json.contains(r#""capabilities":["docker","metrics"]"#));
+        assert!(json.contains(&format!(r#""protocol_version":{}"#, PROTOCOL_VERSION)));
+        assert!(!json.contains("encodings"));
+
+        let msg =
+            AgentMessage::register("agent-123", "server-456", "docker", vec![], Encoding::Msgpack);
+        assert!(msg.to_json().unwrap().contains(r#""encodings":["msgpack"]"#));
+    }
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        let codec = Encoding::Msgpack.codec();
+        let heartbeat = AgentMessage::heartbeat("agent-123", 42, 3, 12.5, 40.0, false);
+        let data = codec.encode(&heartbeat).unwrap();
+        assert!(codec.is_binary());
+        assert!(data.len() < heartbeat.to_json().unwrap().len());
+        let AgentMessage::Heartbeat(decoded) = rmp_serde::from_slice(&data).unwrap() else {
+            panic!("Expected Heartbeat message");
+        };
+        assert_eq!(decoded.uptime_secs, 42);
+
+        let json = r#"{"type":"Ack","payload":
+            {"message_id":"msg-1","timestamp":"2024-01-01T00:00:00Z"}}"#;
+        let ack: ControlPlaneMessage = serde_json::from_str(json).unwrap();
+        let data = rmp_serde::to_vec_named(&ack).unwrap();
+        let ControlPlaneMessage::Ack(payload) = codec.decode(&data).unwrap() else {
+            panic!("Expected Ack message");
+        };
+        assert_eq!(payload.message_id, "msg-1");
+    }
+
+    #[test]
+    fn test_status_response_serialization() {
+        let msg = AgentMessage::StatusResponse(StatusResponsePayload {
+            request_id: "req-1".to_string(),
+            agent_id: "agent-123".to_string(),
+            state: "Connected".to_string(),
+            uptime_secs: 42,
+            connection_attempts: 0,
+            last_connected: None,
+            containers: Some(vec![]),
+            metrics: None,
+            disk_usage: None,
+            transitions: vec![StateTransition {
+                from: AgentState::Connected,
+                to: AgentState::Reconnecting,
+                timestamp: Utc::now(),
+                reason: Some("WebSocket error: reset".to_string()),
+            }],
+            timestamp: Utc::now(),
+        });
+
+        let json = msg.to_json().unwrap();
+        assert!(json.contains("StatusResponse"));
+        assert!(json.contains("req-1"));
+        assert!(json.contains(r#""reason":"WebSocket error: reset""#));
     }
 
     #[test]
@@ -313,4 +862,118 @@ mod tests {
             _ => panic!("Expected Welcome message"),
         }
     }
+
+    #[test]
+    fn test_version_mismatch_deserialization() {
+        let json = r#"{
+            "type": "VersionMismatch",
+            "payload": {
+                "agent_version": 1,
+                "min_version": 2,
+                "max_version": 3,
+                "rejected": true,
+                "timestamp": "2024-01-01T00:00:00Z"
+            }
+        }"#;
+
+        let ControlPlaneMessage::VersionMismatch(payload) =
+            ControlPlaneMessage::from_json(json).unwrap()
+        else {
+            panic!("Expected VersionMismatch message");
+        };
+        assert!(payload.rejected);
+        assert!(payload.summary().ends_with("upgrade this agent"));
+
+        let newer = VersionMismatchPayload {
+            agent_version: 4,
+            rejected: false,
+            ..payload
+        };
+        assert!(newer.summary().contains("upgrade the control plane"));
+    }
+
+    #[test]
+    fn test_drain_deserialization() {
+        let drain = r#"{"type":"Drain","payload":
+            {"reason":"kernel upgrade","timestamp":"2024-01-01T00:00:00Z"}}"#;
+        let ControlPlaneMessage::Drain(payload) = ControlPlaneMessage::from_json(drain).unwrap()
+        else {
+            panic!("Expected Drain message");
+        };
+        assert_eq!(payload.reason.as_deref(), Some("kernel upgrade"));
+
+        let uncordon = r#"{"type":"Uncordon","payload":{"timestamp":"2024-01-01T00:00:00Z"}}"#;
+        assert!(matches!(
+            ControlPlaneMessage::from_json(uncordon).unwrap(),
+            ControlPlaneMessage::Uncordon(CordonPayload { reason: None, .. })
+        ));
+
+        let heartbeat = AgentMessage::heartbeat("agent-1", 0, 0, 0.0, 0.0, true);
+        assert!(heartbeat.to_json().unwrap().contains(r#""draining":true"#));
+    }
+
+    #[test]
+    fn test_register_without_protocol_version() {
+        let json = r#"{
+            "type": "Register",
+            "payload": {
+                "agent_id": "agent-123",
+                "server_id": "server-456",
+                "version": "0.1.0",
+                "capabilities": [],
+                "runtime_type": "docker",
+                "hostname": "host",
+                "timestamp": "2024-01-01T00:00:00Z"
+            }
+        }"#;
+
+        let message: AgentMessage = serde_json::from_str(json).unwrap();
+        let AgentMessage::Register(payload) = message else {
+            panic!("Expected Register message");
+        };
+        assert_eq!(payload.protocol_version, 0);
+    }
+
+    #[test]
+    fn test_ack_deserialization() {
+        let json = r#"{
+            "type": "Ack",
+            "payload": {
+                "message_id": "msg-1",
+                "timestamp": "2024-01-01T00:00:00Z"
+            }
+        }"#;
+
+        match ControlPlaneMessage::from_json(json).unwrap() {
+            ControlPlaneMessage::Ack(payload) => assert_eq!(payload.message_id, "msg-1"),
+            _ => panic!("Expected Ack message"),
+        }
+    }
+
+    #[test]
+    fn test_ensure_message_id_only_tags_critical_messages() {
+        let mut heartbeat = AgentMessage::heartbeat("agent-1", 0, 0, 0.0, 0.0, false);
+        heartbeat.ensure_message_id();
+        assert!(heartbeat.message_id().is_none());
+
+        let mut result = task_result("task-1");
+        result.ensure_message_id();
+        let id = result.message_id().unwrap().to_string();
+        result.ensure_message_id();
+        assert_eq!(result.message_id(), Some(id.as_str()));
+    }
+
+    #[test]
+    fn test_secret_file_content_is_base64_and_redacted() {
+        let json = r#"{"path":"/run/secrets/db","content":"aHVudGVyMg=="}"#;
+        let file: SecretFile = serde_json::from_str(json).unwrap();
+        assert_eq!(file.content, b"hunter2");
+        assert_eq!(file.mode, 0o444);
+        assert!(!format!("{:?}", file).contains("hunter2"));
+        assert!(serde_json::to_string(&file).unwrap().contains("aHVudGVyMg=="));
+
+        let invalid = r#"{"path":"/run/secrets/db","content":"hunter2!"}"#;
+        let err = serde_json::from_str::<SecretFile>(invalid).unwrap_err();
+        assert!(!err.to_string().contains("hunter2"));
+    }
 }
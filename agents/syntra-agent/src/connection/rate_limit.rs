@@ -0,0 +1,93 @@
+//! Outbound Rate Limiting
+//!
+//! Token bucket capping how fast queued messages are written to the control
+//! plane, so a runaway deployment loop or metrics task can't flood it.
+
+use std::time::{Duration, Instant};
+
+/// Token bucket refilled at `rate` tokens per second, holding at most `burst`
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket allowing `rate` messages per second and bursts of `burst`
+    pub fn new(rate: u32, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate: f64::from(rate.max(1)),
+            burst,
+            tokens: burst,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take a token, returning `false` if none is available
+    pub fn try_take(&mut self) -> bool {
+        self.try_take_at(Instant::now())
+    }
+
+    /// Time until the next token is available, zero if one is already
+    pub fn ready_in(&mut self) -> Duration {
+        self.ready_in_at(Instant::now())
+    }
+
+    fn try_take_at(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    fn ready_in_at(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.refilled_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10, 3);
+        bucket.refilled_at = start;
+
+        assert!((0..3).all(|_| bucket.try_take_at(start)));
+        assert!(!bucket.try_take_at(start));
+        assert_eq!(bucket.ready_in_at(start), Duration::from_millis(100));
+
+        // One token every 100ms at 10 per second
+        let later = start + Duration::from_millis(100);
+        assert!(bucket.try_take_at(later));
+        assert!(!bucket.try_take_at(later));
+    }
+
+    #[test]
+    fn test_refill_capped_at_burst() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10, 2);
+        bucket.refilled_at = start;
+
+        let later = start + Duration::from_secs(60);
+        assert!(bucket.try_take_at(later));
+        assert!(bucket.try_take_at(later));
+        assert!(!bucket.try_take_at(later));
+    }
+}
@@ -2,28 +2,113 @@
 //!
 //! Provides WebSocket connection to the control plane with auto-reconnect functionality.
 
-use anyhow::{Context, Result};
-use futures_util::{SinkExt, StreamExt};
+use anyhow::{bail, Context, Result};
+use futures_util::{FutureExt, SinkExt, StreamExt};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::time::{interval, timeout};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, watch, Notify, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::{interval, interval_at, timeout};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::{header, HeaderValue};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, WebSocketConfig};
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_tungstenite::{connect_async_with_config, tungstenite::Message, MaybeTlsStream};
 use tracing::{debug, error, info, warn};
 
-use crate::agent::deploy::DeployHandler;
-use crate::agent::state::{AgentState, AgentStateManager};
-use crate::connection::protocol::{AgentMessage, ControlPlaneMessage};
+use crate::agent::deploy::{DeployHandler, DEFAULT_MAX_CONCURRENT_DEPLOYS};
+use crate::agent::reconcile::Reconciler;
+use crate::agent::metrics::HostMetrics;
+use crate::agent::state::{AgentState, AgentStateManager, STATUS_TRANSITIONS};
+use crate::agent::task::TaskHandler;
+use crate::api::AgentMetrics;
+use crate::cli::config::RuntimeConfig;
+use crate::cli::reload::next_update;
+use crate::connection::ack::AckTracker;
+use crate::connection::channel::{self, DEFAULT_MESSAGE_CHANNEL_CAPACITY};
+use crate::connection::compression;
+use crate::connection::framing::{self, Framing};
+use crate::connection::heartbeat::{HeartbeatWatchdog, DEFAULT_MAX_MISSED_HEARTBEAT_ACKS};
+use crate::connection::outbox::{Outbox, DEFAULT_OUTBOX_CAPACITY};
+use crate::connection::protocol::{
+    AgentMessage, ControlPlaneMessage, Encoding, ErrorPayload, StatusRequestPayload,
+    StatusResponsePayload, WelcomePayload,
+};
 use crate::runtime::adapter::RuntimeAdapter;
 
+/// Default time to wait for an Ack before resending
+pub const DEFAULT_ACK_TIMEOUT_SECS: u64 = 10;
+
+/// Default number of resends before giving up on an Ack
+pub const DEFAULT_ACK_MAX_RETRIES: u32 = 5;
+
+/// Default time in-flight tasks get to finish on shutdown
+pub const DEFAULT_SHUTDOWN_GRACE_SECS: u64 = 60;
+
+/// Default size limit of a message from the control plane
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// How long to keep discarding an oversized message before disconnecting
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long the control plane may go quiet before draining stops early
+const DRAIN_IDLE: Duration = Duration::from_millis(200);
+
 /// WebSocket client for control plane communication
 pub struct WebSocketClient<R: RuntimeAdapter + 'static> {
     url: String,
+    /// Sent as a bearer token on the upgrade request
+    api_key: Option<String>,
     reconnect_interval_ms: u64,
     heartbeat_interval_secs: u64,
+    /// Reloaded heartbeat intervals, which replace `heartbeat_interval_secs`
+    heartbeat_interval_updates: Option<watch::Receiver<u64>>,
+    /// Reconnects once too many heartbeats go unacknowledged
+    heartbeat_watchdog: HeartbeatWatchdog,
     agent_id: String,
     server_id: String,
     runtime: Arc<R>,
+    host_metrics: Mutex<HostMetrics>,
+    started_at: Instant,
+    deploy_permits: Arc<Semaphore>,
+    /// Producer side of the outbound queue, shared with message handlers
+    message_tx: mpsc::Sender<AgentMessage>,
+    /// Taken by `run` to forward handler messages into the outbox
+    message_rx: Option<mpsc::Receiver<AgentMessage>>,
+    /// Messages awaiting delivery, retained across reconnects
+    outbox: Arc<Outbox>,
+    /// How long to wait for an Ack before resending a critical message
+    ack_timeout: Duration,
+    /// Resends of an unacknowledged message before giving up
+    ack_max_retries: u32,
+    /// Outbound messages per second and burst size, unlimited if `None`
+    rate_limit: Option<(u32, u32)>,
+    /// Prometheus metrics updated on each heartbeat
+    metrics: Option<AgentMetrics>,
+    /// Runtime settings, including the resource limits deployments are held to
+    runtime_config: RuntimeConfig,
+    /// Deploy, stop, and task-request work, which outlives any one connection
+    tasks: Mutex<JoinSet<()>>,
+    /// Cancellation signals of running control plane tasks, by task ID
+    task_cancels: Arc<Mutex<HashMap<String, Arc<Notify>>>>,
+    /// How long in-flight tasks may run after shutdown is requested
+    shutdown_grace: Duration,
+    /// Offer to compress large messages during the handshake
+    compression: bool,
+    /// Largest message or frame accepted from the control plane
+    max_message_bytes: usize,
+    /// Encoding offered during registration
+    encoding: Encoding,
+    /// Encoding the control plane picked for the current connection
+    negotiated_encoding: Mutex<Encoding>,
 }
 
 impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
@@ -35,26 +120,218 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
         reconnect_interval_ms: u64,
         runtime: Arc<R>,
     ) -> Self {
+        let (message_tx, message_rx) =
+            mpsc::channel::<AgentMessage>(DEFAULT_MESSAGE_CHANNEL_CAPACITY);
         Self {
             url: url.to_string(),
+            api_key: None,
             reconnect_interval_ms,
             heartbeat_interval_secs: 30,
+            heartbeat_interval_updates: None,
+            heartbeat_watchdog: HeartbeatWatchdog::new(DEFAULT_MAX_MISSED_HEARTBEAT_ACKS),
             agent_id: agent_id.to_string(),
             server_id: server_id.to_string(),
             runtime,
+            host_metrics: Mutex::new(HostMetrics::new()),
+            started_at: Instant::now(),
+            deploy_permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_DEPLOYS)),
+            message_tx,
+            message_rx: Some(message_rx),
+            outbox: build_outbox(
+                Duration::from_secs(DEFAULT_ACK_TIMEOUT_SECS),
+                DEFAULT_ACK_MAX_RETRIES,
+                None,
+            ),
+            ack_timeout: Duration::from_secs(DEFAULT_ACK_TIMEOUT_SECS),
+            ack_max_retries: DEFAULT_ACK_MAX_RETRIES,
+            rate_limit: None,
+            metrics: None,
+            runtime_config: RuntimeConfig::default(),
+            tasks: Mutex::new(JoinSet::new()),
+            task_cancels: Arc::new(Mutex::new(HashMap::new())),
+            shutdown_grace: Duration::from_secs(DEFAULT_SHUTDOWN_GRACE_SECS),
+            compression: true,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            encoding: Encoding::Json,
+            negotiated_encoding: Mutex::new(Encoding::Json),
         }
     }
 
+    /// Authenticate to the control plane with `api_key`
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// Set how many deployments may run at once; extra requests are queued
+    pub fn with_max_concurrent_deploys(mut self, max: usize) -> Self {
+        self.deploy_permits = Arc::new(Semaphore::new(max.max(1)));
+        self
+    }
+
+    /// Hold deployments to the resource limits in `config`
+    pub fn with_runtime_config(mut self, config: RuntimeConfig) -> Self {
+        self.runtime_config = config;
+        self
+    }
+
+    /// Resend task results and status updates not acknowledged within
+    /// `timeout`, up to `max_retries` times
+    pub fn with_ack_retry(mut self, timeout: Duration, max_retries: u32) -> Self {
+        self.ack_timeout = timeout;
+        self.ack_max_retries = max_retries;
+        self.outbox = build_outbox(timeout, max_retries, self.rate_limit);
+        self
+    }
+
+    /// Send at most `rate` queued messages per second, with bursts of up to
+    /// `burst`; a `rate` of 0 means unlimited
+    pub fn with_rate_limit(mut self, rate: u32, burst: u32) -> Self {
+        self.rate_limit = (rate > 0).then_some((rate, burst));
+        self.outbox = build_outbox(self.ack_timeout, self.ack_max_retries, self.rate_limit);
+        self
+    }
+
+    /// Let in-flight tasks run for up to `grace` once shutdown is requested
+    pub fn with_shutdown_grace(mut self, grace: Duration) -> Self {
+        self.shutdown_grace = grace;
+        self
+    }
+
+    /// Offer zstd compression of large messages; it's only used if the
+    /// control plane accepts it
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Reject control plane messages larger than `bytes`
+    pub fn with_max_message_bytes(mut self, bytes: usize) -> Self {
+        self.max_message_bytes = bytes;
+        self
+    }
+
+    /// Offer a binary encoding during registration; messages stay JSON unless
+    /// the control plane picks it
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
     /// Set the heartbeat interval
     pub fn with_heartbeat_interval(mut self, secs: u64) -> Self {
         self.heartbeat_interval_secs = secs;
         self
     }
 
+    /// Switch to each heartbeat interval published on `updates`, without
+    /// reconnecting
+    pub fn with_heartbeat_interval_updates(mut self, updates: watch::Receiver<u64>) -> Self {
+        self.heartbeat_interval_updates = Some(updates);
+        self
+    }
+
+    /// Reconnect once `max_missed` heartbeats in a row go unacknowledged
+    pub fn with_max_missed_heartbeat_acks(mut self, max_missed: u32) -> Self {
+        self.heartbeat_watchdog = HeartbeatWatchdog::new(max_missed);
+        self
+    }
+
+    /// Report heartbeat data to Prometheus metrics
+    pub fn with_metrics(mut self, metrics: AgentMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Hold up to `capacity` messages from handlers before shedding metrics
+    ///
+    /// Replaces the channel, so call it before taking any `message_sender`.
+    pub fn with_message_channel_capacity(mut self, capacity: usize) -> Self {
+        let (message_tx, message_rx) = mpsc::channel::<AgentMessage>(capacity.max(1));
+        self.message_tx = message_tx;
+        self.message_rx = Some(message_rx);
+        self
+    }
+
+    /// Sender for queueing messages to the control plane from outside the client
+    pub fn message_sender(&self) -> mpsc::Sender<AgentMessage> {
+        self.message_tx.clone()
+    }
+
+    /// Number of spawned tasks that haven't finished yet
+    pub fn in_flight(&self) -> usize {
+        let mut tasks = self.tasks.lock();
+        reap_finished(&mut tasks);
+        tasks.len()
+    }
+
+    /// Run `task` in the background, tracked across reconnects
+    fn spawn_task(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let mut tasks = self.tasks.lock();
+        reap_finished(&mut tasks);
+        tasks.spawn(task);
+    }
+
+    /// Wait up to the shutdown grace period for in-flight tasks, aborting the rest
+    async fn drain_tasks(&self) {
+        let mut tasks = std::mem::take(&mut *self.tasks.lock());
+        if tasks.is_empty() {
+            return;
+        }
+
+        info!(
+            count = tasks.len(),
+            grace_secs = self.shutdown_grace.as_secs(),
+            "Waiting for in-flight tasks to finish"
+        );
+        let finished = timeout(self.shutdown_grace, async {
+            while let Some(result) = tasks.join_next().await {
+                if let Err(e) = result {
+                    warn!(error = %e, "Background task panicked");
+                }
+            }
+        })
+        .await;
+
+        if finished.is_err() {
+            warn!(
+                count = tasks.len(),
+                "Aborting tasks still running after the shutdown grace period"
+            );
+            tasks.shutdown().await;
+        }
+    }
+
     /// Run the WebSocket client with auto-reconnect
     pub async fn run(&mut self, state_manager: &AgentStateManager) -> Result<()> {
+        // Drain handler messages into the outbox for the client's whole
+        // lifetime, so nothing produced between connections is lost
+        let forwarder = self
+            .message_rx
+            .take()
+            .map(|rx| tokio::spawn(self.outbox.clone().forward(rx)));
+
+        // One handler for every connection, so a deployment that spans a
+        // reconnect keeps reporting through the shared outbox
+        let deploy_handler = Arc::new(
+            DeployHandler::new(self.runtime.clone(), &self.runtime_config, self.message_tx.clone())
+                .with_deploy_limit(self.deploy_permits.clone())
+                .with_state_manager(state_manager.clone()),
+        );
+
+        // Keep deployed services running across connections
+        let reconciler = self.runtime_config.reconcile.then(|| {
+            let reconciler = Reconciler::new(
+                self.runtime.clone(),
+                deploy_handler.clone(),
+                Duration::from_secs(self.runtime_config.reconcile_interval_secs),
+                self.message_tx.clone(),
+            );
+            tokio::spawn(reconciler.run())
+        });
+
         loop {
-            match self.connect_and_run(state_manager).await {
+            match self.connect_and_run(state_manager, &deploy_handler).await {
                 Ok(()) => {
                     info!("WebSocket connection closed gracefully");
                     if state_manager.current_state() == AgentState::ShuttingDown {
@@ -79,46 +356,101 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
                 interval_ms = self.reconnect_interval_ms,
                 "Waiting before reconnection attempt"
             );
-            tokio::time::sleep(Duration::from_millis(self.reconnect_interval_ms)).await;
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(self.reconnect_interval_ms)) => {}
+                _ = wait_for_shutdown(state_manager) => break,
+            }
+        }
+
+        if let Some(reconciler) = reconciler {
+            reconciler.abort();
+        }
+
+        // Anything still running lost its connection; let it finish anyway so
+        // it doesn't leave half-created containers behind
+        self.drain_tasks().await;
+
+        if let Some(forwarder) = forwarder {
+            forwarder.abort();
         }
 
         Ok(())
     }
 
     /// Connect and run the WebSocket communication loop
-    async fn connect_and_run(&self, state_manager: &AgentStateManager) -> Result<()> {
+    async fn connect_and_run(
+        &self,
+        state_manager: &AgentStateManager,
+        deploy_handler: &Arc<DeployHandler<R>>,
+    ) -> Result<()> {
         state_manager.set_connecting();
 
-        info!(url = %self.url, "Connecting to control plane");
+        info!(
+            url = %self.url,
+            authenticated = self.api_key.is_some(),
+            "Connecting to control plane"
+        );
+        let mut request = build_request(&self.url, self.api_key.as_deref())?;
+        if self.compression {
+            compression::offer(request.headers_mut());
+        }
 
         // Attempt connection with timeout
         let connect_timeout = Duration::from_secs(30);
-        let ws_stream = timeout(connect_timeout, connect_async(&self.url))
+        let config = WebSocketConfig {
+            max_message_size: Some(self.max_message_bytes),
+            max_frame_size: Some(self.max_message_bytes),
+            ..Default::default()
+        };
+        let connect = connect_async_with_config(request, Some(config), false);
+        let (ws_stream, response) = timeout(connect_timeout, connect)
             .await
             .context("Connection timeout")?
-            .context("Failed to connect to WebSocket")?
-            .0;
+            .context("Failed to connect to WebSocket")?;
 
-        info!("WebSocket connection established");
+        // Compression is optional; control planes that don't accept it get text frames
+        let compress = self.compression && compression::accepted(response.headers());
+        info!(compression = compress, "WebSocket connection established");
+        // Messages are JSON until the control plane's Welcome picks an encoding
+        *self.negotiated_encoding.lock() = Encoding::Json;
         state_manager.set_connected();
 
         let (mut write, mut read) = ws_stream.split();
+        let message_tx = self.message_tx.clone();
 
-        // Create channel for outgoing messages
-        let (message_tx, mut message_rx) = mpsc::channel::<AgentMessage>(100);
-
-        // Create deploy handler
-        let deploy_handler = Arc::new(DeployHandler::new(self.runtime.clone(), message_tx.clone()));
+        let in_flight = self.in_flight();
+        if in_flight > 0 {
+            info!(count = in_flight, "Reattaching in-flight tasks to the new connection");
+        }
 
         // Send registration message
-        let register_msg = AgentMessage::register(&self.agent_id, &self.server_id, self.runtime.runtime_type());
-        let register_json = register_msg.to_json()?;
-        write.send(Message::Text(register_json.into())).await?;
+        let register_msg = AgentMessage::register(
+            &self.agent_id,
+            &self.server_id,
+            self.runtime.runtime_type(),
+            agent_capabilities(self.runtime.as_ref()).await,
+            self.encoding,
+        );
+        write.send(self.framing(compress).frame(&register_msg)?).await?;
         debug!("Registration message sent");
 
+        // Deliver anything queued while we were disconnected
+        let flushed = self.outbox.flush(&mut write, self.framing(compress)).await?;
+        if flushed > 0 {
+            info!(count = flushed, "Flushed messages queued while disconnected");
+        }
+
         // Create heartbeat interval
-        let mut heartbeat_interval = interval(Duration::from_secs(self.heartbeat_interval_secs));
-        let mut uptime_secs: u64 = 0;
+        self.heartbeat_watchdog.reset();
+        let mut heartbeat_updates = self.heartbeat_interval_updates.clone();
+        let heartbeat_secs = heartbeat_updates
+            .as_mut()
+            .map_or(self.heartbeat_interval_secs, |rx| *rx.borrow_and_update());
+        let mut heartbeat_interval = interval(Duration::from_secs(heartbeat_secs));
+
+        // Check for unacknowledged messages a few times per ack timeout
+        let ack_check_period = (self.ack_timeout / 2).max(Duration::from_millis(100));
+        let mut ack_check_interval = interval(ack_check_period);
 
         // Get initial container count
         let container_count = self
@@ -128,15 +460,23 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
             .map(|c| c.len() as u32)
             .unwrap_or(0);
 
+        let shutdown = wait_for_shutdown(state_manager);
+        tokio::pin!(shutdown);
+
         loop {
             tokio::select! {
+                // Stop reading new work once shutdown is requested
+                _ = &mut shutdown => {
+                    info!("Shutdown requested, no longer accepting tasks");
+                    break;
+                }
+
                 // Handle incoming messages
                 msg = read.next() => {
                     match msg {
                         Some(Ok(Message::Text(text))) => {
-                            if let Err(e) = self.handle_message(&text, deploy_handler.clone()).await {
-                                warn!(error = %e, "Failed to handle message");
-                            }
+                            let data = text.as_bytes();
+                            self.receive(data, deploy_handler, state_manager, &message_tx).await;
                         }
                         Some(Ok(Message::Ping(data))) => {
                             debug!("Received ping, sending pong");
@@ -150,12 +490,43 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
                             state_manager.set_disconnected(Some("Server closed connection".to_string()));
                             break;
                         }
-                        Some(Ok(Message::Binary(_))) => {
-                            debug!("Received binary message (ignored)");
+                        Some(Ok(Message::Binary(data))) => {
+                            self.receive(&data, deploy_handler, state_manager, &message_tx).await;
                         }
                         Some(Ok(Message::Frame(_))) => {
                             // Raw frame, typically not used
                         }
+                        Some(Err(WsError::Capacity(e))) => {
+                            let message = format!(
+                                "Control plane message exceeded the {} byte limit: {}",
+                                self.max_message_bytes, e
+                            );
+                            error!("{}, reconnecting", message);
+
+                            // The rest of the oversized frame is still unread, so the
+                            // stream can't be resynchronised; report why and start over
+                            let report = AgentMessage::Error(ErrorPayload {
+                                code: "MESSAGE_TOO_LARGE".to_string(),
+                                message: message.clone(),
+                                details: Some(serde_json::json!({
+                                    "max_bytes": self.max_message_bytes,
+                                })),
+                                timestamp: chrono::Utc::now(),
+                            });
+                            let _ = write.send(self.framing(compress).frame(&report)?).await;
+                            let close = CloseFrame {
+                                code: CloseCode::Size,
+                                reason: "Message too large".into(),
+                            };
+                            let _ = write.send(Message::Close(Some(close))).await;
+                            // Closing with the frame unread would reset the
+                            // connection, discarding the report before it's read
+                            if let Ok(mut ws) = read.reunite(write) {
+                                drain(ws.get_mut()).await;
+                            }
+                            state_manager.set_disconnected(Some(message.clone()));
+                            bail!(message);
+                        }
                         Some(Err(e)) => {
                             error!(error = %e, "WebSocket error");
                             state_manager.set_disconnected(Some(format!("WebSocket error: {}", e)));
@@ -169,18 +540,48 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
                     }
                 }
 
-                // Handle outgoing messages from deploy handler
-                outgoing = message_rx.recv() => {
-                    if let Some(msg) = outgoing {
-                        let json = msg.to_json()?;
-                        debug!("Sending message to control plane");
-                        write.send(Message::Text(json.into())).await?;
+                // Handle outgoing messages queued by handlers
+                _ = self.outbox.notified() => {
+                    debug!("Sending queued messages to control plane");
+                    self.outbox.flush(&mut write, self.framing(compress)).await?;
+                }
+
+                // Send messages held back by the rate limit
+                _ = self.outbox.throttle_released() => {
+                    self.outbox.flush(&mut write, self.framing(compress)).await?;
+                }
+
+                // Resend critical messages the control plane hasn't acknowledged
+                _ = ack_check_interval.tick() => {
+                    let requeued = self.outbox.requeue_unacked();
+                    if requeued > 0 {
+                        warn!(count = requeued, "Resending unacknowledged messages");
+                        self.outbox.flush(&mut write, self.framing(compress)).await?;
                     }
                 }
 
+                // Pick up a reloaded heartbeat interval
+                secs = next_update(&mut heartbeat_updates) => {
+                    info!(interval_secs = secs, "Heartbeat interval changed");
+                    let period = Duration::from_secs(secs);
+                    heartbeat_interval =
+                        interval_at(tokio::time::Instant::now() + period, period);
+                }
+
                 // Send heartbeat
                 _ = heartbeat_interval.tick() => {
-                    uptime_secs += self.heartbeat_interval_secs;
+                    // TCP can stay up while the control plane has stopped responding
+                    if !self.heartbeat_watchdog.heartbeat_sent() {
+                        let reason = format!(
+                            "No HeartbeatAck for the last {} heartbeats",
+                            self.heartbeat_watchdog.missed()
+                        );
+                        warn!("{}, reconnecting", reason);
+                        state_manager.transition_to(AgentState::Reconnecting, Some(reason.clone()));
+                        bail!(reason);
+                    }
+
+                    let uptime_secs = self.started_at.elapsed().as_secs();
 
                     // Get current container count
                     let current_container_count = self
@@ -189,31 +590,92 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
                         .await
                         .map(|c| c.len() as u32)
                         .unwrap_or(container_count);
+                    if let Some(metrics) = &self.metrics {
+                        metrics.set_container_count(current_container_count);
+                        metrics.set_message_channel_full(channel::full_count());
+                    }
+
+                    let (cpu_usage, memory_usage) = self.host_metrics.lock().sample();
 
                     let heartbeat = AgentMessage::heartbeat(
                         &self.agent_id,
                         uptime_secs,
                         current_container_count,
+                        cpu_usage,
+                        memory_usage,
+                        state_manager.is_draining(),
                     );
-                    let heartbeat_json = heartbeat.to_json()?;
                     debug!("Sending heartbeat");
-                    write.send(Message::Text(heartbeat_json.into())).await?;
+                    write.send(self.framing(compress).frame(&heartbeat)?).await?;
+
+                    // Catch anything whose wakeup raced with another branch
+                    self.outbox.flush(&mut write, self.framing(compress)).await?;
                 }
             }
         }
 
+        if state_manager.current_state() == AgentState::ShuttingDown {
+            // Keep the connection up so draining tasks can still report results
+            let drain = self.drain_tasks();
+            tokio::pin!(drain);
+            let mut connected = true;
+            loop {
+                tokio::select! {
+                    _ = &mut drain => break,
+                    _ = self.outbox.notified(), if connected => {
+                        let framing = self.framing(compress);
+                        if let Err(e) = self.outbox.flush(&mut write, framing).await {
+                            warn!(error = %e, "Connection lost while draining tasks");
+                            connected = false;
+                        }
+                    }
+                }
+            }
+            if connected {
+                self.outbox.flush(&mut write, self.framing(compress)).await?;
+                let _ = write.send(Message::Close(None)).await;
+            }
+        }
+
         Ok(())
     }
 
+    /// Framing for outgoing messages on the current connection
+    fn framing(&self, compress: bool) -> Framing {
+        Framing {
+            encoding: *self.negotiated_encoding.lock(),
+            compress,
+        }
+    }
+
+    /// Decode and handle a text or binary frame from the control plane
+    async fn receive(
+        &self,
+        data: &[u8],
+        deploy_handler: &Arc<DeployHandler<R>>,
+        state_manager: &AgentStateManager,
+        message_tx: &mpsc::Sender<AgentMessage>,
+    ) {
+        let handled = match framing::decode(data) {
+            Ok(message) => {
+                self.handle_message(message, deploy_handler.clone(), state_manager, message_tx)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = handled {
+            warn!(error = %e, "Failed to handle message");
+        }
+    }
+
     /// Handle an incoming message from the control plane
     async fn handle_message(
         &self,
-        text: &str,
+        message: ControlPlaneMessage,
         deploy_handler: Arc<DeployHandler<R>>,
+        state_manager: &AgentStateManager,
+        message_tx: &mpsc::Sender<AgentMessage>,
     ) -> Result<()> {
-        let message = ControlPlaneMessage::from_json(text)
-            .context("Failed to parse control plane message")?;
-
         match message {
             ControlPlaneMessage::Welcome(payload) => {
                 info!(
@@ -221,9 +683,36 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
                     session_id = %payload.session_id,
                     "Received welcome from control plane"
                 );
+
+                match payload.encoding {
+                    None | Some(Encoding::Json) => {}
+                    Some(encoding) if encoding == self.encoding => {
+                        info!(?encoding, "Switching to the negotiated message encoding");
+                        *self.negotiated_encoding.lock() = encoding;
+                    }
+                    Some(encoding) => {
+                        warn!(?encoding, "Control plane picked an encoding that wasn't offered");
+                    }
+                }
+
+                // Ask for any config that changed while we were offline; messages are
+                // handled in order, so this is queued before anything that follows
+                if let Some(request) = config_request(&self.agent_id, state_manager, &payload) {
+                    channel::send(message_tx, request)
+                        .await
+                        .context("Failed to queue config request")?;
+                }
             }
             ControlPlaneMessage::HeartbeatAck(payload) => {
                 debug!(server_time = %payload.server_time, "Heartbeat acknowledged");
+                self.heartbeat_watchdog.ack_received();
+            }
+            ControlPlaneMessage::Ack(payload) => {
+                if self.outbox.ack(&payload.message_id) {
+                    debug!(message_id = %payload.message_id, "Message acknowledged");
+                } else {
+                    debug!(message_id = %payload.message_id, "Ack for unknown message");
+                }
             }
             ControlPlaneMessage::TaskRequest(payload) => {
                 info!(
@@ -231,7 +720,29 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
                     task_type = %payload.task_type,
                     "Received task request"
                 );
-                // TODO: Implement task execution based on task_type
+
+                let cancel = Arc::new(Notify::new());
+                self.task_cancels
+                    .lock()
+                    .insert(payload.task_id.clone(), cancel.clone());
+                let handler = TaskHandler::new(self.runtime.clone(), message_tx.clone())
+                    .with_cancel(cancel);
+                let task_cancels = self.task_cancels.clone();
+                self.spawn_task(async move {
+                    let task_id = payload.task_id.clone();
+                    handler.handle(payload).await;
+                    task_cancels.lock().remove(&task_id);
+                });
+            }
+            ControlPlaneMessage::CancelTask(payload) => {
+                let cancel = self.task_cancels.lock().remove(&payload.task_id);
+                match cancel {
+                    Some(cancel) => {
+                        info!(task_id = %payload.task_id, "Cancelling task");
+                        cancel.notify_one();
+                    }
+                    None => debug!(task_id = %payload.task_id, "No running task to cancel"),
+                }
             }
             ControlPlaneMessage::DeployContainer(payload) => {
                 info!(
@@ -243,8 +754,8 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
 
                 // Clone the handler and spawn deployment task
                 let handler = deploy_handler.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = handler.deploy(payload).await {
+                self.spawn_task(async move {
+                    if let Err(e) = handler.deploy(*payload).await {
                         error!(error = %e, "Deployment failed");
                     }
                 });
@@ -258,7 +769,7 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
 
                 // Clone the handler and spawn stop task
                 let handler = deploy_handler.clone();
-                tokio::spawn(async move {
+                self.spawn_task(async move {
                     if let Err(e) = handler.stop(payload).await {
                         error!(error = %e, "Stop container failed");
                     }
@@ -269,11 +780,31 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
                     config_version = %payload.config_version,
                     "Received configuration update"
                 );
-                // TODO: Apply config update
+                // TODO: Apply config update, then record its version with
+                // `state_manager.set_config_version` so reconnects stop requesting it
             }
             ControlPlaneMessage::StatusRequest(payload) => {
                 debug!(request_id = %payload.request_id, "Received status request");
-                // TODO: Send status response
+
+                let runtime = self.runtime.clone();
+                let state_manager = state_manager.clone();
+                let agent_id = self.agent_id.clone();
+                let uptime_secs = self.started_at.elapsed().as_secs();
+                let message_tx = message_tx.clone();
+                self.spawn_task(async move {
+                    let response = build_status_response(
+                        runtime.as_ref(),
+                        &state_manager,
+                        &agent_id,
+                        uptime_secs,
+                        payload,
+                    )
+                    .await;
+
+                    if let Err(e) = channel::send(&message_tx, response).await {
+                        warn!(error = %e, "Failed to send status response");
+                    }
+                });
             }
             ControlPlaneMessage::Ping(payload) => {
                 debug!(timestamp = %payload.timestamp, "Received ping");
@@ -286,19 +817,214 @@ impl<R: RuntimeAdapter + 'static> WebSocketClient<R> {
                     "Received error from control plane"
                 );
             }
+            ControlPlaneMessage::VersionMismatch(payload) => {
+                let summary = payload.summary();
+                if payload.rejected {
+                    // Reconnecting would only be rejected again
+                    error!("{}; the control plane refused the connection, stopping", summary);
+                    state_manager.transition_to(AgentState::ShuttingDown, Some(summary));
+                } else {
+                    warn!("{}", summary);
+                }
+            }
+            ControlPlaneMessage::Drain(payload) => {
+                if state_manager.set_draining(true) {
+                    info!(reason = ?payload.reason, "Draining, rejecting new deployments");
+                }
+            }
+            ControlPlaneMessage::Uncordon(payload) => {
+                if state_manager.set_draining(false) {
+                    info!(reason = ?payload.reason, "Uncordoned, accepting deployments again");
+                }
+            }
         }
 
         Ok(())
     }
 }
 
+/// Read and discard what the control plane has sent until it goes quiet or
+/// `DRAIN_TIMEOUT` passes
+async fn drain(stream: &mut MaybeTlsStream<TcpStream>) {
+    let deadline = Instant::now() + DRAIN_TIMEOUT;
+    let mut buf = vec![0; 64 * 1024];
+    while Instant::now() < deadline {
+        match timeout(DRAIN_IDLE, stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => continue,
+            _ => break,
+        }
+    }
+}
+
+/// Resolve once the agent starts shutting down
+async fn wait_for_shutdown(state_manager: &AgentStateManager) {
+    let mut transitions = state_manager.subscribe();
+    while state_manager.current_state() != AgentState::ShuttingDown {
+        if let Err(RecvError::Closed) = transitions.recv().await {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// A config request if the welcome advertises a version other than the applied one
+fn config_request(
+    agent_id: &str,
+    state_manager: &AgentStateManager,
+    welcome: &WelcomePayload,
+) -> Option<AgentMessage> {
+    let applied = state_manager.config_version();
+    if applied.as_deref() == Some(welcome.config_version.as_str()) {
+        return None;
+    }
+
+    info!(
+        applied = ?applied,
+        latest = %welcome.config_version,
+        "Configuration out of date, requesting the latest"
+    );
+    Some(AgentMessage::config_request(agent_id, applied, &welcome.config_version))
+}
+
+/// Capabilities to register with: the runtime type, host metrics, the runtime's
+/// optional features, and `gpu` when the runtime can hand out GPUs
+async fn agent_capabilities<R: RuntimeAdapter>(runtime: &R) -> Vec<String> {
+    let mut capabilities = vec![runtime.runtime_type().to_string(), "metrics".to_string()];
+    capabilities.extend(runtime.capabilities());
+
+    match runtime.gpu_available().await {
+        Ok(true) => capabilities.push("gpu".to_string()),
+        Ok(false) => {}
+        Err(e) => warn!(error = %e, "Failed to detect GPU support"),
+    }
+    capabilities
+}
+
+/// Drop finished tasks from `tasks` without waiting on running ones
+fn reap_finished(tasks: &mut JoinSet<()>) {
+    while let Some(Some(result)) = tasks.join_next().now_or_never() {
+        if let Err(e) = result {
+            warn!(error = %e, "Background task panicked");
+        }
+    }
+}
+
+/// Collect agent and container status in response to a status request
+async fn build_status_response<R: RuntimeAdapter>(
+    runtime: &R,
+    state_manager: &AgentStateManager,
+    agent_id: &str,
+    uptime_secs: u64,
+    request: StatusRequestPayload,
+) -> AgentMessage {
+    let containers = if request.include_containers || request.include_metrics {
+        match runtime.list_containers(false).await {
+            Ok(containers) => Some(containers),
+            Err(e) => {
+                warn!(error = %e, "Failed to list containers for status response");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let metrics = if request.include_metrics {
+        let mut metrics = HashMap::new();
+        for container in containers.iter().flatten() {
+            match runtime.stats(&container.id).await {
+                Ok(stats) => {
+                    metrics.insert(container.id.clone(), stats);
+                }
+                Err(e) => {
+                    debug!(container_id = %container.id, error = %e, "Failed to get container stats");
+                }
+            }
+        }
+        Some(metrics)
+    } else {
+        None
+    };
+
+    let disk_usage = if request.include_disk_usage {
+        match runtime.disk_usage().await {
+            Ok(usage) => Some(usage),
+            Err(e) => {
+                warn!(error = %e, "Failed to get disk usage for status response");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    AgentMessage::StatusResponse(StatusResponsePayload {
+        request_id: request.request_id,
+        agent_id: agent_id.to_string(),
+        state: state_manager.current_state().to_string(),
+        uptime_secs,
+        connection_attempts: state_manager.connection_attempts(),
+        last_connected: state_manager.last_connected(),
+        containers: if request.include_containers { containers } else { None },
+        metrics,
+        disk_usage,
+        transitions: state_manager.recent_transitions(STATUS_TRANSITIONS),
+        timestamp: chrono::Utc::now(),
+    })
+}
+
+/// Build the WebSocket upgrade request, with a bearer token if `api_key` is set
+///
+/// The header is marked sensitive so the key is redacted from Debug output.
+fn build_request(url: &str, api_key: Option<&str>) -> Result<Request> {
+    let mut request = url
+        .into_client_request()
+        .context("Invalid control plane URL")?;
+
+    if let Some(key) = api_key {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", key))
+            .context("API key contains invalid header characters")?;
+        value.set_sensitive(true);
+        request.headers_mut().insert(header::AUTHORIZATION, value);
+    }
+
+    Ok(request)
+}
+
+/// Create the outbound queue, resending unacknowledged messages after
+/// `ack_timeout` and holding sends to `rate_limit` if set
+fn build_outbox(
+    ack_timeout: Duration,
+    ack_max_retries: u32,
+    rate_limit: Option<(u32, u32)>,
+) -> Arc<Outbox> {
+    let outbox = Outbox::new(DEFAULT_OUTBOX_CAPACITY)
+        .with_acks(AckTracker::new(ack_timeout, ack_max_retries));
+    Arc::new(match rate_limit {
+        Some((rate, burst)) => outbox.with_rate_limit(rate, burst),
+        None => outbox,
+    })
+}
+
 /// Builder for WebSocketClient
 pub struct WebSocketClientBuilder<R: RuntimeAdapter + 'static> {
     url: String,
+    api_key: Option<String>,
     agent_id: String,
     server_id: String,
     reconnect_interval_ms: u64,
     heartbeat_interval_secs: u64,
+    max_missed_heartbeat_acks: u32,
+    max_concurrent_deploys: usize,
+    ack_timeout_secs: u64,
+    ack_max_retries: u32,
+    shutdown_grace_secs: u64,
+    metrics: Option<AgentMetrics>,
+    runtime_config: RuntimeConfig,
+    compression: bool,
+    max_message_bytes: usize,
+    encoding: Encoding,
+    rate_limit: Option<(u32, u32)>,
+    message_channel_capacity: usize,
     runtime: Arc<R>,
 }
 
@@ -306,14 +1032,32 @@ impl<R: RuntimeAdapter + 'static> WebSocketClientBuilder<R> {
     pub fn new(url: &str, agent_id: &str, server_id: &str, runtime: Arc<R>) -> Self {
         Self {
             url: url.to_string(),
+            api_key: None,
             agent_id: agent_id.to_string(),
             server_id: server_id.to_string(),
             reconnect_interval_ms: 5000,
             heartbeat_interval_secs: 30,
+            max_missed_heartbeat_acks: DEFAULT_MAX_MISSED_HEARTBEAT_ACKS,
+            max_concurrent_deploys: DEFAULT_MAX_CONCURRENT_DEPLOYS,
+            ack_timeout_secs: DEFAULT_ACK_TIMEOUT_SECS,
+            ack_max_retries: DEFAULT_ACK_MAX_RETRIES,
+            shutdown_grace_secs: DEFAULT_SHUTDOWN_GRACE_SECS,
+            metrics: None,
+            runtime_config: RuntimeConfig::default(),
+            compression: true,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            encoding: Encoding::Json,
+            rate_limit: None,
+            message_channel_capacity: DEFAULT_MESSAGE_CHANNEL_CAPACITY,
             runtime,
         }
     }
 
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
     pub fn reconnect_interval_ms(mut self, ms: u64) -> Self {
         self.reconnect_interval_ms = ms;
         self
@@ -324,19 +1068,409 @@ impl<R: RuntimeAdapter + 'static> WebSocketClientBuilder<R> {
         self
     }
 
+    pub fn max_missed_heartbeat_acks(mut self, max_missed: u32) -> Self {
+        self.max_missed_heartbeat_acks = max_missed;
+        self
+    }
+
+    pub fn max_concurrent_deploys(mut self, max: usize) -> Self {
+        self.max_concurrent_deploys = max;
+        self
+    }
+
+    pub fn ack_retry(mut self, timeout_secs: u64, max_retries: u32) -> Self {
+        self.ack_timeout_secs = timeout_secs;
+        self.ack_max_retries = max_retries;
+        self
+    }
+
+    pub fn shutdown_grace_secs(mut self, secs: u64) -> Self {
+        self.shutdown_grace_secs = secs;
+        self
+    }
+
+    pub fn metrics(mut self, metrics: AgentMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn runtime_config(mut self, config: RuntimeConfig) -> Self {
+        self.runtime_config = config;
+        self
+    }
+
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    pub fn max_message_bytes(mut self, bytes: usize) -> Self {
+        self.max_message_bytes = bytes;
+        self
+    }
+
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn message_channel_capacity(mut self, capacity: usize) -> Self {
+        self.message_channel_capacity = capacity;
+        self
+    }
+
+    pub fn rate_limit(mut self, rate: u32, burst: u32) -> Self {
+        self.rate_limit = (rate > 0).then_some((rate, burst));
+        self
+    }
+
     pub fn build(self) -> WebSocketClient<R> {
+        let (message_tx, message_rx) =
+            mpsc::channel::<AgentMessage>(self.message_channel_capacity.max(1));
         WebSocketClient {
             url: self.url,
+            api_key: self.api_key,
             agent_id: self.agent_id,
             server_id: self.server_id,
             reconnect_interval_ms: self.reconnect_interval_ms,
             heartbeat_interval_secs: self.heartbeat_interval_secs,
+            heartbeat_interval_updates: None,
+            heartbeat_watchdog: HeartbeatWatchdog::new(self.max_missed_heartbeat_acks),
             runtime: self.runtime,
+            host_metrics: Mutex::new(HostMetrics::new()),
+            started_at: Instant::now(),
+            deploy_permits: Arc::new(Semaphore::new(self.max_concurrent_deploys.max(1))),
+            message_tx,
+            message_rx: Some(message_rx),
+            outbox: build_outbox(
+                Duration::from_secs(self.ack_timeout_secs),
+                self.ack_max_retries,
+                self.rate_limit,
+            ),
+            ack_timeout: Duration::from_secs(self.ack_timeout_secs),
+            ack_max_retries: self.ack_max_retries,
+            rate_limit: self.rate_limit,
+            metrics: self.metrics,
+            runtime_config: self.runtime_config,
+            tasks: Mutex::new(JoinSet::new()),
+            task_cancels: Arc::new(Mutex::new(HashMap::new())),
+            shutdown_grace: Duration::from_secs(self.shutdown_grace_secs),
+            compression: self.compression,
+            max_message_bytes: self.max_message_bytes,
+            encoding: self.encoding,
+            negotiated_encoding: Mutex::new(Encoding::Json),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // Tests would use a mock RuntimeAdapter
+    use super::*;
+
+    #[test]
+    fn test_request_carries_bearer_token() {
+        let request = build_request("ws://localhost:3001/ws/agent/a1", Some("secret")).unwrap();
+        let auth = request.headers().get(header::AUTHORIZATION).unwrap();
+        assert_eq!(auth.to_str().unwrap(), "Bearer secret");
+        assert!(!format!("{:?}", request).contains("secret"));
+    }
+
+    #[test]
+    fn test_request_without_api_key_has_no_auth_header() {
+        let request = build_request("ws://localhost:3001/ws/agent/a1", None).unwrap();
+        assert!(request.headers().get(header::AUTHORIZATION).is_none());
+    }
+
+    use crate::connection::protocol::TaskResultPayload;
+    use crate::runtime::mock::MockRuntimeAdapter;
+
+    #[test]
+    fn test_config_requested_only_when_version_differs() {
+        let state_manager = AgentStateManager::new();
+        let welcome = WelcomePayload {
+            agent_id: "a1".to_string(),
+            session_id: "s1".to_string(),
+            server_time: chrono::Utc::now(),
+            config_version: "v2".to_string(),
+            encoding: None,
+        };
+
+        match config_request("a1", &state_manager, &welcome) {
+            Some(AgentMessage::ConfigRequest(request)) => {
+                assert_eq!(request.current_version, None);
+                assert_eq!(request.latest_version, "v2");
+            }
+            other => panic!("Expected ConfigRequest, got {:?}", other),
+        }
+
+        state_manager.set_config_version("v2");
+        assert!(config_request("a1", &state_manager, &welcome).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_agent_capabilities_include_detected_gpu() {
+        let runtime = MockRuntimeAdapter::new();
+        assert_eq!(
+            agent_capabilities(&runtime).await,
+            vec!["mock", "metrics", "logs", "exec", "build", "prune", "push", "rename"]
+        );
+
+        runtime.set_gpu_available(true);
+        assert_eq!(agent_capabilities(&runtime).await.last().map(String::as_str), Some("gpu"));
+    }
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::{accept_async, WebSocketStream};
+
+    /// Read agent messages until one matches `wanted`
+    async fn next_matching(
+        ws: &mut WebSocketStream<TcpStream>,
+        wanted: impl Fn(&AgentMessage) -> bool,
+    ) -> AgentMessage {
+        loop {
+            let text = match ws.next().await {
+                Some(Ok(Message::Text(text))) => text,
+                Some(_) => continue,
+                None => panic!("agent closed the connection"),
+            };
+            let message: AgentMessage = serde_json::from_str(&text).unwrap();
+            if wanted(&message) {
+                return message;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deploy_result_delivered_after_reconnect() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_pull_hangs(true);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws/agent/a1", listener.local_addr().unwrap());
+
+        let control_plane = {
+            let runtime = runtime.clone();
+            tokio::spawn(async move {
+                // The first connection drops while the image is still pulling
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut first = accept_async(stream).await.unwrap();
+                let deploy = r#"{"type":"DeployContainer","payload":
+                    {"request_id":"req-1","image":"app:v1","name":"app"}}"#;
+                first.send(Message::Text(deploy.into())).await.unwrap();
+                next_matching(&mut first, |m| {
+                    matches!(m, AgentMessage::ContainerStatus(s) if s.status == "deploying")
+                })
+                .await;
+                drop(first);
+
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut second = accept_async(stream).await.unwrap();
+                runtime.set_pull_hangs(false);
+                let result =
+                    next_matching(&mut second, |m| matches!(m, AgentMessage::TaskResult(_))).await;
+                match result {
+                    AgentMessage::TaskResult(result) => result,
+                    _ => unreachable!(),
+                }
+            })
+        };
+
+        let mut client = WebSocketClientBuilder::new(&url, "a1", "s1", runtime)
+            .reconnect_interval_ms(10)
+            .build();
+        let state_manager = AgentStateManager::new();
+        let result: TaskResultPayload = tokio::select! {
+            _ = client.run(&state_manager) => panic!("client stopped"),
+            result = timeout(Duration::from_secs(10), control_plane) => result.unwrap().unwrap(),
+        };
+
+        assert_eq!(result.task_id, "req-1");
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn test_large_messages_compressed_when_accepted() {
+        use tokio_tungstenite::accept_hdr_async;
+        use tokio_tungstenite::tungstenite::handshake::server::Response;
+
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        for i in 0..50 {
+            runtime.add_running(&format!("app-{}", i), "nginx:latest");
+        }
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws/agent/a1", listener.local_addr().unwrap());
+
+        let control_plane = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            // The error type is tungstenite's, fixed by its callback signature
+            #[allow(clippy::result_large_err)]
+            let accept = |request: &Request, mut response: Response| {
+                if compression::accepted(request.headers()) {
+                    compression::offer(response.headers_mut());
+                }
+                Ok(response)
+            };
+            let mut ws = accept_hdr_async(stream, accept).await.unwrap();
+            let request = r#"{"type":"StatusRequest","payload":
+                {"request_id":"req-1","include_containers":true,"include_metrics":false}}"#;
+            ws.send(Message::Text(request.into())).await.unwrap();
+            loop {
+                match ws.next().await {
+                    Some(Ok(Message::Binary(data))) => {
+                        let json = compression::decompress(&data).unwrap();
+                        return serde_json::from_slice::<AgentMessage>(&json).unwrap();
+                    }
+                    Some(_) => continue,
+                    None => panic!("agent closed the connection"),
+                }
+            }
+        });
+
+        let mut client = WebSocketClientBuilder::new(&url, "a1", "s1", runtime).build();
+        let state_manager = AgentStateManager::new();
+        let message = tokio::select! {
+            _ = client.run(&state_manager) => panic!("client stopped"),
+            result = timeout(Duration::from_secs(10), control_plane) => result.unwrap().unwrap(),
+        };
+
+        match message {
+            AgentMessage::StatusResponse(response) => {
+                assert_eq!(response.containers.map(|c| c.len()), Some(50));
+            }
+            other => panic!("Expected StatusResponse, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_reported_before_reconnecting() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws/agent/a1", listener.local_addr().unwrap());
+
+        let control_plane = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut first = accept_async(stream).await.unwrap();
+            let oversized = format!(
+                r#"{{"type":"Ping","payload":{{"timestamp":"{}"}}}}"#,
+                "x".repeat(4096)
+            );
+            first.send(Message::Text(oversized)).await.unwrap();
+            let error =
+                next_matching(&mut first, |m| matches!(m, AgentMessage::Error(_))).await;
+
+            // The session is dropped and reopened rather than left desynchronised
+            let (stream, _) = listener.accept().await.unwrap();
+            accept_async(stream).await.unwrap();
+            error
+        });
+
+        let mut client = WebSocketClientBuilder::new(&url, "a1", "s1", runtime)
+            .reconnect_interval_ms(10)
+            .max_message_bytes(1024)
+            .build();
+        let state_manager = AgentStateManager::new();
+        let error = tokio::select! {
+            _ = client.run(&state_manager) => panic!("client stopped"),
+            result = timeout(Duration::from_secs(10), control_plane) => result.unwrap().unwrap(),
+        };
+
+        match error {
+            AgentMessage::Error(error) => {
+                assert_eq!(error.code, "MESSAGE_TOO_LARGE");
+                assert!(error.message.contains("exceeded the 1024 byte limit"));
+            }
+            other => panic!("Expected Error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_when_heartbeats_go_unacknowledged() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws/agent/a1", listener.local_addr().unwrap());
+
+        let control_plane = tokio::spawn(async move {
+            // Receive heartbeats on the first connection without ever acking them
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut first = accept_async(stream).await.unwrap();
+            let reader = tokio::spawn(async move { while first.next().await.is_some() {} });
+
+            let (stream, _) = listener.accept().await.unwrap();
+            accept_async(stream).await.unwrap();
+            reader.abort();
+        });
+
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        let mut client = WebSocketClientBuilder::new(&url, "a1", "s1", runtime)
+            .reconnect_interval_ms(10)
+            .heartbeat_interval_secs(1)
+            .max_missed_heartbeat_acks(1)
+            .build();
+        let state_manager = AgentStateManager::new();
+        tokio::select! {
+            _ = client.run(&state_manager) => panic!("client stopped"),
+            result = timeout(Duration::from_secs(10), control_plane) => result.unwrap().unwrap(),
+        }
+
+        let reasons: Vec<_> = state_manager
+            .recent_transitions(10)
+            .into_iter()
+            .filter_map(|t| t.reason)
+            .collect();
+        assert!(reasons.contains(&"No HeartbeatAck for the last 1 heartbeats".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_in_flight_deploy() {
+        let runtime = Arc::new(MockRuntimeAdapter::new());
+        runtime.set_pull_hangs(true);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws/agent/a1", listener.local_addr().unwrap());
+        let state_manager = AgentStateManager::new();
+
+        let control_plane = {
+            let runtime = runtime.clone();
+            let state_manager = state_manager.clone();
+            tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = accept_async(stream).await.unwrap();
+                let deploy = r#"{"type":"DeployContainer","payload":
+                    {"request_id":"req-1","image":"app:v1","name":"app"}}"#;
+                ws.send(Message::Text(deploy.into())).await.unwrap();
+                next_matching(&mut ws, |m| {
+                    matches!(m, AgentMessage::ContainerStatus(s) if s.status == "deploying")
+                })
+                .await;
+
+                // Shut down mid-pull; the result still arrives on this connection
+                state_manager.set_shutting_down();
+                runtime.set_pull_hangs(false);
+                next_matching(&mut ws, |m| matches!(m, AgentMessage::TaskResult(_))).await
+            })
+        };
+
+        let mut client = WebSocketClientBuilder::new(&url, "a1", "s1", runtime).build();
+        timeout(Duration::from_secs(10), client.run(&state_manager))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let result = control_plane.await.unwrap();
+        assert!(matches!(result, AgentMessage::TaskResult(r) if r.task_id == "req-1" && r.success));
+        assert_eq!(client.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_aborts_tasks_past_grace_period() {
+        let client = WebSocketClientBuilder::new(
+            "ws://127.0.0.1:1/ws/agent/a1",
+            "a1",
+            "s1",
+            Arc::new(MockRuntimeAdapter::new()),
+        )
+        .shutdown_grace_secs(0)
+        .build();
+        client.spawn_task(std::future::pending());
+        assert_eq!(client.in_flight(), 1);
+
+        timeout(Duration::from_secs(5), client.drain_tasks()).await.unwrap();
+        assert_eq!(client.in_flight(), 0);
+    }
 }
@@ -5,6 +5,7 @@
 //! agent state handling.
 
 pub mod agent;
+pub mod api;
 pub mod cli;
 pub mod connection;
 pub mod runtime;
@@ -16,4 +17,7 @@ pub use cli::config::Config;
 pub use connection::protocol::{AgentMessage, ControlPlaneMessage};
 pub use connection::websocket::{WebSocketClient, WebSocketClientBuilder};
 pub use runtime::adapter::RuntimeAdapter;
+pub use runtime::containerd::ContainerdAdapter;
 pub use runtime::docker::adapter::DockerAdapter;
+pub use runtime::error::RuntimeError;
+pub use runtime::podman::PodmanAdapter;
@@ -2,17 +2,30 @@
 //!
 //! This is the main entry point for the Syntra Agent binary.
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::{info, Level};
-use tracing_subscriber::FmtSubscriber;
-
-use syntra_agent::cli::config::Config;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn, Level};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+use syntra_agent::api::{AgentMetrics, LocalApiServer, LocalStatus, PrometheusExporter};
+use syntra_agent::cli::config::{Config, LoggingConfig};
+#[cfg(unix)]
+use syntra_agent::cli::daemon::{self, PidFile};
+use syntra_agent::cli::reload::ConfigWatcher;
+use syntra_agent::agent::events::EventWatcher;
 use syntra_agent::agent::state::AgentStateManager;
+use syntra_agent::agent::telemetry::MetricsReporter;
 use syntra_agent::connection::websocket::WebSocketClient;
+use syntra_agent::runtime::adapter::RuntimeAdapter;
+use syntra_agent::runtime::containerd::ContainerdAdapter;
 use syntra_agent::runtime::docker::adapter::DockerAdapter;
+use syntra_agent::runtime::podman::PodmanAdapter;
 
 #[derive(Parser)]
 #[command(name = "syntra-agent")]
@@ -37,6 +50,10 @@ enum Commands {
         /// Run in foreground (don't daemonize)
         #[arg(short, long)]
         foreground: bool,
+
+        /// Reload the configuration file when it changes
+        #[arg(long)]
+        watch_config: bool,
     },
     /// Show agent status
     Status,
@@ -45,36 +62,102 @@ enum Commands {
         /// Service name
         #[arg(short, long, default_value = "syntra-agent")]
         name: String,
+
+        /// Also enable the service to start on boot
+        #[arg(long)]
+        enable: bool,
+    },
+    /// Stop, disable, and remove the system service
+    Uninstall {
+        /// Service name
+        #[arg(short, long, default_value = "syntra-agent")]
+        name: String,
+    },
+    /// Manage the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
     },
     /// Show version information
     Version,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Write a commented default configuration to the --config path
+    Init {
+        /// Overwrite an existing file
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Initialize logging
-    let log_level = if cli.verbose { Level::DEBUG } else { Level::INFO };
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(log_level)
-        .with_target(true)
-        .with_thread_ids(true)
-        .with_file(true)
-        .with_line_number(true)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)?;
+    // Daemonize before the async runtime starts threads, which a fork would lose
+    let _pid_file = match cli.command {
+        Commands::Start { foreground, .. } => prepare_start(&cli.config, foreground)?,
+        _ => None,
+    };
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("Failed to start the async runtime")?
+        .block_on(run(cli))
+}
+
+/// Refuse to start while another agent is running, then daemonize unless
+/// running in the foreground
+#[cfg(unix)]
+fn prepare_start(config_path: &Path, foreground: bool) -> Result<Option<PidFile>> {
+    let config = Config::load(config_path)?;
+    let pid_file = Path::new(&config.daemon.pid_file);
+    if foreground {
+        daemon::ensure_not_running(pid_file)?;
+        return Ok(None);
+    }
+
+    let log_file = config.logging.file.as_deref().map(Path::new);
+    daemon::daemonize(pid_file, log_file).map(Some)
+}
+
+#[cfg(not(unix))]
+fn prepare_start(_config_path: &Path, foreground: bool) -> Result<Option<()>> {
+    if !foreground {
+        eprintln!("Daemon mode is not supported on this platform, running in the foreground");
+    }
+    Ok(None)
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    // Initialize logging from the config file, if there is one yet
+    let logging = Config::load(&cli.config)
+        .map(|config| config.logging)
+        .unwrap_or_default();
+    let log_level = init_logging(&logging, cli.verbose)?;
 
     match cli.command {
-        Commands::Start { foreground } => {
-            start_agent(&cli.config, foreground).await?;
+        Commands::Start { watch_config, .. } => {
+            // --verbose pins the level, so reloads leave it alone
+            let log_level = (watch_config && !cli.verbose).then_some(log_level);
+            start_agent(&cli.config, watch_config, log_level).await?;
         }
         Commands::Status => {
-            show_status().await?;
+            show_status(&cli.config).await?;
         }
-        Commands::Install { name } => {
-            install_service(&name)?;
+        Commands::Install { name, enable } => {
+            install_service(&name, enable)?;
         }
+        Commands::Uninstall { name } => {
+            uninstall_service(&name)?;
+        }
+        Commands::Config { action } => match action {
+            ConfigAction::Init { force } => {
+                init_config(&cli.config, force)?;
+            }
+        },
         Commands::Version => {
             show_version();
         }
@@ -83,33 +166,144 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn start_agent(config_path: &PathBuf, foreground: bool) -> Result<()> {
+/// Changes the level of the installed tracing subscriber
+type LogLevelHandle = reload::Handle<LevelFilter, Registry>;
+
+/// Install the global tracing subscriber
+///
+/// `--verbose` overrides the configured level to debug.
+fn init_logging(logging: &LoggingConfig, verbose: bool) -> Result<LogLevelHandle> {
+    let log_level = if verbose {
+        Level::DEBUG
+    } else {
+        logging.level.parse().unwrap_or(Level::INFO)
+    };
+    let (filter, handle) = reload::Layer::new(LevelFilter::from_level(log_level));
+    let subscriber = tracing_subscriber::registry().with(filter);
+    let layer = fmt::layer()
+        .with_target(true)
+        .with_thread_ids(true)
+        .with_file(true)
+        .with_line_number(true);
+
+    match logging.format.as_str() {
+        "json" => tracing::subscriber::set_global_default(subscriber.with(layer.json()))?,
+        "compact" => tracing::subscriber::set_global_default(subscriber.with(layer.compact()))?,
+        _ => tracing::subscriber::set_global_default(subscriber.with(layer.pretty()))?,
+    }
+    Ok(handle)
+}
+
+/// Apply each reloaded log level to the installed subscriber
+async fn apply_log_levels(mut levels: watch::Receiver<String>, handle: LogLevelHandle) {
+    while levels.changed().await.is_ok() {
+        let level = levels.borrow_and_update().clone();
+        match level.parse::<Level>() {
+            Ok(parsed) => match handle.reload(LevelFilter::from_level(parsed)) {
+                Ok(()) => info!(level = %level, "Log level changed"),
+                Err(e) => warn!(error = %e, "Failed to change log level"),
+            },
+            Err(_) => warn!(level = %level, "Ignoring unknown log level"),
+        }
+    }
+}
+
+async fn start_agent(
+    config_path: &PathBuf,
+    watch_config: bool,
+    log_level: Option<LogLevelHandle>,
+) -> Result<()> {
     info!("Starting Syntra Agent...");
 
     // Load configuration
     let config = Config::load(config_path)?;
+    if let Err(errors) = config.validate() {
+        eprintln!("Invalid configuration in {}:", config_path.display());
+        for error in &errors {
+            eprintln!("  - {}", error);
+        }
+        bail!("Configuration has {} error(s)", errors.len());
+    }
     info!(agent_id = %config.agent_id, "Configuration loaded");
 
-    if !foreground {
-        info!("Running in foreground mode (daemon mode not yet implemented)");
+    // Apply edits to the configuration file while running
+    let config_watcher = watch_config.then(|| ConfigWatcher::new(config_path, config.clone()));
+    if let (Some(watcher), Some(handle)) = (&config_watcher, log_level) {
+        tokio::spawn(apply_log_levels(watcher.log_level(), handle));
     }
 
-    // Initialize Docker adapter
-    let docker = DockerAdapter::new()
-        .context("Failed to initialize Docker adapter")?;
+    // Initialize the configured container runtime
+    match config.runtime.runtime_type.as_str() {
+        "docker" => {
+            let docker = DockerAdapter::new()
+                .context("Failed to initialize Docker adapter")?
+                .with_default_registry_auth(config.runtime.registry_auth.clone());
+            run_agent(&config, docker, config_watcher).await
+        }
+        "containerd" => {
+            let containerd = ContainerdAdapter::connect(
+                &config.runtime.containerd_socket,
+                &config.runtime.containerd_namespace,
+            )
+            .await
+            .context("Failed to initialize containerd adapter")?;
+            run_agent(&config, containerd, config_watcher).await
+        }
+        "podman" => {
+            let podman = match &config.runtime.podman_socket {
+                Some(socket) => PodmanAdapter::with_socket(socket),
+                None => PodmanAdapter::new(),
+            }
+            .context("Failed to initialize Podman adapter")?
+            .with_default_registry_auth(config.runtime.registry_auth.clone());
+            run_agent(&config, podman, config_watcher).await
+        }
+        other => bail!("Unsupported runtime type: {}", other),
+    }
+}
 
-    // Verify Docker is accessible
-    let version = docker.version().await
-        .context("Failed to get Docker version")?;
-    info!(docker_version = %version, "Docker runtime initialized");
+async fn run_agent<R: RuntimeAdapter + 'static>(
+    config: &Config,
+    runtime: R,
+    config_watcher: Option<ConfigWatcher>,
+) -> Result<()> {
+    // Verify the runtime is accessible
+    let version = runtime.version().await
+        .context("Failed to get runtime version")?;
+    info!(
+        runtime = %runtime.runtime_type(),
+        runtime_version = %version,
+        "Container runtime initialized"
+    );
 
     // Wrap in Arc for shared ownership
-    let runtime = Arc::new(docker);
+    let runtime = Arc::new(runtime);
 
     // Initialize state manager
     let state_manager = AgentStateManager::new();
     info!(state = ?state_manager.current_state(), "Agent state initialized");
 
+    // Start the local status API
+    let local_api = if config.local_api.enabled {
+        let addr = config.local_api.addr.parse()
+            .context("Invalid local_api.addr")?;
+        Some(LocalApiServer::start(addr, runtime.clone(), state_manager.clone()).await?)
+    } else {
+        None
+    };
+
+    // Start the Prometheus exporter
+    let prometheus = match &config.telemetry.prometheus_addr {
+        Some(addr) if config.telemetry.enabled => {
+            let addr = addr.parse().context("Invalid telemetry.prometheus_addr")?;
+            let metrics = AgentMetrics::new()?;
+            let watcher = metrics.watch(&state_manager);
+            let exporter = PrometheusExporter::start(addr, metrics.clone()).await?;
+            Some((metrics, watcher, exporter))
+        }
+        _ => None,
+    };
+
     // Connect to control plane
     let ws_url = format!("{}/ws/agent/{}", config.control_plane.url, config.agent_id);
     info!(url = %ws_url, "Connecting to control plane");
@@ -119,18 +313,148 @@ async fn start_agent(config_path: &PathBuf, foreground: bool) -> Result<()> {
         &config.agent_id,
         &config.server_id,
         config.control_plane.reconnect_interval_ms,
-        runtime,
+        runtime.clone(),
+    )
+    .with_api_key(config.control_plane.api_key.clone())
+    .with_max_concurrent_deploys(config.runtime.max_concurrent_deploys)
+    .with_runtime_config(config.runtime.clone())
+    .with_shutdown_grace(Duration::from_secs(config.runtime.shutdown_grace_secs))
+    .with_heartbeat_interval(config.control_plane.heartbeat_interval_secs)
+    .with_max_missed_heartbeat_acks(config.control_plane.max_missed_heartbeat_acks)
+    .with_compression(config.control_plane.compression)
+    .with_encoding(config.control_plane.encoding)
+    .with_message_channel_capacity(config.control_plane.message_channel_capacity)
+    .with_max_message_bytes(config.control_plane.max_message_bytes)
+    .with_ack_retry(
+        Duration::from_secs(config.control_plane.ack_timeout_secs),
+        config.control_plane.ack_max_retries,
+    )
+    .with_rate_limit(
+        config.control_plane.max_messages_per_sec,
+        config.control_plane.message_burst,
+    );
+    if let Some((metrics, _, _)) = &prometheus {
+        ws_client = ws_client.with_metrics(metrics.clone());
+    }
+    if let Some(watcher) = &config_watcher {
+        ws_client = ws_client.with_heartbeat_interval_updates(watcher.heartbeat_interval_secs());
+    }
+
+    // Report containers that die between heartbeats
+    let event_task = tokio::spawn(
+        EventWatcher::new(runtime.clone(), ws_client.message_sender()).run(),
     );
 
+    // Start pushing container metrics
+    let metrics_task = config.telemetry.enabled.then(|| {
+        let mut reporter = MetricsReporter::new(
+            runtime,
+            &config.agent_id,
+            Duration::from_secs(config.telemetry.metrics_interval_secs),
+            config.telemetry.detailed_metrics,
+            ws_client.message_sender(),
+        )
+        .with_disk_usage(config.telemetry.report_disk_usage);
+        if let Some(watcher) = &config_watcher {
+            reporter = reporter.with_interval_updates(watcher.metrics_interval_secs());
+        }
+        tokio::spawn(reporter.run())
+    });
+
+    // Start reloading only once every setting above is subscribed
+    let watch_task = config_watcher.map(ConfigWatcher::spawn).transpose()?;
+
+    // Finish in-flight work on Ctrl-C or SIGTERM instead of dying mid-deploy
+    let signal_task = tokio::spawn(shutdown_on_signal(state_manager.clone()));
+
     // Start the agent main loop
-    ws_client.run(&state_manager).await?;
+    let result = ws_client.run(&state_manager).await;
 
-    Ok(())
+    signal_task.abort();
+    event_task.abort();
+    if let Some(watch_task) = watch_task {
+        watch_task.abort();
+    }
+    if let Some(metrics_task) = metrics_task {
+        metrics_task.abort();
+    }
+    if let Some(local_api) = local_api {
+        local_api.shutdown();
+    }
+    if let Some((_, watcher, exporter)) = prometheus {
+        watcher.abort();
+        exporter.shutdown();
+    }
+
+    result
 }
 
-async fn show_status() -> Result<()> {
+/// Move the agent to the shutting-down state once a termination signal arrives
+async fn shutdown_on_signal(state_manager: AgentStateManager) {
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => info!("Received Ctrl-C, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+    }
+    state_manager.set_shutting_down();
+}
+
+async fn show_status(config_path: &Path) -> Result<()> {
     println!("Agent Status: checking...");
 
+    // Prefer asking the running agent through its local API
+    let config = Config::load(config_path).unwrap_or_else(|_| Config::default_config());
+    if config.local_api.enabled {
+        let url = format!("http://{}/status", config.local_api.addr);
+        match fetch_local_status(&url).await {
+            Ok(status) => {
+                println!("  Agent: running (uptime {}s)", status.uptime_secs);
+                println!("  Control Plane: {}", status.state);
+                println!("  Connection attempts: {}", status.connection_attempts);
+                if let Some(last) = status.last_connected {
+                    println!("  Last connected: {}", last.to_rfc3339());
+                }
+                if status.draining {
+                    println!("  Draining: new deployments are rejected");
+                }
+                match status.container_count {
+                    Some(count) => println!("  Running containers ({}): {}", status.runtime, count),
+                    None => println!("  Running containers ({}): unknown", status.runtime),
+                }
+                if !status.transitions.is_empty() {
+                    println!("  Recent connection events:");
+                    for t in &status.transitions {
+                        let reason = t.reason.as_deref().unwrap_or("no reason given");
+                        println!(
+                            "    {} {} -> {} ({})",
+                            t.timestamp.to_rfc3339(),
+                            t.from,
+                            t.to,
+                            reason
+                        );
+                    }
+                }
+                return Ok(());
+            }
+            Err(e) => println!("  Agent: not reachable at {} - {}", url, e),
+        }
+    }
+
     // Check Docker connectivity
     match DockerAdapter::new() {
         Ok(docker) => {
@@ -148,16 +472,26 @@ async fn show_status() -> Result<()> {
         Err(e) => println!("  Docker: not available - {}", e),
     }
 
-    // TODO: Implement status check via local socket or HTTP endpoint
-    println!("  Control Plane: Not connected (check agent process)");
+    println!("  Control Plane: unknown (enable [local_api] to query a running agent)");
     Ok(())
 }
 
-fn install_service(name: &str) -> Result<()> {
+async fn fetch_local_status(url: &str) -> Result<LocalStatus> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()?;
+    let status = client.get(url).send().await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(status)
+}
+
+fn install_service(name: &str, enable: bool) -> Result<()> {
     println!("Installing service: {}", name);
 
     // Generate systemd service file
-    let service_content = format!(r#"[Unit]
+    let service_content = r#"[Unit]
 Description=Syntra Agent
 After=network.target docker.service
 Requires=docker.service
@@ -172,19 +506,111 @@ Environment=RUST_LOG=info
 
 [Install]
 WantedBy=multi-user.target
-"#);
-
-    let service_path = format!("/etc/systemd/system/{}.service", name);
-    println!("Service file would be created at: {}", service_path);
-    println!("\nService content:");
-    println!("{}", service_content);
-    println!("\nTo install manually, run:");
-    println!("  sudo cp syntra-agent /usr/local/bin/");
-    println!("  sudo nano {}", service_path);
-    println!("  sudo systemctl daemon-reload");
-    println!("  sudo systemctl enable {}", name);
-    println!("  sudo systemctl start {}", name);
+"#;
+
+    let service_path = unit_path(name);
+    if !is_root() {
+        println!("Not running as root, so nothing was installed.");
+        println!("Service file would be created at: {}", service_path);
+        println!("\nService content:");
+        println!("{}", service_content);
+        println!("\nTo install manually, run:");
+        println!("  sudo cp syntra-agent /usr/local/bin/");
+        println!("  sudo nano {}", service_path);
+        println!("  sudo systemctl daemon-reload");
+        println!("  sudo systemctl enable {}", name);
+        println!("  sudo systemctl start {}", name);
+        return Ok(());
+    }
+
+    std::fs::write(&service_path, service_content)
+        .with_context(|| format!("Failed to write {}", service_path))?;
+    println!("Wrote {}", service_path);
+
+    systemctl(&["daemon-reload"])?;
+    if enable {
+        systemctl(&["enable", name])?;
+    }
+
+    println!("\nStart the agent with:");
+    println!("  systemctl start {}", name);
+    Ok(())
+}
+
+fn uninstall_service(name: &str) -> Result<()> {
+    println!("Uninstalling service: {}", name);
+
+    let service_path = unit_path(name);
+    if !is_root() {
+        println!("Not running as root, so nothing was removed.");
+        println!("\nTo uninstall manually, run:");
+        println!("  sudo systemctl stop {}", name);
+        println!("  sudo systemctl disable {}", name);
+        println!("  sudo rm {}", service_path);
+        println!("  sudo systemctl daemon-reload");
+        return Ok(());
+    }
+
+    if !Path::new(&service_path).exists() {
+        bail!("Service {} is not installed ({} not found)", name, service_path);
+    }
+
+    systemctl(&["stop", name])?;
+    systemctl(&["disable", name])?;
+    std::fs::remove_file(&service_path)
+        .with_context(|| format!("Failed to remove {}", service_path))?;
+    println!("Removed {}", service_path);
+    systemctl(&["daemon-reload"])?;
+    Ok(())
+}
+
+/// Where the systemd unit for service `name` lives
+fn unit_path(name: &str) -> String {
+    format!("/etc/systemd/system/{}.service", name)
+}
+
+/// Whether the agent has the privileges to manage system services
+#[cfg(unix)]
+fn is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+/// Run `systemctl` with `args`, printing the command first
+fn systemctl(args: &[&str]) -> Result<()> {
+    println!("$ systemctl {}", args.join(" "));
+    let status = std::process::Command::new("systemctl")
+        .args(args)
+        .status()
+        .context("Failed to run systemctl")?;
+    if !status.success() {
+        bail!("systemctl {} failed with {}", args.join(" "), status);
+    }
+    Ok(())
+}
+
+fn init_config(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!(
+            "Config file already exists: {} (use --force to overwrite)",
+            path.display()
+        );
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    Config::default_config().save_commented(path)?;
 
+    println!("Configuration written to {}", path.display());
+    println!("Edit control_plane.url to point at your Syntra control plane, then run:");
+    println!("  syntra-agent --config {} start", path.display());
     Ok(())
 }
 
@@ -193,8 +619,9 @@ fn show_version() {
     println!("Rust runtime agent for Syntra container orchestration");
     println!();
     println!("Features:");
-    println!("  - Docker container management");
+    println!("  - Docker, containerd, and Podman container management");
     println!("  - WebSocket control plane communication");
     println!("  - Auto-reconnection with exponential backoff");
     println!("  - Heartbeat and status reporting");
+    println!("  - Local HTTP status API");
 }
@@ -4,8 +4,23 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use crate::runtime::error::RuntimeError;
+
+/// Stream of container log lines, yielded as they arrive
+pub type LogStream = Pin<Box<dyn Stream<Item = Result<String, RuntimeError>> + Send>>;
+
+/// Stream of container stats samples, one per runtime sampling interval
+pub type StatsStream = Pin<Box<dyn Stream<Item = Result<ContainerStats, RuntimeError>> + Send>>;
+
+/// Stream of container lifecycle events, yielded as the runtime reports them
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<RuntimeEvent, RuntimeError>> + Send>>;
 
 /// Container information returned by the runtime
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,7 +29,13 @@ pub struct ContainerInfo {
     pub name: String,
     pub image: String,
     pub status: ContainerStatus,
+    /// Result of the image's own health check, where the runtime reports one
+    pub health: Option<ContainerHealth>,
     pub created_at: String,
+    /// When the container last started, if it ever has
+    pub started_at: Option<String>,
+    /// When the container last stopped, if it ever has
+    pub finished_at: Option<String>,
     pub ports: Vec<PortBinding>,
     pub labels: HashMap<String, String>,
 }
@@ -45,6 +66,27 @@ impl std::fmt::Display for ContainerStatus {
     }
 }
 
+/// Container health, as reported by the image's `HEALTHCHECK`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContainerHealth {
+    /// The image defines no health check
+    None,
+    Starting,
+    Healthy,
+    Unhealthy,
+}
+
+impl std::fmt::Display for ContainerHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContainerHealth::None => write!(f, "none"),
+            ContainerHealth::Starting => write!(f, "starting"),
+            ContainerHealth::Healthy => write!(f, "healthy"),
+            ContainerHealth::Unhealthy => write!(f, "unhealthy"),
+        }
+    }
+}
+
 /// Port binding configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PortBinding {
@@ -59,23 +101,178 @@ pub struct PortBinding {
 pub struct CreateContainerOptions {
     pub name: String,
     pub image: String,
+    /// Overrides the image's entrypoint (and with it the image's default command)
+    pub entrypoint: Option<Vec<String>>,
+    /// Overrides the image's default command
     pub command: Option<Vec<String>>,
+    /// Overrides the image's working directory
+    pub working_dir: Option<String>,
+    /// User to run as: `uid`, `uid:gid`, or a user name, as with Docker
+    pub user: Option<String>,
     pub env: Vec<(String, String)>,
     pub ports: Vec<PortBinding>,
-    pub volumes: Vec<VolumeBinding>,
+    pub mounts: Vec<MountSpec>,
     pub labels: HashMap<String, String>,
-    pub network: Option<String>,
+    /// Networks to attach; the first is the primary network, the rest are
+    /// connected after the container is created
+    pub networks: Vec<String>,
+    /// Extra `/etc/hosts` entries, as `host:ip`
+    pub extra_hosts: Vec<String>,
+    /// DNS servers, replacing the host's resolvers
+    pub dns: Vec<String>,
+    /// Container hostname (the runtime's default when unset)
+    pub hostname: Option<String>,
+    /// Linux capabilities to grant beyond the runtime's defaults
+    pub cap_add: Vec<String>,
+    /// Linux capabilities to remove from the runtime's defaults (`ALL` for every one)
+    pub cap_drop: Vec<String>,
+    /// Run with full host privileges
+    pub privileged: bool,
+    /// Log driver (the runtime's default when unset)
+    pub log_driver: Option<String>,
+    /// Options for `log_driver`
+    pub log_options: HashMap<String, String>,
+    /// NVIDIA GPUs to expose to the container
+    pub gpus: Option<GpuSpec>,
+    /// Resource limits for the container's processes, e.g. `nofile`
+    pub ulimits: Vec<Ulimit>,
+    /// Namespaced kernel parameters, e.g. `net.core.somaxconn`
+    pub sysctls: HashMap<String, String>,
     pub memory_limit: Option<u64>,
     pub cpu_limit: Option<f64>,
+    /// Size of `/dev/shm` in MB (the runtime's 64MB default when unset)
+    pub shm_size_mb: Option<u64>,
     pub restart_policy: Option<RestartPolicy>,
+    /// Restarts attempted before giving up; only valid with `RestartPolicy::OnFailure`
+    pub max_restart_retries: Option<u32>,
+    /// Credentials used to pull `image` if it's not present locally
+    pub registry_auth: Option<RegistryAuth>,
+}
+
+/// A process resource limit, as with `docker run --ulimit name=soft:hard`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ulimit {
+    /// Limit name without the `RLIMIT_` prefix, e.g. `nofile`
+    pub name: String,
+    pub soft: u64,
+    pub hard: u64,
+}
+
+/// GPUs requested for a container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuSpec {
+    /// Every GPU on the host
+    All,
+    /// Any `n` GPUs
+    Count(u32),
+    /// Specific GPUs, by index or UUID
+    DeviceIds(Vec<String>),
+}
+
+/// Registry credentials for private image pulls
+///
+/// The `Debug` implementation redacts the password so credentials never
+/// end up in tracing output.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryAuth {
+    pub username: String,
+    pub password: String,
+    /// Registry host (e.g. `ghcr.io`); Docker Hub when unset
+    #[serde(default)]
+    pub server_address: Option<String>,
 }
 
-/// Volume binding configuration
+impl std::fmt::Debug for RegistryAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryAuth")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .field("server_address", &self.server_address)
+            .finish()
+    }
+}
+
+/// Filesystem mount for a container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MountSpec {
+    /// Host path bind mount
+    Bind {
+        source: String,
+        target: String,
+        #[serde(default)]
+        read_only: bool,
+    },
+    /// Named volume that persists across container replacements
+    Volume {
+        name: String,
+        target: String,
+        #[serde(default)]
+        read_only: bool,
+    },
+    /// In-memory filesystem, e.g. for secrets that must not touch disk
+    Tmpfs {
+        target: String,
+        #[serde(default)]
+        size_mb: Option<u64>,
+    },
+}
+
+/// Named volume information
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VolumeBinding {
-    pub source: String,
-    pub target: String,
-    pub read_only: bool,
+pub struct VolumeInfo {
+    pub name: String,
+    pub driver: String,
+    pub mountpoint: String,
+    pub labels: HashMap<String, String>,
+}
+
+/// Network information
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkInfo {
+    pub id: String,
+    pub name: String,
+    pub driver: String,
+    /// `local`, `global`, or `swarm`
+    pub scope: String,
+    /// IDs of the attached containers; only populated by `inspect_network`
+    pub containers: Vec<String>,
+}
+
+/// Container lifecycle event reported by the runtime
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeEvent {
+    pub container_id: String,
+    /// Container name, if the runtime included it
+    pub name: Option<String>,
+    pub action: RuntimeEventAction,
+    /// Exit code for `Die` events
+    pub exit_code: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Lifecycle actions surfaced by `RuntimeAdapter::events`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeEventAction {
+    /// A signal was sent to the container, e.g. by a stop request
+    Kill,
+    /// The container ran out of memory
+    Oom,
+    /// The container's main process exited
+    Die,
+    /// The container was stopped on request
+    Stop,
+    /// The container was removed
+    Destroy,
+}
+
+/// Outcome of a prune operation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneReport {
+    /// IDs or names of the removed items
+    pub items_removed: Vec<String>,
+    pub space_reclaimed_bytes: u64,
 }
 
 /// Container restart policy
@@ -96,6 +293,61 @@ pub struct ImageInfo {
     pub created_at: String,
 }
 
+/// Build context for an image build, as a tar archive
+#[derive(Clone)]
+pub enum BuildContext {
+    /// Path to a tar archive on the agent host
+    TarPath(PathBuf),
+    /// In-memory tar archive
+    TarBytes(Vec<u8>),
+}
+
+impl std::fmt::Debug for BuildContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildContext::TarPath(path) => f.debug_tuple("TarPath").field(path).finish(),
+            BuildContext::TarBytes(bytes) => write!(f, "TarBytes({} bytes)", bytes.len()),
+        }
+    }
+}
+
+/// Image build options
+#[derive(Debug, Clone)]
+pub struct BuildImageOptions {
+    pub context: BuildContext,
+    /// Tag applied to the built image
+    pub tag: String,
+    /// Dockerfile path relative to the context root; `Dockerfile` when unset
+    pub dockerfile: Option<String>,
+    pub build_args: HashMap<String, String>,
+}
+
+/// Options for creating a network
+#[derive(Debug, Clone, Default)]
+pub struct CreateNetworkOptions {
+    pub name: String,
+    /// Network driver, e.g. `bridge` or `overlay`
+    pub driver: String,
+    /// IPAM subnet in CIDR form (e.g. `10.20.0.0/24`); allocated by the runtime when unset
+    pub subnet: Option<String>,
+    /// Gateway address within `subnet`
+    pub gateway: Option<String>,
+    /// Cut the network off from outside traffic
+    pub internal: bool,
+    pub labels: HashMap<String, String>,
+}
+
+impl CreateNetworkOptions {
+    /// A plain bridge network with runtime-allocated addressing
+    pub fn bridge(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            driver: "bridge".to_string(),
+            ..Default::default()
+        }
+    }
+}
+
 /// Container logs options
 #[derive(Debug, Clone, Default)]
 pub struct LogsOptions {
@@ -107,6 +359,73 @@ pub struct LogsOptions {
     pub until: Option<String>,
 }
 
+/// Options for running a command in a container
+#[derive(Debug, Clone, Default)]
+pub struct ExecOptions {
+    pub cmd: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub working_dir: Option<String>,
+    /// Bytes written to the command's stdin, which is then closed
+    pub stdin: Option<Vec<u8>>,
+}
+
+impl ExecOptions {
+    /// Run `cmd` with no extra environment, default working directory, and no stdin
+    pub fn new(cmd: Vec<String>) -> Self {
+        Self {
+            cmd,
+            ..Default::default()
+        }
+    }
+}
+
+/// Result of a command run in a container
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecResult {
+    pub exit_code: i64,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// A process running inside a container
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub user: Option<String>,
+    pub command: String,
+    /// CPU usage percent, when the platform reports it
+    pub cpu: Option<f64>,
+    /// Memory usage percent, when the platform reports it
+    pub mem: Option<f64>,
+}
+
+/// How a path in a container's filesystem differs from its image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FsChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A path a container has changed relative to its image
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FsChange {
+    pub path: String,
+    pub kind: FsChangeKind,
+}
+
+/// Disk space used by the runtime, as reported by `docker system df`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub images_bytes: u64,
+    pub containers_bytes: u64,
+    pub volumes_bytes: u64,
+    pub build_cache_bytes: u64,
+    /// Space a prune of unused images, stopped containers, unreferenced
+    /// volumes, and idle build cache would free
+    pub reclaimable_bytes: u64,
+}
+
 /// Container stats
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContainerStats {
@@ -125,51 +444,226 @@ pub trait RuntimeAdapter: Send + Sync {
     /// Get the runtime type name
     fn runtime_type(&self) -> &str;
 
+    /// Optional features this runtime supports, advertised at registration so the
+    /// control plane doesn't send work the agent can't do. Features that depend on
+    /// the host, like `gpu`, are detected separately.
+    fn capabilities(&self) -> Vec<String> {
+        ["logs", "exec", "build", "prune", "push", "rename"]
+            .iter()
+            .map(|capability| capability.to_string())
+            .collect()
+    }
+
     /// Check if the runtime is available and healthy
-    async fn health_check(&self) -> Result<bool>;
+    async fn health_check(&self) -> Result<bool, RuntimeError>;
 
     /// Get runtime version information
-    async fn version(&self) -> Result<String>;
+    async fn version(&self) -> Result<String, RuntimeError>;
+
+    /// Whether containers can be given NVIDIA GPUs
+    async fn gpu_available(&self) -> Result<bool, RuntimeError>;
 
     /// List all containers
-    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>>;
+    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, RuntimeError>;
 
     /// Get container by ID or name
-    async fn get_container(&self, id_or_name: &str) -> Result<Option<ContainerInfo>>;
+    async fn get_container(&self, id_or_name: &str) -> Result<Option<ContainerInfo>, RuntimeError>;
 
     /// Create a new container
-    async fn create_container(&self, options: CreateContainerOptions) -> Result<String>;
+    async fn create_container(
+        &self,
+        options: CreateContainerOptions,
+    ) -> Result<String, RuntimeError>;
 
     /// Start a container
-    async fn start_container(&self, id: &str) -> Result<()>;
+    async fn start_container(&self, id: &str) -> Result<(), RuntimeError>;
 
     /// Stop a container
-    async fn stop_container(&self, id: &str, timeout_secs: Option<u64>) -> Result<()>;
+    async fn stop_container(&self, id: &str, timeout_secs: Option<u64>) -> Result<(), RuntimeError>;
+
+    /// Send a signal (a name like `SIGTERM` or a number) to a container's main process
+    async fn kill_container(&self, id: &str, signal: &str) -> Result<(), RuntimeError>;
+
+    /// Wait for a container to exit, returning its exit code
+    async fn wait_container(&self, id: &str) -> Result<i64, RuntimeError>;
 
     /// Remove a container
-    async fn remove_container(&self, id: &str, force: bool) -> Result<()>;
+    async fn remove_container(&self, id: &str, force: bool) -> Result<(), RuntimeError>;
+
+    /// Rename a container
+    ///
+    /// Fails with `RuntimeError::Conflict` if `new_name` is already in use.
+    async fn rename_container(&self, id: &str, new_name: &str) -> Result<(), RuntimeError>;
 
     /// Get container logs
-    async fn logs(&self, id: &str, options: LogsOptions) -> Result<Vec<String>>;
+    ///
+    /// Buffers the log history into memory; `follow` is ignored since a
+    /// buffered call could never return. Use `logs_stream` to follow.
+    async fn logs(&self, id: &str, options: LogsOptions) -> Result<Vec<String>, RuntimeError>;
+
+    /// Stream container logs line by line as they arrive
+    async fn logs_stream(&self, id: &str, options: LogsOptions) -> Result<LogStream, RuntimeError>;
 
     /// Get container stats
-    async fn stats(&self, id: &str) -> Result<ContainerStats>;
+    async fn stats(&self, id: &str) -> Result<ContainerStats, RuntimeError>;
+
+    /// Stream container stats continuously
+    ///
+    /// Each sample's CPU percentage is computed against the previous one, so
+    /// it's more accurate than repeated `stats` calls.
+    async fn stats_stream(&self, id: &str) -> Result<StatsStream, RuntimeError>;
 
-    /// Pull an image
-    async fn pull_image(&self, image: &str) -> Result<()>;
+    /// Pull an image, authenticating with `auth` when provided
+    async fn pull_image(&self, image: &str, auth: Option<RegistryAuth>) -> Result<(), RuntimeError>;
+
+    /// Build an image from a tar build context, returning the image ID
+    async fn build_image(&self, options: BuildImageOptions) -> Result<String, RuntimeError>;
 
     /// List images
-    async fn list_images(&self) -> Result<Vec<ImageInfo>>;
+    async fn list_images(&self) -> Result<Vec<ImageInfo>, RuntimeError>;
+
+    /// Whether `image` is present locally
+    async fn image_exists(&self, image: &str) -> Result<bool, RuntimeError>;
+
+    /// Registry digests (`sha256:...`) of a local image; empty for images
+    /// that were built locally rather than pulled
+    async fn image_digests(&self, image: &str) -> Result<Vec<String>, RuntimeError>;
 
     /// Remove an image
-    async fn remove_image(&self, id: &str, force: bool) -> Result<()>;
+    async fn remove_image(&self, id: &str, force: bool) -> Result<(), RuntimeError>;
 
-    /// Create a network
-    async fn create_network(&self, name: &str) -> Result<String>;
+    /// Create a network, returning its ID
+    async fn create_network(&self, options: CreateNetworkOptions) -> Result<String, RuntimeError>;
 
     /// Remove a network
-    async fn remove_network(&self, name: &str) -> Result<()>;
+    async fn remove_network(&self, name: &str) -> Result<(), RuntimeError>;
+
+    /// List networks
+    async fn list_networks(&self) -> Result<Vec<NetworkInfo>, RuntimeError>;
+
+    /// Look up a network by name or ID, with its attached containers
+    async fn inspect_network(&self, name: &str) -> Result<Option<NetworkInfo>, RuntimeError>;
+
+    /// Attach a container to a network
+    async fn connect_network(&self, container_id: &str, network: &str) -> Result<(), RuntimeError>;
+
+    /// Detach a container from a network
+    async fn disconnect_network(
+        &self,
+        container_id: &str,
+        network: &str,
+    ) -> Result<(), RuntimeError>;
+
+    /// Create a named volume
+    async fn create_volume(
+        &self,
+        name: &str,
+        labels: HashMap<String, String>,
+    ) -> Result<String, RuntimeError>;
+
+    /// List named volumes
+    async fn list_volumes(&self) -> Result<Vec<VolumeInfo>, RuntimeError>;
+
+    /// Remove a named volume
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), RuntimeError>;
+
+    /// Remove all stopped containers
+    async fn prune_containers(&self) -> Result<PruneReport, RuntimeError>;
+
+    /// Remove unused images; only untagged ones when `dangling_only` is set
+    async fn prune_images(&self, dangling_only: bool) -> Result<PruneReport, RuntimeError>;
+
+    /// Remove volumes not used by any container
+    async fn prune_volumes(&self) -> Result<PruneReport, RuntimeError>;
+
+    /// Extract a tar archive into the container at `dest_path`
+    ///
+    /// `tar_data` must be a tar-encoded archive, not raw file contents. Fails
+    /// with `RuntimeError::NotFound` if the container or path doesn't exist.
+    async fn copy_to_container(
+        &self,
+        id: &str,
+        dest_path: &str,
+        tar_data: Vec<u8>,
+    ) -> Result<(), RuntimeError>;
+
+    /// Read `src_path` from the container as a tar archive
+    ///
+    /// The returned bytes are tar-encoded even for a single file. Fails with
+    /// `RuntimeError::NotFound` if the container or path doesn't exist.
+    async fn copy_from_container(&self, id: &str, src_path: &str) -> Result<Vec<u8>, RuntimeError>;
+
+    /// Stream lifecycle events for containers managed by the agent
+    ///
+    /// Only kill, OOM, die, and stop events are reported.
+    async fn events(&self) -> Result<EventStream, RuntimeError>;
 
     /// Execute a command in a running container
-    async fn exec(&self, id: &str, cmd: Vec<String>) -> Result<(i64, String)>;
+    async fn exec(&self, id: &str, options: ExecOptions) -> Result<ExecResult, RuntimeError>;
+
+    /// List the processes running in a container
+    async fn top(&self, id: &str) -> Result<Vec<ProcessInfo>, RuntimeError>;
+
+    /// List the paths a container has added, modified, or deleted since it was created
+    async fn container_diff(&self, id: &str) -> Result<Vec<FsChange>, RuntimeError>;
+
+    /// Report disk space used by images, containers, volumes, and build cache
+    async fn disk_usage(&self) -> Result<DiskUsage, RuntimeError>;
+
+    /// Snapshot a container's filesystem as the image `repo:tag`, returning the image ID
+    async fn commit_container(
+        &self,
+        id: &str,
+        repo: &str,
+        tag: &str,
+        message: Option<String>,
+    ) -> Result<String, RuntimeError>;
+
+    /// Tag the image `source` (an ID or reference) as `repo:tag`
+    async fn tag_image(&self, source: &str, repo: &str, tag: &str) -> Result<(), RuntimeError>;
+
+    /// Push `image` (`repo:tag`) to its registry, authenticating with `auth` when
+    /// provided. Rejected credentials fail with `RuntimeError::Unauthorized`.
+    async fn push_image(&self, image: &str, auth: Option<RegistryAuth>) -> Result<(), RuntimeError>;
+
+    /// Execute a command and return its exit code with stdout and stderr combined
+    async fn exec_output(&self, id: &str, cmd: Vec<String>) -> Result<(i64, String), RuntimeError> {
+        let result = self.exec(id, ExecOptions::new(cmd)).await?;
+        Ok((result.exit_code, result.stdout + &result.stderr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_auth_debug_redacts_password() {
+        let auth = RegistryAuth {
+            username: "deploy".to_string(),
+            password: "hunter2".to_string(),
+            server_address: Some("ghcr.io".to_string()),
+        };
+
+        let debug = format!("{:?}", auth);
+        assert!(debug.contains("deploy"));
+        assert!(!debug.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_build_context_debug_omits_bytes() {
+        let context = BuildContext::TarBytes(vec![0u8; 2048]);
+        assert_eq!(format!("{:?}", context), "TarBytes(2048 bytes)");
+    }
+
+    #[tokio::test]
+    async fn test_exec_output_wraps_exec() {
+        let runtime = crate::runtime::mock::MockRuntimeAdapter::new();
+        let id = runtime.add_running("app", "nginx:latest");
+        runtime.set_exec_exit_code(3);
+
+        let (code, output) = runtime.exec_output(&id, vec!["false".to_string()]).await.unwrap();
+        assert_eq!(code, 3);
+        assert!(output.is_empty());
+    }
 }
@@ -0,0 +1,1212 @@
+//! containerd Adapter
+//!
+//! Implementation of RuntimeAdapter for containerd using its gRPC API.
+//!
+//! containerd has no notion of container names, so the container name is
+//! used as the containerd container ID, snapshot key, and task ID. Port
+//! bindings and networks require CNI and are not handled by this adapter;
+//! operations without a containerd equivalent return
+//! `RuntimeError::Unsupported`.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use containerd_client::services::v1::container::Runtime;
+use containerd_client::services::v1::containers_client::ContainersClient;
+use containerd_client::services::v1::content_client::ContentClient;
+use containerd_client::services::v1::images_client::ImagesClient;
+use containerd_client::services::v1::snapshots::snapshots_client::SnapshotsClient;
+use containerd_client::services::v1::snapshots::{
+    MountsRequest, PrepareSnapshotRequest, RemoveSnapshotRequest,
+};
+use containerd_client::services::v1::tasks_client::TasksClient;
+use containerd_client::services::v1::transfer_client::TransferClient;
+use containerd_client::services::v1::version_client::VersionClient;
+use containerd_client::services::v1::{
+    Container, CreateContainerRequest, CreateTaskRequest, DeleteContainerRequest,
+    DeleteImageRequest, DeleteTaskRequest, GetContainerRequest, GetImageRequest, GetRequest,
+    KillRequest, ListContainersRequest, ListImagesRequest, ReadContentRequest, StartRequest,
+    TransferRequest, WaitRequest,
+};
+use containerd_client::tonic::metadata::AsciiMetadataValue;
+use containerd_client::tonic::transport::Channel;
+use containerd_client::tonic::{Code, Request};
+use containerd_client::types::v1::Status;
+use containerd_client::types::Platform;
+use prost::Message;
+use prost_types::Any;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use super::transfer::{ImageStore, OciRegistry, UnpackConfiguration};
+use crate::runtime::adapter::{
+    BuildImageOptions, ContainerInfo, ContainerStats, ContainerStatus, CreateContainerOptions,
+    CreateNetworkOptions, DiskUsage, EventStream, ExecOptions, ExecResult, FsChange, ImageInfo,
+    LogStream, LogsOptions, MountSpec, NetworkInfo, ProcessInfo, PruneReport, RegistryAuth,
+    RuntimeAdapter, StatsStream, Ulimit, VolumeInfo,
+};
+use crate::runtime::error::RuntimeError;
+
+const RUNTIME: &str = "containerd";
+const RUNC_RUNTIME: &str = "io.containerd.runc.v2";
+const DEFAULT_SNAPSHOTTER: &str = "overlayfs";
+const SPEC_TYPE_URL: &str = "types.containerd.io/opencontainers/runtime-spec/1/Spec";
+const OCI_REGISTRY_TYPE_URL: &str = "types.containerd.io/containerd.types.transfer.OCIRegistry";
+const IMAGE_STORE_TYPE_URL: &str = "types.containerd.io/containerd.types.transfer.ImageStore";
+/// Capabilities granted to containers unless dropped, matching Docker's defaults
+const DEFAULT_CAPABILITIES: [&str; 14] = [
+    "CAP_CHOWN",
+    "CAP_DAC_OVERRIDE",
+    "CAP_FSETID",
+    "CAP_FOWNER",
+    "CAP_MKNOD",
+    "CAP_NET_RAW",
+    "CAP_SETGID",
+    "CAP_SETUID",
+    "CAP_SETFCAP",
+    "CAP_SETPCAP",
+    "CAP_NET_BIND_SERVICE",
+    "CAP_SYS_CHROOT",
+    "CAP_KILL",
+    "CAP_AUDIT_WRITE",
+];
+const SIGTERM: u32 = 15;
+const SIGKILL: u32 = 9;
+
+/// Image configuration needed to create a container from an image
+struct ImageConfig {
+    chain_id: String,
+    entrypoint: Vec<String>,
+    cmd: Vec<String>,
+    env: Vec<String>,
+    working_dir: String,
+}
+
+/// containerd runtime adapter
+pub struct ContainerdAdapter {
+    channel: Channel,
+    socket_path: String,
+    namespace: String,
+    namespace_header: AsciiMetadataValue,
+    snapshotter: String,
+}
+
+impl ContainerdAdapter {
+    /// Connect to containerd on the given socket, scoping all calls to `namespace`
+    pub async fn connect(socket_path: &str, namespace: &str) -> Result<Self> {
+        let channel = containerd_client::connect(socket_path)
+            .await
+            .with_context(|| format!("Failed to connect to containerd socket at {}", socket_path))?;
+
+        let namespace_header = namespace
+            .parse()
+            .with_context(|| format!("Invalid containerd namespace: {}", namespace))?;
+
+        Ok(Self {
+            channel,
+            socket_path: socket_path.to_string(),
+            namespace: namespace.to_string(),
+            namespace_header,
+            snapshotter: DEFAULT_SNAPSHOTTER.to_string(),
+        })
+    }
+
+    /// Get the socket path this adapter is connected to
+    pub fn socket_path(&self) -> &str {
+        &self.socket_path
+    }
+
+    /// Get the containerd namespace used by this adapter
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Wrap a message in a request scoped to our namespace
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request
+            .metadata_mut()
+            .insert("containerd-namespace", self.namespace_header.clone());
+        request
+    }
+
+    /// Convert a containerd task status to our ContainerStatus
+    fn parse_status(status: i32) -> ContainerStatus {
+        match Status::try_from(status) {
+            Ok(Status::Created) => ContainerStatus::Created,
+            Ok(Status::Running) => ContainerStatus::Running,
+            Ok(Status::Stopped) => ContainerStatus::Exited,
+            Ok(Status::Paused) | Ok(Status::Pausing) => ContainerStatus::Paused,
+            _ => ContainerStatus::Unknown,
+        }
+    }
+
+    /// Get the status of a container's task; a container without a task is Created
+    async fn task_status(&self, id: &str) -> Result<ContainerStatus> {
+        let mut tasks = TasksClient::new(self.channel.clone());
+        let request = self.request(GetRequest {
+            container_id: id.to_string(),
+            exec_id: String::new(),
+        });
+
+        match tasks.get(request).await {
+            Ok(response) => Ok(response
+                .into_inner()
+                .process
+                .map(|p| Self::parse_status(p.status))
+                .unwrap_or(ContainerStatus::Unknown)),
+            Err(status) if status.code() == Code::NotFound => Ok(ContainerStatus::Created),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Convert a containerd container into ContainerInfo
+    async fn container_info(&self, container: Container) -> Result<ContainerInfo> {
+        let status = self.task_status(&container.id).await?;
+
+        Ok(ContainerInfo {
+            id: container.id.clone(),
+            name: container.id,
+            image: container.image,
+            status,
+            health: None,
+            created_at: container
+                .created_at
+                .and_then(|ts| Utc.timestamp_opt(ts.seconds, ts.nanos as u32).single())
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+            started_at: None,
+            finished_at: None,
+            ports: vec![],
+            labels: container.labels,
+        })
+    }
+
+    /// Read a blob from the content store
+    async fn read_content(&self, digest: &str) -> Result<Vec<u8>> {
+        let mut content = ContentClient::new(self.channel.clone());
+        let request = self.request(ReadContentRequest {
+            digest: digest.to_string(),
+            offset: 0,
+            size: 0,
+        });
+
+        let mut stream = content
+            .read(request)
+            .await
+            .with_context(|| format!("Failed to read content {}", digest))?
+            .into_inner();
+
+        let mut data = Vec::new();
+        while let Some(chunk) = stream.message().await? {
+            data.extend_from_slice(&chunk.data);
+        }
+
+        Ok(data)
+    }
+
+    /// Resolve an image's rootfs chain ID and default process configuration
+    async fn image_config(&self, image: &str) -> Result<ImageConfig> {
+        let mut images = ImagesClient::new(self.channel.clone());
+        let target = images
+            .get(self.request(GetImageRequest {
+                name: image.to_string(),
+            }))
+            .await
+            .with_context(|| format!("Image {} not found; pull it first", image))?
+            .into_inner()
+            .image
+            .and_then(|i| i.target)
+            .ok_or_else(|| anyhow!("Image {} has no target descriptor", image))?;
+
+        let mut manifest: Value = serde_json::from_slice(&self.read_content(&target.digest).await?)?;
+
+        // Multi-platform images point at an index; pick the manifest for this host
+        if manifest.get("manifests").is_some() {
+            let digest = select_platform_manifest(&manifest).ok_or_else(|| {
+                anyhow!("Image {} has no manifest for linux/{}", image, oci_arch())
+            })?;
+            manifest = serde_json::from_slice(&self.read_content(&digest).await?)?;
+        }
+
+        let config_digest = manifest["config"]["digest"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Image {} manifest has no config", image))?;
+        let config: Value = serde_json::from_slice(&self.read_content(config_digest).await?)?;
+
+        let diff_ids = json_strings(&config["rootfs"]["diff_ids"]);
+        let chain_id =
+            chain_id(&diff_ids).ok_or_else(|| anyhow!("Image {} has no layers", image))?;
+
+        Ok(ImageConfig {
+            chain_id,
+            entrypoint: json_strings(&config["config"]["Entrypoint"]),
+            cmd: json_strings(&config["config"]["Cmd"]),
+            env: json_strings(&config["config"]["Env"]),
+            working_dir: config["config"]["WorkingDir"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
+    /// Send a signal to a container's task
+    async fn kill_task(&self, id: &str, signal: u32) -> Result<()> {
+        let mut tasks = TasksClient::new(self.channel.clone());
+        tasks
+            .kill(self.request(KillRequest {
+                container_id: id.to_string(),
+                exec_id: String::new(),
+                signal,
+                all: true,
+            }))
+            .await?;
+        Ok(())
+    }
+
+    /// Wait for a container's task to exit, returning its exit status
+    async fn wait_task(&self, id: &str) -> Result<u32> {
+        let mut tasks = TasksClient::new(self.channel.clone());
+        let response = tasks
+            .wait(self.request(WaitRequest {
+                container_id: id.to_string(),
+                exec_id: String::new(),
+            }))
+            .await?;
+        Ok(response.into_inner().exit_status)
+    }
+
+    /// Delete a container's task, ignoring a task that doesn't exist
+    async fn delete_task(&self, id: &str) -> Result<()> {
+        let mut tasks = TasksClient::new(self.channel.clone());
+        match tasks
+            .delete(self.request(DeleteTaskRequest {
+                container_id: id.to_string(),
+            }))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(status) if status.code() == Code::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl From<containerd_client::tonic::Status> for RuntimeError {
+    /// Classify a containerd API error by its gRPC status code
+    fn from(status: containerd_client::tonic::Status) -> Self {
+        match status.code() {
+            Code::NotFound => RuntimeError::not_found("object", status.message()),
+            Code::Unavailable | Code::DeadlineExceeded => {
+                RuntimeError::daemon_unavailable(status.message())
+            }
+            _ => RuntimeError::Other(status.into()),
+        }
+    }
+}
+
+/// Map the host architecture to its OCI platform name
+fn oci_arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => other,
+    }
+}
+
+/// Select the manifest digest matching this host from an image index
+fn select_platform_manifest(index: &Value) -> Option<String> {
+    index["manifests"].as_array()?.iter().find_map(|m| {
+        let platform = &m["platform"];
+        if platform["os"] == "linux" && platform["architecture"] == oci_arch() {
+            m["digest"].as_str().map(|d| d.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Collect a JSON array of strings, treating anything else as empty
+fn json_strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Compute the snapshot chain ID for a list of layer diff IDs
+fn chain_id(diff_ids: &[String]) -> Option<String> {
+    let mut iter = diff_ids.iter();
+    let mut chain = iter.next()?.clone();
+    for diff_id in iter {
+        let digest = Sha256::digest(format!("{} {}", chain, diff_id).as_bytes());
+        chain = format!("sha256:{}", hex::encode(digest));
+    }
+    Some(chain)
+}
+
+/// Expand a Docker-style image reference into the fully qualified form containerd expects
+fn normalize_reference(image: &str) -> String {
+    let (name, suffix) = match image.split_once('@') {
+        Some((name, digest)) => (name.to_string(), format!("@{}", digest)),
+        None => {
+            // A colon after the last slash is a tag, not a registry port
+            let last_segment = image.rsplit('/').next().unwrap_or(image);
+            if last_segment.contains(':') {
+                (image.to_string(), String::new())
+            } else {
+                (image.to_string(), ":latest".to_string())
+            }
+        }
+    };
+
+    let first = name.split('/').next().unwrap_or_default();
+    let has_registry =
+        name.contains('/') && (first.contains('.') || first.contains(':') || first == "localhost");
+
+    let name = if has_registry {
+        name
+    } else if name.contains('/') {
+        format!("docker.io/{}", name)
+    } else {
+        format!("docker.io/library/{}", name)
+    };
+
+    format!("{}{}", name, suffix)
+}
+
+/// Number of a signal given by name (`SIGQUIT` or `QUIT`) or number
+fn signal_number(signal: &str) -> Option<u32> {
+    if let Ok(number) = signal.parse() {
+        return Some(number);
+    }
+    let name = signal.to_uppercase();
+    let number = match name.strip_prefix("SIG").unwrap_or(&name) {
+        "HUP" => 1,
+        "INT" => 2,
+        "QUIT" => 3,
+        "KILL" => SIGKILL,
+        "USR1" => 10,
+        "USR2" => 12,
+        "TERM" => SIGTERM,
+        "WINCH" => 28,
+        _ => return None,
+    };
+    Some(number)
+}
+
+/// Parse a `uid` or `uid:gid` user; names would need the image's /etc/passwd
+fn numeric_user(user: &str) -> Option<(u32, u32)> {
+    match user.split_once(':') {
+        Some((uid, gid)) => Some((uid.parse().ok()?, gid.parse().ok()?)),
+        None => Some((user.parse().ok()?, 0)),
+    }
+}
+
+/// Process rlimits: a 1024 open-file default, overridden or extended by `ulimits`
+fn rlimits(ulimits: &[Ulimit]) -> Vec<Value> {
+    let mut limits = vec![("RLIMIT_NOFILE".to_string(), 1024, 1024)];
+    for ulimit in ulimits {
+        let kind = format!("RLIMIT_{}", ulimit.name.to_ascii_uppercase());
+        limits.retain(|(existing, _, _)| *existing != kind);
+        limits.push((kind, ulimit.soft, ulimit.hard));
+    }
+
+    limits
+        .into_iter()
+        .map(|(kind, soft, hard)| json!({ "type": kind, "soft": soft, "hard": hard }))
+        .collect()
+}
+
+/// Apply `cap_add` and `cap_drop` to the default capability set
+///
+/// Names are accepted with or without the `CAP_` prefix, as with Docker.
+fn capabilities(cap_add: &[String], cap_drop: &[String]) -> Vec<String> {
+    fn normalize(cap: &str) -> String {
+        let cap = cap.to_ascii_uppercase();
+        if cap.starts_with("CAP_") || cap == "ALL" {
+            cap
+        } else {
+            format!("CAP_{}", cap)
+        }
+    }
+
+    let drop: Vec<String> = cap_drop.iter().map(|c| normalize(c)).collect();
+    let mut caps: Vec<String> = if drop.iter().any(|c| c == "ALL") {
+        Vec::new()
+    } else {
+        DEFAULT_CAPABILITIES
+            .iter()
+            .map(|c| c.to_string())
+            .filter(|c| !drop.contains(c))
+            .collect()
+    };
+
+    for cap in cap_add.iter().map(|c| normalize(c)) {
+        if cap != "ALL" && !caps.contains(&cap) {
+            caps.push(cap);
+        }
+    }
+    caps
+}
+
+/// Build an OCI runtime spec for a container
+fn build_spec(options: &CreateContainerOptions, image: &ImageConfig) -> Value {
+    // As with Docker, overriding the entrypoint also drops the image's command
+    let (mut args, default_cmd) = match &options.entrypoint {
+        Some(entrypoint) => (entrypoint.clone(), &[][..]),
+        None => (image.entrypoint.clone(), &image.cmd[..]),
+    };
+    match &options.command {
+        Some(command) => args.extend(command.iter().cloned()),
+        None => args.extend(default_cmd.iter().cloned()),
+    }
+
+    let mut env = image.env.clone();
+    env.extend(options.env.iter().map(|(k, v)| format!("{}={}", k, v)));
+
+    let cwd = match &options.working_dir {
+        Some(dir) => dir.clone(),
+        None if image.working_dir.is_empty() => "/".to_string(),
+        None => image.working_dir.clone(),
+    };
+
+    let shm_size = format!("size={}k", options.shm_size_mb.unwrap_or(64) * 1024);
+    let (uid, gid) = options.user.as_deref().and_then(numeric_user).unwrap_or((0, 0));
+
+    let mut mounts = vec![
+        json!({ "destination": "/proc", "type": "proc", "source": "proc", "options": ["nosuid", "noexec", "nodev"] }),
+        json!({ "destination": "/dev", "type": "tmpfs", "source": "tmpfs", "options": ["nosuid", "strictatime", "mode=755", "size=65536k"] }),
+        json!({ "destination": "/dev/pts", "type": "devpts", "source": "devpts", "options": ["nosuid", "noexec", "newinstance", "ptmxmode=0666", "mode=0620", "gid=5"] }),
+        json!({ "destination": "/dev/shm", "type": "tmpfs", "source": "shm", "options": ["nosuid", "noexec", "nodev", "mode=1777", shm_size] }),
+        json!({ "destination": "/dev/mqueue", "type": "mqueue", "source": "mqueue", "options": ["nosuid", "noexec", "nodev"] }),
+        json!({ "destination": "/sys", "type": "sysfs", "source": "sysfs", "options": ["nosuid", "noexec", "nodev", "ro"] }),
+        json!({ "destination": "/sys/fs/cgroup", "type": "cgroup", "source": "cgroup", "options": ["ro", "nosuid", "noexec", "nodev"] }),
+    ];
+    mounts.extend(options.mounts.iter().filter_map(|mount| match mount {
+        MountSpec::Bind {
+            source,
+            target,
+            read_only,
+        } => Some(json!({
+            "destination": target,
+            "type": "bind",
+            "source": source,
+            "options": ["rbind", if *read_only { "ro" } else { "rw" }],
+        })),
+        MountSpec::Tmpfs { target, size_mb } => {
+            let mut tmpfs_options = vec!["nosuid".to_string(), "nodev".to_string()];
+            tmpfs_options.extend(size_mb.map(|mb| format!("size={}m", mb)));
+            Some(json!({
+                "destination": target,
+                "type": "tmpfs",
+                "source": "tmpfs",
+                "options": tmpfs_options,
+            }))
+        }
+        // Rejected by create_container
+        MountSpec::Volume { .. } => None,
+    }));
+
+    let mut resources = json!({ "devices": [{ "allow": false, "access": "rwm" }] });
+    if let Some(memory_mb) = options.memory_limit {
+        resources["memory"] = json!({ "limit": memory_mb * 1024 * 1024 });
+    }
+    if let Some(cpus) = options.cpu_limit {
+        resources["cpu"] = json!({ "quota": (cpus * 100_000.0) as i64, "period": 100_000 });
+    }
+
+    let capabilities = json!(capabilities(&options.cap_add, &options.cap_drop));
+
+    json!({
+        "ociVersion": "1.1.0",
+        "process": {
+            "terminal": false,
+            "user": { "uid": uid, "gid": gid },
+            "args": args,
+            "env": env,
+            "cwd": cwd,
+            "capabilities": {
+                "bounding": capabilities,
+                "effective": capabilities,
+                "permitted": capabilities,
+            },
+            "rlimits": rlimits(&options.ulimits),
+            "noNewPrivileges": true,
+        },
+        "root": { "path": "rootfs" },
+        "hostname": options.hostname.as_deref().unwrap_or(&options.name),
+        "mounts": mounts,
+        "linux": {
+            "resources": resources,
+            "sysctl": options.sysctls,
+            "cgroupsPath": format!("/syntra/{}", options.name),
+            "namespaces": [
+                { "type": "pid" },
+                { "type": "ipc" },
+                { "type": "uts" },
+                { "type": "mount" },
+                { "type": "network" },
+            ],
+            "maskedPaths": [
+                "/proc/acpi", "/proc/asound", "/proc/kcore", "/proc/keys",
+                "/proc/latency_stats", "/proc/timer_list", "/proc/timer_stats",
+                "/proc/sched_debug", "/sys/firmware", "/proc/scsi",
+            ],
+            "readonlyPaths": [
+                "/proc/bus", "/proc/fs", "/proc/irq", "/proc/sys", "/proc/sysrq-trigger",
+            ],
+        },
+    })
+}
+
+#[async_trait]
+impl RuntimeAdapter for ContainerdAdapter {
+    fn runtime_type(&self) -> &str {
+        RUNTIME
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        // Logs, exec, builds, pruning, and pushes all go through unimplemented APIs
+        Vec::new()
+    }
+
+    async fn health_check(&self) -> Result<bool, RuntimeError> {
+        let mut version = VersionClient::new(self.channel.clone());
+        match version.version(self.request(())).await {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                debug!(error = %e, "containerd health check failed");
+                Ok(false)
+            }
+        }
+    }
+
+    async fn version(&self) -> Result<String, RuntimeError> {
+        let mut version = VersionClient::new(self.channel.clone());
+        let response = version.version(self.request(())).await?.into_inner();
+        Ok(format!(
+            "containerd {} (revision {})",
+            response.version, response.revision
+        ))
+    }
+
+    async fn gpu_available(&self) -> Result<bool, RuntimeError> {
+        // The OCI spec we generate has no device hooks for GPUs
+        Ok(false)
+    }
+
+    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, RuntimeError> {
+        let mut containers = ContainersClient::new(self.channel.clone());
+        let response = containers
+            .list(self.request(ListContainersRequest { filters: vec![] }))
+            .await?
+            .into_inner();
+
+        let mut result = Vec::new();
+        for container in response.containers {
+            let info = self.container_info(container).await?;
+            if all || info.status == ContainerStatus::Running {
+                result.push(info);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_container(&self, id_or_name: &str) -> Result<Option<ContainerInfo>, RuntimeError> {
+        let mut containers = ContainersClient::new(self.channel.clone());
+        let request = self.request(GetContainerRequest {
+            id: id_or_name.to_string(),
+        });
+
+        match containers.get(request).await {
+            Ok(response) => match response.into_inner().container {
+                Some(container) => Ok(Some(self.container_info(container).await?)),
+                None => Ok(None),
+            },
+            Err(status) if status.code() == Code::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn create_container(
+        &self,
+        options: CreateContainerOptions,
+    ) -> Result<String, RuntimeError> {
+        if options.privileged {
+            return Err(RuntimeError::unsupported(RUNTIME, "privileged containers"));
+        }
+        if options.gpus.is_some() {
+            return Err(RuntimeError::unsupported(RUNTIME, "GPU access"));
+        }
+        if options.user.as_deref().is_some_and(|user| numeric_user(user).is_none()) {
+            return Err(RuntimeError::unsupported(RUNTIME, "user names (use uid[:gid])"));
+        }
+        if options.mounts.iter().any(|m| matches!(m, MountSpec::Volume { .. })) {
+            return Err(RuntimeError::unsupported(RUNTIME, "named volume mounts"));
+        }
+        if !options.ports.is_empty() {
+            warn!(
+                name = %options.name,
+                "Port bindings require CNI and are ignored by the containerd runtime"
+            );
+        }
+        if !options.networks.is_empty() {
+            // Every deploy names at least the default network, so don't warn
+            debug!(name = %options.name, "Networks require CNI and are ignored by containerd");
+        }
+
+        let image_ref = normalize_reference(&options.image);
+        let image = self.image_config(&image_ref).await?;
+
+        // Prepare a writable rootfs snapshot on top of the image layers
+        let mut snapshots = SnapshotsClient::new(self.channel.clone());
+        snapshots
+            .prepare(self.request(PrepareSnapshotRequest {
+                snapshotter: self.snapshotter.clone(),
+                key: options.name.clone(),
+                parent: image.chain_id.clone(),
+                labels: HashMap::new(),
+            }))
+            .await
+            .context("Failed to prepare rootfs snapshot")?;
+
+        let spec = build_spec(&options, &image);
+        let container = Container {
+            id: options.name.clone(),
+            labels: options.labels.clone(),
+            image: image_ref,
+            runtime: Some(Runtime {
+                name: RUNC_RUNTIME.to_string(),
+                options: None,
+            }),
+            spec: Some(Any {
+                type_url: SPEC_TYPE_URL.to_string(),
+                value: serde_json::to_vec(&spec).context("Failed to serialize OCI spec")?,
+            }),
+            snapshotter: self.snapshotter.clone(),
+            snapshot_key: options.name.clone(),
+            ..Default::default()
+        };
+
+        let mut containers = ContainersClient::new(self.channel.clone());
+        if let Err(e) = containers
+            .create(self.request(CreateContainerRequest {
+                container: Some(container),
+            }))
+            .await
+        {
+            // Release the snapshot so a retry can reuse the key
+            let _ = snapshots
+                .remove(self.request(RemoveSnapshotRequest {
+                    snapshotter: self.snapshotter.clone(),
+                    key: options.name.clone(),
+                }))
+                .await;
+            return Err(e.into());
+        }
+
+        info!(container_id = %options.name, "Container created");
+        Ok(options.name)
+    }
+
+    async fn start_container(&self, id: &str) -> Result<(), RuntimeError> {
+        let mut snapshots = SnapshotsClient::new(self.channel.clone());
+        let mounts = snapshots
+            .mounts(self.request(MountsRequest {
+                snapshotter: self.snapshotter.clone(),
+                key: id.to_string(),
+            }))
+            .await
+            .context("Failed to get rootfs mounts")?
+            .into_inner()
+            .mounts;
+
+        let mut tasks = TasksClient::new(self.channel.clone());
+        tasks
+            .create(self.request(CreateTaskRequest {
+                container_id: id.to_string(),
+                rootfs: mounts,
+                ..Default::default()
+            }))
+            .await
+            .context("Failed to create task")?;
+
+        tasks
+            .start(self.request(StartRequest {
+                container_id: id.to_string(),
+                exec_id: String::new(),
+            }))
+            .await
+            .context("Failed to start task")?;
+
+        info!(container_id = %id, "Container started");
+        Ok(())
+    }
+
+    async fn stop_container(
+        &self,
+        id: &str,
+        timeout_secs: Option<u64>,
+    ) -> Result<(), RuntimeError> {
+        if self.task_status(id).await? != ContainerStatus::Running {
+            self.delete_task(id).await?;
+            return Ok(());
+        }
+
+        self.kill_task(id, SIGTERM).await?;
+
+        let timeout = Duration::from_secs(timeout_secs.unwrap_or(10));
+        match tokio::time::timeout(timeout, self.wait_task(id)).await {
+            Ok(result) => {
+                result?;
+            }
+            Err(_) => {
+                warn!(container_id = %id, "Task did not exit in time, sending SIGKILL");
+                self.kill_task(id, SIGKILL).await?;
+                self.wait_task(id).await?;
+            }
+        }
+
+        self.delete_task(id).await?;
+        info!(container_id = %id, "Container stopped");
+        Ok(())
+    }
+
+    async fn kill_container(&self, id: &str, signal: &str) -> Result<(), RuntimeError> {
+        let number = signal_number(signal).ok_or_else(|| anyhow!("Unknown signal: {}", signal))?;
+        self.kill_task(id, number).await?;
+        info!(container_id = %id, signal = %signal, "Signal sent to container");
+        Ok(())
+    }
+
+    async fn wait_container(&self, id: &str) -> Result<i64, RuntimeError> {
+        Ok(self.wait_task(id).await? as i64)
+    }
+
+    async fn remove_container(&self, id: &str, force: bool) -> Result<(), RuntimeError> {
+        match self.task_status(id).await? {
+            ContainerStatus::Running | ContainerStatus::Paused if !force => {
+                return Err(anyhow!(
+                    "Container {} is running; stop it first or force removal",
+                    id
+                )
+                .into());
+            }
+            ContainerStatus::Running | ContainerStatus::Paused => {
+                self.kill_task(id, SIGKILL).await?;
+                self.wait_task(id).await?;
+            }
+            _ => {}
+        }
+        self.delete_task(id).await?;
+
+        let mut containers = ContainersClient::new(self.channel.clone());
+        containers
+            .delete(self.request(DeleteContainerRequest { id: id.to_string() }))
+            .await?;
+
+        let mut snapshots = SnapshotsClient::new(self.channel.clone());
+        if let Err(e) = snapshots
+            .remove(self.request(RemoveSnapshotRequest {
+                snapshotter: self.snapshotter.clone(),
+                key: id.to_string(),
+            }))
+            .await
+        {
+            debug!(container_id = %id, error = %e, "Failed to remove rootfs snapshot");
+        }
+
+        info!(container_id = %id, "Container removed");
+        Ok(())
+    }
+
+    async fn rename_container(&self, _id: &str, _new_name: &str) -> Result<(), RuntimeError> {
+        // containerd identifies containers by ID only
+        Err(RuntimeError::unsupported(RUNTIME, "rename_container"))
+    }
+
+    async fn logs(&self, _id: &str, _options: LogsOptions) -> Result<Vec<String>, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "logs"))
+    }
+
+    async fn logs_stream(
+        &self,
+        _id: &str,
+        _options: LogsOptions,
+    ) -> Result<LogStream, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "logs_stream"))
+    }
+
+    async fn stats(&self, _id: &str) -> Result<ContainerStats, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "stats"))
+    }
+
+    async fn stats_stream(&self, _id: &str) -> Result<StatsStream, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "stats_stream"))
+    }
+
+    async fn pull_image(
+        &self,
+        image: &str,
+        auth: Option<RegistryAuth>,
+    ) -> Result<(), RuntimeError> {
+        if auth.is_some() {
+            return Err(RuntimeError::unsupported(RUNTIME, "authenticated pull"));
+        }
+
+        let reference = normalize_reference(image);
+        let platform = Platform {
+            os: "linux".to_string(),
+            architecture: oci_arch().to_string(),
+            ..Default::default()
+        };
+
+        let source = OciRegistry {
+            reference: reference.clone(),
+        };
+        let destination = ImageStore {
+            name: reference.clone(),
+            platforms: vec![platform.clone()],
+            unpacks: vec![UnpackConfiguration {
+                platform: Some(platform),
+                snapshotter: self.snapshotter.clone(),
+            }],
+        };
+
+        let request = TransferRequest {
+            source: Some(Any {
+                type_url: OCI_REGISTRY_TYPE_URL.to_string(),
+                value: source.encode_to_vec(),
+            }),
+            destination: Some(Any {
+                type_url: IMAGE_STORE_TYPE_URL.to_string(),
+                value: destination.encode_to_vec(),
+            }),
+            options: None,
+        };
+
+        debug!(image = %reference, "Starting image pull");
+        let mut transfer = TransferClient::new(self.channel.clone());
+        transfer
+            .transfer(self.request(request))
+            .await
+            .map_err(|status| match status.code() {
+                Code::Unauthenticated | Code::PermissionDenied => {
+                    RuntimeError::unauthorized(image, status.message())
+                }
+                Code::Unavailable | Code::DeadlineExceeded => status.into(),
+                _ => RuntimeError::image_pull_failed(image, status.message()),
+            })?;
+
+        info!(image = %reference, "Image pulled");
+        Ok(())
+    }
+
+    async fn build_image(&self, _options: BuildImageOptions) -> Result<String, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "build_image"))
+    }
+
+    async fn list_images(&self) -> Result<Vec<ImageInfo>, RuntimeError> {
+        let mut images = ImagesClient::new(self.channel.clone());
+        let response = images
+            .list(self.request(ListImagesRequest { filters: vec![] }))
+            .await?
+            .into_inner();
+
+        Ok(response
+            .images
+            .into_iter()
+            .map(|img| {
+                let (id, size) = img
+                    .target
+                    .map(|t| (t.digest, t.size as u64))
+                    .unwrap_or_default();
+                ImageInfo {
+                    id,
+                    repo_tags: vec![img.name],
+                    size,
+                    created_at: img
+                        .created_at
+                        .and_then(|ts| Utc.timestamp_opt(ts.seconds, ts.nanos as u32).single())
+                        .map(|t| t.to_rfc3339())
+                        .unwrap_or_default(),
+                }
+            })
+            .collect())
+    }
+
+    async fn image_exists(&self, image: &str) -> Result<bool, RuntimeError> {
+        let mut images = ImagesClient::new(self.channel.clone());
+        let request = self.request(GetImageRequest {
+            name: normalize_reference(image),
+        });
+
+        match images.get(request).await {
+            Ok(response) => Ok(response.into_inner().image.is_some()),
+            Err(status) if status.code() == Code::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn image_digests(&self, image: &str) -> Result<Vec<String>, RuntimeError> {
+        // The image's target is the manifest (or index) it was pulled as
+        let mut images = ImagesClient::new(self.channel.clone());
+        let target = images
+            .get(self.request(GetImageRequest {
+                name: normalize_reference(image),
+            }))
+            .await?
+            .into_inner()
+            .image
+            .and_then(|i| i.target);
+        Ok(target.map(|t| t.digest).into_iter().collect())
+    }
+
+    async fn remove_image(&self, id: &str, _force: bool) -> Result<(), RuntimeError> {
+        let mut images = ImagesClient::new(self.channel.clone());
+        images
+            .delete(self.request(DeleteImageRequest {
+                name: normalize_reference(id),
+                ..Default::default()
+            }))
+            .await?;
+        info!(image_id = %id, "Image removed");
+        Ok(())
+    }
+
+    async fn create_network(&self, _options: CreateNetworkOptions) -> Result<String, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "create_network"))
+    }
+
+    async fn remove_network(&self, _name: &str) -> Result<(), RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "remove_network"))
+    }
+
+    async fn list_networks(&self) -> Result<Vec<NetworkInfo>, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "list_networks"))
+    }
+
+    async fn inspect_network(&self, _name: &str) -> Result<Option<NetworkInfo>, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "inspect_network"))
+    }
+
+    async fn connect_network(
+        &self,
+        _container_id: &str,
+        _network: &str,
+    ) -> Result<(), RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "connect_network"))
+    }
+
+    async fn disconnect_network(
+        &self,
+        _container_id: &str,
+        _network: &str,
+    ) -> Result<(), RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "disconnect_network"))
+    }
+
+    async fn create_volume(
+        &self,
+        _name: &str,
+        _labels: HashMap<String, String>,
+    ) -> Result<String, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "create_volume"))
+    }
+
+    async fn list_volumes(&self) -> Result<Vec<VolumeInfo>, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "list_volumes"))
+    }
+
+    async fn remove_volume(&self, _name: &str, _force: bool) -> Result<(), RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "remove_volume"))
+    }
+
+    async fn prune_containers(&self) -> Result<PruneReport, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "prune_containers"))
+    }
+
+    async fn prune_images(&self, _dangling_only: bool) -> Result<PruneReport, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "prune_images"))
+    }
+
+    async fn prune_volumes(&self) -> Result<PruneReport, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "prune_volumes"))
+    }
+
+    async fn copy_to_container(
+        &self,
+        _id: &str,
+        _dest_path: &str,
+        _tar_data: Vec<u8>,
+    ) -> Result<(), RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "copy_to_container"))
+    }
+
+    async fn copy_from_container(
+        &self,
+        _id: &str,
+        _src_path: &str,
+    ) -> Result<Vec<u8>, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "copy_from_container"))
+    }
+
+    async fn events(&self) -> Result<EventStream, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "events"))
+    }
+
+    async fn exec(&self, _id: &str, _options: ExecOptions) -> Result<ExecResult, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "exec"))
+    }
+
+    async fn top(&self, _id: &str) -> Result<Vec<ProcessInfo>, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "top"))
+    }
+
+    async fn container_diff(&self, _id: &str) -> Result<Vec<FsChange>, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "container_diff"))
+    }
+
+    async fn disk_usage(&self) -> Result<DiskUsage, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "disk_usage"))
+    }
+
+    async fn commit_container(
+        &self,
+        _id: &str,
+        _repo: &str,
+        _tag: &str,
+        _message: Option<String>,
+    ) -> Result<String, RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "commit_container"))
+    }
+
+    async fn tag_image(&self, _source: &str, _repo: &str, _tag: &str) -> Result<(), RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "tag_image"))
+    }
+
+    async fn push_image(
+        &self,
+        _image: &str,
+        _auth: Option<RegistryAuth>,
+    ) -> Result<(), RuntimeError> {
+        Err(RuntimeError::unsupported(RUNTIME, "push_image"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(
+            ContainerdAdapter::parse_status(Status::Running as i32),
+            ContainerStatus::Running
+        );
+        assert_eq!(
+            ContainerdAdapter::parse_status(Status::Stopped as i32),
+            ContainerStatus::Exited
+        );
+        assert_eq!(ContainerdAdapter::parse_status(-1), ContainerStatus::Unknown);
+    }
+
+    #[test]
+    fn test_normalize_reference() {
+        assert_eq!(normalize_reference("nginx"), "docker.io/library/nginx:latest");
+        assert_eq!(normalize_reference("nginx:1.25"), "docker.io/library/nginx:1.25");
+        assert_eq!(normalize_reference("acme/api"), "docker.io/acme/api:latest");
+        assert_eq!(normalize_reference("ghcr.io/acme/api:v2"), "ghcr.io/acme/api:v2");
+        assert_eq!(
+            normalize_reference("localhost:5000/api"),
+            "localhost:5000/api:latest"
+        );
+        assert_eq!(
+            normalize_reference("nginx@sha256:abc"),
+            "docker.io/library/nginx@sha256:abc"
+        );
+    }
+
+    #[test]
+    fn test_capabilities_add_and_drop() {
+        let caps = capabilities(&["net_admin".to_string()], &["CAP_KILL".to_string()]);
+        assert!(caps.contains(&"CAP_NET_ADMIN".to_string()));
+        assert!(!caps.contains(&"CAP_KILL".to_string()));
+        assert_eq!(caps.len(), DEFAULT_CAPABILITIES.len());
+
+        let caps = capabilities(&["NET_BIND_SERVICE".to_string()], &["ALL".to_string()]);
+        assert_eq!(caps, vec!["CAP_NET_BIND_SERVICE"]);
+    }
+
+    #[test]
+    fn test_chain_id() {
+        assert_eq!(chain_id(&[]), None);
+        assert_eq!(
+            chain_id(&["sha256:aaa".to_string()]),
+            Some("sha256:aaa".to_string())
+        );
+
+        let expected = format!(
+            "sha256:{}",
+            hex::encode(Sha256::digest(b"sha256:aaa sha256:bbb"))
+        );
+        assert_eq!(
+            chain_id(&["sha256:aaa".to_string(), "sha256:bbb".to_string()]),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_rlimits_override_default_nofile() {
+        let ulimits = vec![
+            Ulimit {
+                name: "nofile".to_string(),
+                soft: 65536,
+                hard: 65536,
+            },
+            Ulimit {
+                name: "nproc".to_string(),
+                soft: 4096,
+                hard: 8192,
+            },
+        ];
+
+        assert_eq!(
+            rlimits(&ulimits),
+            vec![
+                json!({ "type": "RLIMIT_NOFILE", "soft": 65536, "hard": 65536 }),
+                json!({ "type": "RLIMIT_NPROC", "soft": 4096, "hard": 8192 }),
+            ]
+        );
+        assert_eq!(
+            rlimits(&[]),
+            vec![json!({ "type": "RLIMIT_NOFILE", "soft": 1024, "hard": 1024 })]
+        );
+    }
+
+    #[test]
+    fn test_numeric_user() {
+        assert_eq!(numeric_user("1000"), Some((1000, 0)));
+        assert_eq!(numeric_user("1000:1000"), Some((1000, 1000)));
+        assert_eq!(numeric_user("nginx"), None);
+        assert_eq!(numeric_user("1000:staff"), None);
+    }
+
+    #[test]
+    fn test_signal_number() {
+        assert_eq!(signal_number("SIGTERM"), Some(15));
+        assert_eq!(signal_number("quit"), Some(3));
+        assert_eq!(signal_number("9"), Some(9));
+        assert_eq!(signal_number("SIGBOGUS"), None);
+    }
+}
@@ -0,0 +1,8 @@
+//! containerd Runtime Module
+//!
+//! Provides containerd-specific implementation of the RuntimeAdapter trait.
+
+pub mod adapter;
+mod transfer;
+
+pub use adapter::ContainerdAdapter;
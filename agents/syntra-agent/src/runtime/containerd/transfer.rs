@@ -0,0 +1,61 @@
+//! Transfer Types
+//!
+//! Messages from containerd's `containerd.types.transfer` package, which the
+//! transfer service takes as `Any` source and destination. containerd-client
+//! compiles the transfer service but doesn't export these types, so the
+//! fields the adapter sets are mirrored here with the upstream field numbers.
+
+use containerd_client::types::Platform;
+
+/// A registry to pull an image from (`OCIRegistry`)
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct OciRegistry {
+    #[prost(string, tag = "1")]
+    pub reference: String,
+}
+
+/// The local image store to pull an image into (`ImageStore`)
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ImageStore {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(message, repeated, tag = "3")]
+    pub platforms: Vec<Platform>,
+    #[prost(message, repeated, tag = "10")]
+    pub unpacks: Vec<UnpackConfiguration>,
+}
+
+/// A platform to unpack a pulled image for, into a snapshotter
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct UnpackConfiguration {
+    #[prost(message, optional, tag = "1")]
+    pub platform: Option<Platform>,
+    #[prost(string, tag = "2")]
+    pub snapshotter: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_field_numbers_match_upstream() {
+        let registry = OciRegistry {
+            reference: "a".to_string(),
+        };
+        // Field 1, length-delimited
+        assert_eq!(registry.encode_to_vec(), vec![0x0a, 1, b'a']);
+
+        let store = ImageStore {
+            name: String::new(),
+            platforms: Vec::new(),
+            unpacks: vec![UnpackConfiguration {
+                platform: None,
+                snapshotter: "o".to_string(),
+            }],
+        };
+        // Field 10 holding field 2
+        assert_eq!(store.encode_to_vec(), vec![0x52, 3, 0x12, 1, b'o']);
+    }
+}
@@ -2,30 +2,48 @@
 //!
 //! Implementation of RuntimeAdapter for Docker using the bollard library.
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use bollard::container::{
-    Config, CreateContainerOptions as BollardCreateOptions, ListContainersOptions,
-    LogsOptions as BollardLogsOptions, RemoveContainerOptions, StartContainerOptions,
-    StopContainerOptions, StatsOptions,
+    Config, CreateContainerOptions as BollardCreateOptions, KillContainerOptions,
+    ListContainersOptions, DownloadFromContainerOptions,
+    LogsOptions as BollardLogsOptions, PruneContainersOptions, RemoveContainerOptions,
+    RenameContainerOptions, StartContainerOptions, StopContainerOptions, StatsOptions, TopOptions,
+    UploadToContainerOptions, WaitContainerOptions,
 };
 use bollard::exec::{CreateExecOptions, StartExecResults};
-use bollard::image::{CreateImageOptions, ListImagesOptions, RemoveImageOptions};
-use bollard::network::CreateNetworkOptions;
+use bollard::system::EventsOptions;
+use bollard::image::{
+    BuildImageOptions as BollardBuildOptions, CreateImageOptions, ListImagesOptions,
+    PruneImagesOptions, PushImageOptions, RemoveImageOptions, TagImageOptions,
+};
+use bollard::network::{
+    ConnectNetworkOptions, CreateNetworkOptions as BollardNetworkOptions, DisconnectNetworkOptions,
+    InspectNetworkOptions, ListNetworksOptions,
+};
+use bollard::volume::{
+    CreateVolumeOptions, ListVolumesOptions, PruneVolumesOptions, RemoveVolumeOptions,
+};
 use bollard::Docker;
 use futures_util::StreamExt;
 use std::collections::HashMap;
+use tokio::io::AsyncWriteExt;
 use tracing::{debug, info};
 
+use super::convert;
 use crate::runtime::adapter::{
-    ContainerInfo, ContainerStats, ContainerStatus, CreateContainerOptions, ImageInfo,
-    LogsOptions, PortBinding, RuntimeAdapter,
+    BuildContext, BuildImageOptions, ContainerInfo, ContainerStats, CreateContainerOptions,
+    CreateNetworkOptions, DiskUsage, EventStream, ExecOptions, ExecResult, FsChange, ImageInfo,
+    LogStream, LogsOptions, MountSpec, NetworkInfo, ProcessInfo, PruneReport, RegistryAuth,
+    RuntimeAdapter, StatsStream, VolumeInfo,
 };
+use crate::runtime::error::RuntimeError;
 
 /// Docker runtime adapter
 pub struct DockerAdapter {
     client: Docker,
     socket_path: String,
+    default_registry_auth: Option<RegistryAuth>,
 }
 
 impl DockerAdapter {
@@ -37,6 +55,7 @@ impl DockerAdapter {
         Ok(Self {
             client,
             socket_path: "/var/run/docker.sock".to_string(),
+            default_registry_auth: None,
         })
     }
 
@@ -48,26 +67,25 @@ impl DockerAdapter {
         Ok(Self {
             client,
             socket_path: socket_path.to_string(),
+            default_registry_auth: None,
         })
     }
 
+    /// Set registry credentials used for pulls that don't provide their own
+    pub fn with_default_registry_auth(mut self, auth: Option<RegistryAuth>) -> Self {
+        self.default_registry_auth = auth;
+        self
+    }
+
+    /// Get the socket path this adapter is connected to
+    pub fn socket_path(&self) -> &str {
+        &self.socket_path
+    }
+
     /// Get the Docker client reference
     pub fn client(&self) -> &Docker {
         &self.client
     }
-
-    /// Convert bollard container state to our ContainerStatus
-    fn parse_status(state: Option<&str>) -> ContainerStatus {
-        match state {
-            Some("created") => ContainerStatus::Created,
-            Some("running") => ContainerStatus::Running,
-            Some("paused") => ContainerStatus::Paused,
-            Some("restarting") => ContainerStatus::Restarting,
-            Some("exited") => ContainerStatus::Exited,
-            Some("dead") => ContainerStatus::Dead,
-            _ => ContainerStatus::Unknown,
-        }
-    }
 }
 
 #[async_trait]
@@ -76,7 +94,7 @@ impl RuntimeAdapter for DockerAdapter {
         "docker"
     }
 
-    async fn health_check(&self) -> Result<bool> {
+    async fn health_check(&self) -> Result<bool, RuntimeError> {
         match self.client.ping().await {
             Ok(_) => Ok(true),
             Err(e) => {
@@ -86,7 +104,7 @@ impl RuntimeAdapter for DockerAdapter {
         }
     }
 
-    async fn version(&self) -> Result<String> {
+    async fn version(&self) -> Result<String, RuntimeError> {
         let version = self.client.version().await?;
         Ok(format!(
             "Docker {} (API {})",
@@ -95,7 +113,12 @@ impl RuntimeAdapter for DockerAdapter {
         ))
     }
 
-    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>> {
+    async fn gpu_available(&self) -> Result<bool, RuntimeError> {
+        let info = self.client.info().await?;
+        Ok(info.runtimes.is_some_and(|runtimes| runtimes.contains_key("nvidia")))
+    }
+
+    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, RuntimeError> {
         let options = ListContainersOptions::<String> {
             all,
             ..Default::default()
@@ -103,110 +126,12 @@ impl RuntimeAdapter for DockerAdapter {
 
         let containers = self.client.list_containers(Some(options)).await?;
 
-        let mut result = Vec::new();
-        for container in containers {
-            let ports = container
-                .ports
-                .unwrap_or_default()
-                .iter()
-                .map(|p| PortBinding {
-                    container_port: p.private_port,
-                    host_port: p.public_port,
-                    host_ip: p.ip.clone(),
-                    protocol: p.typ.as_ref().map(|t| t.to_string()).unwrap_or_else(|| "tcp".to_string()),
-                })
-                .collect();
-
-            result.push(ContainerInfo {
-                id: container.id.unwrap_or_default(),
-                name: container
-                    .names
-                    .and_then(|n| n.first().cloned())
-                    .unwrap_or_default()
-                    .trim_start_matches('/')
-                    .to_string(),
-                image: container.image.unwrap_or_default(),
-                status: Self::parse_status(container.state.as_deref()),
-                created_at: container.created.map(|c| c.to_string()).unwrap_or_default(),
-                ports,
-                labels: container.labels.unwrap_or_default(),
-            });
-        }
-
-        Ok(result)
+        Ok(containers.into_iter().map(convert::summary_to_info).collect())
     }
 
-    async fn get_container(&self, id_or_name: &str) -> Result<Option<ContainerInfo>> {
+    async fn get_container(&self, id_or_name: &str) -> Result<Option<ContainerInfo>, RuntimeError> {
         match self.client.inspect_container(id_or_name, None).await {
-            Ok(container) => {
-                let state = container.state.as_ref();
-                let config = container.config.as_ref();
-
-                let ports = container
-                    .network_settings
-                    .as_ref()
-                    .and_then(|ns| ns.ports.as_ref())
-                    .map(|ports| {
-                        ports
-                            .iter()
-                            .filter_map(|(key, bindings)| {
-                                let parts: Vec<&str> = key.split('/').collect();
-                                let container_port = parts.first()?.parse().ok()?;
-                                let protocol = parts.get(1).unwrap_or(&"tcp").to_string();
-
-                                let (host_port, host_ip) = bindings
-                                    .as_ref()
-                                    .and_then(|b| b.first())
-                                    .map(|b| {
-                                        (
-                                            b.host_port.as_ref().and_then(|p| p.parse().ok()),
-                                            b.host_ip.clone(),
-                                        )
-                                    })
-                                    .unwrap_or((None, None));
-
-                                Some(PortBinding {
-                                    container_port,
-                                    host_port,
-                                    host_ip,
-                                    protocol,
-                                })
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default();
-
-                Ok(Some(ContainerInfo {
-                    id: container.id.unwrap_or_default(),
-                    name: container
-                        .name
-                        .unwrap_or_default()
-                        .trim_start_matches('/')
-                        .to_string(),
-                    image: config
-                        .and_then(|c| c.image.clone())
-                        .unwrap_or_default(),
-                    status: Self::parse_status(
-                        state
-                            .and_then(|s| s.status.as_ref())
-                            .map(|s| match s {
-                                bollard::service::ContainerStateStatusEnum::CREATED => "created",
-                                bollard::service::ContainerStateStatusEnum::RUNNING => "running",
-                                bollard::service::ContainerStateStatusEnum::PAUSED => "paused",
-                                bollard::service::ContainerStateStatusEnum::RESTARTING => "restarting",
-                                bollard::service::ContainerStateStatusEnum::REMOVING => "removing",
-                                bollard::service::ContainerStateStatusEnum::EXITED => "exited",
-                                bollard::service::ContainerStateStatusEnum::DEAD => "dead",
-                                _ => "unknown",
-                            })
-                    ),
-                    created_at: container.created.unwrap_or_default(),
-                    ports,
-                    labels: config
-                        .and_then(|c| c.labels.clone())
-                        .unwrap_or_default(),
-                }))
-            }
+            Ok(container) => Ok(Some(convert::inspect_to_info(container))),
             Err(bollard::errors::Error::DockerResponseServerError {
                 status_code: 404, ..
             }) => Ok(None),
@@ -214,7 +139,10 @@ impl RuntimeAdapter for DockerAdapter {
         }
     }
 
-    async fn create_container(&self, options: CreateContainerOptions) -> Result<String> {
+    async fn create_container(
+        &self,
+        options: CreateContainerOptions,
+    ) -> Result<String, RuntimeError> {
         let env: Vec<String> = options
             .env
             .iter()
@@ -241,23 +169,70 @@ impl RuntimeAdapter for DockerAdapter {
             })
             .collect();
 
-        let binds: Vec<String> = options
-            .volumes
-            .iter()
-            .map(|v| {
-                if v.read_only {
-                    format!("{}:{}:ro", v.source, v.target)
+        let mut binds = Vec::new();
+        let mut mounts = Vec::new();
+        let mut tmpfs = HashMap::new();
+        for mount in &options.mounts {
+            match mount {
+                MountSpec::Bind {
+                    source,
+                    target,
+                    read_only,
+                } => binds.push(if *read_only {
+                    format!("{}:{}:ro", source, target)
                 } else {
-                    format!("{}:{}", v.source, v.target)
+                    format!("{}:{}", source, target)
+                }),
+                MountSpec::Volume {
+                    name,
+                    target,
+                    read_only,
+                } => mounts.push(bollard::service::Mount {
+                    target: Some(target.clone()),
+                    source: Some(name.clone()),
+                    typ: Some(bollard::service::MountTypeEnum::VOLUME),
+                    read_only: Some(*read_only),
+                    ..Default::default()
+                }),
+                MountSpec::Tmpfs { target, size_mb } => {
+                    let opts = size_mb.map(|mb| format!("size={}m", mb)).unwrap_or_default();
+                    tmpfs.insert(target.clone(), opts);
                 }
-            })
-            .collect();
+            }
+        }
 
         let host_config = bollard::service::HostConfig {
             binds: Some(binds),
+            mounts: Some(mounts),
+            tmpfs: Some(tmpfs),
             port_bindings: Some(port_bindings),
-            network_mode: options.network,
-            memory: options.memory_limit.map(|m| m as i64 * 1024 * 1024),
+            network_mode: options.networks.first().cloned(),
+            extra_hosts: Some(options.extra_hosts),
+            dns: Some(options.dns),
+            cap_add: Some(options.cap_add),
+            cap_drop: Some(options.cap_drop),
+            privileged: Some(options.privileged),
+            ulimits: Some(
+                options
+                    .ulimits
+                    .into_iter()
+                    .map(|ulimit| bollard::service::ResourcesUlimits {
+                        name: Some(ulimit.name),
+                        soft: Some(ulimit.soft as i64),
+                        hard: Some(ulimit.hard as i64),
+                    })
+                    .collect(),
+            ),
+            sysctls: Some(options.sysctls),
+            device_requests: options
+                .gpus
+                .map(|gpus| vec![convert::gpu_device_request(gpus)]),
+            log_config: options.log_driver.map(|driver| bollard::service::HostConfigLogConfig {
+                typ: Some(driver),
+                config: Some(options.log_options),
+            }),
+            memory: options.memory_limit.map(convert::mb_to_bytes),
+            shm_size: options.shm_size_mb.map(convert::mb_to_bytes),
             nano_cpus: options.cpu_limit.map(|c| (c * 1_000_000_000.0) as i64),
             restart_policy: options.restart_policy.map(|p| {
                 bollard::service::RestartPolicy {
@@ -275,7 +250,7 @@ impl RuntimeAdapter for DockerAdapter {
                             bollard::service::RestartPolicyNameEnum::UNLESS_STOPPED
                         }
                     }),
-                    maximum_retry_count: None,
+                    maximum_retry_count: options.max_restart_retries.map(i64::from),
                 }
             }),
             ..Default::default()
@@ -283,7 +258,11 @@ impl RuntimeAdapter for DockerAdapter {
 
         let config = Config {
             image: Some(options.image),
+            entrypoint: options.entrypoint,
             cmd: options.command,
+            working_dir: options.working_dir,
+            user: options.user,
+            hostname: options.hostname,
             env: Some(env),
             labels: Some(options.labels),
             exposed_ports: Some(exposed_ports),
@@ -292,17 +271,41 @@ impl RuntimeAdapter for DockerAdapter {
         };
 
         let create_options = BollardCreateOptions {
-            name: &options.name,
+            name: options.name.as_str(),
             platform: None,
         };
 
-        let response = self.client.create_container(Some(create_options), config).await?;
+        let response = match self
+            .client
+            .create_container(Some(create_options.clone()), config.clone())
+            .await
+        {
+            Ok(response) => response,
+            // Image is missing locally; pull it with the provided credentials and retry
+            Err(e) if convert::is_missing_image(&e) && options.registry_auth.is_some() => {
+                let image = config.image.clone().unwrap_or_default();
+                self.pull_image(&image, options.registry_auth.clone()).await?;
+                self.client
+                    .create_container(Some(create_options), config)
+                    .await?
+            }
+            Err(e) => return Err(e.into()),
+        };
         info!(container_id = %response.id, name = %options.name, "Container created");
 
+        // Only the primary network can be set at creation; attach the rest now
+        for network in options.networks.iter().skip(1) {
+            if let Err(e) = self.connect_network(&response.id, network).await {
+                let _ = self.remove_container(&response.id, true).await;
+                let context = format!("Failed to attach container to {}", network);
+                return Err(RuntimeError::Other(anyhow::Error::from(e).context(context)));
+            }
+        }
+
         Ok(response.id)
     }
 
-    async fn start_container(&self, id: &str) -> Result<()> {
+    async fn start_container(&self, id: &str) -> Result<(), RuntimeError> {
         self.client
             .start_container(id, None::<StartContainerOptions<String>>)
             .await?;
@@ -310,7 +313,11 @@ impl RuntimeAdapter for DockerAdapter {
         Ok(())
     }
 
-    async fn stop_container(&self, id: &str, timeout_secs: Option<u64>) -> Result<()> {
+    async fn stop_container(
+        &self,
+        id: &str,
+        timeout_secs: Option<u64>,
+    ) -> Result<(), RuntimeError> {
         let options = StopContainerOptions {
             t: timeout_secs.map(|t| t as i64).unwrap_or(10),
         };
@@ -319,7 +326,38 @@ impl RuntimeAdapter for DockerAdapter {
         Ok(())
     }
 
-    async fn remove_container(&self, id: &str, force: bool) -> Result<()> {
+    async fn kill_container(&self, id: &str, signal: &str) -> Result<(), RuntimeError> {
+        let options = KillContainerOptions { signal };
+        match self.client.kill_container(id, Some(options)).await {
+            Ok(()) => {
+                info!(container_id = %id, signal = %signal, "Signal sent to container");
+                Ok(())
+            }
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Err(RuntimeError::not_found("container", id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn wait_container(&self, id: &str) -> Result<i64, RuntimeError> {
+        let options = WaitContainerOptions {
+            condition: "not-running",
+        };
+        let mut stream = self.client.wait_container(id, Some(options));
+
+        match stream.next().await {
+            Some(Ok(response)) => Ok(response.status_code),
+            // bollard reports a non-zero exit as an error
+            Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. })) => Ok(code),
+            Some(Err(e)) => Err(e.into()),
+            None => Err(
+                anyhow!("Docker closed the wait for container {} without a status", id).into(),
+            ),
+        }
+    }
+
+    async fn remove_container(&self, id: &str, force: bool) -> Result<(), RuntimeError> {
         let options = RemoveContainerOptions {
             force,
             ..Default::default()
@@ -329,24 +367,32 @@ impl RuntimeAdapter for DockerAdapter {
         Ok(())
     }
 
-    async fn logs(&self, id: &str, options: LogsOptions) -> Result<Vec<String>> {
-        let bollard_options = BollardLogsOptions::<String> {
-            stdout: options.stdout,
-            stderr: options.stderr,
-            follow: options.follow,
-            tail: options.tail.map(|t| t.to_string()).unwrap_or_else(|| "all".to_string()),
-            since: options.since.map(|s| s.parse().unwrap_or(0)).unwrap_or(0),
-            until: options.until.map(|s| s.parse().unwrap_or(0)).unwrap_or(0),
-            ..Default::default()
+    async fn rename_container(&self, id: &str, new_name: &str) -> Result<(), RuntimeError> {
+        let options = RenameContainerOptions { name: new_name };
+        match self.client.rename_container(id, options).await {
+            Ok(()) => {
+                info!(container_id = %id, name = %new_name, "Container renamed");
+                Ok(())
+            }
+            Err(e) => Err(convert::rename_error(new_name, e)),
+        }
+    }
+
+    async fn logs(&self, id: &str, options: LogsOptions) -> Result<Vec<String>, RuntimeError> {
+        // A buffered read can never complete while following, so always
+        // read the existing history only.
+        let options = LogsOptions {
+            follow: false,
+            ..options
         };
 
-        let mut logs_stream = self.client.logs(id, Some(bollard_options));
+        let mut logs_stream = self.logs_stream(id, options).await?;
         let mut logs = Vec::new();
 
         while let Some(log) = logs_stream.next().await {
             match log {
-                Ok(output) => {
-                    logs.push(output.to_string());
+                Ok(line) => {
+                    logs.push(line);
                 }
                 Err(e) => {
                     debug!(error = %e, "Error reading log");
@@ -358,74 +404,87 @@ impl RuntimeAdapter for DockerAdapter {
         Ok(logs)
     }
 
-    async fn stats(&self, id: &str) -> Result<ContainerStats> {
+    async fn logs_stream(&self, id: &str, options: LogsOptions) -> Result<LogStream, RuntimeError> {
+        let bollard_options = BollardLogsOptions::<String> {
+            stdout: options.stdout,
+            stderr: options.stderr,
+            follow: options.follow,
+            tail: options.tail.map(|t| t.to_string()).unwrap_or_else(|| "all".to_string()),
+            since: options.since.map(|s| s.parse().unwrap_or(0)).unwrap_or(0),
+            until: options.until.map(|s| s.parse().unwrap_or(0)).unwrap_or(0),
+            ..Default::default()
+        };
+
+        let stream = self
+            .client
+            .logs(id, Some(bollard_options))
+            .map(|log| log.map(|output| output.to_string()).map_err(RuntimeError::from));
+
+        Ok(Box::pin(stream))
+    }
+
+    async fn stats(&self, id: &str) -> Result<ContainerStats, RuntimeError> {
         let options = StatsOptions {
             stream: false,
             one_shot: true,
         };
 
-        let mut stats_stream = self.client.stats(id, Some(options));
+        let stats = self
+            .client
+            .stats(id, Some(options))
+            .next()
+            .await
+            .ok_or_else(|| anyhow!("No stats available for container"))??;
 
-        if let Some(stats) = stats_stream.next().await {
-            let stats = stats?;
+        if convert::has_precpu(&stats) {
+            return Ok(convert::stats_from_bollard(stats));
+        }
 
-            let cpu_delta = stats.cpu_stats.cpu_usage.total_usage as f64
-                - stats.precpu_stats.cpu_usage.total_usage as f64;
-            let system_delta = stats.cpu_stats.system_cpu_usage.unwrap_or(0) as f64
-                - stats.precpu_stats.system_cpu_usage.unwrap_or(0) as f64;
-            let cpu_percent = if system_delta > 0.0 {
-                (cpu_delta / system_delta) * stats.cpu_stats.online_cpus.unwrap_or(1) as f64 * 100.0
-            } else {
-                0.0
-            };
+        // A one-shot sample has no previous CPU reading to diff against, so
+        // take the second streamed sample, whose precpu is the first
+        let mut samples = self.stats_stream(id).await?.skip(1);
+        match samples.next().await {
+            Some(sample) => sample,
+            None => Ok(convert::stats_from_bollard(stats)),
+        }
+    }
 
-            let memory_usage = stats.memory_stats.usage.unwrap_or(0);
-            let memory_limit = stats.memory_stats.limit.unwrap_or(0);
+    async fn stats_stream(&self, id: &str) -> Result<StatsStream, RuntimeError> {
+        let options = StatsOptions {
+            stream: true,
+            one_shot: false,
+        };
 
-            let (rx_bytes, tx_bytes) = stats
-                .networks
-                .map(|nets| {
-                    nets.values().fold((0u64, 0u64), |(rx, tx), net| {
-                        (rx + net.rx_bytes, tx + net.tx_bytes)
-                    })
-                })
-                .unwrap_or((0, 0));
-
-            let (read_bytes, write_bytes) = stats
-                .blkio_stats
-                .io_service_bytes_recursive
-                .map(|ios| {
-                    ios.iter().fold((0u64, 0u64), |(r, w), io| {
-                        match io.op.as_str() {
-                            "read" | "Read" => (r + io.value, w),
-                            "write" | "Write" => (r, w + io.value),
-                            _ => (r, w),
-                        }
-                    })
-                })
-                .unwrap_or((0, 0));
-
-            return Ok(ContainerStats {
-                cpu_usage_percent: cpu_percent,
-                memory_usage_bytes: memory_usage,
-                memory_limit_bytes: memory_limit,
-                network_rx_bytes: rx_bytes,
-                network_tx_bytes: tx_bytes,
-                block_read_bytes: read_bytes,
-                block_write_bytes: write_bytes,
-            });
-        }
+        let stream = self.client.stats(id, Some(options)).map(|stats| {
+            stats
+                .map(convert::stats_from_bollard)
+                .map_err(RuntimeError::from)
+        });
 
-        Err(anyhow::anyhow!("No stats available for container"))
+        Ok(Box::pin(stream))
     }
 
-    async fn pull_image(&self, image: &str) -> Result<()> {
+    async fn pull_image(
+        &self,
+        image: &str,
+        auth: Option<RegistryAuth>,
+    ) -> Result<(), RuntimeError> {
         let options = CreateImageOptions {
             from_image: image,
             ..Default::default()
         };
 
-        let mut stream = self.client.create_image(Some(options), None, None);
+        let credentials = auth
+            .as_ref()
+            .or(self.default_registry_auth.as_ref())
+            .map(convert::credentials);
+        debug!(
+            image = %image,
+            authenticated = credentials.is_some(),
+            "Starting image pull"
+        );
+
+        let mut stream = self.client.create_image(Some(options), None, credentials);
 
         while let Some(result) = stream.next().await {
             match result {
@@ -435,7 +494,7 @@ impl RuntimeAdapter for DockerAdapter {
                     }
                 }
                 Err(e) => {
-                    return Err(e.into());
+                    return Err(convert::pull_error(image, e));
                 }
             }
         }
@@ -444,7 +503,76 @@ impl RuntimeAdapter for DockerAdapter {
         Ok(())
     }
 
-    async fn list_images(&self) -> Result<Vec<ImageInfo>> {
+    async fn build_image(&self, options: BuildImageOptions) -> Result<String, RuntimeError> {
+        let context = match options.context {
+            BuildContext::TarBytes(bytes) => bytes,
+            BuildContext::TarPath(path) => tokio::fs::read(&path)
+                .await
+                .with_context(|| format!("Failed to read build context: {}", path.display()))?,
+        };
+
+        let build_options = BollardBuildOptions {
+            dockerfile: options.dockerfile.unwrap_or_else(|| "Dockerfile".to_string()),
+            t: options.tag.clone(),
+            buildargs: options.build_args,
+            rm: true,
+            ..Default::default()
+        };
+
+        info!(tag = %options.tag, context_bytes = context.len(), "Starting image build");
+        let mut stream = self
+            .client
+            .build_image(build_options, None, Some(context.into()));
+
+        // Output of the current build step, reported if the build fails
+        let mut step_output: Vec<String> = Vec::new();
+        let mut image_id = None;
+
+        while let Some(result) = stream.next().await {
+            let build_info = match result {
+                Ok(build_info) => build_info,
+                Err(e) => {
+                    let output = step_output.join("\n");
+                    return Err(anyhow!("Image build failed: {}\n{}", e, output).into());
+                }
+            };
+
+            if let Some(line) = build_info.stream {
+                let line = line.trim_end();
+                if !line.is_empty() {
+                    debug!(tag = %options.tag, output = %line, "Building image");
+                    if line.starts_with("Step ") {
+                        step_output.clear();
+                    }
+                    step_output.push(line.to_string());
+                }
+            }
+
+            if let Some(error) = build_info.error {
+                let output = step_output.join("\n");
+                return Err(anyhow!("Image build failed: {}\n{}", error, output).into());
+            }
+
+            if let Some(id) = build_info.aux.and_then(|aux| aux.id) {
+                image_id = Some(id);
+            }
+        }
+
+        let image_id = match image_id {
+            Some(id) => id,
+            None => self
+                .client
+                .inspect_image(&options.tag)
+                .await?
+                .id
+                .unwrap_or_default(),
+        };
+
+        info!(tag = %options.tag, image_id = %image_id, "Image built");
+        Ok(image_id)
+    }
+
+    async fn list_images(&self) -> Result<Vec<ImageInfo>, RuntimeError> {
         let options = ListImagesOptions::<String> {
             all: false,
             ..Default::default()
@@ -463,7 +591,28 @@ impl RuntimeAdapter for DockerAdapter {
             .collect())
     }
 
-    async fn remove_image(&self, id: &str, force: bool) -> Result<()> {
+    async fn image_exists(&self, image: &str) -> Result<bool, RuntimeError> {
+        match self.client.inspect_image(image).await {
+            Ok(_) => Ok(true),
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn image_digests(&self, image: &str) -> Result<Vec<String>, RuntimeError> {
+        let inspect = self.client.inspect_image(image).await?;
+        Ok(inspect
+            .repo_digests
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|reference| reference.split_once('@'))
+            .map(|(_, digest)| digest.to_string())
+            .collect())
+    }
+
+    async fn remove_image(&self, id: &str, force: bool) -> Result<(), RuntimeError> {
         let options = RemoveImageOptions {
             force,
             ..Default::default()
@@ -473,28 +622,275 @@ impl RuntimeAdapter for DockerAdapter {
         Ok(())
     }
 
-    async fn create_network(&self, name: &str) -> Result<String> {
-        let options = CreateNetworkOptions {
-            name: name.to_string(),
-            driver: "bridge".to_string(),
+    async fn create_network(&self, options: CreateNetworkOptions) -> Result<String, RuntimeError> {
+        let create_options = BollardNetworkOptions {
+            name: options.name.clone(),
+            // Standalone containers can only join overlay networks created attachable
+            attachable: options.driver == "overlay",
+            ipam: convert::network_ipam(options.subnet, options.gateway),
+            driver: options.driver,
+            internal: options.internal,
+            labels: options.labels,
             ..Default::default()
         };
 
-        let response = self.client.create_network(options).await?;
+        let response = self.client.create_network(create_options).await?;
         let id = response.id.unwrap_or_default();
-        info!(network_id = %id, name = %name, "Network created");
+        info!(network_id = %id, name = %options.name, "Network created");
         Ok(id)
     }
 
-    async fn remove_network(&self, name: &str) -> Result<()> {
+    async fn remove_network(&self, name: &str) -> Result<(), RuntimeError> {
         self.client.remove_network(name).await?;
         info!(network = %name, "Network removed");
         Ok(())
     }
 
-    async fn exec(&self, id: &str, cmd: Vec<String>) -> Result<(i64, String)> {
+    async fn list_networks(&self) -> Result<Vec<NetworkInfo>, RuntimeError> {
+        let networks = self
+            .client
+            .list_networks(None::<ListNetworksOptions<String>>)
+            .await?;
+        Ok(networks.into_iter().map(convert::network_from_bollard).collect())
+    }
+
+    async fn inspect_network(&self, name: &str) -> Result<Option<NetworkInfo>, RuntimeError> {
+        match self
+            .client
+            .inspect_network(name, None::<InspectNetworkOptions<String>>)
+            .await
+        {
+            Ok(network) => Ok(Some(convert::network_from_bollard(network))),
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn connect_network(&self, container_id: &str, network: &str) -> Result<(), RuntimeError> {
+        let options = ConnectNetworkOptions {
+            container: container_id,
+            ..Default::default()
+        };
+        self.client.connect_network(network, options).await?;
+        info!(container_id = %container_id, network = %network, "Container attached to network");
+        Ok(())
+    }
+
+    async fn disconnect_network(
+        &self,
+        container_id: &str,
+        network: &str,
+    ) -> Result<(), RuntimeError> {
+        let options = DisconnectNetworkOptions {
+            container: container_id,
+            force: false,
+        };
+        self.client.disconnect_network(network, options).await?;
+        info!(
+            container_id = %container_id,
+            network = %network,
+            "Container detached from network"
+        );
+        Ok(())
+    }
+
+    async fn create_volume(
+        &self,
+        name: &str,
+        labels: HashMap<String, String>,
+    ) -> Result<String, RuntimeError> {
+        let options = CreateVolumeOptions {
+            name: name.to_string(),
+            driver: "local".to_string(),
+            labels,
+            ..Default::default()
+        };
+
+        let volume = self.client.create_volume(options).await?;
+        info!(volume = %volume.name, "Volume created");
+        Ok(volume.name)
+    }
+
+    async fn list_volumes(&self) -> Result<Vec<VolumeInfo>, RuntimeError> {
+        let response = self
+            .client
+            .list_volumes(None::<ListVolumesOptions<String>>)
+            .await?;
+
+        Ok(response
+            .volumes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|v| VolumeInfo {
+                name: v.name,
+                driver: v.driver,
+                mountpoint: v.mountpoint,
+                labels: v.labels,
+            })
+            .collect())
+    }
+
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), RuntimeError> {
+        let options = RemoveVolumeOptions { force };
+        self.client.remove_volume(name, Some(options)).await?;
+        info!(volume = %name, "Volume removed");
+        Ok(())
+    }
+
+    async fn prune_containers(&self) -> Result<PruneReport, RuntimeError> {
+        let response = self
+            .client
+            .prune_containers(None::<PruneContainersOptions<String>>)
+            .await?;
+
+        let report = PruneReport {
+            items_removed: response.containers_deleted.unwrap_or_default(),
+            space_reclaimed_bytes: response.space_reclaimed.unwrap_or(0).max(0) as u64,
+        };
+        info!(
+            count = report.items_removed.len(),
+            bytes = report.space_reclaimed_bytes,
+            "Containers pruned"
+        );
+        Ok(report)
+    }
+
+    async fn prune_images(&self, dangling_only: bool) -> Result<PruneReport, RuntimeError> {
+        let dangling = if dangling_only { "true" } else { "false" };
+        let options = PruneImagesOptions {
+            filters: HashMap::from([("dangling", vec![dangling])]),
+        };
+        let response = self.client.prune_images(Some(options)).await?;
+
+        let report = PruneReport {
+            items_removed: response
+                .images_deleted
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|image| image.deleted.or(image.untagged))
+                .collect(),
+            space_reclaimed_bytes: response.space_reclaimed.unwrap_or(0).max(0) as u64,
+        };
+        info!(
+            count = report.items_removed.len(),
+            bytes = report.space_reclaimed_bytes,
+            dangling_only,
+            "Images pruned"
+        );
+        Ok(report)
+    }
+
+    async fn prune_volumes(&self) -> Result<PruneReport, RuntimeError> {
+        let response = self
+            .client
+            .prune_volumes(None::<PruneVolumesOptions<String>>)
+            .await?;
+
+        let report = PruneReport {
+            items_removed: response.volumes_deleted.unwrap_or_default(),
+            space_reclaimed_bytes: response.space_reclaimed.unwrap_or(0).max(0) as u64,
+        };
+        info!(
+            count = report.items_removed.len(),
+            bytes = report.space_reclaimed_bytes,
+            "Volumes pruned"
+        );
+        Ok(report)
+    }
+
+    async fn copy_to_container(
+        &self,
+        id: &str,
+        dest_path: &str,
+        tar_data: Vec<u8>,
+    ) -> Result<(), RuntimeError> {
+        let options = UploadToContainerOptions {
+            path: dest_path,
+            ..Default::default()
+        };
+
+        match self
+            .client
+            .upload_to_container(id, Some(options), tar_data.into())
+            .await
+        {
+            Ok(()) => {
+                debug!(container_id = %id, path = %dest_path, "Archive copied to container");
+                Ok(())
+            }
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Err(RuntimeError::not_found("path", format!("{}:{}", id, dest_path))),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn copy_from_container(&self, id: &str, src_path: &str) -> Result<Vec<u8>, RuntimeError> {
+        let options = DownloadFromContainerOptions { path: src_path };
+        let mut stream = self.client.download_from_container(id, Some(options));
+
+        let mut tar_data = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(bytes) => tar_data.extend_from_slice(&bytes),
+                Err(bollard::errors::Error::DockerResponseServerError {
+                    status_code: 404, ..
+                }) => {
+                    return Err(RuntimeError::not_found("path", format!("{}:{}", id, src_path)));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        debug!(
+            container_id = %id,
+            path = %src_path,
+            bytes = tar_data.len(),
+            "Archive copied from container"
+        );
+        Ok(tar_data)
+    }
+
+    async fn events(&self) -> Result<EventStream, RuntimeError> {
+        let options = EventsOptions::<String> {
+            filters: HashMap::from([
+                ("type".to_string(), vec!["container".to_string()]),
+                (
+                    "event".to_string(),
+                    ["kill", "oom", "die", "stop", "destroy"].map(String::from).to_vec(),
+                ),
+                ("label".to_string(), vec!["syntra.managed=true".to_string()]),
+            ]),
+            ..Default::default()
+        };
+
+        let stream = self
+            .client
+            .events(Some(options))
+            .filter_map(|event| async move {
+                match event {
+                    Ok(event) => convert::event_from_bollard(event).map(Ok),
+                    Err(e) => Some(Err(e.into())),
+                }
+            });
+
+        Ok(Box::pin(stream))
+    }
+
+    async fn exec(&self, id: &str, options: ExecOptions) -> Result<ExecResult, RuntimeError> {
+        let env: Vec<String> = options
+            .env
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+
         let exec_options = CreateExecOptions {
-            cmd: Some(cmd),
+            cmd: Some(options.cmd),
+            env: Some(env),
+            working_dir: options.working_dir,
+            attach_stdin: Some(options.stdin.is_some()),
             attach_stdout: Some(true),
             attach_stderr: Some(true),
             ..Default::default()
@@ -504,16 +900,21 @@ impl RuntimeAdapter for DockerAdapter {
 
         let start_result = self.client.start_exec(&exec.id, None).await?;
 
-        let mut output = String::new();
+        let mut result = ExecResult::default();
+
+        if let StartExecResults::Attached { output: mut stream, mut input } = start_result {
+            if let Some(stdin) = options.stdin {
+                input.write_all(&stdin).await.context("Failed to write exec stdin")?;
+                input.shutdown().await.context("Failed to close exec stdin")?;
+            }
 
-        if let StartExecResults::Attached { output: mut stream, .. } = start_result {
             while let Some(chunk) = stream.next().await {
                 match chunk {
                     Ok(bollard::container::LogOutput::StdOut { message }) => {
-                        output.push_str(&String::from_utf8_lossy(&message));
+                        result.stdout.push_str(&String::from_utf8_lossy(&message));
                     }
                     Ok(bollard::container::LogOutput::StdErr { message }) => {
-                        output.push_str(&String::from_utf8_lossy(&message));
+                        result.stderr.push_str(&String::from_utf8_lossy(&message));
                     }
                     _ => {}
                 }
@@ -522,20 +923,123 @@ impl RuntimeAdapter for DockerAdapter {
 
         // Get exit code
         let inspect = self.client.inspect_exec(&exec.id).await?;
-        let exit_code = inspect.exit_code.unwrap_or(-1);
+        result.exit_code = inspect.exit_code.unwrap_or(-1);
 
-        Ok((exit_code, output))
+        Ok(result)
+    }
+
+    async fn top(&self, id: &str) -> Result<Vec<ProcessInfo>, RuntimeError> {
+        // aux adds the %CPU and %MEM columns to the default ps output
+        let top = self
+            .client
+            .top_processes(id, Some(TopOptions { ps_args: "aux" }))
+            .await?;
+        Ok(convert::processes_from_top(top))
+    }
+
+    async fn container_diff(&self, id: &str) -> Result<Vec<FsChange>, RuntimeError> {
+        // Docker answers with no body when nothing has changed
+        let changes = self.client.container_changes(id).await?.unwrap_or_default();
+        Ok(changes.into_iter().map(convert::fs_change_from_bollard).collect())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    async fn disk_usage(&self) -> Result<DiskUsage, RuntimeError> {
+        Ok(convert::disk_usage_from_bollard(self.client.df().await?))
+    }
+
+    async fn commit_container(
+        &self,
+        id: &str,
+        repo: &str,
+        tag: &str,
+        message: Option<String>,
+    ) -> Result<String, RuntimeError> {
+        let options = convert::commit_options(id, repo, tag, message);
+        let commit = self
+            .client
+            .commit_container(options, Config::<String>::default())
+            .await?;
 
-    #[test]
-    fn test_parse_status() {
-        assert_eq!(DockerAdapter::parse_status(Some("running")), ContainerStatus::Running);
-        assert_eq!(DockerAdapter::parse_status(Some("exited")), ContainerStatus::Exited);
-        assert_eq!(DockerAdapter::parse_status(None), ContainerStatus::Unknown);
+        // bollard reads the ID from a field Docker doesn't send, so look up
+        // the image the commit tagged instead
+        let image = format!("{}:{}", repo, tag);
+        let image_id = match commit.id {
+            Some(image_id) => image_id,
+            None => self
+                .client
+                .inspect_image(&image)
+                .await?
+                .id
+                .context("Docker did not return the committed image ID")?,
+        };
+        info!(container_id = %id, image = %image, "Committed container");
+        Ok(image_id)
+    }
+
+    async fn tag_image(&self, source: &str, repo: &str, tag: &str) -> Result<(), RuntimeError> {
+        let options = TagImageOptions { repo, tag };
+        match self.client.tag_image(source, Some(options)).await {
+            Ok(()) => {
+                info!(source = %source, image = %format!("{}:{}", repo, tag), "Image tagged");
+                Ok(())
+            }
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => Err(RuntimeError::not_found("image", source)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn push_image(
+        &self,
+        image: &str,
+        auth: Option<RegistryAuth>,
+    ) -> Result<(), RuntimeError> {
+        let (repository, tag) = convert::split_tag(image);
+        let options = PushImageOptions { tag };
+
+        let credentials = auth
+            .as_ref()
+            .or(self.default_registry_auth.as_ref())
+            .map(convert::credentials);
+        debug!(
+            image = %image,
+            authenticated = credentials.is_some(),
+            "Starting image push"
+        );
+
+        let mut stream = self.client.push_image(repository, Some(options), credentials);
+
+        while let Some(result) = stream.next().await {
+            // Registries report failures in the progress stream rather than the HTTP status
+            let message = match result {
+                Ok(info) => match info.error {
+                    Some(error) => error,
+                    None => {
+                        if let Some(status) = info.status {
+                            debug!(status = %status, "Pushing image");
+                        }
+                        continue;
+                    }
+                },
+                Err(bollard::errors::Error::DockerStreamError { error }) => error,
+                Err(bollard::errors::Error::DockerResponseServerError {
+                    status_code: 401,
+                    message,
+                }) => message,
+                Err(e) => {
+                    let context = format!("Failed to push {}", image);
+                    return Err(RuntimeError::Other(anyhow::Error::from(e).context(context)));
+                }
+            };
+
+            if convert::is_auth_failure(&message) {
+                return Err(RuntimeError::unauthorized(image, message));
+            }
+            return Err(anyhow!("Failed to push {}: {}", image, message).into());
+        }
+
+        info!(image = %image, "Image pushed");
+        Ok(())
     }
 }
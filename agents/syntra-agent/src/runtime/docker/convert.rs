@@ -0,0 +1,876 @@
+//! Bollard Conversions
+//!
+//! Translation between bollard's Docker API models and our runtime types.
+//! Shared by every adapter that speaks the Docker-compatible API.
+
+use bollard::auth::DockerCredentials;
+use bollard::container::Stats;
+use bollard::image::CommitContainerOptions;
+use bollard::service::{
+    ChangeType, ContainerInspectResponse, ContainerStateStatusEnum, ContainerSummary,
+    ContainerTopResponse, DeviceRequest, EventMessage, FilesystemChange, HealthStatusEnum, Ipam,
+    IpamConfig, Network, SystemDataUsageResponse,
+};
+use chrono::{DateTime, Utc};
+
+use crate::runtime::adapter::{
+    ContainerHealth, ContainerInfo, ContainerStats, ContainerStatus, DiskUsage, FsChange,
+    FsChangeKind, GpuSpec, NetworkInfo, PortBinding, ProcessInfo, RegistryAuth, RuntimeEvent,
+    RuntimeEventAction,
+};
+use crate::runtime::error::RuntimeError;
+
+impl From<bollard::errors::Error> for RuntimeError {
+    /// Classify a Docker API error by what went wrong
+    fn from(err: bollard::errors::Error) -> Self {
+        use bollard::errors::Error;
+
+        match err {
+            Error::DockerResponseServerError {
+                status_code: 404,
+                message,
+            } => RuntimeError::not_found("object", message),
+            Error::IOError { .. }
+            | Error::HyperResponseError { .. }
+            | Error::RequestTimeoutError => RuntimeError::daemon_unavailable(err.to_string()),
+            err => RuntimeError::Other(err.into()),
+        }
+    }
+}
+
+/// Classify an error from pulling `image`
+pub(crate) fn pull_error(image: &str, err: bollard::errors::Error) -> RuntimeError {
+    use bollard::errors::Error;
+
+    let message = match err {
+        Error::DockerResponseServerError {
+            status_code: 401 | 403,
+            message,
+        } => return RuntimeError::unauthorized(image, message),
+        Error::DockerResponseServerError { message, .. } => message,
+        Error::DockerStreamError { error } => error,
+        err => return err.into(),
+    };
+    if is_auth_failure(&message) {
+        RuntimeError::unauthorized(image, message)
+    } else {
+        RuntimeError::image_pull_failed(image, message)
+    }
+}
+
+/// Classify an error from renaming a container to `new_name`, which Docker
+/// refuses with a 409 when the name is taken
+pub(crate) fn rename_error(new_name: &str, err: bollard::errors::Error) -> RuntimeError {
+    match err {
+        bollard::errors::Error::DockerResponseServerError {
+            status_code: 409, ..
+        } => RuntimeError::conflict(new_name),
+        err => err.into(),
+    }
+}
+
+/// Whether creating a container failed because its image isn't present
+///
+/// A missing network or volume is a 404 too, which pulling wouldn't fix.
+pub(crate) fn is_missing_image(err: &bollard::errors::Error) -> bool {
+    matches!(
+        err,
+        bollard::errors::Error::DockerResponseServerError { status_code: 404, message }
+            if message.starts_with("No such image")
+    )
+}
+
+/// Convert bollard container state to our ContainerStatus
+pub(crate) fn parse_status(state: Option<&str>) -> ContainerStatus {
+    match state {
+        Some("created") => ContainerStatus::Created,
+        Some("running") => ContainerStatus::Running,
+        Some("paused") => ContainerStatus::Paused,
+        Some("restarting") => ContainerStatus::Restarting,
+        Some("exited") => ContainerStatus::Exited,
+        Some("dead") => ContainerStatus::Dead,
+        _ => ContainerStatus::Unknown,
+    }
+}
+
+/// Convert an inspect state enum to the string form used by `parse_status`
+fn state_status_str(status: &ContainerStateStatusEnum) -> &'static str {
+    match status {
+        ContainerStateStatusEnum::CREATED => "created",
+        ContainerStateStatusEnum::RUNNING => "running",
+        ContainerStateStatusEnum::PAUSED => "paused",
+        ContainerStateStatusEnum::RESTARTING => "restarting",
+        ContainerStateStatusEnum::REMOVING => "removing",
+        ContainerStateStatusEnum::EXITED => "exited",
+        ContainerStateStatusEnum::DEAD => "dead",
+        _ => "unknown",
+    }
+}
+
+/// Convert a Docker health status to our ContainerHealth
+///
+/// An empty status means the runtime reported no health at all.
+pub(crate) fn parse_health(health: Option<&str>) -> Option<ContainerHealth> {
+    match health {
+        Some("none") => Some(ContainerHealth::None),
+        Some("starting") => Some(ContainerHealth::Starting),
+        Some("healthy") => Some(ContainerHealth::Healthy),
+        Some("unhealthy") => Some(ContainerHealth::Unhealthy),
+        _ => None,
+    }
+}
+
+/// Convert an inspect health enum to the string form used by `parse_health`
+fn health_status_str(status: &HealthStatusEnum) -> &'static str {
+    match status {
+        HealthStatusEnum::NONE => "none",
+        HealthStatusEnum::STARTING => "starting",
+        HealthStatusEnum::HEALTHY => "healthy",
+        HealthStatusEnum::UNHEALTHY => "unhealthy",
+        _ => "",
+    }
+}
+
+/// A container state timestamp, or None for Docker's zero time, which it
+/// reports for a container that never started or stopped
+pub(crate) fn state_time(time: Option<&str>) -> Option<String> {
+    time.filter(|t| !t.is_empty() && !t.starts_with("0001-01-01"))
+        .map(str::to_string)
+}
+
+/// Build bollard credentials from registry auth
+pub(crate) fn credentials(auth: &RegistryAuth) -> DockerCredentials {
+    DockerCredentials {
+        username: Some(auth.username.clone()),
+        password: Some(auth.password.clone()),
+        serveraddress: auth.server_address.clone(),
+        ..Default::default()
+    }
+}
+
+/// Convert a container list entry into ContainerInfo
+pub(crate) fn summary_to_info(container: ContainerSummary) -> ContainerInfo {
+    let ports = container
+        .ports
+        .unwrap_or_default()
+        .iter()
+        .map(|p| PortBinding {
+            container_port: p.private_port,
+            host_port: p.public_port,
+            host_ip: p.ip.clone(),
+            protocol: p.typ.as_ref().map(|t| t.to_string()).unwrap_or_else(|| "tcp".to_string()),
+        })
+        .collect();
+
+    ContainerInfo {
+        id: container.id.unwrap_or_default(),
+        name: container
+            .names
+            .and_then(|n| n.first().cloned())
+            .unwrap_or_default()
+            .trim_start_matches('/')
+            .to_string(),
+        image: container.image.unwrap_or_default(),
+        status: parse_status(container.state.as_deref()),
+        health: None,
+        created_at: container.created.map(|c| c.to_string()).unwrap_or_default(),
+        started_at: None,
+        finished_at: None,
+        ports,
+        labels: container.labels.unwrap_or_default(),
+    }
+}
+
+/// Convert a container inspect response into ContainerInfo
+pub(crate) fn inspect_to_info(container: ContainerInspectResponse) -> ContainerInfo {
+    let state = container.state.as_ref();
+    let config = container.config.as_ref();
+
+    let ports = container
+        .network_settings
+        .as_ref()
+        .and_then(|ns| ns.ports.as_ref())
+        .map(|ports| {
+            ports
+                .iter()
+                .filter_map(|(key, bindings)| {
+                    let parts: Vec<&str> = key.split('/').collect();
+                    let container_port = parts.first()?.parse().ok()?;
+                    let protocol = parts.get(1).unwrap_or(&"tcp").to_string();
+
+                    let (host_port, host_ip) = bindings
+                        .as_ref()
+                        .and_then(|b| b.first())
+                        .map(|b| {
+                            (
+                                b.host_port.as_ref().and_then(|p| p.parse().ok()),
+                                b.host_ip.clone(),
+                            )
+                        })
+                        .unwrap_or((None, None));
+
+                    Some(PortBinding {
+                        container_port,
+                        host_port,
+                        host_ip,
+                        protocol,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    ContainerInfo {
+        id: container.id.unwrap_or_default(),
+        name: container
+            .name
+            .unwrap_or_default()
+            .trim_start_matches('/')
+            .to_string(),
+        image: config
+            .and_then(|c| c.image.clone())
+            .unwrap_or_default(),
+        status: parse_status(
+            state
+                .and_then(|s| s.status.as_ref())
+                .map(state_status_str),
+        ),
+        health: parse_health(
+            state
+                .and_then(|s| s.health.as_ref())
+                .and_then(|h| h.status.as_ref())
+                .map(health_status_str),
+        ),
+        created_at: container.created.unwrap_or_default(),
+        started_at: state.and_then(|s| state_time(s.started_at.as_deref())),
+        finished_at: state.and_then(|s| state_time(s.finished_at.as_deref())),
+        ports,
+        labels: config
+            .and_then(|c| c.labels.clone())
+            .unwrap_or_default(),
+    }
+}
+
+/// Whether a stats sample carries the previous CPU reading needed for a delta
+pub(crate) fn has_precpu(stats: &Stats) -> bool {
+    stats.precpu_stats.system_cpu_usage.unwrap_or(0) > 0
+}
+
+/// Convert a bollard stats sample into ContainerStats
+pub(crate) fn stats_from_bollard(stats: Stats) -> ContainerStats {
+    let cpu_delta = stats.cpu_stats.cpu_usage.total_usage as f64
+        - stats.precpu_stats.cpu_usage.total_usage as f64;
+    let system_delta = stats.cpu_stats.system_cpu_usage.unwrap_or(0) as f64
+        - stats.precpu_stats.system_cpu_usage.unwrap_or(0) as f64;
+    let cpu_percent = if system_delta > 0.0 {
+        (cpu_delta / system_delta) * stats.cpu_stats.online_cpus.unwrap_or(1) as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    let memory_usage = stats.memory_stats.usage.unwrap_or(0);
+    let memory_limit = stats.memory_stats.limit.unwrap_or(0);
+
+    let (rx_bytes, tx_bytes) = stats
+        .networks
+        .map(|nets| {
+            nets.values().fold((0u64, 0u64), |(rx, tx), net| {
+                (rx + net.rx_bytes, tx + net.tx_bytes)
+            })
+        })
+        .unwrap_or((0, 0));
+
+    let (read_bytes, write_bytes) = stats
+        .blkio_stats
+        .io_service_bytes_recursive
+        .map(|ios| {
+            ios.iter().fold((0u64, 0u64), |(r, w), io| {
+                match io.op.as_str() {
+                    "read" | "Read" => (r + io.value, w),
+                    "write" | "Write" => (r, w + io.value),
+                    _ => (r, w),
+                }
+            })
+        })
+        .unwrap_or((0, 0));
+
+    ContainerStats {
+        cpu_usage_percent: cpu_percent,
+        memory_usage_bytes: memory_usage,
+        memory_limit_bytes: memory_limit,
+        network_rx_bytes: rx_bytes,
+        network_tx_bytes: tx_bytes,
+        block_read_bytes: read_bytes,
+        block_write_bytes: write_bytes,
+    }
+}
+
+/// Convert a Docker event into a RuntimeEvent, skipping actions we don't report
+pub(crate) fn event_from_bollard(event: EventMessage) -> Option<RuntimeEvent> {
+    let action = match event.action.as_deref()? {
+        "kill" => RuntimeEventAction::Kill,
+        "oom" => RuntimeEventAction::Oom,
+        "die" => RuntimeEventAction::Die,
+        "stop" => RuntimeEventAction::Stop,
+        "destroy" => RuntimeEventAction::Destroy,
+        _ => return None,
+    };
+
+    let actor = event.actor?;
+    let attributes = actor.attributes.unwrap_or_default();
+    let timestamp = event
+        .time_nano
+        .map(DateTime::from_timestamp_nanos)
+        .or_else(|| event.time.and_then(|secs| DateTime::from_timestamp(secs, 0)))
+        .unwrap_or_else(Utc::now);
+
+    Some(RuntimeEvent {
+        container_id: actor.id?,
+        name: attributes.get("name").cloned(),
+        action,
+        exit_code: attributes.get("exitCode").and_then(|code| code.parse().ok()),
+        timestamp,
+    })
+}
+
+/// Convert `docker top` output into process entries
+///
+/// Column titles vary by platform and `ps` arguments, so columns are found by
+/// title and rows without a numeric PID are skipped.
+pub(crate) fn processes_from_top(top: ContainerTopResponse) -> Vec<ProcessInfo> {
+    let titles = top.titles.unwrap_or_default();
+    let column = |names: &[&str]| {
+        titles
+            .iter()
+            .position(|title| names.iter().any(|name| title.eq_ignore_ascii_case(name)))
+    };
+    let Some(pid) = column(&["PID"]) else {
+        return Vec::new();
+    };
+    let user = column(&["USER", "UID"]);
+    let command = column(&["COMMAND", "CMD", "Name"]);
+    let cpu = column(&["%CPU", "CPU", "C"]);
+    let mem = column(&["%MEM"]);
+
+    top.processes
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|row| {
+            let field = |index: Option<usize>| index.and_then(|i| row.get(i)).cloned();
+            Some(ProcessInfo {
+                pid: row.get(pid)?.trim().parse().ok()?,
+                user: field(user),
+                command: field(command).unwrap_or_default(),
+                cpu: field(cpu).and_then(|value| value.trim().parse().ok()),
+                mem: field(mem).and_then(|value| value.trim().parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// Convert a Docker filesystem change, whose kind is 0, 1, or 2 on the wire
+pub(crate) fn fs_change_from_bollard(change: FilesystemChange) -> FsChange {
+    let kind = match change.kind {
+        ChangeType::_0 => FsChangeKind::Modified,
+        ChangeType::_1 => FsChangeKind::Added,
+        ChangeType::_2 => FsChangeKind::Deleted,
+    };
+    FsChange {
+        path: change.path,
+        kind,
+    }
+}
+
+/// IPAM settings for a new network; runtime-allocated when no subnet is given
+pub(crate) fn network_ipam(subnet: Option<String>, gateway: Option<String>) -> Ipam {
+    if subnet.is_none() && gateway.is_none() {
+        return Ipam::default();
+    }
+    Ipam {
+        config: Some(vec![IpamConfig {
+            subnet,
+            gateway,
+            ..Default::default()
+        }]),
+        ..Default::default()
+    }
+}
+
+/// Convert a bollard network into NetworkInfo
+pub(crate) fn network_from_bollard(network: Network) -> NetworkInfo {
+    let mut containers: Vec<String> = network.containers.unwrap_or_default().into_keys().collect();
+    containers.sort();
+
+    NetworkInfo {
+        id: network.id.unwrap_or_default(),
+        name: network.name.unwrap_or_default(),
+        driver: network.driver.unwrap_or_default(),
+        scope: network.scope.unwrap_or_default(),
+        containers,
+    }
+}
+
+/// Options committing container `id` as `repo:tag`, paused so the snapshot
+/// is consistent
+pub(crate) fn commit_options(
+    id: &str,
+    repo: &str,
+    tag: &str,
+    message: Option<String>,
+) -> CommitContainerOptions<String> {
+    CommitContainerOptions {
+        container: id.to_string(),
+        repo: repo.to_string(),
+        tag: tag.to_string(),
+        comment: message.unwrap_or_default(),
+        pause: true,
+        ..Default::default()
+    }
+}
+
+/// Total up `docker system df` output
+///
+/// Docker reports -1 for sizes it hasn't calculated, which count as zero.
+pub(crate) fn disk_usage_from_bollard(df: SystemDataUsageResponse) -> DiskUsage {
+    let bytes = |size: i64| size.max(0) as u64;
+    let mut usage = DiskUsage::default();
+
+    let images = df.images.unwrap_or_default();
+    let image_sizes: u64 = images.iter().map(|image| bytes(image.size)).sum();
+    // Shared layers are counted once in layers_size but in every image's size
+    usage.images_bytes = df.layers_size.map(bytes).unwrap_or(image_sizes);
+    usage.reclaimable_bytes += images
+        .iter()
+        .filter(|image| image.containers == 0)
+        .map(|image| bytes(image.size) - bytes(image.shared_size).min(bytes(image.size)))
+        .sum::<u64>();
+
+    for container in df.containers.unwrap_or_default() {
+        let size = container.size_rw.map(bytes).unwrap_or(0);
+        usage.containers_bytes += size;
+        if container.state.as_deref() != Some("running") {
+            usage.reclaimable_bytes += size;
+        }
+    }
+
+    for volume in df.volumes.unwrap_or_default() {
+        let Some(data) = volume.usage_data else {
+            continue;
+        };
+        usage.volumes_bytes += bytes(data.size);
+        if data.ref_count == 0 {
+            usage.reclaimable_bytes += bytes(data.size);
+        }
+    }
+
+    for cache in df.build_cache.unwrap_or_default() {
+        let size = cache.size.map(bytes).unwrap_or(0);
+        usage.build_cache_bytes += size;
+        if !cache.in_use.unwrap_or(false) && !cache.shared.unwrap_or(false) {
+            usage.reclaimable_bytes += size;
+        }
+    }
+
+    usage
+}
+
+/// Split an image reference into repository and tag, defaulting to `latest`
+pub(crate) fn split_tag(image: &str) -> (&str, &str) {
+    // A colon after the last slash is a tag, not a registry port
+    match image.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') => (repository, tag),
+        _ => (image, "latest"),
+    }
+}
+
+/// Whether a registry error message means the credentials were rejected
+pub(crate) fn is_auth_failure(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["unauthorized", "authentication required", "denied"]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// Convert a size in MB to the byte count Docker expects
+pub(crate) fn mb_to_bytes(mb: u64) -> i64 {
+    (mb * 1024 * 1024) as i64
+}
+
+/// Build the device request `docker run --gpus` would send for `gpus`
+pub(crate) fn gpu_device_request(gpus: GpuSpec) -> DeviceRequest {
+    let (count, device_ids) = match gpus {
+        GpuSpec::All => (Some(-1), None),
+        GpuSpec::Count(count) => (Some(i64::from(count)), None),
+        GpuSpec::DeviceIds(ids) => (None, Some(ids)),
+    };
+    DeviceRequest {
+        driver: Some("nvidia".to_string()),
+        count,
+        device_ids,
+        capabilities: Some(vec![vec!["gpu".to_string()]]),
+        options: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::service::EventActor;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(parse_status(Some("running")), ContainerStatus::Running);
+        assert_eq!(parse_status(Some("exited")), ContainerStatus::Exited);
+        assert_eq!(parse_status(None), ContainerStatus::Unknown);
+    }
+
+    #[test]
+    fn test_event_from_bollard() {
+        let event = EventMessage {
+            action: Some("die".to_string()),
+            actor: Some(EventActor {
+                id: Some("abc123".to_string()),
+                attributes: Some(HashMap::from([
+                    ("name".to_string(), "app".to_string()),
+                    ("exitCode".to_string(), "137".to_string()),
+                ])),
+            }),
+            time: Some(1_700_000_000),
+            ..Default::default()
+        };
+
+        let event = event_from_bollard(event).unwrap();
+        assert_eq!(event.container_id, "abc123");
+        assert_eq!(event.name.as_deref(), Some("app"));
+        assert_eq!(event.action, RuntimeEventAction::Die);
+        assert_eq!(event.exit_code, Some(137));
+        assert_eq!(event.timestamp.timestamp(), 1_700_000_000);
+
+        let start = EventMessage {
+            action: Some("start".to_string()),
+            ..Default::default()
+        };
+        assert!(event_from_bollard(start).is_none());
+    }
+
+    #[test]
+    fn test_state_status_str_round_trip() {
+        assert_eq!(
+            parse_status(Some(state_status_str(&ContainerStateStatusEnum::RUNNING))),
+            ContainerStatus::Running
+        );
+        assert_eq!(
+            parse_status(Some(state_status_str(&ContainerStateStatusEnum::REMOVING))),
+            ContainerStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn test_state_time() {
+        assert_eq!(
+            state_time(Some("2024-05-01T10:00:00.123456789Z")).as_deref(),
+            Some("2024-05-01T10:00:00.123456789Z")
+        );
+        assert_eq!(state_time(Some("0001-01-01T00:00:00Z")), None);
+        assert_eq!(state_time(Some("")), None);
+        assert_eq!(state_time(None), None);
+    }
+
+    #[test]
+    fn test_parse_health() {
+        assert_eq!(parse_health(Some("none")), Some(ContainerHealth::None));
+        assert_eq!(parse_health(Some("starting")), Some(ContainerHealth::Starting));
+        assert_eq!(parse_health(Some("healthy")), Some(ContainerHealth::Healthy));
+        assert_eq!(parse_health(Some("unhealthy")), Some(ContainerHealth::Unhealthy));
+        assert_eq!(parse_health(Some("")), None);
+        assert_eq!(parse_health(None), None);
+
+        assert_eq!(
+            parse_health(Some(health_status_str(&HealthStatusEnum::UNHEALTHY))),
+            Some(ContainerHealth::Unhealthy)
+        );
+        assert_eq!(parse_health(Some(health_status_str(&HealthStatusEnum::EMPTY))), None);
+    }
+
+    #[test]
+    fn test_commit_options() {
+        let options = commit_options("abc123", "registry.io/app", "snap-1", Some("debug".into()));
+        assert_eq!(options.container, "abc123");
+        assert_eq!(options.repo, "registry.io/app");
+        assert_eq!(options.tag, "snap-1");
+        assert_eq!(options.comment, "debug");
+        assert!(options.pause);
+        assert!(options.changes.is_none());
+
+        assert_eq!(commit_options("abc123", "app", "latest", None).comment, "");
+    }
+
+    fn top(titles: &[&str], processes: &[&[&str]]) -> ContainerTopResponse {
+        let strings = |row: &[&str]| row.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        ContainerTopResponse {
+            titles: Some(strings(titles)),
+            processes: Some(processes.iter().map(|row| strings(row)).collect()),
+        }
+    }
+
+    #[test]
+    fn test_processes_from_ps_aux() {
+        let titles = [
+            "USER", "PID", "%CPU", "%MEM", "VSZ", "RSS", "TTY", "STAT", "START", "TIME", "COMMAND",
+        ];
+        let row = [
+            "root", "1", "0.5", "1.2", "10", "5", "?", "Ss", "10:00", "0:00",
+            "nginx -g daemon off;",
+        ];
+        let processes = processes_from_top(top(&titles, &[&row]));
+
+        assert_eq!(
+            processes,
+            vec![ProcessInfo {
+                pid: 1,
+                user: Some("root".to_string()),
+                command: "nginx -g daemon off;".to_string(),
+                cpu: Some(0.5),
+                mem: Some(1.2),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_processes_from_windows_top() {
+        let titles = ["Name", "PID", "CPU", "Private Working Set"];
+        let processes = processes_from_top(top(
+            &titles,
+            &[&["app.exe", "4312", "00:00:01.250", "12.3MB"], &["idle", "n/a", "", ""]],
+        ));
+
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].pid, 4312);
+        assert_eq!(processes[0].command, "app.exe");
+        assert_eq!(processes[0].user, None);
+        assert_eq!(processes[0].cpu, None);
+        assert_eq!(processes[0].mem, None);
+    }
+
+    #[test]
+    fn test_fs_change_from_bollard() {
+        let changes: Vec<FilesystemChange> = serde_json::from_str(
+            r#"[
+                {"Path": "/etc", "Kind": 0},
+                {"Path": "/etc/app.conf", "Kind": 1},
+                {"Path": "/tmp/x", "Kind": 2}
+            ]"#,
+        )
+        .unwrap();
+        let kinds: Vec<_> = changes
+            .into_iter()
+            .map(fs_change_from_bollard)
+            .map(|change| change.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![FsChangeKind::Modified, FsChangeKind::Added, FsChangeKind::Deleted]
+        );
+    }
+
+    #[test]
+    fn test_disk_usage_from_bollard() {
+        use bollard::service::{BuildCache, ImageSummary, Volume, VolumeUsageData};
+
+        let image = |size, shared_size, containers| ImageSummary {
+            size,
+            shared_size,
+            containers,
+            ..Default::default()
+        };
+        let container = |size_rw, state: &str| ContainerSummary {
+            size_rw: Some(size_rw),
+            state: Some(state.to_string()),
+            ..Default::default()
+        };
+        let volume = |size, ref_count| Volume {
+            usage_data: Some(VolumeUsageData { size, ref_count }),
+            ..Default::default()
+        };
+        let cache = |size, in_use| BuildCache {
+            size: Some(size),
+            in_use: Some(in_use),
+            shared: Some(false),
+            ..Default::default()
+        };
+
+        let usage = disk_usage_from_bollard(SystemDataUsageResponse {
+            layers_size: Some(1000),
+            images: Some(vec![image(700, 200, 1), image(500, 200, 0)]),
+            containers: Some(vec![container(30, "running"), container(20, "exited")]),
+            volumes: Some(vec![volume(400, 1), volume(100, 0), volume(-1, 0)]),
+            build_cache: Some(vec![cache(60, true), cache(40, false)]),
+        });
+
+        assert_eq!(
+            usage,
+            DiskUsage {
+                images_bytes: 1000,
+                containers_bytes: 50,
+                volumes_bytes: 500,
+                build_cache_bytes: 100,
+                // Unused image 300 + stopped container 20 + volume 100 + idle cache 40
+                reclaimable_bytes: 460,
+            }
+        );
+    }
+
+    #[test]
+    fn test_gpu_device_request() {
+        let all = gpu_device_request(GpuSpec::All);
+        assert_eq!(all.driver.as_deref(), Some("nvidia"));
+        assert_eq!(all.count, Some(-1));
+        assert_eq!(all.capabilities, Some(vec![vec!["gpu".to_string()]]));
+
+        assert_eq!(gpu_device_request(GpuSpec::Count(2)).count, Some(2));
+
+        let ids = gpu_device_request(GpuSpec::DeviceIds(vec!["GPU-3a1f".to_string()]));
+        assert_eq!(ids.count, None);
+        assert_eq!(ids.device_ids, Some(vec!["GPU-3a1f".to_string()]));
+    }
+
+    #[test]
+    fn test_mb_to_bytes() {
+        assert_eq!(mb_to_bytes(0), 0);
+        assert_eq!(mb_to_bytes(1), 1_048_576);
+        assert_eq!(mb_to_bytes(256), 268_435_456);
+    }
+
+    #[test]
+    fn test_split_tag() {
+        assert_eq!(split_tag("nginx:1.25"), ("nginx", "1.25"));
+        assert_eq!(split_tag("nginx"), ("nginx", "latest"));
+        assert_eq!(split_tag("registry:5000/team/app"), ("registry:5000/team/app", "latest"));
+        assert_eq!(split_tag("registry:5000/team/app:v2"), ("registry:5000/team/app", "v2"));
+    }
+
+    #[test]
+    fn test_is_auth_failure() {
+        assert!(is_auth_failure("unauthorized: authentication required"));
+        assert!(is_auth_failure("denied: requested access to the resource is denied"));
+        assert!(!is_auth_failure("dial tcp 10.0.0.1:443: i/o timeout"));
+    }
+
+    #[test]
+    fn test_runtime_error_from_bollard() {
+        let not_found = bollard::errors::Error::DockerResponseServerError {
+            status_code: 404,
+            message: "No such container: app".to_string(),
+        };
+        assert!(matches!(RuntimeError::from(not_found), RuntimeError::NotFound { .. }));
+
+        let timeout = RuntimeError::from(bollard::errors::Error::RequestTimeoutError);
+        assert!(matches!(timeout, RuntimeError::DaemonUnavailable { .. }));
+
+        let server = bollard::errors::Error::DockerResponseServerError {
+            status_code: 500,
+            message: "driver failed".to_string(),
+        };
+        assert!(matches!(RuntimeError::from(server), RuntimeError::Other(_)));
+    }
+
+    #[test]
+    fn test_rename_error() {
+        let conflict = bollard::errors::Error::DockerResponseServerError {
+            status_code: 409,
+            message: "Conflict. The container name \"/app\" is already in use".to_string(),
+        };
+        assert!(matches!(
+            rename_error("app", conflict),
+            RuntimeError::Conflict { ref name } if name == "app"
+        ));
+
+        let missing = bollard::errors::Error::DockerResponseServerError {
+            status_code: 404,
+            message: "No such container: app-green".to_string(),
+        };
+        assert!(matches!(rename_error("app", missing), RuntimeError::NotFound { .. }));
+    }
+
+    #[test]
+    fn test_is_missing_image() {
+        let not_found = |message: &str| bollard::errors::Error::DockerResponseServerError {
+            status_code: 404,
+            message: message.to_string(),
+        };
+        assert!(is_missing_image(&not_found("No such image: private/app:1.0")));
+        assert!(!is_missing_image(&not_found("network backend not found")));
+        assert!(!is_missing_image(&not_found("No such volume: pgdata")));
+
+        let server = bollard::errors::Error::DockerResponseServerError {
+            status_code: 500,
+            message: "No such image: private/app:1.0".to_string(),
+        };
+        assert!(!is_missing_image(&server));
+    }
+
+    #[test]
+    fn test_pull_error() {
+        let denied = bollard::errors::Error::DockerResponseServerError {
+            status_code: 500,
+            message: "pull access denied for private/app".to_string(),
+        };
+        assert!(matches!(
+            pull_error("private/app", denied),
+            RuntimeError::Unauthorized { .. }
+        ));
+
+        let missing = bollard::errors::Error::DockerStreamError {
+            error: "manifest for nginx:nope not found".to_string(),
+        };
+        assert!(matches!(
+            pull_error("nginx:nope", missing),
+            RuntimeError::ImagePullFailed { .. }
+        ));
+
+        let unreachable = pull_error("nginx", bollard::errors::Error::RequestTimeoutError);
+        assert!(matches!(unreachable, RuntimeError::DaemonUnavailable { .. }));
+    }
+
+    #[test]
+    fn test_network_from_bollard() {
+        use bollard::service::NetworkContainer;
+
+        let network = Network {
+            id: Some("3f2a".to_string()),
+            name: Some("syntra-network".to_string()),
+            driver: Some("overlay".to_string()),
+            scope: Some("swarm".to_string()),
+            containers: Some(HashMap::from([
+                ("c2".to_string(), NetworkContainer::default()),
+                ("c1".to_string(), NetworkContainer::default()),
+            ])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            network_from_bollard(network),
+            NetworkInfo {
+                id: "3f2a".to_string(),
+                name: "syntra-network".to_string(),
+                driver: "overlay".to_string(),
+                scope: "swarm".to_string(),
+                containers: vec!["c1".to_string(), "c2".to_string()],
+            }
+        );
+        assert_eq!(network_from_bollard(Network::default()).containers, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_network_ipam() {
+        assert_eq!(network_ipam(None, None).config, None);
+
+        let ipam = network_ipam(Some("10.20.0.0/24".to_string()), Some("10.20.0.1".to_string()));
+        let config = ipam.config.unwrap();
+        assert_eq!(config.len(), 1);
+        assert_eq!(config[0].subnet.as_deref(), Some("10.20.0.0/24"));
+        assert_eq!(config[0].gateway.as_deref(), Some("10.20.0.1"));
+    }
+}
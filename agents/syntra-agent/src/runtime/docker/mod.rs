@@ -3,5 +3,6 @@
 //! Provides Docker-specific implementation of the RuntimeAdapter trait.
 
 pub mod adapter;
+pub(crate) mod convert;
 
 pub use adapter::DockerAdapter;
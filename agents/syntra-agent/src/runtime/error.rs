@@ -0,0 +1,138 @@
+//! Runtime Errors
+//!
+//! Typed errors returned by every `RuntimeAdapter` method, so callers can
+//! tell a missing object from an unreachable daemon or a refused pull without
+//! matching on messages. `RuntimeError` converts into `anyhow::Error` with
+//! `?`, and can be recovered from one with `downcast_ref::<RuntimeError>()`.
+
+use thiserror::Error;
+
+/// Errors with a meaning callers may want to act on
+#[derive(Debug, Error)]
+pub enum RuntimeError {
+    /// The operation is not implemented by this runtime
+    #[error("{operation} is not supported by the {runtime} runtime")]
+    Unsupported {
+        runtime: &'static str,
+        operation: &'static str,
+    },
+
+    /// The requested container name is already taken
+    #[error("container name \"{name}\" is already in use")]
+    Conflict { name: String },
+
+    /// The referenced object does not exist
+    #[error("{kind} not found: {name}")]
+    NotFound { kind: &'static str, name: String },
+
+    /// The registry refused the credentials (or their absence) for an image
+    #[error("registry denied access to {image}: {message}")]
+    Unauthorized { image: String, message: String },
+
+    /// The runtime daemon could not be reached
+    #[error("container runtime unavailable: {message}")]
+    DaemonUnavailable { message: String },
+
+    /// An image could not be pulled for a reason other than authorization
+    #[error("failed to pull {image}: {message}")]
+    ImagePullFailed { image: String, message: String },
+
+    /// Any other failure
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl From<anyhow::Error> for RuntimeError {
+    /// Recover a `RuntimeError` that was passed through `anyhow`, or wrap the
+    /// error as `Other`
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<RuntimeError>() {
+            Ok(err) => err,
+            Err(err) => RuntimeError::Other(err),
+        }
+    }
+}
+
+impl RuntimeError {
+    /// Create an unsupported-operation error
+    pub fn unsupported(runtime: &'static str, operation: &'static str) -> Self {
+        RuntimeError::Unsupported { runtime, operation }
+    }
+
+    /// Create a name-conflict error
+    pub fn conflict(name: impl Into<String>) -> Self {
+        RuntimeError::Conflict { name: name.into() }
+    }
+
+    /// Create a not-found error for an object of the given kind
+    pub fn not_found(kind: &'static str, name: impl Into<String>) -> Self {
+        RuntimeError::NotFound {
+            kind,
+            name: name.into(),
+        }
+    }
+
+    /// Create a registry authorization error
+    pub fn unauthorized(image: impl Into<String>, message: impl Into<String>) -> Self {
+        RuntimeError::Unauthorized {
+            image: image.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Create an unreachable-daemon error
+    pub fn daemon_unavailable(message: impl Into<String>) -> Self {
+        RuntimeError::DaemonUnavailable {
+            message: message.into(),
+        }
+    }
+
+    /// Create an image pull error
+    pub fn image_pull_failed(image: impl Into<String>, message: impl Into<String>) -> Self {
+        RuntimeError::ImagePullFailed {
+            image: image.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Whether the same operation may succeed if simply tried again
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            RuntimeError::DaemonUnavailable { .. } | RuntimeError::ImagePullFailed { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsupported_downcast() {
+        let err: anyhow::Error = RuntimeError::unsupported("containerd", "exec").into();
+        assert_eq!(
+            err.to_string(),
+            "exec is not supported by the containerd runtime"
+        );
+        assert!(matches!(
+            err.downcast_ref::<RuntimeError>(),
+            Some(RuntimeError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_anyhow_round_trip_keeps_variant() {
+        let err: anyhow::Error = RuntimeError::not_found("container", "app").into();
+        assert!(matches!(
+            RuntimeError::from(err),
+            RuntimeError::NotFound { kind: "container", .. }
+        ));
+
+        let err = RuntimeError::from(anyhow::anyhow!("disk full"));
+        assert!(matches!(err, RuntimeError::Other(_)));
+        assert_eq!(err.to_string(), "disk full");
+        assert!(!err.is_transient());
+        assert!(RuntimeError::daemon_unavailable("connection refused").is_transient());
+    }
+}
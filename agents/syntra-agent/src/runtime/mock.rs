@@ -0,0 +1,775 @@
+//! Mock Runtime Adapter
+//!
+//! In-memory RuntimeAdapter for exercising agent logic in tests without a
+//! container runtime. It records every call and can be scripted to fail
+//! chosen methods or walk a container through a queue of states.
+//!
+//! Available to other crates with the `testing` feature.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use chrono::Utc;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::Notify;
+
+use crate::runtime::adapter::{
+    BuildImageOptions, ContainerHealth, ContainerInfo, ContainerStats, ContainerStatus,
+    CreateContainerOptions, CreateNetworkOptions, DiskUsage, EventStream, ExecOptions, ExecResult,
+    FsChange, ImageInfo, LogStream, LogsOptions, NetworkInfo, PortBinding, ProcessInfo,
+    PruneReport, RegistryAuth, RuntimeAdapter, RuntimeEvent, StatsStream, VolumeInfo,
+};
+use crate::runtime::error::RuntimeError;
+
+/// In-memory runtime adapter
+#[derive(Default)]
+pub struct MockRuntimeAdapter {
+    containers: Mutex<Vec<ContainerInfo>>,
+    next_id: Mutex<u64>,
+    exec_exit_code: Mutex<i64>,
+    /// Exit code containers report to `wait_container`
+    exit_code: Mutex<i64>,
+    /// Archives copied in, keyed by (container ID, path)
+    files: Mutex<HashMap<(String, String), Vec<u8>>>,
+    /// Events yielded by the next `events` call
+    events: Mutex<Vec<RuntimeEvent>>,
+    /// Whether `pull_image` blocks until cleared
+    pull_hangs: Mutex<bool>,
+    /// Wakes pulls blocked on `pull_hangs`
+    pull_released: Notify,
+    /// Images present locally, by reference
+    images: Mutex<Vec<String>>,
+    /// Every image `pull_image` was asked for, in order
+    pulls: Mutex<Vec<String>>,
+    /// Every image `push_image` was asked for, in order
+    pushes: Mutex<Vec<String>>,
+    /// Whether `push_image` fails as if the registry rejected the credentials
+    push_denied: Mutex<bool>,
+    /// Signals sent with `kill_container`, as (container ID, signal)
+    signals: Mutex<Vec<(String, String)>>,
+    /// Whether containers ignore signals other than SIGKILL
+    ignores_signals: Mutex<bool>,
+    /// Every trait method called, in order
+    calls: Mutex<Vec<&'static str>>,
+    /// Trait methods that fail
+    failing: Mutex<Vec<&'static str>>,
+    /// Containers `rename_container` refuses to rename, by current name
+    refused_renames: Mutex<Vec<String>>,
+    /// Default capabilities the runtime doesn't advertise
+    unsupported: Mutex<Vec<&'static str>>,
+    /// States `get_container` moves containers through, keyed by name
+    queued_statuses: Mutex<HashMap<String, VecDeque<ContainerStatus>>>,
+    /// Health `get_container` moves containers through, keyed by name
+    queued_health: Mutex<HashMap<String, VecDeque<ContainerHealth>>>,
+    /// Whether containers exit as soon as they're started
+    exits_on_start: Mutex<bool>,
+    /// Registry digests reported for images, keyed by reference
+    image_digests: Mutex<HashMap<String, String>>,
+    /// Networks and the containers attached to each
+    networks: Mutex<HashMap<String, Vec<String>>>,
+    /// Filesystem changes reported by `container_diff`, keyed by container ID
+    fs_changes: Mutex<HashMap<String, Vec<FsChange>>>,
+    /// Lines reported by `logs` and `logs_stream`, keyed by container ID
+    logs: Mutex<HashMap<String, Vec<String>>>,
+    /// Reported by `disk_usage`
+    disk_usage: Mutex<DiskUsage>,
+    /// Reported by `gpu_available`
+    gpu_available: Mutex<bool>,
+}
+
+impl MockRuntimeAdapter {
+    /// Create an empty mock runtime
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a running container and return its ID
+    pub fn add_running(&self, name: &str, image: &str) -> String {
+        let id = self.allocate_id();
+        self.containers.lock().push(ContainerInfo {
+            id: id.clone(),
+            name: name.to_string(),
+            image: image.to_string(),
+            status: ContainerStatus::Running,
+            health: None,
+            created_at: Utc::now().to_rfc3339(),
+            started_at: Some(Utc::now().to_rfc3339()),
+            finished_at: None,
+            ports: Vec::new(),
+            labels: HashMap::new(),
+        });
+        id
+    }
+
+    /// Set the exit code returned by `exec`
+    pub fn set_exec_exit_code(&self, code: i64) {
+        *self.exec_exit_code.lock() = code;
+    }
+
+    /// Set the exit code returned by `wait_container`
+    pub fn set_exit_code(&self, code: i64) {
+        *self.exit_code.lock() = code;
+    }
+
+    /// Make `pull_image` hang, as on a stalled registry, until cleared again
+    pub fn set_pull_hangs(&self, hangs: bool) {
+        *self.pull_hangs.lock() = hangs;
+        if !hangs {
+            self.pull_released.notify_waiters();
+        }
+    }
+
+    /// Mark `image` as present locally, as if pulled earlier
+    pub fn add_image(&self, image: &str) {
+        self.images.lock().push(image.to_string());
+    }
+
+    /// Images pulled so far, in order
+    pub fn pulls(&self) -> Vec<String> {
+        self.pulls.lock().clone()
+    }
+
+    /// Images pushed so far, in order
+    pub fn pushes(&self) -> Vec<String> {
+        self.pushes.lock().clone()
+    }
+
+    /// Make `push_image` fail with an authorization error
+    pub fn set_push_denied(&self, denied: bool) {
+        *self.push_denied.lock() = denied;
+    }
+
+    /// Signals sent so far, as (container ID, signal)
+    pub fn signals(&self) -> Vec<(String, String)> {
+        self.signals.lock().clone()
+    }
+
+    /// Make containers survive every signal but SIGKILL, like a hung process
+    pub fn set_ignores_signals(&self, ignores: bool) {
+        *self.ignores_signals.lock() = ignores;
+    }
+
+    /// Trait methods called so far, in order
+    pub fn calls(&self) -> Vec<&'static str> {
+        self.calls.lock().clone()
+    }
+
+    /// Make the trait method `operation` fail until cleared again
+    pub fn set_fails(&self, operation: &'static str, fails: bool) {
+        let mut failing = self.failing.lock();
+        failing.retain(|op| *op != operation);
+        if fails {
+            failing.push(operation);
+        }
+    }
+
+    /// Make `rename_container` fail for the container currently named `name`
+    pub fn refuse_rename(&self, name: &str) {
+        self.refused_renames.lock().push(name.to_string());
+    }
+
+    /// Stop advertising the default capability `capability`
+    pub fn set_unsupported(&self, capability: &'static str) {
+        self.unsupported.lock().push(capability);
+    }
+
+    /// Move container `name` through `statuses`, one per `get_container` call,
+    /// before it settles on the last one
+    pub fn queue_statuses(&self, name: &str, statuses: impl IntoIterator<Item = ContainerStatus>) {
+        self.queued_statuses
+            .lock()
+            .entry(name.to_string())
+            .or_default()
+            .extend(statuses);
+    }
+
+    /// Move container `name` through `health`, one per `get_container` call,
+    /// before it settles on the last one
+    pub fn queue_health(&self, name: &str, health: impl IntoIterator<Item = ContainerHealth>) {
+        self.queued_health
+            .lock()
+            .entry(name.to_string())
+            .or_default()
+            .extend(health);
+    }
+
+    /// Make containers exit straight after starting, like a crashing process
+    pub fn set_exits_on_start(&self, exits: bool) {
+        *self.exits_on_start.lock() = exits;
+    }
+
+    /// Report `digest` as the registry digest of `image`
+    pub fn set_image_digest(&self, image: &str, digest: &str) {
+        self.image_digests
+            .lock()
+            .insert(image.to_string(), digest.to_string());
+    }
+
+    /// Report `changes` as the filesystem diff of container `id`
+    pub fn set_fs_changes(&self, id: &str, changes: Vec<FsChange>) {
+        self.fs_changes.lock().insert(id.to_string(), changes);
+    }
+
+    /// Report `lines` as the logs of container `id`
+    pub fn set_logs(&self, id: &str, lines: &[&str]) {
+        let lines = lines.iter().map(|line| line.to_string()).collect();
+        self.logs.lock().insert(id.to_string(), lines);
+    }
+
+    /// Set whether `gpu_available` reports an NVIDIA runtime
+    pub fn set_gpu_available(&self, available: bool) {
+        *self.gpu_available.lock() = available;
+    }
+
+    /// Report `usage` from `disk_usage`
+    pub fn set_disk_usage(&self, usage: DiskUsage) {
+        *self.disk_usage.lock() = usage;
+    }
+
+    /// Queue an event for the next `events` stream
+    pub fn push_event(&self, event: RuntimeEvent) {
+        self.events.lock().push(event);
+    }
+
+    /// Containers attached to `network`, or `None` if it doesn't exist
+    pub fn network_members(&self, network: &str) -> Option<Vec<String>> {
+        self.networks.lock().get(network).cloned()
+    }
+
+    /// Snapshot every container, running or not
+    pub fn containers(&self) -> Vec<ContainerInfo> {
+        self.containers.lock().clone()
+    }
+
+    fn allocate_id(&self) -> String {
+        let mut next_id = self.next_id.lock();
+        *next_id += 1;
+        format!("mock-{:04}", *next_id)
+    }
+
+    /// Apply `f` to the container matching `id_or_name`
+    fn with_container<T>(
+        &self,
+        id_or_name: &str,
+        f: impl FnOnce(&mut ContainerInfo) -> T,
+    ) -> Result<T, RuntimeError> {
+        let mut containers = self.containers.lock();
+        containers
+            .iter_mut()
+            .find(|c| c.id == id_or_name || c.name == id_or_name)
+            .map(f)
+            .ok_or_else(|| RuntimeError::not_found("container", id_or_name))
+    }
+
+    /// The last `tail` lines set for container `id`, or all of them
+    fn log_tail(&self, id: &str, tail: Option<usize>) -> Vec<String> {
+        let logs = self.logs.lock();
+        let lines = logs.get(id).map(Vec::as_slice).unwrap_or_default();
+        let skip = tail.map_or(0, |tail| lines.len().saturating_sub(tail));
+        lines[skip..].to_vec()
+    }
+
+    /// Record a call to `operation`, failing if it was made to with `set_fails`
+    fn call(&self, operation: &'static str) -> Result<(), RuntimeError> {
+        self.calls.lock().push(operation);
+        if self.failing.lock().contains(&operation) {
+            return Err(RuntimeError::Other(anyhow!("{} failed", operation)));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RuntimeAdapter for MockRuntimeAdapter {
+    fn runtime_type(&self) -> &str {
+        "mock"
+    }
+
+    fn capabilities(&self) -> Vec<String> {
+        let unsupported = self.unsupported.lock();
+        ["logs", "exec", "build", "prune", "push", "rename"]
+            .iter()
+            .filter(|capability| !unsupported.contains(capability))
+            .map(|capability| capability.to_string())
+            .collect()
+    }
+
+    async fn health_check(&self) -> Result<bool, RuntimeError> {
+        self.call("health_check")?;
+        Ok(true)
+    }
+
+    async fn version(&self) -> Result<String, RuntimeError> {
+        self.call("version")?;
+        Ok("Mock 1.0".to_string())
+    }
+
+    async fn gpu_available(&self) -> Result<bool, RuntimeError> {
+        self.call("gpu_available")?;
+        Ok(*self.gpu_available.lock())
+    }
+
+    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, RuntimeError> {
+        self.call("list_containers")?;
+        Ok(self
+            .containers
+            .lock()
+            .iter()
+            .filter(|c| all || c.status == ContainerStatus::Running)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_container(&self, id_or_name: &str) -> Result<Option<ContainerInfo>, RuntimeError> {
+        self.call("get_container")?;
+        let queued = &self.queued_statuses;
+        let queued_health = &self.queued_health;
+        Ok(self
+            .with_container(id_or_name, |c| {
+                if let Some(status) = queued.lock().get_mut(&c.name).and_then(VecDeque::pop_front) {
+                    c.status = status;
+                }
+                if let Some(health) =
+                    queued_health.lock().get_mut(&c.name).and_then(VecDeque::pop_front)
+                {
+                    c.health = Some(health);
+                }
+                c.clone()
+            })
+            .ok())
+    }
+
+    async fn create_container(
+        &self,
+        options: CreateContainerOptions,
+    ) -> Result<String, RuntimeError> {
+        self.call("create_container")?;
+        if self.containers.lock().iter().any(|c| c.name == options.name) {
+            return Err(RuntimeError::conflict(options.name));
+        }
+
+        let id = self.allocate_id();
+        self.containers.lock().push(ContainerInfo {
+            id: id.clone(),
+            name: options.name,
+            image: options.image,
+            status: ContainerStatus::Created,
+            health: None,
+            created_at: Utc::now().to_rfc3339(),
+            started_at: None,
+            finished_at: None,
+            ports: options.ports,
+            labels: options.labels,
+        });
+
+        let mut networks = self.networks.lock();
+        for network in &options.networks {
+            if let Some(members) = networks.get_mut(network) {
+                members.push(id.clone());
+            }
+        }
+        Ok(id)
+    }
+
+    async fn start_container(&self, id: &str) -> Result<(), RuntimeError> {
+        self.call("start_container")?;
+        // Like Docker, refuse a host port another running container holds
+        let wanted = self.with_container(id, |c| c.ports.clone())?;
+        let conflicts = |held: &PortBinding| {
+            wanted.iter().any(|p| {
+                p.host_port.is_some_and(|port| port != 0)
+                    && p.host_port == held.host_port
+                    && p.protocol == held.protocol
+            })
+        };
+        let taken = self.containers.lock().iter().any(|c| {
+            c.status == ContainerStatus::Running
+                && c.id != id
+                && c.name != id
+                && c.ports.iter().any(&conflicts)
+        });
+        if taken {
+            return Err(RuntimeError::Other(anyhow!("port is already allocated")));
+        }
+        let status = if *self.exits_on_start.lock() {
+            ContainerStatus::Exited
+        } else {
+            ContainerStatus::Running
+        };
+        self.with_container(id, |c| {
+            c.status = status;
+            c.started_at = Some(Utc::now().to_rfc3339());
+        })
+    }
+
+    async fn stop_container(
+        &self,
+        id: &str,
+        _timeout_secs: Option<u64>,
+    ) -> Result<(), RuntimeError> {
+        self.call("stop_container")?;
+        self.with_container(id, |c| {
+            c.status = ContainerStatus::Exited;
+            c.finished_at = Some(Utc::now().to_rfc3339());
+        })
+    }
+
+    async fn kill_container(&self, id: &str, signal: &str) -> Result<(), RuntimeError> {
+        self.call("kill_container")?;
+        let exits = signal == "SIGKILL" || !*self.ignores_signals.lock();
+        let id = self.with_container(id, |c| {
+            if exits {
+                c.status = ContainerStatus::Exited;
+            }
+            c.id.clone()
+        })?;
+        self.signals.lock().push((id, signal.to_string()));
+        Ok(())
+    }
+
+    async fn wait_container(&self, id: &str) -> Result<i64, RuntimeError> {
+        self.call("wait_container")?;
+        // Containers run to completion as soon as they're waited on
+        self.with_container(id, |c| c.status = ContainerStatus::Exited)?;
+        Ok(*self.exit_code.lock())
+    }
+
+    async fn remove_container(&self, id: &str, force: bool) -> Result<(), RuntimeError> {
+        self.call("remove_container")?;
+        let mut containers = self.containers.lock();
+        let index = containers
+            .iter()
+            .position(|c| c.id == id || c.name == id)
+            .ok_or_else(|| RuntimeError::not_found("container", id))?;
+
+        if containers[index].status == ContainerStatus::Running && !force {
+            return Err(anyhow!("Cannot remove running container: {}", id).into());
+        }
+        containers.remove(index);
+        Ok(())
+    }
+
+    async fn rename_container(&self, id: &str, new_name: &str) -> Result<(), RuntimeError> {
+        self.call("rename_container")?;
+        let mut containers = self.containers.lock();
+        if containers.iter().any(|c| c.name == new_name && c.id != id) {
+            return Err(RuntimeError::conflict(new_name));
+        }
+
+        let container = containers
+            .iter_mut()
+            .find(|c| c.id == id || c.name == id)
+            .ok_or_else(|| RuntimeError::not_found("container", id))?;
+        if self.refused_renames.lock().contains(&container.name) {
+            return Err(RuntimeError::Other(anyhow!("renaming {} failed", container.name)));
+        }
+        container.name = new_name.to_string();
+        Ok(())
+    }
+
+    async fn logs(&self, id: &str, options: LogsOptions) -> Result<Vec<String>, RuntimeError> {
+        self.call("logs")?;
+        let id = self.with_container(id, |container| container.id.clone())?;
+        Ok(self.log_tail(&id, options.tail))
+    }
+
+    async fn logs_stream(
+        &self,
+        id: &str,
+        options: LogsOptions,
+    ) -> Result<LogStream, RuntimeError> {
+        self.call("logs_stream")?;
+        let id = self.with_container(id, |container| container.id.clone())?;
+        let lines = self.log_tail(&id, options.tail).into_iter().map(Ok::<_, RuntimeError>);
+        let lines = futures_util::stream::iter(lines);
+        // Following never ends on its own, as if the container kept running
+        if options.follow {
+            return Ok(Box::pin(lines.chain(futures_util::stream::pending())));
+        }
+        Ok(Box::pin(lines))
+    }
+
+    async fn stats(&self, id: &str) -> Result<ContainerStats, RuntimeError> {
+        self.call("stats")?;
+        self.with_container(id, |_| ContainerStats {
+            cpu_usage_percent: 0.0,
+            memory_usage_bytes: 0,
+            memory_limit_bytes: 0,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+            block_read_bytes: 0,
+            block_write_bytes: 0,
+        })
+    }
+
+    async fn stats_stream(&self, id: &str) -> Result<StatsStream, RuntimeError> {
+        self.call("stats_stream")?;
+        let stats = self.stats(id).await?;
+        Ok(Box::pin(futures_util::stream::once(async move { Ok(stats) })))
+    }
+
+    async fn pull_image(
+        &self,
+        image: &str,
+        _auth: Option<RegistryAuth>,
+    ) -> Result<(), RuntimeError> {
+        self.call("pull_image")?;
+        self.pulls.lock().push(image.to_string());
+        loop {
+            let released = self.pull_released.notified();
+            if !*self.pull_hangs.lock() {
+                break;
+            }
+            released.await;
+        }
+        self.images.lock().push(image.to_string());
+        Ok(())
+    }
+
+    async fn build_image(&self, options: BuildImageOptions) -> Result<String, RuntimeError> {
+        self.call("build_image")?;
+        Ok(format!("sha256:{}", options.tag))
+    }
+
+    async fn list_images(&self) -> Result<Vec<ImageInfo>, RuntimeError> {
+        self.call("list_images")?;
+        Ok(Vec::new())
+    }
+
+    async fn image_exists(&self, image: &str) -> Result<bool, RuntimeError> {
+        self.call("image_exists")?;
+        Ok(self.images.lock().iter().any(|i| i == image))
+    }
+
+    async fn image_digests(&self, image: &str) -> Result<Vec<String>, RuntimeError> {
+        self.call("image_digests")?;
+        // Images pulled by digest resolve to that digest unless overridden
+        let digest = self.image_digests.lock().get(image).cloned().or_else(|| {
+            image
+                .split_once('@')
+                .map(|(_, digest)| digest.to_string())
+        });
+        Ok(digest.into_iter().collect())
+    }
+
+    async fn remove_image(&self, _id: &str, _force: bool) -> Result<(), RuntimeError> {
+        self.call("remove_image")?;
+        Ok(())
+    }
+
+    async fn create_network(&self, options: CreateNetworkOptions) -> Result<String, RuntimeError> {
+        self.call("create_network")?;
+        self.networks.lock().entry(options.name.clone()).or_default();
+        Ok(options.name)
+    }
+
+    async fn remove_network(&self, name: &str) -> Result<(), RuntimeError> {
+        self.call("remove_network")?;
+        self.networks.lock().remove(name);
+        Ok(())
+    }
+
+    async fn list_networks(&self) -> Result<Vec<NetworkInfo>, RuntimeError> {
+        self.call("list_networks")?;
+        let networks = self.networks.lock();
+        Ok(networks.keys().map(|name| mock_network(name, Vec::new())).collect())
+    }
+
+    async fn inspect_network(&self, name: &str) -> Result<Option<NetworkInfo>, RuntimeError> {
+        self.call("inspect_network")?;
+        let networks = self.networks.lock();
+        Ok(networks
+            .get(name)
+            .map(|members| mock_network(name, members.clone())))
+    }
+
+    async fn connect_network(&self, container_id: &str, network: &str) -> Result<(), RuntimeError> {
+        self.call("connect_network")?;
+        self.networks
+            .lock()
+            .get_mut(network)
+            .ok_or_else(|| RuntimeError::not_found("network", network))?
+            .push(container_id.to_string());
+        Ok(())
+    }
+
+    async fn disconnect_network(
+        &self,
+        container_id: &str,
+        network: &str,
+    ) -> Result<(), RuntimeError> {
+        self.call("disconnect_network")?;
+        if let Some(members) = self.networks.lock().get_mut(network) {
+            members.retain(|id| id != container_id);
+        }
+        Ok(())
+    }
+
+    async fn create_volume(
+        &self,
+        name: &str,
+        _labels: HashMap<String, String>,
+    ) -> Result<String, RuntimeError> {
+        self.call("create_volume")?;
+        Ok(name.to_string())
+    }
+
+    async fn list_volumes(&self) -> Result<Vec<VolumeInfo>, RuntimeError> {
+        self.call("list_volumes")?;
+        Ok(Vec::new())
+    }
+
+    async fn remove_volume(&self, _name: &str, _force: bool) -> Result<(), RuntimeError> {
+        self.call("remove_volume")?;
+        Ok(())
+    }
+
+    async fn prune_containers(&self) -> Result<PruneReport, RuntimeError> {
+        self.call("prune_containers")?;
+        let mut containers = self.containers.lock();
+        let (running, stopped): (Vec<_>, Vec<_>) = containers
+            .drain(..)
+            .partition(|c| c.status == ContainerStatus::Running);
+        *containers = running;
+        Ok(PruneReport {
+            items_removed: stopped.into_iter().map(|c| c.id).collect(),
+            space_reclaimed_bytes: 0,
+        })
+    }
+
+    async fn prune_images(&self, _dangling_only: bool) -> Result<PruneReport, RuntimeError> {
+        self.call("prune_images")?;
+        Ok(PruneReport::default())
+    }
+
+    async fn prune_volumes(&self) -> Result<PruneReport, RuntimeError> {
+        self.call("prune_volumes")?;
+        Ok(PruneReport::default())
+    }
+
+    async fn copy_to_container(
+        &self,
+        id: &str,
+        dest_path: &str,
+        tar_data: Vec<u8>,
+    ) -> Result<(), RuntimeError> {
+        self.call("copy_to_container")?;
+        let id = self.with_container(id, |c| c.id.clone())?;
+        self.files.lock().insert((id, dest_path.to_string()), tar_data);
+        Ok(())
+    }
+
+    async fn copy_from_container(&self, id: &str, src_path: &str) -> Result<Vec<u8>, RuntimeError> {
+        self.call("copy_from_container")?;
+        let id = self.with_container(id, |c| c.id.clone())?;
+        self.files
+            .lock()
+            .get(&(id.clone(), src_path.to_string()))
+            .cloned()
+            .ok_or_else(|| RuntimeError::not_found("path", format!("{}:{}", id, src_path)))
+    }
+
+    async fn events(&self) -> Result<EventStream, RuntimeError> {
+        self.call("events")?;
+        let events: Vec<_> = self.events.lock().drain(..).map(Ok).collect();
+        Ok(Box::pin(futures_util::stream::iter(events)))
+    }
+
+    async fn exec(&self, id: &str, _options: ExecOptions) -> Result<ExecResult, RuntimeError> {
+        self.call("exec")?;
+        let exit_code = *self.exec_exit_code.lock();
+        self.with_container(id, |_| ExecResult {
+            exit_code,
+            ..Default::default()
+        })
+    }
+
+    async fn top(&self, id: &str) -> Result<Vec<ProcessInfo>, RuntimeError> {
+        self.call("top")?;
+        self.with_container(id, |container| {
+            vec![ProcessInfo {
+                pid: 1,
+                user: Some("root".to_string()),
+                command: container.image.clone(),
+                cpu: Some(0.0),
+                mem: Some(0.0),
+            }]
+        })
+    }
+
+    async fn container_diff(&self, id: &str) -> Result<Vec<FsChange>, RuntimeError> {
+        self.call("container_diff")?;
+        let id = self.with_container(id, |container| container.id.clone())?;
+        Ok(self.fs_changes.lock().get(&id).cloned().unwrap_or_default())
+    }
+
+    async fn disk_usage(&self) -> Result<DiskUsage, RuntimeError> {
+        self.call("disk_usage")?;
+        Ok(*self.disk_usage.lock())
+    }
+
+    async fn commit_container(
+        &self,
+        id: &str,
+        repo: &str,
+        tag: &str,
+        _message: Option<String>,
+    ) -> Result<String, RuntimeError> {
+        self.call("commit_container")?;
+        self.with_container(id, |_| format!("sha256:{}-{}", repo, tag))
+    }
+
+    async fn tag_image(&self, _source: &str, repo: &str, tag: &str) -> Result<(), RuntimeError> {
+        self.call("tag_image")?;
+        self.images.lock().push(format!("{}:{}", repo, tag));
+        Ok(())
+    }
+
+    async fn push_image(
+        &self,
+        image: &str,
+        _auth: Option<RegistryAuth>,
+    ) -> Result<(), RuntimeError> {
+        self.call("push_image")?;
+        if *self.push_denied.lock() {
+            return Err(RuntimeError::unauthorized(image, "authentication required"));
+        }
+        self.pushes.lock().push(image.to_string());
+        Ok(())
+    }
+}
+
+/// A local bridge network named `name`, which is also its ID
+fn mock_network(name: &str, containers: Vec<String>) -> NetworkInfo {
+    NetworkInfo {
+        id: name.to_string(),
+        name: name.to_string(),
+        driver: "bridge".to_string(),
+        scope: "local".to_string(),
+        containers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_copy_round_trip_and_missing_path() {
+        let runtime = MockRuntimeAdapter::new();
+        let id = runtime.add_running("app", "nginx:latest");
+
+        runtime
+            .copy_to_container(&id, "/etc/app", b"archive".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            runtime.copy_from_container(&id, "/etc/app").await.unwrap(),
+            b"archive".to_vec()
+        );
+
+        let err = runtime.copy_from_container(&id, "/missing").await.unwrap_err();
+        assert!(matches!(err, RuntimeError::NotFound { kind: "path", .. }));
+    }
+}
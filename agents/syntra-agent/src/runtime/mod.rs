@@ -4,4 +4,9 @@
 //! (Docker, containerd, Podman, etc.) through a common RuntimeAdapter trait.
 
 pub mod adapter;
+pub mod containerd;
 pub mod docker;
+pub mod error;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
+pub mod podman;
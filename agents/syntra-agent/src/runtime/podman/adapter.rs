@@ -0,0 +1,290 @@
+//! Podman Adapter
+//!
+//! Implementation of RuntimeAdapter for Podman. Podman serves a
+//! Docker-compatible API, so container operations are delegated to a
+//! DockerAdapter connected to the Podman socket.
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::runtime::adapter::{
+    BuildImageOptions, ContainerInfo, ContainerStats, CreateContainerOptions, CreateNetworkOptions,
+    DiskUsage, EventStream, ExecOptions, ExecResult, FsChange, ImageInfo, LogStream, LogsOptions,
+    NetworkInfo, ProcessInfo, PruneReport, RegistryAuth, RuntimeAdapter, StatsStream, VolumeInfo,
+};
+use crate::runtime::docker::DockerAdapter;
+use crate::runtime::error::RuntimeError;
+
+/// Rootful Podman API socket
+const ROOTFUL_SOCKET: &str = "/run/podman/podman.sock";
+
+/// Podman runtime adapter
+pub struct PodmanAdapter {
+    inner: DockerAdapter,
+    socket_path: String,
+}
+
+impl PodmanAdapter {
+    /// Create a new Podman adapter connecting to the detected socket
+    pub fn new() -> Result<Self> {
+        match Self::detect_socket() {
+            Some(socket_path) => Self::with_socket(&socket_path.to_string_lossy()),
+            None => bail!(
+                "Podman socket not found; enable it with `systemctl enable --now podman.socket`"
+            ),
+        }
+    }
+
+    /// Create a new Podman adapter with a custom socket path
+    pub fn with_socket(socket_path: &str) -> Result<Self> {
+        Ok(Self {
+            inner: DockerAdapter::with_socket(socket_path)?,
+            socket_path: socket_path.to_string(),
+        })
+    }
+
+    /// Set registry credentials used for pulls that don't provide their own
+    pub fn with_default_registry_auth(mut self, auth: Option<RegistryAuth>) -> Self {
+        self.inner = self.inner.with_default_registry_auth(auth);
+        self
+    }
+
+    /// Get the socket path this adapter is connected to
+    pub fn socket_path(&self) -> &str {
+        &self.socket_path
+    }
+
+    /// Find the Podman socket, preferring the rootful socket over the user one
+    fn detect_socket() -> Option<PathBuf> {
+        let rootless = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(|dir| Path::new(&dir).join("podman").join("podman.sock"));
+
+        std::iter::once(PathBuf::from(ROOTFUL_SOCKET))
+            .chain(rootless)
+            .find(|path| path.exists())
+    }
+}
+
+#[async_trait]
+impl RuntimeAdapter for PodmanAdapter {
+    fn runtime_type(&self) -> &str {
+        "podman"
+    }
+
+    async fn health_check(&self) -> Result<bool, RuntimeError> {
+        self.inner.health_check().await
+    }
+
+    async fn version(&self) -> Result<String, RuntimeError> {
+        let version = self.inner.client().version().await?;
+        Ok(format!(
+            "Podman {} (API {})",
+            version.version.unwrap_or_default(),
+            version.api_version.unwrap_or_default()
+        ))
+    }
+
+    async fn gpu_available(&self) -> Result<bool, RuntimeError> {
+        self.inner.gpu_available().await
+    }
+
+    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, RuntimeError> {
+        self.inner.list_containers(all).await
+    }
+
+    async fn get_container(&self, id_or_name: &str) -> Result<Option<ContainerInfo>, RuntimeError> {
+        self.inner.get_container(id_or_name).await
+    }
+
+    async fn create_container(
+        &self,
+        options: CreateContainerOptions,
+    ) -> Result<String, RuntimeError> {
+        self.inner.create_container(options).await
+    }
+
+    async fn start_container(&self, id: &str) -> Result<(), RuntimeError> {
+        self.inner.start_container(id).await
+    }
+
+    async fn stop_container(
+        &self,
+        id: &str,
+        timeout_secs: Option<u64>,
+    ) -> Result<(), RuntimeError> {
+        self.inner.stop_container(id, timeout_secs).await
+    }
+
+    async fn kill_container(&self, id: &str, signal: &str) -> Result<(), RuntimeError> {
+        self.inner.kill_container(id, signal).await
+    }
+
+    async fn wait_container(&self, id: &str) -> Result<i64, RuntimeError> {
+        self.inner.wait_container(id).await
+    }
+
+    async fn remove_container(&self, id: &str, force: bool) -> Result<(), RuntimeError> {
+        self.inner.remove_container(id, force).await
+    }
+
+    async fn rename_container(&self, id: &str, new_name: &str) -> Result<(), RuntimeError> {
+        self.inner.rename_container(id, new_name).await
+    }
+
+    async fn logs(&self, id: &str, options: LogsOptions) -> Result<Vec<String>, RuntimeError> {
+        self.inner.logs(id, options).await
+    }
+
+    async fn logs_stream(&self, id: &str, options: LogsOptions) -> Result<LogStream, RuntimeError> {
+        self.inner.logs_stream(id, options).await
+    }
+
+    async fn stats(&self, id: &str) -> Result<ContainerStats, RuntimeError> {
+        self.inner.stats(id).await
+    }
+
+    async fn stats_stream(&self, id: &str) -> Result<StatsStream, RuntimeError> {
+        self.inner.stats_stream(id).await
+    }
+
+    async fn pull_image(
+        &self,
+        image: &str,
+        auth: Option<RegistryAuth>,
+    ) -> Result<(), RuntimeError> {
+        self.inner.pull_image(image, auth).await
+    }
+
+    async fn build_image(&self, options: BuildImageOptions) -> Result<String, RuntimeError> {
+        self.inner.build_image(options).await
+    }
+
+    async fn list_images(&self) -> Result<Vec<ImageInfo>, RuntimeError> {
+        self.inner.list_images().await
+    }
+
+    async fn image_exists(&self, image: &str) -> Result<bool, RuntimeError> {
+        self.inner.image_exists(image).await
+    }
+
+    async fn image_digests(&self, image: &str) -> Result<Vec<String>, RuntimeError> {
+        self.inner.image_digests(image).await
+    }
+
+    async fn remove_image(&self, id: &str, force: bool) -> Result<(), RuntimeError> {
+        self.inner.remove_image(id, force).await
+    }
+
+    async fn create_network(&self, options: CreateNetworkOptions) -> Result<String, RuntimeError> {
+        self.inner.create_network(options).await
+    }
+
+    async fn remove_network(&self, name: &str) -> Result<(), RuntimeError> {
+        self.inner.remove_network(name).await
+    }
+
+    async fn list_networks(&self) -> Result<Vec<NetworkInfo>, RuntimeError> {
+        self.inner.list_networks().await
+    }
+
+    async fn inspect_network(&self, name: &str) -> Result<Option<NetworkInfo>, RuntimeError> {
+        self.inner.inspect_network(name).await
+    }
+
+    async fn connect_network(&self, container_id: &str, network: &str) -> Result<(), RuntimeError> {
+        self.inner.connect_network(container_id, network).await
+    }
+
+    async fn disconnect_network(
+        &self,
+        container_id: &str,
+        network: &str,
+    ) -> Result<(), RuntimeError> {
+        self.inner.disconnect_network(container_id, network).await
+    }
+
+    async fn create_volume(
+        &self,
+        name: &str,
+        labels: HashMap<String, String>,
+    ) -> Result<String, RuntimeError> {
+        self.inner.create_volume(name, labels).await
+    }
+
+    async fn list_volumes(&self) -> Result<Vec<VolumeInfo>, RuntimeError> {
+        self.inner.list_volumes().await
+    }
+
+    async fn remove_volume(&self, name: &str, force: bool) -> Result<(), RuntimeError> {
+        self.inner.remove_volume(name, force).await
+    }
+
+    async fn prune_containers(&self) -> Result<PruneReport, RuntimeError> {
+        self.inner.prune_containers().await
+    }
+
+    async fn prune_images(&self, dangling_only: bool) -> Result<PruneReport, RuntimeError> {
+        self.inner.prune_images(dangling_only).await
+    }
+
+    async fn prune_volumes(&self) -> Result<PruneReport, RuntimeError> {
+        self.inner.prune_volumes().await
+    }
+
+    async fn copy_to_container(
+        &self,
+        id: &str,
+        dest_path: &str,
+        tar_data: Vec<u8>,
+    ) -> Result<(), RuntimeError> {
+        self.inner.copy_to_container(id, dest_path, tar_data).await
+    }
+
+    async fn copy_from_container(&self, id: &str, src_path: &str) -> Result<Vec<u8>, RuntimeError> {
+        self.inner.copy_from_container(id, src_path).await
+    }
+
+    async fn events(&self) -> Result<EventStream, RuntimeError> {
+        self.inner.events().await
+    }
+
+    async fn exec(&self, id: &str, options: ExecOptions) -> Result<ExecResult, RuntimeError> {
+        self.inner.exec(id, options).await
+    }
+
+    async fn top(&self, id: &str) -> Result<Vec<ProcessInfo>, RuntimeError> {
+        self.inner.top(id).await
+    }
+
+    async fn container_diff(&self, id: &str) -> Result<Vec<FsChange>, RuntimeError> {
+        self.inner.container_diff(id).await
+    }
+
+    async fn disk_usage(&self) -> Result<DiskUsage, RuntimeError> {
+        self.inner.disk_usage().await
+    }
+
+    async fn commit_container(
+        &self,
+        id: &str,
+        repo: &str,
+        tag: &str,
+        message: Option<String>,
+    ) -> Result<String, RuntimeError> {
+        self.inner.commit_container(id, repo, tag, message).await
+    }
+
+    async fn tag_image(&self, source: &str, repo: &str, tag: &str) -> Result<(), RuntimeError> {
+        self.inner.tag_image(source, repo, tag).await
+    }
+
+    async fn push_image(
+        &self,
+        image: &str,
+        auth: Option<RegistryAuth>,
+    ) -> Result<(), RuntimeError> {
+        self.inner.push_image(image, auth).await
+    }
+}
@@ -0,0 +1,7 @@
+//! Podman Runtime Module
+//!
+//! Provides Podman support through its Docker-compatible API socket.
+
+pub mod adapter;
+
+pub use adapter::PodmanAdapter;
@@ -21,6 +21,12 @@ import {
   handleDisconnect,
 } from './handlers';
 
+/**
+ * Wire protocol versions of the Rust agent this control plane understands
+ */
+const MIN_PROTOCOL_VERSION = 1;
+const MAX_PROTOCOL_VERSION = 1;
+
 /**
  * AgentHub manages WebSocket connections from all agents
  */
@@ -99,6 +105,10 @@ class AgentHub {
         return;
       }
 
+      if (isRustAgent && !this.checkProtocolVersion(ws, message.payload)) {
+        return;
+      }
+
       const payload = isRustAgent
         ? this.convertRustHello(message.payload)
         : message.payload as AgentHelloPayload;
@@ -129,6 +139,38 @@ class AgentHub {
     }
   }
 
+  /**
+   * Send VersionMismatch to a Rust agent speaking an unsupported protocol
+   * version. Agents older than the supported range are rejected and
+   * disconnected; newer ones are only warned. Agents from before versioning
+   * send no version and are accepted as before.
+   */
+  private checkProtocolVersion(ws: WebSocket, payload: any): boolean {
+    const version = payload?.protocol_version;
+    if (typeof version !== 'number') {
+      return true;
+    }
+    if (version >= MIN_PROTOCOL_VERSION && version <= MAX_PROTOCOL_VERSION) {
+      return true;
+    }
+
+    const rejected = version < MIN_PROTOCOL_VERSION;
+    ws.send(JSON.stringify({
+      type: 'VersionMismatch',
+      payload: {
+        agent_version: version,
+        min_version: MIN_PROTOCOL_VERSION,
+        max_version: MAX_PROTOCOL_VERSION,
+        rejected,
+        timestamp: new Date().toISOString(),
+      },
+    }));
+    if (rejected) {
+      ws.close(4004, 'Unsupported protocol version');
+    }
+    return !rejected;
+  }
+
   private convertRustHello(payload: any): AgentHelloPayload {
     return {
       agent_id: payload.agent_id,
@@ -67,6 +67,7 @@ export interface RustHeartbeatPayload {
   container_count: number;
   cpu_usage: number;
   memory_usage: number;
+  draining?: boolean;
 }
 
 export interface WebSocketMessage {
@@ -2,18 +2,58 @@
 //!
 //! HTTP client for communicating with the Syntra control plane API.
 
-use anyhow::{bail, Context, Result};
-use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use std::time::{Duration, Instant};
 
 use crate::config::Config;
 
+/// Upper bound on the time spent establishing a connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most pages a paginated GET will follow, even with no item limit
+const MAX_PAGES: usize = 100;
+
 #[derive(Debug, Deserialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<ApiError>,
+    #[serde(default)]
+    pub pagination: Option<Pagination>,
+}
+
+/// Pagination metadata on list responses
+///
+/// The API uses either cursors or page numbers; whichever is present is followed.
+#[derive(Debug, Default, Deserialize)]
+pub struct Pagination {
+    pub next_cursor: Option<String>,
+    pub page: Option<u32>,
+    pub total_pages: Option<u32>,
+}
+
+impl Pagination {
+    /// Query parameter requesting the page after this one, if there is one
+    fn next_query(&self) -> Option<(&'static str, String)> {
+        if let Some(cursor) = &self.next_cursor {
+            return Some(("cursor", cursor.clone()));
+        }
+        match (self.page, self.total_pages) {
+            (Some(page), Some(total)) if page < total => Some(("page", (page + 1).to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Items gathered from a paginated GET
+#[derive(Debug)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    /// Whether more items were available than were fetched
+    pub truncated: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,9 +62,18 @@ pub struct ApiError {
     pub message: String,
 }
 
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
 pub struct ApiClient {
     client: reqwest::Client,
     base_url: String,
+    timeout: Duration,
 }
 
 impl ApiClient {
@@ -32,9 +81,19 @@ impl ApiClient {
     pub fn from_config() -> Result<Self> {
         let config = Config::load()?;
         let base_url = config.api_url().to_string();
-        let token = config
-            .token
-            .context("Not logged in. Run `syntra login` first.")?;
+        let timeout = config.timeout();
+        let token = match config.token {
+            Some(token) => token,
+            None if config.token_in_keyring => bail!(
+                "Could not read the API token from the OS keyring. \
+                 Run `syntra login` again, or `syntra login --plaintext` without a keyring."
+            ),
+            None if config.profile.is_some() => bail!(
+                "Not logged in to profile {0}. Run `syntra login --profile {0}` first.",
+                config.profile_name()
+            ),
+            None => bail!("Not logged in. Run `syntra login` first."),
+        };
 
         let mut headers = HeaderMap::new();
         headers.insert(
@@ -43,37 +102,83 @@ impl ApiClient {
         );
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
+        // The overall timeout is applied per request so streams can stay open
         let client = reqwest::Client::builder()
             .default_headers(headers)
+            .connect_timeout(timeout.min(CONNECT_TIMEOUT))
             .build()?;
 
         Ok(Self {
             client,
             base_url,
+            timeout,
         })
     }
 
     /// GET request
     pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
         let url = format!("{}/api/v1{}", self.base_url, path);
+        self.execute(self.client.get(&url), &url).await
+    }
+
+    /// GET every page of a list, stopping after `limit` items if set
+    pub async fn get_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        limit: Option<usize>,
+    ) -> Result<Paginated<T>> {
+        let url = format!("{}/api/v1{}", self.base_url, path);
+        let mut items: Vec<T> = Vec::new();
+        let mut next_query = None;
+
+        for _ in 0..MAX_PAGES {
+            let mut request = self.client.get(&url);
+            if let Some(query) = &next_query {
+                request = request.query(&[query]);
+            }
+            let response: ApiResponse<Vec<T>> = self.send(request, &url).await?;
+            items.extend(response.data.unwrap_or_default());
+
+            next_query = response.pagination.and_then(|p| p.next_query());
+            if next_query.is_none() {
+                break;
+            }
+            if limit.is_some_and(|limit| items.len() >= limit) {
+                break;
+            }
+        }
+
+        let mut truncated = next_query.is_some();
+        if let Some(limit) = limit {
+            if items.len() > limit {
+                items.truncate(limit);
+                truncated = true;
+            }
+        }
+
+        Ok(Paginated { items, truncated })
+    }
+
+    /// Open a server-sent events stream
+    ///
+    /// The response body is left unread for the caller to consume chunk by chunk.
+    pub async fn stream(&self, path: &str) -> Result<reqwest::Response> {
+        let url = format!("{}/api/v1{}", self.base_url, path);
+        let started_at = Instant::now();
         let response = self
             .client
             .get(&url)
+            .header(ACCEPT, "text/event-stream")
             .send()
             .await
-            .with_context(|| format!("Failed to connect to {}", url))?;
+            .map_err(|e| request_error(e, &url, started_at))?;
 
         let status = response.status();
-        let body: ApiResponse<T> = response.json().await?;
-
-        if !body.success {
-            if let Some(err) = body.error {
-                bail!("[{}] {}", err.code, err.message);
-            }
-            bail!("API request failed with status {}", status);
+        if !status.is_success() {
+            bail!("Stream request failed with status {}", status);
         }
 
-        body.data.context("Empty response from API")
+        Ok(response)
     }
 
     /// POST request
@@ -83,25 +188,7 @@ impl ApiClient {
         body: &B,
     ) -> Result<T> {
         let url = format!("{}/api/v1{}", self.base_url, path);
-        let response = self
-            .client
-            .post(&url)
-            .json(body)
-            .send()
-            .await
-            .with_context(|| format!("Failed to connect to {}", url))?;
-
-        let status = response.status();
-        let body: ApiResponse<T> = response.json().await?;
-
-        if !body.success {
-            if let Some(err) = body.error {
-                bail!("[{}] {}", err.code, err.message);
-            }
-            bail!("API request failed with status {}", status);
-        }
-
-        body.data.context("Empty response from API")
+        self.execute(self.client.post(&url).json(body), &url).await
     }
 
     /// PATCH request
@@ -111,47 +198,67 @@ impl ApiClient {
         body: &B,
     ) -> Result<T> {
         let url = format!("{}/api/v1{}", self.base_url, path);
-        let response = self
-            .client
-            .patch(&url)
-            .json(body)
-            .send()
-            .await
-            .with_context(|| format!("Failed to connect to {}", url))?;
-
-        let status = response.status();
-        let body: ApiResponse<T> = response.json().await?;
-
-        if !body.success {
-            if let Some(err) = body.error {
-                bail!("[{}] {}", err.code, err.message);
-            }
-            bail!("API request failed with status {}", status);
-        }
-
-        body.data.context("Empty response from API")
+        self.execute(self.client.patch(&url).json(body), &url).await
     }
 
     /// DELETE request
     pub async fn delete<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
         let url = format!("{}/api/v1{}", self.base_url, path);
-        let response = self
-            .client
-            .delete(&url)
+        self.execute(self.client.delete(&url), &url).await
+    }
+
+    /// Send a request and unwrap the API response data
+    async fn execute<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+        url: &str,
+    ) -> Result<T> {
+        let body: ApiResponse<T> = self.send(request, url).await?;
+        body.data.context("Empty response from API")
+    }
+
+    /// Send a request with the overall timeout, failing on an API error
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+        url: &str,
+    ) -> Result<ApiResponse<T>> {
+        let started_at = Instant::now();
+        let response = request
+            .timeout(self.timeout)
             .send()
             .await
-            .with_context(|| format!("Failed to connect to {}", url))?;
+            .map_err(|e| request_error(e, url, started_at))?;
 
         let status = response.status();
-        let body: ApiResponse<T> = response.json().await?;
+        let body: ApiResponse<T> = response.json().await.map_err(|e| {
+            if e.is_timeout() {
+                request_error(e, url, started_at)
+            } else {
+                e.into()
+            }
+        })?;
 
         if !body.success {
             if let Some(err) = body.error {
-                bail!("[{}] {}", err.code, err.message);
+                return Err(err.into());
             }
             bail!("API request failed with status {}", status);
         }
 
-        body.data.context("Empty response from API")
+        Ok(body)
+    }
+}
+
+/// Describe a failed request, naming the URL and how long it ran on timeout
+pub fn request_error(error: reqwest::Error, url: &str, started_at: Instant) -> anyhow::Error {
+    if error.is_timeout() {
+        anyhow!(
+            "Request to {} timed out after {:.1}s",
+            url,
+            started_at.elapsed().as_secs_f64()
+        )
+    } else {
+        anyhow::Error::new(error).context(format!("Failed to connect to {}", url))
     }
 }
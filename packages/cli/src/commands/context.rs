@@ -8,6 +8,11 @@ use crate::config::Config;
 pub enum ContextCommands {
     /// Show current context
     Current,
+    /// Switch to a profile from ~/.syntra/config.toml
+    Use {
+        /// Profile name (`default` for the top-level settings)
+        profile: String,
+    },
     /// Set default organization
     SetOrg {
         /// Organization ID
@@ -27,6 +32,7 @@ pub async fn run(cmd: ContextCommands) -> Result<()> {
         ContextCommands::Current => {
             let config = Config::load()?;
             println!("{}", "Current Context:".bold());
+            println!("  Profile:    {}", config.profile_name().cyan());
             println!(
                 "  API URL:    {}",
                 config.api_url().cyan()
@@ -57,6 +63,15 @@ pub async fn run(cmd: ContextCommands) -> Result<()> {
             );
         }
 
+        ContextCommands::Use { profile } => {
+            Config::use_profile(&profile)?;
+            println!(
+                "{} Switched to profile {}",
+                "✓".green().bold(),
+                profile.cyan()
+            );
+        }
+
         ContextCommands::SetOrg { org_id } => {
             let mut config = Config::load()?;
             config.default_org_id = Some(org_id.clone());
@@ -2,8 +2,16 @@ use anyhow::Result;
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 use crate::api::ApiClient;
+use crate::commands::logs::{print_entry, LogEntry};
+
+/// How often `deploy-status --watch` polls the deployment
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Deployment statuses that won't change again
+const TERMINAL_STATUSES: &[&str] = &["running", "failed", "cancelled"];
 
 #[derive(Debug, Serialize)]
 struct DeployRequest {
@@ -20,12 +28,20 @@ enum DeploySource {
     Image { image: String },
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Deployment {
     pub id: String,
     pub status: String,
     pub created_at: String,
+    /// Branch, tag, or commit a git deployment was built from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Whether this is the deployment the service is currently running
+    #[serde(default)]
+    pub is_active: bool,
 }
 
 /// Deploy a service
@@ -51,12 +67,7 @@ pub async fn run(service_id: &str, branch: Option<String>, image: Option<String>
         .post(&format!("/services/{}/deployments", service_id), &request)
         .await?;
 
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
-            .template("{spinner:.blue} {msg}")?,
-    );
+    let spinner = spinner()?;
     spinner.set_message(format!("Deployment {} started", deployment.id));
     spinner.finish_with_message(format!(
         "{} Deployment {} created (status: {})",
@@ -67,10 +78,91 @@ pub async fn run(service_id: &str, branch: Option<String>, image: Option<String>
 
     println!();
     println!(
-        "  Track progress: {} deploy status {}",
+        "  Track progress: {} deploy-status --watch {}",
         "syntra".dimmed(),
         deployment.id
     );
 
     Ok(())
 }
+
+/// Show a deployment's status, polling until it settles when `watch` is set
+pub async fn status(deployment_id: &str, watch: bool, logs: bool) -> Result<()> {
+    let api = ApiClient::from_config()?;
+    let path = format!("/deployments/{}", deployment_id);
+
+    let deployment: Deployment = api.get(&path).await?;
+    if !watch {
+        println!("Deployment {} is {}", deployment.id, format_status(&deployment.status));
+        if logs {
+            for entry in &fetch_logs(&api, deployment_id).await? {
+                print_entry(entry);
+            }
+        }
+        return Ok(());
+    }
+
+    let spinner = spinner()?;
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    let mut current = deployment.status;
+    let mut logs_seen = 0;
+    spinner.println(format!("  {}", format_status(&current)));
+
+    loop {
+        spinner.set_message(format!("Deployment {} is {}", deployment_id, current));
+
+        if logs {
+            let entries = fetch_logs(&api, deployment_id).await?;
+            for entry in entries.iter().skip(logs_seen) {
+                spinner.suspend(|| print_entry(entry));
+            }
+            logs_seen = logs_seen.max(entries.len());
+        }
+
+        if TERMINAL_STATUSES.contains(&current.as_str()) {
+            break;
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let deployment: Deployment = api.get(&path).await?;
+        if deployment.status != current {
+            current = deployment.status;
+            spinner.println(format!("  → {}", format_status(&current)));
+        }
+    }
+
+    let mark = if current == "running" {
+        "✓".green().bold()
+    } else {
+        "✗".red().bold()
+    };
+    spinner.finish_with_message(format!("{} Deployment {} {}", mark, deployment_id, current));
+
+    Ok(())
+}
+
+/// Fetch the build logs recorded so far for a deployment
+async fn fetch_logs(api: &ApiClient, deployment_id: &str) -> Result<Vec<LogEntry>> {
+    api.get(&format!("/deployments/{}/logs", deployment_id)).await
+}
+
+/// Color a deployment status by outcome
+pub fn format_status(status: &str) -> colored::ColoredString {
+    match status {
+        "running" => status.green(),
+        "failed" | "cancelled" => status.red(),
+        "queued" => status.dimmed(),
+        _ => status.yellow(),
+    }
+}
+
+/// Spinner in the style shared by deploy commands
+fn spinner() -> Result<ProgressBar> {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
+            .template("{spinner:.blue} {msg}")?,
+    );
+    Ok(spinner)
+}
@@ -1,23 +1,26 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use clap::Subcommand;
-use colored::Colorize;
+use colored::{ColoredString, Colorize};
 use serde::{Deserialize, Serialize};
 
 use crate::api::ApiClient;
+use crate::output::OutputFormat;
+use crate::select;
 
 #[derive(Subcommand)]
 pub enum DomainsCommands {
     /// List domains for a service
     List {
-        /// Service ID
+        /// Service ID (picked from a menu when omitted)
         #[arg(short, long)]
-        service_id: String,
+        service_id: Option<String>,
     },
     /// Add a domain to a service
     Add {
-        /// Service ID
+        /// Service ID (picked from a menu when omitted)
         #[arg(short, long)]
-        service_id: String,
+        service_id: Option<String>,
         /// Domain name (e.g., app.example.com)
         #[arg(short, long)]
         domain: String,
@@ -32,9 +35,25 @@ pub enum DomainsCommands {
         /// Domain ID
         domain_id: String,
     },
+    /// Show a domain's SSL certificate
+    Ssl {
+        /// Domain ID
+        domain_id: String,
+    },
+    /// Reissue a domain's SSL certificate
+    RenewSsl {
+        /// Domain ID
+        domain_id: String,
+    },
 }
 
-#[derive(Debug, Deserialize)]
+/// Days before expiry at which a certificate is shown in red
+const EXPIRY_CRITICAL_DAYS: i64 = 14;
+
+/// Days before expiry at which a certificate is shown in yellow
+const EXPIRY_WARNING_DAYS: i64 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 struct Domain {
     id: String,
@@ -46,6 +65,19 @@ struct Domain {
     verification_token: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct SslCertificate {
+    domain: String,
+    status: String,
+    issuer: Option<String>,
+    issued_at: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    san: Vec<String>,
+    /// Why the last issuance failed, if it did
+    error: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct DomainList {
@@ -65,15 +97,20 @@ struct GenericResponse {
     verified: Option<bool>,
 }
 
-pub async fn run(cmd: DomainsCommands) -> Result<()> {
+pub async fn run(cmd: DomainsCommands, output: OutputFormat) -> Result<()> {
     let api = ApiClient::from_config()?;
 
     match cmd {
         DomainsCommands::List { service_id } => {
+            let service_id = select::service_id(service_id).await?;
             let result: Vec<Domain> = api
                 .get(&format!("/services/{}/domains", service_id))
                 .await?;
 
+            if output.print(&result)? {
+                return Ok(());
+            }
+
             if result.is_empty() {
                 println!("{}", "No domains configured.".dimmed());
                 return Ok(());
@@ -126,6 +163,7 @@ pub async fn run(cmd: DomainsCommands) -> Result<()> {
             service_id,
             domain,
         } => {
+            let service_id = select::service_id(service_id).await?;
             let request = AddDomainRequest {
                 service_id,
                 domain: domain.clone(),
@@ -162,7 +200,68 @@ pub async fn run(cmd: DomainsCommands) -> Result<()> {
                 .await?;
             println!("{} Domain verification initiated", "✓".green().bold());
         }
+
+        DomainsCommands::Ssl { domain_id } => {
+            let cert: SslCertificate = api.get(&format!("/domains/{}/ssl", domain_id)).await?;
+
+            if output.print(&cert)? {
+                return Ok(());
+            }
+
+            let status = match cert.status.as_str() {
+                "active" | "issued" => cert.status.green(),
+                "pending" | "renewing" => cert.status.yellow(),
+                _ => cert.status.red(),
+            };
+            println!("{} {}", "SSL Certificate:".bold(), cert.domain.cyan());
+            println!("  Status:  {}", status);
+            println!("  Issuer:  {}", cert.issuer.as_deref().unwrap_or("-"));
+            if let Some(issued_at) = cert.issued_at {
+                println!("  Issued:  {}", issued_at.format("%Y-%m-%d %H:%M UTC"));
+            }
+            if let Some(expires_at) = cert.expires_at {
+                println!("  Expires: {}", expiry(expires_at, Utc::now()));
+            }
+            if !cert.san.is_empty() {
+                println!("  SANs:");
+                for name in &cert.san {
+                    println!("    {}", name);
+                }
+            }
+            if let Some(error) = &cert.error {
+                println!("  {} {}", "Error:".red().bold(), error);
+            }
+        }
+
+        DomainsCommands::RenewSsl { domain_id } => {
+            let cert: SslCertificate = api
+                .post(&format!("/domains/{}/ssl/renew", domain_id), &())
+                .await?;
+            println!(
+                "{} Certificate renewal for {} requested (status: {})",
+                "✓".green().bold(),
+                cert.domain.cyan(),
+                cert.status
+            );
+        }
     }
 
     Ok(())
 }
+
+/// Expiry date and days left, colored by how soon it is
+fn expiry(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> ColoredString {
+    let days = (expires_at - now).num_days();
+    let text = if expires_at <= now {
+        format!("{} (expired)", expires_at.format("%Y-%m-%d"))
+    } else {
+        format!("{} ({} days left)", expires_at.format("%Y-%m-%d"), days)
+    };
+    if days < EXPIRY_CRITICAL_DAYS {
+        text.red()
+    } else if days < EXPIRY_WARNING_DAYS {
+        text.yellow()
+    } else {
+        text.green()
+    }
+}
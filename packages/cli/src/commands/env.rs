@@ -1,24 +1,30 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::Subcommand;
 use colored::Colorize;
+use dialoguer::Confirm;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::OpenOptions;
+use std::io::Write;
 
 use crate::api::ApiClient;
+use crate::commands::secrets;
+use crate::output::OutputFormat;
+use crate::select;
 
 #[derive(Subcommand)]
 pub enum EnvCommands {
     /// List environment variables
     List {
-        /// Service ID
+        /// Service ID (picked from a menu when omitted)
         #[arg(short, long)]
-        service_id: String,
+        service_id: Option<String>,
     },
     /// Set an environment variable
     Set {
-        /// Service ID
+        /// Service ID (picked from a menu when omitted)
         #[arg(short, long)]
-        service_id: String,
+        service_id: Option<String>,
         /// Variable key
         #[arg(short, long)]
         key: String,
@@ -28,25 +34,43 @@ pub enum EnvCommands {
     },
     /// Delete an environment variable
     Delete {
-        /// Service ID
+        /// Service ID (picked from a menu when omitted)
         #[arg(short, long)]
-        service_id: String,
+        service_id: Option<String>,
         /// Variable key
         #[arg(short, long)]
         key: String,
     },
     /// Import environment variables from a .env file
     BulkImport {
-        /// Service ID
+        /// Service ID (picked from a menu when omitted)
         #[arg(short, long)]
-        service_id: String,
+        service_id: Option<String>,
         /// Path to .env file
         #[arg(short, long)]
         file: String,
     },
+    /// Export environment variables to a .env file
+    Export {
+        /// Service ID (picked from a menu when omitted)
+        #[arg(short, long)]
+        service_id: Option<String>,
+        /// Path to write the .env file to
+        #[arg(short, long)]
+        file: String,
+        /// Also export secret values, after confirming
+        #[arg(long)]
+        include_secrets: bool,
+        /// Skip the confirmation prompt for secrets
+        #[arg(short, long)]
+        yes: bool,
+        /// Overwrite the file if it exists
+        #[arg(long)]
+        force: bool,
+    },
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct EnvVars {
     pub env_vars: HashMap<String, String>,
@@ -69,13 +93,18 @@ struct GenericResponse {
     success: Option<bool>,
 }
 
-pub async fn run(cmd: EnvCommands) -> Result<()> {
+pub async fn run(cmd: EnvCommands, output: OutputFormat) -> Result<()> {
     let api = ApiClient::from_config()?;
 
     match cmd {
         EnvCommands::List { service_id } => {
+            let service_id = select::service_id(service_id).await?;
             let vars: EnvVars = api.get(&format!("/services/{}/env", service_id)).await?;
 
+            if output.print(&vars)? {
+                return Ok(());
+            }
+
             if vars.env_vars.is_empty() {
                 println!("{}", "No environment variables set.".dimmed());
                 return Ok(());
@@ -95,6 +124,7 @@ pub async fn run(cmd: EnvCommands) -> Result<()> {
             key,
             value,
         } => {
+            let service_id = select::service_id(service_id).await?;
             let request = SetEnvRequest {
                 key: key.clone(),
                 value,
@@ -106,6 +136,7 @@ pub async fn run(cmd: EnvCommands) -> Result<()> {
         }
 
         EnvCommands::Delete { service_id, key } => {
+            let service_id = select::service_id(service_id).await?;
             let _: GenericResponse = api
                 .delete(&format!("/services/{}/env/{}", service_id, key))
                 .await?;
@@ -113,6 +144,7 @@ pub async fn run(cmd: EnvCommands) -> Result<()> {
         }
 
         EnvCommands::BulkImport { service_id, file } => {
+            let service_id = select::service_id(service_id).await?;
             let content = std::fs::read_to_string(&file)
                 .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file, e))?;
 
@@ -144,7 +176,110 @@ pub async fn run(cmd: EnvCommands) -> Result<()> {
                 file.dimmed()
             );
         }
+
+        EnvCommands::Export {
+            service_id,
+            file,
+            include_secrets,
+            yes,
+            force,
+        } => {
+            let service_id = select::service_id(service_id).await?;
+            if !force && std::path::Path::new(&file).exists() {
+                bail!("{} already exists. Use --force to overwrite it.", file);
+            }
+
+            let vars: EnvVars = api.get(&format!("/services/{}/env", service_id)).await?;
+            let mut env_vars: BTreeMap<String, String> = vars.env_vars.into_iter().collect();
+
+            // Secrets come back masked, so they're either revealed or left out
+            let secret_keys = secrets::secret_keys(&api, &service_id).await?;
+            for key in &secret_keys {
+                env_vars.remove(key);
+            }
+            let secrets_exported = if include_secrets && !secret_keys.is_empty() {
+                let confirmed = yes
+                    || Confirm::new()
+                        .with_prompt(format!(
+                            "Write {} secret values to {} in plain text?",
+                            secret_keys.len(),
+                            file
+                        ))
+                        .default(false)
+                        .interact()?;
+                if !confirmed {
+                    println!("{} Export cancelled", "✗".yellow().bold());
+                    return Ok(());
+                }
+                let count = secret_keys.len();
+                for key in secret_keys {
+                    let value = secrets::reveal(&api, &service_id, &key).await?;
+                    env_vars.insert(key, value);
+                }
+                count
+            } else {
+                0
+            };
+
+            let content: String = env_vars
+                .iter()
+                .map(|(key, value)| format!("{}={}\n", key, dotenv_quote(value)))
+                .collect();
+            write_private(&file, &content, force)
+                .with_context(|| format!("Failed to write {}", file))?;
+
+            println!(
+                "{} Exported {} variables ({} secrets) to {}",
+                "✓".green().bold(),
+                env_vars.len(),
+                secrets_exported,
+                file.dimmed()
+            );
+        }
     }
 
     Ok(())
 }
+
+/// Quote a .env value if it has spaces or characters a .env parser or shell
+/// would treat specially
+fn dotenv_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.,:/@+%".contains(c));
+    if plain {
+        return value.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' | '\\' | '$' | '`' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Write a file only its owner can read, since it may hold secrets
+fn write_private(path: &str, content: &str, overwrite: bool) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content.as_bytes())
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+use crate::api::ApiClient;
+
+#[derive(Debug, Serialize)]
+struct ExecRequest {
+    command: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    container_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExecResponse {
+    container_id: Option<String>,
+    #[serde(default)]
+    stdout: String,
+    #[serde(default)]
+    stderr: String,
+    exit_code: i32,
+}
+
+/// Run a one-off command in a service container, returning its exit code
+///
+/// Without `container`, the control plane picks one of the service's replicas.
+pub async fn run(service_id: &str, container: Option<String>, command: Vec<String>) -> Result<i32> {
+    let api = ApiClient::from_config()?;
+
+    eprintln!(
+        "{} Running {} in service {}...",
+        "→".blue().bold(),
+        command.join(" ").cyan(),
+        service_id.dimmed()
+    );
+
+    let request = ExecRequest {
+        command,
+        container_id: container,
+    };
+    let result: ExecResponse = api
+        .post(&format!("/services/{}/exec", service_id), &request)
+        .await?;
+
+    print!("{}", result.stdout);
+    std::io::stdout().flush()?;
+    eprint!("{}", result.stderr);
+
+    let container = result.container_id.as_deref().unwrap_or("unknown container");
+    if result.exit_code == 0 {
+        eprintln!("{} Exited with code 0 ({})", "✓".green().bold(), container.dimmed());
+    } else {
+        eprintln!(
+            "{} Exited with code {} ({})",
+            "✗".red().bold(),
+            result.exit_code,
+            container.dimmed()
+        );
+    }
+
+    Ok(result.exit_code)
+}
@@ -1,11 +1,35 @@
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use colored::Colorize;
 use dialoguer::Password;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
 
+use crate::api::{request_error, ApiResponse};
 use crate::config::Config;
 
+/// How long to wait for the user to finish logging in through the browser
+const BROWSER_LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Page shown in the browser once the callback has been received
+const CALLBACK_PAGE: &str = "<html><body><h2>Syntra CLI login complete</h2>\
+    <p>You can close this window and return to the terminal.</p></body></html>";
+
+#[derive(Debug, Serialize)]
+struct TokenExchangeRequest<'a> {
+    code: &'a str,
+    redirect_uri: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenExchangeResponse {
+    token: String,
+}
+
 /// Handle the login command
-pub async fn run(api_url: Option<String>) -> Result<()> {
+pub async fn run(api_url: Option<String>, browser: bool, plaintext: bool) -> Result<()> {
     println!("{}", "Syntra Login".bold());
     println!();
 
@@ -15,28 +39,33 @@ pub async fn run(api_url: Option<String>) -> Result<()> {
         config.api_url = Some(url);
     }
 
-    let token: String = Password::new()
-        .with_prompt("API Token")
-        .interact()?;
-
-    if token.is_empty() {
-        bail!("Token cannot be empty");
-    }
+    let browser_token = if browser {
+        browser_login(&config).await?
+    } else {
+        None
+    };
+    let token = match browser_token {
+        Some(token) => token,
+        None => prompt_token()?,
+    };
 
     // Verify token by making a test request
-    let client = reqwest::Client::new();
+    let client = reqwest::Client::builder().timeout(config.timeout()).build()?;
     let base = config.api_url();
+    let url = format!("{}/api/v1/health", base);
+    let started_at = Instant::now();
     let resp = client
-        .get(format!("{}/api/v1/health", base))
+        .get(&url)
         .header("Authorization", format!("Bearer {}", token))
         .send()
-        .await?;
+        .await
+        .map_err(|e| request_error(e, &url, started_at))?;
 
     if !resp.status().is_success() {
         bail!("Invalid token or cannot reach API at {}", base);
     }
 
-    config.token = Some(token);
+    config.set_token(token, plaintext)?;
     config.save()?;
 
     println!();
@@ -49,6 +78,162 @@ pub async fn run(api_url: Option<String>) -> Result<()> {
         "  Config saved to {}",
         Config::path()?.display().to_string().dimmed()
     );
+    if config.profile.is_some() {
+        println!("  Profile: {}", config.profile_name().cyan());
+    }
+    if config.token_in_keyring {
+        println!("  Token stored in the OS keyring");
+    }
 
     Ok(())
 }
+
+/// Ask the user to paste an API token
+fn prompt_token() -> Result<String> {
+    let token: String = Password::new()
+        .with_prompt("API Token")
+        .interact()?;
+
+    if token.is_empty() {
+        bail!("Token cannot be empty");
+    }
+
+    Ok(token)
+}
+
+/// Log in through the browser, returning `None` if no browser could be opened
+async fn browser_login(config: &Config) -> Result<Option<String>> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("Failed to start local login listener")?;
+    let redirect_uri = format!("http://{}/callback", listener.local_addr()?);
+    let state = uuid::Uuid::new_v4().to_string();
+
+    let mut authorize_url = Url::parse(&format!("{}/cli/authorize", config.api_url()))
+        .context("Invalid API URL")?;
+    authorize_url
+        .query_pairs_mut()
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("state", &state);
+
+    if let Err(e) = open::that(authorize_url.as_str()) {
+        println!(
+            "{} Could not open a browser ({}), falling back to token login",
+            "!".yellow().bold(),
+            e
+        );
+        return Ok(None);
+    }
+
+    println!("{} Opened your browser to log in", "→".blue().bold());
+    println!("  If it did not open, visit:");
+    println!("  {}", authorize_url.as_str().cyan());
+    println!();
+    println!("{}", "Waiting for login to complete...".dimmed());
+
+    let code = tokio::time::timeout(BROWSER_LOGIN_TIMEOUT, wait_for_code(&listener, &state))
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "Timed out after {}s waiting for browser login. Run `syntra login` to paste a \
+                 token instead.",
+                BROWSER_LOGIN_TIMEOUT.as_secs()
+            )
+        })??;
+
+    exchange_code(config, &code, &redirect_uri).await.map(Some)
+}
+
+/// Accept callback requests until one carries an authorization code
+async fn wait_for_code(listener: &TcpListener, state: &str) -> Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let Some(params) = read_callback(&mut stream).await? else {
+            // Favicon and other stray requests
+            respond(&mut stream, "404 Not Found", "").await;
+            continue;
+        };
+
+        let param = |key: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        };
+
+        if let Some(error) = param("error") {
+            respond(&mut stream, "400 Bad Request", "Login was not completed.").await;
+            bail!("Browser login failed: {}", error);
+        }
+        if param("state").as_deref() != Some(state) {
+            respond(&mut stream, "400 Bad Request", "Login state did not match.").await;
+            bail!("Browser login returned a mismatched state, please try again");
+        }
+        let Some(code) = param("code") else {
+            respond(&mut stream, "400 Bad Request", "Missing authorization code.").await;
+            bail!("Browser login did not return an authorization code");
+        };
+
+        respond(&mut stream, "200 OK", CALLBACK_PAGE).await;
+        return Ok(code);
+    }
+}
+
+/// Read a request, returning its query parameters if it targets `/callback`
+async fn read_callback(stream: &mut TcpStream) -> Result<Option<Vec<(String, String)>>> {
+    let mut buf = vec![0u8; 8192];
+    let read = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..read]);
+
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or_default();
+    let url = Url::parse(&format!("http://localhost{}", target))?;
+    if url.path() != "/callback" {
+        return Ok(None);
+    }
+
+    Ok(Some(url.query_pairs().into_owned().collect()))
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+/// Exchange an authorization code for an API token
+async fn exchange_code(config: &Config, code: &str, redirect_uri: &str) -> Result<String> {
+    let client = reqwest::Client::builder().timeout(config.timeout()).build()?;
+    let url = format!("{}/api/v1/auth/cli/token", config.api_url());
+    let started_at = Instant::now();
+    let resp = client
+        .post(&url)
+        .json(&TokenExchangeRequest { code, redirect_uri })
+        .send()
+        .await
+        .map_err(|e| request_error(e, &url, started_at))?;
+
+    let body: ApiResponse<TokenExchangeResponse> = resp
+        .json()
+        .await
+        .context("Failed to parse token exchange response")?;
+    if !body.success {
+        let message = body
+            .error
+            .map(|e| e.message)
+            .unwrap_or_else(|| "unknown error".to_string());
+        bail!("Failed to exchange login code: {}", message);
+    }
+
+    body.data
+        .map(|data| data.token)
+        .context("Token exchange response did not include a token")
+}
@@ -1,9 +1,13 @@
 use anyhow::Result;
 use colored::Colorize;
 use serde::Deserialize;
+use std::time::Duration;
 
 use crate::api::ApiClient;
 
+/// Delay before reconnecting after the log stream drops
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 pub struct LogEntry {
@@ -24,35 +28,83 @@ pub async fn run(service_id: &str, lines: usize, follow: bool) -> Result<()> {
         ))
         .await?;
 
-    if logs.is_empty() {
+    if logs.is_empty() && !follow {
         println!("{}", "No logs found.".dimmed());
         return Ok(());
     }
 
     for entry in &logs {
-        let level_color = match entry.level.as_str() {
-            "error" | "fatal" => entry.level.red().bold(),
-            "warn" => entry.level.yellow(),
-            "info" => entry.level.green(),
-            "debug" => entry.level.dimmed(),
-            _ => entry.level.normal(),
-        };
-
-        let ts = &entry.timestamp[..19]; // Trim to seconds
-        println!(
-            "{} {} {}",
-            ts.dimmed(),
-            format!("[{}]", level_color).bold(),
-            entry.message
-        );
+        print_entry(entry);
     }
 
     if follow {
-        println!();
-        println!(
-            "{}",
-            "Live log streaming not yet implemented. Use --no-follow for now.".yellow()
-        );
+        follow_logs(&api, service_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Print a log entry with its level colored
+pub fn print_entry(entry: &LogEntry) {
+    let level_color = match entry.level.as_str() {
+        "error" | "fatal" => entry.level.red().bold(),
+        "warn" => entry.level.yellow(),
+        "info" => entry.level.green(),
+        "debug" => entry.level.dimmed(),
+        _ => entry.level.normal(),
+    };
+
+    let ts = entry.timestamp.get(..19).unwrap_or(&entry.timestamp); // Trim to seconds
+    println!(
+        "{} {} {}",
+        ts.dimmed(),
+        format!("[{}]", level_color).bold(),
+        entry.message
+    );
+}
+
+/// Stream new log entries until Ctrl-C, reconnecting whenever the stream drops
+async fn follow_logs(api: &ApiClient, service_id: &str) -> Result<()> {
+    let path = format!("/logs/stream?service_id={}", service_id);
+
+    loop {
+        tokio::select! {
+            result = stream_logs(api, &path) => match result {
+                Ok(()) => eprintln!("{}", "Log stream closed, reconnecting...".yellow()),
+                Err(e) => {
+                    let message = format!("Log stream dropped ({}), reconnecting...", e);
+                    eprintln!("{}", message.yellow());
+                }
+            },
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Print entries from one server-sent events connection until it ends
+async fn stream_logs(api: &ApiClient, path: &str) -> Result<()> {
+    let mut response = api.stream(path).await?;
+    let mut buffer: Vec<u8> = Vec::new();
+
+    while let Some(chunk) = response.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+
+            // Only `data:` lines carry entries; comments and keep-alives are skipped
+            if let Some(data) = line.trim_end().strip_prefix("data:") {
+                if let Ok(entry) = serde_json::from_str::<LogEntry>(data.trim()) {
+                    print_entry(&entry);
+                }
+            }
+        }
     }
 
     Ok(())
@@ -2,6 +2,7 @@ pub mod context;
 pub mod deploy;
 pub mod domains;
 pub mod env;
+pub mod exec;
 pub mod login;
 pub mod logs;
 pub mod projects;
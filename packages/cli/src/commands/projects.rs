@@ -1,10 +1,11 @@
 use anyhow::Result;
 use colored::Colorize;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::api::ApiClient;
+use crate::output::{print_truncated, OutputFormat};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Project {
     pub id: String,
@@ -14,10 +15,18 @@ pub struct Project {
     pub created_at: String,
 }
 
-/// List projects
-pub async fn list() -> Result<()> {
+/// List projects, fetching at most `limit` unless it's `None`
+pub async fn list(limit: Option<usize>, output: OutputFormat) -> Result<()> {
     let api = ApiClient::from_config()?;
-    let projects: Vec<Project> = api.get("/projects").await?;
+    let page = api.get_all::<Project>("/projects", limit).await?;
+    let projects = page.items;
+    if page.truncated {
+        print_truncated(projects.len(), "projects");
+    }
+
+    if output.print(&projects)? {
+        return Ok(());
+    }
 
     if projects.is_empty() {
         println!("{}", "No projects found.".dimmed());
@@ -1,8 +1,29 @@
 use anyhow::Result;
+use clap::Subcommand;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 
 use crate::api::ApiClient;
+use crate::commands::deploy::{format_status, Deployment};
+use crate::output::{print_truncated, OutputFormat};
+use crate::select;
+
+#[derive(Subcommand)]
+pub enum RollbackCommands {
+    /// List a service's deployments to roll back to
+    List {
+        /// Service ID (picked from a menu when omitted)
+        service_id: Option<String>,
+
+        /// Maximum number of deployments to fetch
+        #[arg(long, default_value = "20", conflicts_with = "all")]
+        limit: usize,
+
+        /// Fetch every deployment
+        #[arg(long)]
+        all: bool,
+    },
+}
 
 #[derive(Debug, Serialize)]
 struct RollbackRequest {
@@ -18,6 +39,87 @@ struct RollbackResponse {
     rollback_from_id: Option<String>,
 }
 
+pub async fn run_command(cmd: RollbackCommands, output: OutputFormat) -> Result<()> {
+    match cmd {
+        RollbackCommands::List {
+            service_id,
+            limit,
+            all,
+        } => {
+            let service_id = select::service_id(service_id).await?;
+            list(&service_id, (!all).then_some(limit), output).await
+        }
+    }
+}
+
+/// List a service's deployments, newest first, marking the active one
+async fn list(service_id: &str, limit: Option<usize>, output: OutputFormat) -> Result<()> {
+    let api = ApiClient::from_config()?;
+    let page = api
+        .get_all::<Deployment>(&format!("/services/{}/deployments", service_id), limit)
+        .await?;
+    let deployments = page.items;
+    if page.truncated {
+        print_truncated(deployments.len(), "deployments");
+    }
+
+    if output.print(&deployments)? {
+        return Ok(());
+    }
+
+    if deployments.is_empty() {
+        println!("{}", "No deployments found.".dimmed());
+        return Ok(());
+    }
+
+    // Without an explicit flag, the newest running deployment is the active one
+    let active = deployments
+        .iter()
+        .position(|d| d.is_active)
+        .or_else(|| deployments.iter().position(|d| d.status == "running"));
+
+    println!("{}", "Deployments".bold());
+    println!("{}", "─".repeat(90));
+    println!(
+        "    {:<38} {:<12} {:<22} {}",
+        "ID".dimmed(),
+        "STATUS".dimmed(),
+        "CREATED".dimmed(),
+        "SOURCE".dimmed(),
+    );
+    println!("{}", "─".repeat(90));
+
+    for (index, deployment) in deployments.iter().enumerate() {
+        let marker = if Some(index) == active {
+            "*".green().bold()
+        } else {
+            " ".normal()
+        };
+        let source = deployment
+            .image
+            .as_deref()
+            .or(deployment.git_ref.as_deref())
+            .unwrap_or("-");
+        println!(
+            "  {} {:<38} {:<12} {:<22} {}",
+            marker,
+            deployment.id,
+            format_status(&deployment.status),
+            deployment.created_at,
+            source,
+        );
+    }
+
+    println!();
+    println!(
+        "{} deployment(s); {} marks the active one",
+        deployments.len(),
+        "*".green().bold()
+    );
+
+    Ok(())
+}
+
 /// Rollback a service to a previous deployment
 pub async fn run(service_id: &str, to_deployment: Option<String>) -> Result<()> {
     let api = ApiClient::from_config()?;
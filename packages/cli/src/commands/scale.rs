@@ -1,5 +1,6 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use colored::Colorize;
+use dialoguer::Confirm;
 use serde::{Deserialize, Serialize};
 
 use crate::api::ApiClient;
@@ -9,6 +10,26 @@ struct ScaleRequest {
     replicas: u32,
 }
 
+#[derive(Debug, Serialize)]
+struct AutoscaleRequest {
+    enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_replicas: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_replicas: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_target_percent: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AutoscalePolicy {
+    enabled: bool,
+    min_replicas: Option<u32>,
+    max_replicas: Option<u32>,
+    cpu_target_percent: Option<u32>,
+    replicas: Option<u32>,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct ScaleResponse {
@@ -18,7 +39,24 @@ struct ScaleResponse {
 }
 
 /// Scale a service to the specified number of replicas
-pub async fn run(service_id: &str, replicas: u32) -> Result<()> {
+///
+/// Scaling to 0 stops the service, so it asks for confirmation unless `yes` is set.
+pub async fn run(service_id: &str, replicas: u32, yes: bool) -> Result<()> {
+    if replicas == 0 && !yes {
+        let confirmed = Confirm::new()
+            .with_prompt(format!(
+                "Scaling service {} to 0 replicas will stop it. Continue?",
+                service_id
+            ))
+            .default(false)
+            .interact()?;
+
+        if !confirmed {
+            println!("{} Scale cancelled", "✗".yellow().bold());
+            return Ok(());
+        }
+    }
+
     let api = ApiClient::from_config()?;
 
     println!(
@@ -42,3 +80,64 @@ pub async fn run(service_id: &str, replicas: u32) -> Result<()> {
 
     Ok(())
 }
+
+/// Let the control plane scale a service between `min` and `max` replicas
+/// to hold its average CPU usage at `cpu_target` percent
+pub async fn autoscale(service_id: &str, min: u32, max: u32, cpu_target: u32) -> Result<()> {
+    if min == 0 || min > max {
+        bail!("--min must be at least 1 and no more than --max");
+    }
+    if !(1..=100).contains(&cpu_target) {
+        bail!("--cpu-target must be a percentage between 1 and 100");
+    }
+
+    let request = AutoscaleRequest {
+        enabled: true,
+        min_replicas: Some(min),
+        max_replicas: Some(max),
+        cpu_target_percent: Some(cpu_target),
+    };
+    let policy = update_autoscale(service_id, &request).await?;
+    println!("{} Autoscaling enabled", "✓".green().bold());
+    print_policy(&policy);
+    Ok(())
+}
+
+/// Turn off autoscaling, leaving the service at its current replica count
+pub async fn disable_autoscale(service_id: &str) -> Result<()> {
+    let request = AutoscaleRequest {
+        enabled: false,
+        min_replicas: None,
+        max_replicas: None,
+        cpu_target_percent: None,
+    };
+    let policy = update_autoscale(service_id, &request).await?;
+    println!("{} Autoscaling disabled", "✓".green().bold());
+    print_policy(&policy);
+    Ok(())
+}
+
+async fn update_autoscale(service_id: &str, request: &AutoscaleRequest) -> Result<AutoscalePolicy> {
+    let api = ApiClient::from_config()?;
+    api.patch(&format!("/services/{}/autoscale", service_id), request)
+        .await
+}
+
+fn print_policy(policy: &AutoscalePolicy) {
+    let or_dash = |value: Option<u32>| value.map_or_else(|| "-".to_string(), |v| v.to_string());
+    let mode = if policy.enabled {
+        "autoscale".green()
+    } else {
+        "manual".yellow()
+    };
+    println!("  Mode:       {}", mode);
+    if policy.enabled {
+        println!(
+            "  Replicas:   {} to {}",
+            or_dash(policy.min_replicas),
+            or_dash(policy.max_replicas)
+        );
+        println!("  CPU target: {}%", or_dash(policy.cpu_target_percent));
+    }
+    println!("  Current:    {}", or_dash(policy.replicas));
+}
@@ -1,49 +1,76 @@
-use anyhow::Result;
-use clap::Subcommand;
+use anyhow::{Context, Result};
+use clap::{ArgGroup, Subcommand};
 use colored::Colorize;
+use dialoguer::Confirm;
 use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::PathBuf;
 
 use crate::api::ApiClient;
+use crate::output::OutputFormat;
+use crate::select;
 
 #[derive(Subcommand)]
 pub enum SecretsCommands {
     /// List secrets (names only, values are masked)
     List {
-        /// Service ID
+        /// Service ID (picked from a menu when omitted)
         #[arg(short, long)]
-        service_id: String,
+        service_id: Option<String>,
     },
     /// Set a secret
+    #[command(group(ArgGroup::new("source").required(true).args(["value", "stdin", "file"])))]
     Set {
-        /// Service ID
+        /// Service ID (picked from a menu when omitted)
         #[arg(short, long)]
-        service_id: String,
+        service_id: Option<String>,
         /// Secret key
         #[arg(short, long)]
         key: String,
-        /// Secret value
+        /// Secret value (visible in shell history; prefer --stdin or --file)
         #[arg(short, long)]
-        value: String,
+        value: Option<String>,
+        /// Read the secret value from standard input
+        #[arg(long)]
+        stdin: bool,
+        /// Read the secret value from a file
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Print a secret's value, after confirming
+    Get {
+        /// Service ID (picked from a menu when omitted)
+        #[arg(short, long)]
+        service_id: Option<String>,
+        /// Secret key
+        #[arg(short, long)]
+        key: String,
+        /// Print `export KEY=value` for `eval` in a shell
+        #[arg(long)]
+        export: bool,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
     },
     /// Delete a secret
     Delete {
-        /// Service ID
+        /// Service ID (picked from a menu when omitted)
         #[arg(short, long)]
-        service_id: String,
+        service_id: Option<String>,
         /// Secret key
         #[arg(short, long)]
         key: String,
     },
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 struct SecretItem {
     key: String,
     created_at: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 struct SecretsList {
     secrets: Vec<SecretItem>,
@@ -56,21 +83,33 @@ struct SetSecretRequest {
     is_secret: bool,
 }
 
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct RevealedSecret {
+    key: String,
+    value: String,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct GenericResponse {
     success: Option<bool>,
 }
 
-pub async fn run(cmd: SecretsCommands) -> Result<()> {
+pub async fn run(cmd: SecretsCommands, output: OutputFormat) -> Result<()> {
     let api = ApiClient::from_config()?;
 
     match cmd {
         SecretsCommands::List { service_id } => {
+            let service_id = select::service_id(service_id).await?;
             let secrets: SecretsList = api
                 .get(&format!("/services/{}/secrets", service_id))
                 .await?;
 
+            if output.print(&secrets)? {
+                return Ok(());
+            }
+
             if secrets.secrets.is_empty() {
                 println!("{}", "No secrets set.".dimmed());
                 return Ok(());
@@ -86,7 +125,24 @@ pub async fn run(cmd: SecretsCommands) -> Result<()> {
             service_id,
             key,
             value,
+            stdin: _,
+            file,
         } => {
+            let service_id = select::service_id(service_id).await?;
+            let value = match (value, file) {
+                (Some(value), _) => value,
+                (None, Some(path)) => std::fs::read_to_string(&path)
+                    .map(trim_newline)
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+                // The argument group leaves --stdin as the only option here
+                (None, None) => {
+                    let mut input = String::new();
+                    std::io::stdin()
+                        .read_to_string(&mut input)
+                        .context("Failed to read the secret from stdin")?;
+                    trim_newline(input)
+                }
+            };
             let request = SetSecretRequest {
                 key: key.clone(),
                 value,
@@ -98,7 +154,27 @@ pub async fn run(cmd: SecretsCommands) -> Result<()> {
             println!("{} Secret {} set", "✓".green().bold(), key.cyan());
         }
 
+        SecretsCommands::Get {
+            service_id,
+            key,
+            export,
+            yes,
+        } => {
+            let service_id = select::service_id(service_id).await?;
+            if !confirm_reveal(&format!("secret {}", key), yes)? {
+                eprintln!("{} Cancelled", "✗".yellow().bold());
+                return Ok(());
+            }
+            let value = reveal(&api, &service_id, &key).await?;
+            if export {
+                println!("export {}={}", key, shell_quote(&value));
+            } else {
+                println!("{}", value);
+            }
+        }
+
         SecretsCommands::Delete { service_id, key } => {
+            let service_id = select::service_id(service_id).await?;
             let _: GenericResponse = api
                 .delete(&format!("/services/{}/env/{}", service_id, key))
                 .await?;
@@ -108,3 +184,53 @@ pub async fn run(cmd: SecretsCommands) -> Result<()> {
 
     Ok(())
 }
+
+/// Ask before printing secret values, warning that they stay in the
+/// terminal's scrollback
+///
+/// The prompt and warning go to stderr, so stdout can be captured or `eval`ed.
+pub fn confirm_reveal(what: &str, yes: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    eprintln!(
+        "{} The value will be shown in plain text and remain in your terminal's scrollback.",
+        "!".yellow().bold()
+    );
+    Ok(Confirm::new()
+        .with_prompt(format!("Reveal {}?", what))
+        .default(false)
+        .interact()?)
+}
+
+/// Keys of a service's secrets
+pub async fn secret_keys(api: &ApiClient, service_id: &str) -> Result<Vec<String>> {
+    let secrets: SecretsList = api
+        .get(&format!("/services/{}/secrets", service_id))
+        .await?;
+    Ok(secrets.secrets.into_iter().map(|s| s.key).collect())
+}
+
+/// Fetch a secret's decrypted value from the control plane
+pub async fn reveal(api: &ApiClient, service_id: &str, key: &str) -> Result<String> {
+    let secret: RevealedSecret = api
+        .get(&format!("/services/{}/env/{}?reveal=true", service_id, key))
+        .await?;
+    Ok(secret.value)
+}
+
+/// Quote a value for a POSIX shell
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Drop the one trailing newline that `echo` or an editor leaves on a value
+fn trim_newline(mut value: String) -> String {
+    if value.ends_with('\n') {
+        value.pop();
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+    value
+}
@@ -1,10 +1,55 @@
 use anyhow::Result;
+use clap::Subcommand;
 use colored::Colorize;
-use serde::Deserialize;
+use dialoguer::Confirm;
+use serde::{Deserialize, Serialize};
 
 use crate::api::ApiClient;
+use crate::output::{print_truncated, OutputFormat};
+use crate::select;
 
-#[derive(Debug, Deserialize)]
+#[derive(Subcommand)]
+pub enum ServicesCommands {
+    /// List services for a project
+    List {
+        /// Project ID (the context's default, or picked from a menu, when omitted)
+        #[arg(short, long)]
+        project_id: Option<String>,
+
+        /// Maximum number of services to fetch
+        #[arg(long, default_value = "100", conflicts_with = "all")]
+        limit: usize,
+
+        /// Fetch every service
+        #[arg(long)]
+        all: bool,
+    },
+    /// Create a service in a project
+    Create {
+        /// Project ID (the context's default, or picked from a menu, when omitted)
+        #[arg(short, long)]
+        project_id: Option<String>,
+        /// Service name
+        #[arg(short, long)]
+        name: String,
+        /// Docker image to run
+        #[arg(short, long)]
+        image: String,
+        /// Port the container listens on
+        #[arg(long)]
+        port: Option<u16>,
+    },
+    /// Delete a service
+    Delete {
+        /// Service ID (picked from a menu when omitted)
+        service_id: Option<String>,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Service {
     pub id: String,
@@ -15,10 +60,59 @@ pub struct Service {
     pub created_at: String,
 }
 
-/// List services for a project
-pub async fn list(project_id: &str) -> Result<()> {
+#[derive(Debug, Serialize)]
+struct CreateServiceRequest {
+    name: String,
+    image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct GenericResponse {
+    deleted: Option<bool>,
+}
+
+pub async fn run(cmd: ServicesCommands, output: OutputFormat) -> Result<()> {
+    match cmd {
+        ServicesCommands::List {
+            project_id,
+            limit,
+            all,
+        } => {
+            let project_id = select::project_id(project_id).await?;
+            list(&project_id, (!all).then_some(limit), output).await
+        }
+        ServicesCommands::Create {
+            project_id,
+            name,
+            image,
+            port,
+        } => {
+            let project_id = select::project_id(project_id).await?;
+            create(&project_id, name, image, port, output).await
+        }
+        ServicesCommands::Delete { service_id, yes } => {
+            delete(&select::service_id(service_id).await?, yes).await
+        }
+    }
+}
+
+/// List services for a project, fetching at most `limit` unless it's `None`
+pub async fn list(project_id: &str, limit: Option<usize>, output: OutputFormat) -> Result<()> {
     let api = ApiClient::from_config()?;
-    let services: Vec<Service> = api.get(&format!("/projects/{}/services", project_id)).await?;
+    let page = api
+        .get_all::<Service>(&format!("/projects/{}/services", project_id), limit)
+        .await?;
+    let services = page.items;
+    if page.truncated {
+        print_truncated(services.len(), "services");
+    }
+
+    if output.print(&services)? {
+        return Ok(());
+    }
 
     if services.is_empty() {
         println!("{}", "No services found.".dimmed());
@@ -48,3 +142,55 @@ pub async fn list(project_id: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// Create a service and print its ID and assigned domain
+async fn create(
+    project_id: &str,
+    name: String,
+    image: String,
+    port: Option<u16>,
+    output: OutputFormat,
+) -> Result<()> {
+    let api = ApiClient::from_config()?;
+
+    let request = CreateServiceRequest { name, image, port };
+    let service: Service = api
+        .post(&format!("/projects/{}/services", project_id), &request)
+        .await?;
+
+    if output.print(&service)? {
+        return Ok(());
+    }
+
+    println!("{} Service {} created", "✓".green().bold(), service.name.cyan());
+    println!("    ID: {}", service.id.dimmed());
+    if let Some(domain) = &service.domain {
+        println!("    Domain: {}", domain.cyan());
+    }
+
+    Ok(())
+}
+
+/// Delete a service, confirming first unless `yes` is set
+async fn delete(service_id: &str, yes: bool) -> Result<()> {
+    if !yes {
+        let confirmed = Confirm::new()
+            .with_prompt(format!(
+                "Delete service {}? This stops it and cannot be undone.",
+                service_id
+            ))
+            .default(false)
+            .interact()?;
+
+        if !confirmed {
+            println!("{} Delete cancelled", "✗".yellow().bold());
+            return Ok(());
+        }
+    }
+
+    let api = ApiClient::from_config()?;
+    let _: GenericResponse = api.delete(&format!("/services/{}", service_id)).await?;
+    println!("{} Service {} deleted", "✓".green().bold(), service_id.cyan());
+
+    Ok(())
+}
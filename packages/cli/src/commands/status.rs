@@ -1,10 +1,13 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use colored::Colorize;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::time::Duration;
 
 use crate::api::ApiClient;
+use crate::output::OutputFormat;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct ServerStatus {
     pub id: String,
@@ -15,20 +18,106 @@ pub struct ServerStatus {
     pub uptime_seconds: Option<u64>,
 }
 
+/// Clears the screen and moves the cursor to the top left
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
 /// Show status of servers
-pub async fn run(server_id: Option<String>) -> Result<()> {
+pub async fn run(server_id: Option<String>, output: OutputFormat) -> Result<()> {
     let api = ApiClient::from_config()?;
+    let servers = fetch(&api, server_id.as_deref()).await?;
+
+    if output.print(&servers)? {
+        return Ok(());
+    }
+    print_table(&servers);
+    Ok(())
+}
 
-    let path = match &server_id {
+/// Redraw the server table every `interval_secs` until interrupted
+///
+/// A failed refresh is shown above the last table rather than ending the
+/// watch, and the table is redrawn straight away when the terminal resizes.
+pub async fn watch(
+    server_id: Option<String>,
+    output: OutputFormat,
+    interval_secs: u64,
+) -> Result<()> {
+    if output != OutputFormat::Table {
+        bail!("--watch only supports table output");
+    }
+    let api = ApiClient::from_config()?;
+    let interval = Duration::from_secs(interval_secs.max(1));
+    let mut servers = Vec::new();
+
+    loop {
+        let error = match fetch(&api, server_id.as_deref()).await {
+            Ok(latest) => {
+                servers = latest;
+                None
+            }
+            Err(e) => Some(e),
+        };
+
+        loop {
+            print!("{}", CLEAR_SCREEN);
+            println!(
+                "{}",
+                format!(
+                    "Every {}s, updated {}. Press Ctrl-C to exit.",
+                    interval.as_secs(),
+                    chrono::Local::now().format("%H:%M:%S")
+                )
+                .dimmed()
+            );
+            if let Some(e) = &error {
+                println!("{} {:#}", "Refresh failed:".red().bold(), e);
+            }
+            println!();
+            print_table(&servers);
+            std::io::stdout().flush()?;
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => break,
+                _ = resized() => {}
+                _ = tokio::signal::ctrl_c() => {
+                    println!();
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Fetch one server's status, or every server's
+async fn fetch(api: &ApiClient, server_id: Option<&str>) -> Result<Vec<ServerStatus>> {
+    let path = match server_id {
         Some(id) => format!("/servers/{}", id),
         None => "/servers".to_string(),
     };
+    api.get(&path).await
+}
+
+/// Resolve when the terminal window changes size
+#[cfg(unix)]
+async fn resized() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::window_change()) {
+        Ok(mut resizes) => {
+            resizes.recv().await;
+        }
+        Err(_) => std::future::pending().await,
+    }
+}
 
-    let servers: Vec<ServerStatus> = api.get(&path).await?;
+#[cfg(not(unix))]
+async fn resized() {
+    std::future::pending().await
+}
 
+fn print_table(servers: &[ServerStatus]) {
     if servers.is_empty() {
         println!("{}", "No servers found.".dimmed());
-        return Ok(());
+        return;
     }
 
     println!("{}", "Servers".bold());
@@ -43,7 +132,7 @@ pub async fn run(server_id: Option<String>) -> Result<()> {
     );
     println!("{}", "─".repeat(70));
 
-    for server in &servers {
+    for server in servers {
         let status_color = match server.status.as_str() {
             "online" => server.status.green(),
             "offline" => server.status.red(),
@@ -63,7 +152,7 @@ pub async fn run(server_id: Option<String>) -> Result<()> {
 
         let uptime = server
             .uptime_seconds
-            .map(|s| format_uptime(s))
+            .map(format_uptime)
             .unwrap_or_else(|| "-".to_string());
 
         println!(
@@ -74,8 +163,6 @@ pub async fn run(server_id: Option<String>) -> Result<()> {
 
     println!();
     println!("{} server(s)", servers.len());
-
-    Ok(())
 }
 
 fn format_uptime(seconds: u64) -> String {
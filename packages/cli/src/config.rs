@@ -1,18 +1,105 @@
 //! CLI Configuration
 //!
 //! Manages authentication tokens and API base URL stored in ~/.syntra/config.toml
+//!
+//! Tokens are kept in the OS keyring when one is available, in which case the
+//! config file only records that the keyring holds the token.
+//!
+//! The file can hold several named profiles under `[profiles.<name>]`, each
+//! with its own API URL, token, and defaults. The top-level settings, which
+//! is all older versions wrote, are the `default` profile. The profile used
+//! is the one named by `--profile`, then `SYNTRA_PROFILE`, then the one set
+//! with `syntra context use`.
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Keyring service name the API token is stored under
+const KEYRING_SERVICE: &str = "syntra-cli";
+
+/// Keyring entry name for the default profile's API token; other profiles
+/// append their name
+const KEYRING_USER: &str = "api-token";
+
+/// Name of the profile kept in the top-level settings
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Environment variable selecting a profile when `--profile` isn't given
+const PROFILE_ENV: &str = "SYNTRA_PROFILE";
+
+/// Default overall timeout for API requests
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+/// Timeout from the `--timeout` flag, which takes precedence over the config file
+static TIMEOUT_OVERRIDE: OnceLock<u64> = OnceLock::new();
+
+/// Profile from the `--profile` flag, which takes precedence over everything else
+static PROFILE_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Settings of one profile
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     pub api_url: Option<String>,
     pub token: Option<String>,
+    /// Whether the token lives in the OS keyring rather than this file
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub token_in_keyring: bool,
     pub organization_id: Option<String>,
     pub default_org_id: Option<String>,
     pub default_project_id: Option<String>,
+    /// Overall API request timeout in seconds
+    pub timeout_secs: Option<u64>,
+    /// Profile these settings were loaded from; `None` for the default
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+/// Layout of config.toml
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct ConfigFile {
+    /// Profile set with `syntra context use`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    active_profile: Option<String>,
+    /// The default profile, at the top level where older versions kept it
+    #[serde(flatten)]
+    default: Config,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, Config>,
+}
+
+impl ConfigFile {
+    fn load() -> Result<Self> {
+        let path = Config::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config at {}", path.display()))?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Config::path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Name of the profile to use, `None` meaning the default
+    fn active_profile(&self) -> Option<String> {
+        PROFILE_OVERRIDE
+            .get()
+            .cloned()
+            .or_else(|| std::env::var(PROFILE_ENV).ok().filter(|p| !p.is_empty()))
+            .or_else(|| self.active_profile.clone())
+            .filter(|p| p != DEFAULT_PROFILE)
+    }
 }
 
 impl Config {
@@ -22,26 +109,88 @@ impl Config {
         Ok(home.join(".syntra").join("config.toml"))
     }
 
-    /// Load config from disk
+    /// Load the active profile's config from disk
+    ///
+    /// A profile that doesn't exist yet loads empty, for `syntra login` to fill in.
     pub fn load() -> Result<Self> {
-        let path = Self::path()?;
-        if !path.exists() {
-            return Ok(Self::default());
+        let mut file = ConfigFile::load()?;
+        let profile = file.active_profile();
+        let mut config = match &profile {
+            Some(name) => file.profiles.remove(name).unwrap_or_default(),
+            None => file.default,
+        };
+        config.profile = profile;
+        if config.token_in_keyring {
+            // Leave the token unset if the keyring can't be read; API calls report it
+            config.token = keyring_entry(config.profile.as_deref())
+                .and_then(|e| e.get_password())
+                .ok();
         }
-        let content = std::fs::read_to_string(&path)
-            .with_context(|| format!("Failed to read config at {}", path.display()))?;
-        let config: Config = toml::from_str(&content)?;
         Ok(config)
     }
 
-    /// Save config to disk
+    /// Save config to disk under its profile, leaving other profiles as they are
     pub fn save(&self) -> Result<()> {
-        let path = Self::path()?;
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+        let mut file = ConfigFile::load()?;
+        let on_disk = Config {
+            token: if self.token_in_keyring { None } else { self.token.clone() },
+            profile: None,
+            ..self.clone()
+        };
+        match &self.profile {
+            Some(name) => {
+                file.profiles.insert(name.clone(), on_disk);
+            }
+            None => file.default = on_disk,
+        }
+        file.save()
+    }
+
+    /// Use `--profile` for this process, over `SYNTRA_PROFILE` and the config file
+    pub fn set_profile_override(profile: String) {
+        let _ = PROFILE_OVERRIDE.set(profile);
+    }
+
+    /// Name of the profile these settings belong to
+    pub fn profile_name(&self) -> &str {
+        self.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// Make `profile` the one used when `--profile` and `SYNTRA_PROFILE` aren't set
+    pub fn use_profile(profile: &str) -> Result<()> {
+        let mut file = ConfigFile::load()?;
+        if profile == DEFAULT_PROFILE {
+            file.active_profile = None;
+        } else if file.profiles.contains_key(profile) {
+            file.active_profile = Some(profile.to_string());
+        } else {
+            let mut known = vec![DEFAULT_PROFILE.to_string()];
+            known.extend(file.profiles.into_keys());
+            bail!(
+                "No profile named {} (have: {}). Create it with `syntra login --profile {}`.",
+                profile,
+                known.join(", "),
+                profile
+            );
+        }
+        file.save()
+    }
+
+    /// Set the API token, storing it in the OS keyring unless `plaintext` is set
+    pub fn set_token(&mut self, token: String, plaintext: bool) -> Result<()> {
+        if plaintext {
+            if self.token_in_keyring {
+                // Best effort: a stale keyring entry is harmless once the flag is cleared
+                let _ = keyring_entry(self.profile.as_deref()).and_then(|e| e.delete_password());
+            }
+            self.token_in_keyring = false;
+        } else {
+            keyring_entry(self.profile.as_deref())
+                .and_then(|e| e.set_password(&token))
+                .context("Failed to store token in the OS keyring (use --plaintext to skip it)")?;
+            self.token_in_keyring = true;
         }
-        let content = toml::to_string_pretty(self)?;
-        std::fs::write(&path, content)?;
+        self.token = Some(token);
         Ok(())
     }
 
@@ -52,9 +201,32 @@ impl Config {
             .unwrap_or("https://app.syntra.io")
     }
 
+    /// Override the request timeout for this process
+    pub fn set_timeout_override(secs: u64) {
+        let _ = TIMEOUT_OVERRIDE.set(secs);
+    }
+
+    /// Get the overall API request timeout
+    pub fn timeout(&self) -> Duration {
+        let secs = TIMEOUT_OVERRIDE
+            .get()
+            .copied()
+            .or(self.timeout_secs)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS);
+        Duration::from_secs(secs.max(1))
+    }
+
     /// Check if authenticated
     #[allow(dead_code)]
     pub fn is_authenticated(&self) -> bool {
         self.token.is_some()
     }
 }
+
+fn keyring_entry(profile: Option<&str>) -> keyring::Result<keyring::Entry> {
+    let user = match profile {
+        Some(profile) => format!("{}:{}", KEYRING_USER, profile),
+        None => KEYRING_USER.to_string(),
+    };
+    keyring::Entry::new(KEYRING_SERVICE, &user)
+}
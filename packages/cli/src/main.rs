@@ -1,14 +1,42 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 
 mod api;
 mod commands;
 mod config;
+mod output;
+mod select;
+
+use output::{ColorChoice, OutputFormat};
 
 #[derive(Parser)]
 #[command(name = "syntra", about = "Syntra CLI - Manage your Syntra deployments")]
 #[command(version, propagate_version = true)]
 struct Cli {
+    /// Output format for list commands
+    #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
+    /// Print errors to stderr as JSON (implied by `--output json`)
+    #[arg(long, global = true)]
+    json_errors: bool,
+
+    /// When to color output
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// API request timeout in seconds (overrides timeout_secs in the config)
+    #[arg(long, global = true)]
+    timeout: Option<u64>,
+
+    /// Config profile to use (overrides SYNTRA_PROFILE and `syntra context use`)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Never prompt for an omitted service or project ID
+    #[arg(long, global = true)]
+    no_interactive: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -20,22 +48,37 @@ enum Commands {
         /// API base URL (default: https://app.syntra.io)
         #[arg(long)]
         api_url: Option<String>,
+
+        /// Log in through the browser instead of pasting a token
+        #[arg(long)]
+        browser: bool,
+
+        /// Store the token in config.toml instead of the OS keyring
+        #[arg(long)]
+        plaintext: bool,
     },
 
     /// List projects
-    Projects,
+    Projects {
+        /// Maximum number of projects to fetch
+        #[arg(long, default_value = "100", conflicts_with = "all")]
+        limit: usize,
+
+        /// Fetch every project
+        #[arg(long)]
+        all: bool,
+    },
 
-    /// List services for a project
+    /// Manage services
     Services {
-        /// Project ID
-        #[arg(short, long)]
-        project_id: String,
+        #[command(subcommand)]
+        command: commands::services::ServicesCommands,
     },
 
     /// Deploy a service
     Deploy {
-        /// Service ID
-        service_id: String,
+        /// Service ID (picked from a menu when omitted)
+        service_id: Option<String>,
 
         /// Git branch to deploy
         #[arg(short, long)]
@@ -46,10 +89,24 @@ enum Commands {
         image: Option<String>,
     },
 
+    /// Show the status of a deployment
+    DeployStatus {
+        /// Deployment ID
+        deployment_id: String,
+
+        /// Keep polling until the deployment finishes
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Also show the deployment's build logs
+        #[arg(short, long)]
+        logs: bool,
+    },
+
     /// Fetch logs for a service
     Logs {
-        /// Service ID
-        service_id: String,
+        /// Service ID (picked from a menu when omitted)
+        service_id: Option<String>,
 
         /// Number of log lines to fetch
         #[arg(short = 'n', long, default_value = "50")]
@@ -65,6 +122,14 @@ enum Commands {
         /// Filter by server ID
         #[arg(short, long)]
         server_id: Option<String>,
+
+        /// Keep refreshing the table until Ctrl-C
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Seconds between refreshes in watch mode
+        #[arg(long, default_value = "5", requires = "watch")]
+        interval: u64,
     },
 
     /// Manage environment variables
@@ -85,87 +150,236 @@ enum Commands {
         command: commands::domains::DomainsCommands,
     },
 
-    /// Scale a service
+    /// Scale a service to a fixed number of replicas, or set up autoscaling
     Scale {
-        /// Service ID
-        service_id: String,
+        /// Service ID (picked from a menu when omitted)
+        service_id: Option<String>,
 
         /// Number of replicas
+        #[arg(
+            short,
+            long,
+            required_unless_present_any = ["autoscale", "disable_autoscale"],
+            conflicts_with_all = ["autoscale", "disable_autoscale"]
+        )]
+        replicas: Option<u32>,
+
+        /// Skip the confirmation prompt when scaling to 0
         #[arg(short, long)]
-        replicas: u32,
+        yes: bool,
+
+        /// Scale between --min and --max replicas to hold --cpu-target
+        #[arg(long, requires_all = ["min", "max"], conflicts_with = "disable_autoscale")]
+        autoscale: bool,
+
+        /// Fewest replicas to run when autoscaling
+        #[arg(long, requires = "autoscale")]
+        min: Option<u32>,
+
+        /// Most replicas to run when autoscaling
+        #[arg(long, requires = "autoscale")]
+        max: Option<u32>,
+
+        /// Average CPU usage in percent that autoscaling aims for
+        #[arg(long, default_value = "70", requires = "autoscale")]
+        cpu_target: u32,
+
+        /// Turn off autoscaling, keeping the current replica count
+        #[arg(long)]
+        disable_autoscale: bool,
     },
 
-    /// Rollback a service to a previous deployment
-    Rollback {
+    /// Run a one-off command in a service container
+    Exec {
+        // Always required: if it were optional, `exec ls -la` would take
+        // `ls` as the service ID
         /// Service ID
         service_id: String,
 
+        /// Container ID of the replica to run in (defaults to any replica)
+        #[arg(long)]
+        container: Option<String>,
+
+        /// Command and arguments to run
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+
+    /// Rollback a service to a previous deployment
+    #[command(args_conflicts_with_subcommands = true)]
+    Rollback {
+        #[command(subcommand)]
+        command: Option<commands::rollback::RollbackCommands>,
+
+        // Not `required`: without a subcommand, an omitted ID is picked from a
+        // menu, or reported missing under --no-interactive
+        /// Service ID (picked from a menu when omitted)
+        service_id: Option<String>,
+
         /// Target deployment ID (defaults to previous)
         #[arg(long)]
         to_deployment: Option<String>,
     },
 
-    /// Manage CLI context (default org, project)
+    /// Manage CLI context (profile, default org, project)
     Context {
         #[command(subcommand)]
         command: commands::context::ContextCommands,
     },
+
+    /// Print a shell completion script
+    ///
+    /// Install it by adding the output to your shell's startup files:
+    ///
+    ///   bash:        syntra completions bash > ~/.local/share/bash-completion/completions/syntra
+    ///
+    ///   zsh:         syntra completions zsh > "${fpath[1]}/_syntra"
+    ///
+    ///   fish:        syntra completions fish > ~/.config/fish/completions/syntra.fish
+    ///
+    ///   powershell:  syntra completions powershell >> $PROFILE
+    ///
+    ///   elvish:      syntra completions elvish >> ~/.config/elvish/rc.elv
+    #[command(verbatim_doc_comment)]
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let cli = Cli::parse();
+    let json_errors = cli.json_errors || cli.output == OutputFormat::Json;
+    if let Err(e) = run(cli).await {
+        if json_errors {
+            output::print_error_json(&e);
+        } else {
+            eprintln!("Error: {:?}", e);
+        }
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let output = cli.output;
+    cli.color.apply();
+    if let Some(timeout) = cli.timeout {
+        config::Config::set_timeout_override(timeout);
+    }
+    if let Some(profile) = cli.profile {
+        config::Config::set_profile_override(profile);
+    }
+    if cli.no_interactive {
+        select::disable_prompts();
+    }
 
     match cli.command {
-        Commands::Login { api_url } => {
-            commands::login::run(api_url).await
+        Commands::Login {
+            api_url,
+            browser,
+            plaintext,
+        } => {
+            commands::login::run(api_url, browser, plaintext).await
         }
-        Commands::Projects => {
-            commands::projects::list().await
+        Commands::Projects { limit, all } => {
+            commands::projects::list((!all).then_some(limit), output).await
         }
-        Commands::Services { project_id } => {
-            commands::services::list(&project_id).await
+        Commands::Services { command } => {
+            commands::services::run(command, output).await
         }
         Commands::Deploy {
             service_id,
             branch,
             image,
         } => {
-            commands::deploy::run(&service_id, branch, image).await
+            commands::deploy::run(&select::service_id(service_id).await?, branch, image).await
+        }
+        Commands::DeployStatus {
+            deployment_id,
+            watch,
+            logs,
+        } => {
+            commands::deploy::status(&deployment_id, watch, logs).await
         }
         Commands::Logs {
             service_id,
             lines,
             follow,
         } => {
-            commands::logs::run(&service_id, lines, follow).await
+            commands::logs::run(&select::service_id(service_id).await?, lines, follow).await
         }
-        Commands::Status { server_id } => {
-            commands::status::run(server_id).await
+        Commands::Status {
+            server_id,
+            watch,
+            interval,
+        } => {
+            if watch {
+                commands::status::watch(server_id, output, interval).await
+            } else {
+                commands::status::run(server_id, output).await
+            }
         }
         Commands::Env { command } => {
-            commands::env::run(command).await
+            commands::env::run(command, output).await
         }
         Commands::Secrets { command } => {
-            commands::secrets::run(command).await
+            commands::secrets::run(command, output).await
         }
         Commands::Domains { command } => {
-            commands::domains::run(command).await
+            commands::domains::run(command, output).await
         }
         Commands::Scale {
             service_id,
             replicas,
+            yes,
+            autoscale,
+            min,
+            max,
+            cpu_target,
+            disable_autoscale: _,
         } => {
-            commands::scale::run(&service_id, replicas).await
+            let service_id = select::service_id(service_id).await?;
+            match (replicas, min, max) {
+                (Some(replicas), _, _) => commands::scale::run(&service_id, replicas, yes).await,
+                (None, Some(min), Some(max)) if autoscale => {
+                    commands::scale::autoscale(&service_id, min, max, cpu_target).await
+                }
+                // Without --replicas or --autoscale, clap requires --disable-autoscale
+                _ => commands::scale::disable_autoscale(&service_id).await,
+            }
         }
-        Commands::Rollback {
+        Commands::Exec {
             service_id,
-            to_deployment,
+            container,
+            command,
         } => {
-            commands::rollback::run(&service_id, to_deployment).await
+            let exit_code = commands::exec::run(&service_id, container, command).await?;
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+            Ok(())
         }
+        Commands::Rollback {
+            command,
+            service_id,
+            to_deployment,
+        } => match command {
+            Some(command) => commands::rollback::run_command(command, output).await,
+            None => {
+                let service_id = select::service_id(service_id).await?;
+                commands::rollback::run(&service_id, to_deployment).await
+            }
+        },
         Commands::Context { command } => {
             commands::context::run(command).await
         }
+        Commands::Completions { shell } => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+            Ok(())
+        }
     }
 }
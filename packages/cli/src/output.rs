@@ -0,0 +1,84 @@
+//! Output Formatting
+//!
+//! Machine-readable alternatives to the CLI's colored table output, and
+//! whether that output is colored at all.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+use std::io::IsTerminal;
+
+use crate::api::ApiError;
+
+/// When to color output, selected with `--color`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    /// Always color, even when piped
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    /// Turn coloring on or off for everything the process prints
+    pub fn apply(self) {
+        let enabled = match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                !no_color && std::io::stdout().is_terminal()
+            }
+        };
+        colored::control::set_override(enabled);
+    }
+}
+
+/// Output format selected with `--output`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable, colored output
+    #[default]
+    Table,
+    /// JSON for scripts
+    Json,
+    /// YAML for scripts
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Print `value` in this format, returning false for `Table` so the
+    /// caller can render its own human-readable output instead
+    pub fn print<T: Serialize>(self, value: &T) -> Result<bool> {
+        match self {
+            OutputFormat::Table => return Ok(false),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+        }
+        Ok(true)
+    }
+}
+
+/// Note on stderr that a list was cut short, keeping stdout parseable
+pub fn print_truncated(shown: usize, noun: &str) {
+    let note = format!("Showing the first {} {}; pass --all to fetch everything.", shown, noun);
+    eprintln!("{}", note.yellow());
+}
+
+/// Print an error to stderr as `{"error": {"code", "message"}}` for scripts
+///
+/// The control plane's error code is kept when the error came from the API;
+/// anything else is reported as `CLI_ERROR`.
+pub fn print_error_json(error: &anyhow::Error) {
+    let api_error = error.chain().find_map(|e| e.downcast_ref::<ApiError>());
+    let (code, message) = match api_error {
+        Some(e) => (e.code.clone(), e.message.clone()),
+        None => ("CLI_ERROR".to_string(), format!("{:#}", error)),
+    };
+    let body = serde_json::json!({ "error": { "code": code, "message": message } });
+    eprintln!("{}", body);
+}
@@ -0,0 +1,89 @@
+//! Interactive Selection
+//!
+//! Commands that take a service or project ID let it be left out when stdin
+//! is a terminal, offering a menu to pick from instead. Services are listed
+//! from the context's default project, or from a project picked first.
+//! `--no-interactive` turns the menus off, so scripts get an error naming
+//! the missing ID rather than a prompt.
+
+use anyhow::{bail, Result};
+use dialoguer::Select;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::api::ApiClient;
+use crate::commands::projects::Project;
+use crate::commands::services::Service;
+use crate::config::Config;
+
+/// Set by `--no-interactive`
+static NO_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Never prompt for missing IDs in this process
+pub fn disable_prompts() {
+    NO_INTERACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Whether a missing ID can be asked for
+fn interactive() -> bool {
+    !NO_INTERACTIVE.load(Ordering::Relaxed) && std::io::stdin().is_terminal()
+}
+
+/// The given service ID, or one picked from a menu
+pub async fn service_id(given: Option<String>) -> Result<String> {
+    if let Some(id) = given {
+        return Ok(id);
+    }
+    if !interactive() {
+        bail!("A service ID is required");
+    }
+
+    let project_id = project_id(None).await?;
+    let api = ApiClient::from_config()?;
+    let services = api
+        .get_all::<Service>(&format!("/projects/{}/services", project_id), None)
+        .await?
+        .items;
+    if services.is_empty() {
+        bail!("Project {} has no services", project_id);
+    }
+
+    let labels: Vec<String> = services
+        .iter()
+        .map(|s| format!("{} [{}]", s.name, s.status))
+        .collect();
+    let index = pick("Service", &labels)?;
+    Ok(services[index].id.clone())
+}
+
+/// The given project ID, else the context's default project, else one
+/// picked from a menu
+pub async fn project_id(given: Option<String>) -> Result<String> {
+    if let Some(id) = given.or(Config::load()?.default_project_id) {
+        return Ok(id);
+    }
+    if !interactive() {
+        bail!("A project ID is required (or set a default with `syntra context set-project`)");
+    }
+
+    let api = ApiClient::from_config()?;
+    let projects = api.get_all::<Project>("/projects", None).await?.items;
+    if projects.is_empty() {
+        bail!("No projects found");
+    }
+
+    let labels: Vec<String> = projects
+        .iter()
+        .map(|p| format!("{} ({})", p.name, p.slug))
+        .collect();
+    let index = pick("Project", &labels)?;
+    Ok(projects[index].id.clone())
+}
+
+fn pick(prompt: &str, labels: &[String]) -> Result<usize> {
+    Ok(Select::new()
+        .with_prompt(prompt)
+        .items(labels)
+        .default(0)
+        .interact()?)
+}